@@ -0,0 +1,160 @@
+use async_trait::async_trait;
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use aws_sigv4::http_request::{
+    sign, PercentEncodingMode, SignableBody, SignableRequest, SignatureLocation, SigningSettings,
+    UriPathNormalizationMode,
+};
+use aws_sigv4::sign::v4;
+use log::{info, warn};
+use std::env;
+use std::time::{Duration, SystemTime};
+
+use crate::error::{AppError, Result};
+
+// Lifetime of a document download URL unless DOCUMENT_URL_TTL_SECONDS says otherwise
+pub const DEFAULT_DOCUMENT_URL_TTL_SECONDS: u64 = 300;
+// S3 rejects presigned URLs valid for longer than seven days
+const MAX_DOCUMENT_URL_TTL_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// Produces short-lived GET URLs for S3-backed documents
+#[async_trait]
+pub trait DocumentPresigner: Send + Sync {
+    async fn presign_get(&self, key: &str, expires_in: Duration) -> Result<String>;
+}
+
+/// Presigns GETs against the bucket in DOCUMENTS_BUCKET with SigV4 query
+/// signing, using the credentials and region from the default AWS config
+pub struct S3Presigner {
+    bucket: String,
+    region: String,
+    credentials: SharedCredentialsProvider,
+}
+
+impl S3Presigner {
+    pub fn new(bucket: String, region: String, credentials: SharedCredentialsProvider) -> Self {
+        Self {
+            bucket,
+            region,
+            credentials,
+        }
+    }
+
+    /// None when DOCUMENTS_BUCKET isn't set or the AWS config has no
+    /// credentials or region
+    pub async fn from_env() -> Option<Self> {
+        let bucket = env::var("DOCUMENTS_BUCKET")
+            .ok()
+            .filter(|b| !b.is_empty())?;
+
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        let (Some(credentials), Some(region)) = (config.credentials_provider(), config.region())
+        else {
+            warn!("DOCUMENTS_BUCKET is set but no AWS credentials or region are configured");
+            return None;
+        };
+
+        info!("Presigning document URLs for bucket {}", bucket);
+        Some(Self::new(bucket, region.to_string(), credentials))
+    }
+
+    fn object_url(&self, key: &str) -> String {
+        format!(
+            "https://{}.s3.{}.amazonaws.com/{}",
+            self.bucket,
+            self.region,
+            encode_key(key)
+        )
+    }
+}
+
+#[async_trait]
+impl DocumentPresigner for S3Presigner {
+    async fn presign_get(&self, key: &str, expires_in: Duration) -> Result<String> {
+        let credentials = self
+            .credentials
+            .provide_credentials()
+            .await
+            .map_err(AppError::internal_error)?;
+        let identity = credentials.into();
+
+        // S3 signs the path as sent, so no double encoding or normalisation
+        let mut settings = SigningSettings::default();
+        settings.signature_location = SignatureLocation::QueryParams;
+        settings.expires_in = Some(expires_in);
+        settings.percent_encoding_mode = PercentEncodingMode::Single;
+        settings.uri_path_normalization_mode = UriPathNormalizationMode::Disabled;
+
+        let params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&self.region)
+            .name("s3")
+            .time(SystemTime::now())
+            .settings(settings)
+            .build()
+            .map_err(AppError::internal_error)?
+            .into();
+
+        let url = self.object_url(key);
+        let signable = SignableRequest::new(
+            "GET",
+            &url,
+            std::iter::empty(),
+            SignableBody::UnsignedPayload,
+        )
+        .map_err(AppError::internal_error)?;
+        let (instructions, _signature) = sign(signable, &params)
+            .map_err(AppError::internal_error)?
+            .into_parts();
+
+        let mut request = http::Request::builder()
+            .uri(&url)
+            .body(())
+            .map_err(AppError::internal_error)?;
+        instructions.apply_to_request_http1x(&mut request);
+        Ok(request.uri().to_string())
+    }
+}
+
+// Download URL lifetime from DOCUMENT_URL_TTL_SECONDS, between 1s and S3's 7 day limit
+pub fn document_url_ttl() -> Duration {
+    let seconds = env::var("DOCUMENT_URL_TTL_SECONDS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|seconds| *seconds > 0)
+        .unwrap_or(DEFAULT_DOCUMENT_URL_TTL_SECONDS)
+        .min(MAX_DOCUMENT_URL_TTL_SECONDS);
+    Duration::from_secs(seconds)
+}
+
+// Objects for a box live under this prefix, so an owner can only link their own box's objects
+pub fn document_key_prefix(box_id: &str) -> String {
+    format!("boxes/{}/", box_id)
+}
+
+/// Whether `key` names an object under the box's prefix. Keys with `.` or
+/// `..` segments or empty segments are refused so the prefix can't be escaped.
+pub fn is_box_document_key(box_id: &str, key: &str) -> bool {
+    match key.strip_prefix(&document_key_prefix(box_id)) {
+        Some(rest) => {
+            !rest.is_empty()
+                && rest
+                    .split('/')
+                    .all(|segment| !segment.is_empty() && segment != "." && segment != "..")
+        }
+        None => false,
+    }
+}
+
+// Percent-encodes an object key for the URL path, keeping `/` as the separator
+fn encode_key(key: &str) -> String {
+    let mut encoded = String::with_capacity(key.len());
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
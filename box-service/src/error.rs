@@ -1,25 +1,72 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+use lockbox_shared::error::{
+    internal_error_body, SERVICE_UNAVAILABLE_MESSAGE, THROTTLED_RETRY_AFTER_SECS,
+};
 use log::{error, info, warn};
+use serde::Serialize;
 use serde_json::json;
 use thiserror::Error;
 
+// A single failed field check, reported back to the client in 422 responses
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub reason: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, reason: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Not found: {0}")]
     NotFound(String),
 
-    #[error("Bad request: {0}")]
-    BadRequest(String),
+    #[error("Validation error: {0}")]
+    Validation(String),
+
+    #[error("Validation failed: {0:?}")]
+    FieldValidation(Vec<FieldError>),
+
+    #[error("Unprocessable: {0}")]
+    Unprocessable(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Gone: {0}")]
+    Gone(String),
+
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
+    #[error("Store throttled: {0}")]
+    Throttled(String),
 
     #[error("Internal server error: {0}")]
-    InternalServerError(String),
+    Internal(String),
 
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
@@ -36,6 +83,11 @@ impl AppError {
         AppError::Unauthorized(msg)
     }
 
+    pub fn forbidden(msg: String) -> Self {
+        warn!("Forbidden error: {}", msg);
+        AppError::Forbidden(msg)
+    }
+
     pub fn not_found(msg: String) -> Self {
         warn!("Not found error: {}", msg);
         AppError::NotFound(msg)
@@ -43,17 +95,43 @@ impl AppError {
 
     pub fn bad_request(msg: String) -> Self {
         warn!("Bad request error: {}", msg);
-        AppError::BadRequest(msg)
+        AppError::Validation(msg)
+    }
+
+    #[allow(dead_code)]
+    pub fn conflict(msg: String) -> Self {
+        warn!("Conflict error: {}", msg);
+        AppError::Conflict(msg)
+    }
+
+    pub fn unprocessable(msg: String) -> Self {
+        warn!("Unprocessable error: {}", msg);
+        AppError::Unprocessable(msg)
+    }
+
+    pub fn payload_too_large(msg: String) -> Self {
+        warn!("Payload too large error: {}", msg);
+        AppError::PayloadTooLarge(msg)
+    }
+
+    pub fn gone(msg: String) -> Self {
+        warn!("Gone error: {}", msg);
+        AppError::Gone(msg)
+    }
+
+    pub fn precondition_failed(msg: String) -> Self {
+        warn!("Precondition failed error: {}", msg);
+        AppError::PreconditionFailed(msg)
     }
 
     pub fn internal_server_error(msg: String) -> Self {
         error!("Internal server error: {}", msg);
-        AppError::InternalServerError(msg)
+        AppError::Internal(msg)
     }
 
     #[allow(dead_code)]
     pub fn internal_error<T: std::fmt::Display>(error: T) -> Self {
-        AppError::InternalServerError(error.to_string())
+        AppError::Internal(error.to_string())
     }
 
     #[allow(dead_code)]
@@ -64,22 +142,77 @@ impl AppError {
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let retry_after = match &self {
+            AppError::Throttled(_) => Some(THROTTLED_RETRY_AFTER_SECS),
+            _ => None,
+        };
+
+        let mut fields = None;
         let (status, error_message) = match self {
             AppError::Unauthorized(msg) => {
                 warn!("Unauthorized error: {}", msg);
                 (StatusCode::UNAUTHORIZED, msg.clone())
             }
+            AppError::Forbidden(msg) => {
+                warn!("Forbidden error: {}", msg);
+                (StatusCode::FORBIDDEN, msg.clone())
+            }
             AppError::NotFound(msg) => {
                 warn!("Not found error: {}", msg);
                 (StatusCode::NOT_FOUND, msg.clone())
             }
-            AppError::BadRequest(msg) => {
-                warn!("Bad request error: {}", msg);
+            AppError::Validation(msg) => {
+                warn!("Validation error: {}", msg);
                 (StatusCode::BAD_REQUEST, msg.clone())
             }
-            AppError::InternalServerError(msg) => {
+            AppError::FieldValidation(field_errors) => {
+                warn!("Validation failed: {:?}", field_errors);
+                fields = Some(field_errors);
+                (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "Validation failed".to_string(),
+                )
+            }
+            AppError::Unprocessable(msg) => {
+                warn!("Unprocessable error: {}", msg);
+                (StatusCode::UNPROCESSABLE_ENTITY, msg.clone())
+            }
+            AppError::PayloadTooLarge(msg) => {
+                warn!("Payload too large error: {}", msg);
+                (StatusCode::PAYLOAD_TOO_LARGE, msg.clone())
+            }
+            AppError::Conflict(msg) => {
+                warn!("Conflict error: {}", msg);
+                (StatusCode::CONFLICT, msg.clone())
+            }
+            AppError::Gone(msg) => {
+                warn!("Gone error: {}", msg);
+                (StatusCode::GONE, msg.clone())
+            }
+            AppError::PreconditionFailed(msg) => {
+                warn!("Precondition failed error: {}", msg);
+                (StatusCode::PRECONDITION_FAILED, msg.clone())
+            }
+            AppError::ServiceUnavailable(msg) => {
+                warn!("Service unavailable error: {}", msg);
+                (StatusCode::SERVICE_UNAVAILABLE, msg.clone())
+            }
+            AppError::Throttled(msg) => {
+                // The detail names tables and SDK errors, so it stays in the logs
+                warn!("Store throttled: {}", msg);
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    SERVICE_UNAVAILABLE_MESSAGE.to_string(),
+                )
+            }
+            AppError::Internal(msg) => {
+                // Logged in full here; the response never carries the detail
                 error!("Internal server error: {}", msg);
-                (StatusCode::INTERNAL_SERVER_ERROR, msg.clone())
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(internal_error_body()),
+                )
+                    .into_response();
             }
             AppError::SerializationError(err) => {
                 warn!("Serialization error: {}", err);
@@ -91,12 +224,22 @@ impl IntoResponse for AppError {
             }
         };
 
-        let body = Json(json!({ "error": error_message }));
+        let mut body = json!({ "error": error_message });
+        if let Some(fields) = fields {
+            body["fields"] = json!(fields);
+        }
+        let body = Json(body);
         info!(
             "Responding with error: status={}, message={:?}",
             status, body
         );
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
@@ -105,10 +248,10 @@ impl From<lockbox_shared::error::StoreError> for AppError {
     fn from(err: lockbox_shared::error::StoreError) -> Self {
         match err {
             lockbox_shared::error::StoreError::NotFound(msg) => AppError::NotFound(msg),
-            lockbox_shared::error::StoreError::ValidationError(msg) => AppError::BadRequest(msg),
+            lockbox_shared::error::StoreError::ValidationError(msg) => AppError::Validation(msg),
             lockbox_shared::error::StoreError::InternalError(msg) => {
                 error!("Store internal error: {}", msg);
-                AppError::InternalServerError(msg)
+                AppError::Internal(msg)
             }
             lockbox_shared::error::StoreError::InvitationExpired => {
                 // Map to the specific 422 error variant
@@ -117,9 +260,14 @@ impl From<lockbox_shared::error::StoreError> for AppError {
             lockbox_shared::error::StoreError::AuthError(msg) => AppError::Unauthorized(msg),
             lockbox_shared::error::StoreError::VersionConflict(msg) => {
                 warn!("Concurrent modification detected: {}", msg);
-                AppError::BadRequest(
-                    format!("Concurrent modification detected, please retry: {}", msg).into(),
-                )
+                AppError::Conflict(format!(
+                    "Concurrent modification detected, please retry: {}",
+                    msg
+                ))
+            }
+            lockbox_shared::error::StoreError::Throttled(msg) => AppError::Throttled(msg),
+            err @ lockbox_shared::error::StoreError::AmbiguousInviteCode => {
+                AppError::Conflict(err.to_string())
             }
         }
     }
@@ -0,0 +1,46 @@
+use aws_sdk_sns::Client as SnsClient;
+use log::debug;
+use std::env;
+
+use lockbox_shared::models::{events::InvitationEvent, Invitation};
+
+use crate::error::{AppError, Result};
+
+// Publishes an invitation event to SNS_TOPIC_ARN; skipped when TEST_SNS=true
+pub async fn publish_invitation_event(invitation: &Invitation, event_type: &str) -> Result<()> {
+    if env::var("TEST_SNS").map(|v| v == "true").unwrap_or(false) {
+        debug!(
+            "Test mode: Skipping SNS publishing for event_type={}, invitation_id={}",
+            event_type, invitation.id
+        );
+        return Ok(());
+    }
+
+    let topic_arn = env::var("SNS_TOPIC_ARN")
+        .map_err(|_| AppError::internal_server_error("SNS_TOPIC_ARN is not set".into()))?;
+
+    let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+    let sns_client = SnsClient::new(&config);
+
+    let message = serde_json::to_string(&InvitationEvent::for_invitation(invitation, event_type))
+        .map_err(AppError::internal_error)?;
+
+    // Same eventType attribute the invitation service sets, for subscription filters
+    let event_type_attribute = aws_sdk_sns::types::MessageAttributeValue::builder()
+        .data_type("String")
+        .string_value(event_type)
+        .build()
+        .map_err(AppError::internal_error)?;
+
+    sns_client
+        .publish()
+        .topic_arn(topic_arn)
+        .message(message)
+        .subject("Invitation Response")
+        .message_attributes("eventType", event_type_attribute)
+        .send()
+        .await
+        .map_err(AppError::internal_error)?;
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use axum::Json;
+use lockbox_shared::auth::{id_token_enforcement_enabled, AuthClaims};
+
+// GET /whoami - the claims the server extracted from the caller's token, to help
+// debug auth during integration. Only decoded claims are returned, never the token.
+pub async fn whoami(AuthClaims(claims): AuthClaims) -> Json<serde_json::Value> {
+    // The signature is checked by API Gateway; reaching a handler means the
+    // token_use/aud checks passed whenever ENFORCE_ID_TOKEN turns them on
+    let verification = if id_token_enforcement_enabled() {
+        "passed"
+    } else {
+        "disabled"
+    };
+
+    Json(serde_json::json!({
+        "sub": claims.sub,
+        "email": claims.email,
+        "cognitoUsername": claims.cognito_username,
+        "exp": claims.exp,
+        "idTokenVerification": verification,
+    }))
+}
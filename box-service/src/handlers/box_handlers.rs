@@ -1,36 +1,159 @@
 use axum::{
-    extract::{Extension, Path, State},
-    http::StatusCode,
+    extract::{Extension, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     Json,
 };
-use lockbox_shared::store::BoxStore;
+use lockbox_shared::auth::UserName;
+use lockbox_shared::error::StoreError;
+use lockbox_shared::extract::{JsonBody, ListParams, Order};
+use lockbox_shared::idempotency::{
+    claim_idempotency_key, idempotency_key, IdempotencyClaim, REQUEST_IN_PROGRESS,
+};
+use lockbox_shared::store::{
+    update_box_with_retry, BoxStore, IdempotencyStore, DEFAULT_UPDATE_ATTEMPTS,
+};
 use serde_json;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::error::{AppError, Result};
+use crate::documents::{
+    document_key_prefix, document_url_ttl, is_box_document_key, DocumentPresigner,
+};
+use crate::error::{AppError, FieldError, Result};
 // Import models from shared crate
-use lockbox_shared::models::{now_str, BoxRecord, Document, Guardian};
+use lockbox_shared::models::{
+    normalize_rfc3339, now_str, BoxRecord, Document, DocumentStorage, Guardian, GuardianStatus,
+    UnlockPolicy, UnlockRequestStatus,
+};
 // Import request/response types from local models
 use crate::models::{
-    BoxResponse, CreateBoxRequest, DocumentUpdateRequest, DocumentUpdateResponse,
-    GuardianUpdateRequest, GuardianUpdateResponse, OptionalField, UpdateBoxRequest,
+    max_document_bytes, max_documents_per_box, max_guardians_per_box, BatchGetBoxesRequest,
+    BoxResponse, BoxSortField, CreateBoxRequest, DocumentUpdateRequest, DocumentUpdateResponse,
+    GuardianPatchRequest, GuardianUpdateRequest, GuardianUpdateResponse, GuardiansUpdateRequest,
+    GuardiansUpdateResponse, ListBoxesParams, OptionalField, SearchBoxesParams, UpdateBoxParams,
+    UpdateBoxRequest, ALLOWED_DOCUMENT_CONTENT_TYPES, DEFAULT_PAGE_SIZE, MAX_BATCH_GET_IDS,
+    MAX_BOX_ITEM_BYTES, MAX_BOX_NAME_LENGTH, MAX_SEARCH_RESULTS, MIN_SEARCH_QUERY_LENGTH,
 };
 
+// Validates `sort` for the box listings, before anything is fetched
+pub fn box_sort_field(sort: Option<&str>) -> Result<Option<BoxSortField>> {
+    sort.map(|field| {
+        BoxSortField::parse(field).ok_or_else(|| {
+            AppError::unprocessable(format!(
+                "sort must be one of name, createdAt or updatedAt, got {}",
+                field
+            ))
+        })
+    })
+    .transpose()
+}
+
+// Without a sort field the store's order is kept
+pub fn sort_boxes(boxes: &mut [BoxRecord], sort: Option<BoxSortField>, order: Order) {
+    if let Some(sort) = sort {
+        boxes.sort_by(|a, b| order.apply(sort.compare(a, b)));
+    }
+}
+
 // GET /boxes
 pub async fn get_boxes<S>(
     State(store): State<Arc<S>>,
     Extension(user_id): Extension<String>,
+    list: ListParams,
+    Query(params): Query<ListBoxesParams>,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    // Get boxes from store
-    let boxes = store.get_boxes_by_owner(&user_id).await?;
+    // Reject bad sort options before touching the store
+    let sort = box_sort_field(list.sort.as_deref())?;
+
+    // Without limit/cursor keep returning every box in one response
+    let (boxes, next_cursor) = if !list.is_paginated() {
+        (store.get_boxes_by_owner(&user_id).await?, None)
+    } else {
+        let limit = list.limit.map_or(DEFAULT_PAGE_SIZE, |limit| limit as usize);
+        store
+            .get_boxes_by_owner_paged(&user_id, limit, list.cursor)
+            .await?
+    };
+
+    // Filtering and sorting apply to the boxes fetched, i.e. within a page when paginating
+    let mut boxes: Vec<BoxRecord> = match params.locked {
+        Some(locked) => boxes
+            .into_iter()
+            .filter(|b| b.is_locked == locked)
+            .collect(),
+        None => boxes,
+    };
+    sort_boxes(&mut boxes, sort, list.order);
 
     let my_boxes: Vec<_> = boxes.into_iter().map(BoxResponse::from).collect();
 
-    Ok(Json(serde_json::json!({ "boxes": my_boxes })))
+    Ok(Json(serde_json::json!({
+        "boxes": my_boxes,
+        "nextCursor": next_cursor
+    })))
+}
+
+// GET /boxes/owned/count
+// Badge counts come from a count query, so no boxes are read or deserialized
+pub async fn count_boxes<S>(
+    State(store): State<Arc<S>>,
+    Extension(user_id): Extension<String>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    let count = store.count_boxes_by_owner(&user_id).await?;
+
+    Ok(Json(serde_json::json!({ "count": count })))
+}
+
+// GET /boxes/owned/search?q=
+// DynamoDB can't match substrings without a scan, so the owner's boxes are
+// fetched and filtered here
+pub async fn search_boxes<S>(
+    State(store): State<Arc<S>>,
+    Extension(user_id): Extension<String>,
+    Query(params): Query<SearchBoxesParams>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    let query = params
+        .q
+        .as_deref()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    if query.chars().count() < MIN_SEARCH_QUERY_LENGTH {
+        return Err(AppError::bad_request(format!(
+            "q must be at least {} characters",
+            MIN_SEARCH_QUERY_LENGTH
+        )));
+    }
+
+    let mut matches: Vec<BoxRecord> = store
+        .get_boxes_by_owner(&user_id)
+        .await?
+        .into_iter()
+        .filter(|b| {
+            b.name.to_lowercase().contains(&query) || b.description.to_lowercase().contains(&query)
+        })
+        .collect();
+
+    // Stable order, so the cap always drops the same boxes
+    matches.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+    let truncated = matches.len() > MAX_SEARCH_RESULTS;
+    matches.truncate(MAX_SEARCH_RESULTS);
+
+    let boxes: Vec<_> = matches.into_iter().map(BoxResponse::from).collect();
+
+    Ok(Json(serde_json::json!({
+        "boxes": boxes,
+        "truncated": truncated
+    })))
 }
 
 // GET /boxes/:id
@@ -38,54 +161,162 @@ pub async fn get_box<S>(
     State(store): State<Arc<S>>,
     Path(id): Path<String>,
     Extension(user_id): Extension<String>,
-) -> Result<Json<serde_json::Value>>
+) -> Result<(HeaderMap, Json<serde_json::Value>)>
 where
     S: BoxStore,
 {
     // Get box from store
-    let box_rec = store.get_box(&id).await?;
+    let box_rec = store.get_box_consistent(&id).await?;
 
     // TODO: Is it safe to check here or should we do filter in the db query?
     if box_rec.owner_id != user_id {
-        return Err(AppError::unauthorized(
+        return Err(AppError::forbidden(
             "You don't have permission to view this box".into(),
         ));
     }
 
-    // Return full box info for owner
-    Ok(Json(serde_json::json!({
-        "box": BoxResponse::from(box_rec)
-    })))
+    // Return full box info for owner, tagged with its version for If-Match
+    Ok((
+        etag_headers(box_rec.version),
+        Json(serde_json::json!({
+            "box": BoxResponse::from(box_rec)
+        })),
+    ))
+}
+
+// The box version doubles as its entity tag
+fn etag_headers(version: u64) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    // A quoted integer is always a valid header value
+    let etag = HeaderValue::from_str(&format!("\"{}\"", version)).expect("valid ETag");
+    headers.insert(header::ETAG, etag);
+    headers
+}
+
+// Box version the client expects from If-Match; none when absent or `*`
+fn if_match_version(headers: &HeaderMap) -> Result<Option<u64>> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+    let value = value
+        .to_str()
+        .map_err(|_| AppError::bad_request("If-Match header is not valid text".into()))?
+        .trim();
+    if value == "*" {
+        return Ok(None);
+    }
+
+    // An entity tag that isn't one of ours can never match
+    value
+        .trim_start_matches("W/")
+        .trim_matches('"')
+        .parse::<u64>()
+        .map(Some)
+        .map_err(|_| {
+            AppError::precondition_failed(format!(
+                "If-Match {} does not match the current box version",
+                value
+            ))
+        })
+}
+
+// POST /boxes/owned/batch-get
+pub async fn batch_get_boxes<S>(
+    State(store): State<Arc<S>>,
+    Extension(user_id): Extension<String>,
+    JsonBody(payload): JsonBody<BatchGetBoxesRequest>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    if payload.ids.len() > MAX_BATCH_GET_IDS {
+        return Err(AppError::unprocessable(format!(
+            "At most {} ids can be fetched at once",
+            MAX_BATCH_GET_IDS
+        )));
+    }
+
+    let boxes = store.get_boxes_by_ids(&payload.ids).await?;
+
+    // Ids the caller doesn't own are dropped rather than failing the whole batch
+    let boxes: Vec<BoxResponse> = boxes
+        .into_iter()
+        .filter(|b| b.owner_id == user_id)
+        .map(BoxResponse::from)
+        .collect();
+
+    Ok(Json(serde_json::json!({ "boxes": boxes })))
 }
 
 // POST /boxes
 pub async fn create_box<S>(
     State(store): State<Arc<S>>,
     Extension(user_id): Extension<String>,
-    Json(payload): Json<CreateBoxRequest>,
+    user_name: Option<Extension<UserName>>,
+    idempotency: Option<Extension<Arc<dyn IdempotencyStore>>>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<CreateBoxRequest>,
 ) -> Result<(StatusCode, Json<serde_json::Value>)>
 where
     S: BoxStore,
 {
+    payload.validate().map_err(AppError::FieldValidation)?;
+    let key = idempotency_key(&headers).map_err(AppError::bad_request)?;
+
+    // A retry with the same Idempotency-Key gets the box the first request created
+    let box_id = Uuid::new_v4().to_string();
+    let claim = claim_idempotency_key(
+        idempotency.as_deref(),
+        key.as_deref(),
+        "create_box",
+        &user_id,
+        &box_id,
+    )
+    .await?;
+    if let IdempotencyClaim::Replay(existing_id) = claim {
+        // Until the first request has written the box there's nothing to replay
+        let existing_box = match store.get_box_consistent(&existing_id).await {
+            Err(StoreError::NotFound(_)) => {
+                return Err(AppError::conflict(REQUEST_IN_PROGRESS.into()))
+            }
+            result => result?,
+        };
+        return Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "box": BoxResponse::from(existing_box) })),
+        ));
+    }
+
     let now = now_str();
     let new_box = BoxRecord {
-        id: Uuid::new_v4().to_string(),
+        id: box_id,
         name: payload.name,
         description: payload.description,
         is_locked: false,
         created_at: now.clone(),
         updated_at: now.clone(),
         owner_id: user_id,
-        owner_name: None,
+        owner_name: user_name.map(|Extension(UserName(name))| name),
         documents: vec![],
         guardians: vec![],
         unlock_instructions: None,
-        unlock_request: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
         version: 0,
     };
 
-    // Create the box in store
-    let created_box = store.create_box(new_box).await?;
+    // Create the box in store, freeing the key again if that fails
+    let created_box = match store.create_box(new_box).await {
+        Ok(created_box) => created_box,
+        Err(e) => {
+            if let IdempotencyClaim::Claimed(claimed) = claim {
+                claimed.release().await;
+            }
+            return Err(e.into());
+        }
+    };
 
     Ok((
         StatusCode::CREATED,
@@ -98,21 +329,35 @@ pub async fn update_box<S>(
     State(store): State<Arc<S>>,
     Path(id): Path<String>,
     Extension(user_id): Extension<String>,
-    Json(payload): Json<UpdateBoxRequest>,
-) -> Result<Json<serde_json::Value>>
+    Query(params): Query<UpdateBoxParams>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<UpdateBoxRequest>,
+) -> Result<(HeaderMap, Json<serde_json::Value>)>
 where
     S: BoxStore,
 {
+    let expected_version = if_match_version(&headers)?;
+
     // Get the current box from store
-    let mut box_rec = store.get_box(&id).await?;
+    let mut box_rec = store.get_box_consistent(&id).await?;
 
     // Check if the user is the owner
     if box_rec.owner_id != user_id {
-        return Err(AppError::unauthorized(
+        return Err(AppError::forbidden(
             "You don't have permission to update this box".into(),
         ));
     }
 
+    // Without If-Match the last write wins, as before
+    if let Some(expected) = expected_version {
+        if expected != box_rec.version {
+            return Err(AppError::precondition_failed(format!(
+                "Box {} is at version {}, not {}",
+                id, box_rec.version, expected
+            )));
+        }
+    }
+
     // Update fields if provided
     if let Some(name) = payload.name {
         box_rec.name = name;
@@ -132,16 +377,88 @@ where
     }
 
     if let Some(is_locked) = payload.is_locked {
+        // Locking through PATCH follows the same rules as the lock action
+        if is_locked && !box_rec.is_locked {
+            ensure_lockable(&box_rec)?;
+        }
         box_rec.is_locked = is_locked;
     }
 
+    // Clearing requiredApprovals falls back to a majority of accepted guardians
+    if let Some(field) = &payload.required_approvals {
+        match field {
+            OptionalField::Value(0) => {
+                return Err(AppError::bad_request(
+                    "requiredApprovals must be at least 1".into(),
+                ))
+            }
+            OptionalField::Value(val) => box_rec.required_approvals = Some(*val),
+            OptionalField::Null => box_rec.required_approvals = None,
+        }
+    }
+
+    if let Some(policy) = &payload.unlock_policy {
+        match policy {
+            UnlockPolicy::Threshold(0) => {
+                return Err(AppError::bad_request(
+                    "unlockPolicy threshold must be at least 1".into(),
+                ))
+            }
+            // More approvals than there are guardians to give them could never be met
+            UnlockPolicy::Threshold(required)
+                if *required as usize > box_rec.accepted_guardian_count() =>
+            {
+                return Err(AppError::unprocessable(format!(
+                    "unlockPolicy threshold {} is more than the box's {} accepted guardians",
+                    required,
+                    box_rec.accepted_guardian_count()
+                )));
+            }
+            // Only accepted leads can vote, so an invited one can't meet the policy yet
+            UnlockPolicy::AllLeads | UnlockPolicy::AnyLead if !has_accepted_lead(&box_rec) => {
+                return Err(AppError::unprocessable(
+                    "A lead guardian unlock policy needs a lead guardian who has accepted their invitation".into(),
+                ));
+            }
+            _ => {}
+        }
+        box_rec.unlock_policy = policy.clone();
+    }
+
+    // requiredApprovals is only read by the majority policy, so setting it alongside
+    // another policy would be silently ignored
+    if matches!(payload.required_approvals, Some(OptionalField::Value(_)))
+        && box_rec.unlock_policy != UnlockPolicy::Majority
+    {
+        return Err(AppError::unprocessable(
+            "requiredApprovals only applies to the majority unlock policy; use a threshold policy instead".into(),
+        ));
+    }
+
     box_rec.updated_at = now_str();
 
-    // Save the updated box
-    let updated_box = store.update_box(box_rec).await?;
+    // Everything has been checked; a dry run stops short of the write
+    if params.dry_run {
+        return Ok((
+            etag_headers(box_rec.version),
+            Json(serde_json::json!({
+                "box": BoxResponse::from(box_rec),
+                "dryRun": true
+            })),
+        ));
+    }
 
-    Ok(Json(
-        serde_json::json!({ "box": BoxResponse::from(updated_box) }),
+    // A write that lost a race is stale from the client's point of view too
+    let updated_box = store.update_box(box_rec).await.map_err(|err| match err {
+        StoreError::VersionConflict(msg) if expected_version.is_some() => {
+            AppError::precondition_failed(msg)
+        }
+        err => err.into(),
+    })?;
+
+    Ok((
+        etag_headers(updated_box.version),
+        Json(serde_json::json!({ "box": BoxResponse::from(updated_box) })),
     ))
 }
 
@@ -155,11 +472,11 @@ where
     S: BoxStore,
 {
     // Get the box to check ownership
-    let box_rec = store.get_box(&id).await?;
+    let box_rec = store.get_box_consistent(&id).await?;
 
     // Check if the user is the owner
     if box_rec.owner_id != user_id {
-        return Err(AppError::unauthorized(
+        return Err(AppError::forbidden(
             "You don't have permission to delete this box".into(),
         ));
     }
@@ -172,6 +489,158 @@ where
     ))
 }
 
+// POST /boxes/owned/:id/restore
+pub async fn restore_box<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    // Only soft-deleted boxes can be restored
+    let box_rec = store.get_deleted_box(&id).await?;
+
+    if box_rec.owner_id != user_id {
+        return Err(AppError::forbidden(
+            "You don't have permission to restore this box".into(),
+        ));
+    }
+
+    let restored_box = store.restore_box(&id).await?;
+
+    Ok(Json(
+        serde_json::json!({ "box": BoxResponse::from(restored_box) }),
+    ))
+}
+
+// Appended to the name of a cloned box
+const CLONE_NAME_SUFFIX: &str = " (copy)";
+
+// POST /boxes/owned/:id/clone
+// Starts a new box from an existing one: same metadata and unlock settings, but
+// no guardians or documents. Guardians are only ever added through an invitation,
+// so the owner invites them to the clone afresh.
+pub async fn clone_box<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+    user_name: Option<Extension<UserName>>,
+) -> Result<(StatusCode, Json<serde_json::Value>)>
+where
+    S: BoxStore,
+{
+    let source = store.get_box_consistent(&id).await?;
+
+    if source.owner_id != user_id {
+        return Err(AppError::forbidden(
+            "You don't have permission to clone this box".into(),
+        ));
+    }
+
+    // Shorten long names so the suffix still fits within the name limit
+    let kept = MAX_BOX_NAME_LENGTH - CLONE_NAME_SUFFIX.chars().count();
+    let name: String = source.name.chars().take(kept).collect();
+
+    let now = now_str();
+    let new_box = BoxRecord {
+        id: Uuid::new_v4().to_string(),
+        name: format!("{}{}", name, CLONE_NAME_SUFFIX),
+        description: source.description,
+        is_locked: false,
+        created_at: now.clone(),
+        updated_at: now,
+        owner_id: user_id,
+        owner_name: user_name.map(|Extension(UserName(name))| name),
+        documents: vec![],
+        guardians: vec![],
+        unlock_instructions: source.unlock_instructions,
+        unlock_requests: vec![],
+        required_approvals: source.required_approvals,
+        unlock_policy: source.unlock_policy,
+        deleted_at: None,
+        version: 0,
+    };
+
+    let created_box = store.create_box(new_box).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({ "box": BoxResponse::from(created_box) })),
+    ))
+}
+
+// POST /boxes/owned/:id/lock
+// Locks the box once someone can unlock it again: an accepted lead guardian
+// to request the unlock and instructions for the guardians to follow
+pub async fn lock_box<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    let locked_box =
+        update_box_with_retry(store.as_ref(), &id, DEFAULT_UPDATE_ATTEMPTS, |box_rec| {
+            if box_rec.owner_id != user_id {
+                return Err(AppError::forbidden(
+                    "You don't have permission to lock this box".into(),
+                ));
+            }
+
+            ensure_lockable(box_rec)?;
+
+            box_rec.is_locked = true;
+            box_rec.updated_at = now_str();
+            Ok(())
+        })
+        .await?;
+
+    Ok(Json(
+        serde_json::json!({ "box": BoxResponse::from(locked_box) }),
+    ))
+}
+
+// POST /boxes/owned/:id/unlock
+// The owner can always unlock; open unlock requests are cancelled since
+// there is nothing left for the guardians to approve
+pub async fn unlock_box<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    let unlocked_box =
+        update_box_with_retry(store.as_ref(), &id, DEFAULT_UPDATE_ATTEMPTS, |box_rec| {
+            if box_rec.owner_id != user_id {
+                return Err(AppError::forbidden(
+                    "You don't have permission to unlock this box".into(),
+                ));
+            }
+
+            // Decided requests stay as the box's history
+            for unlock in box_rec
+                .unlock_requests
+                .iter_mut()
+                .filter(|unlock| unlock.status == UnlockRequestStatus::Requested)
+            {
+                unlock.status = UnlockRequestStatus::Cancelled;
+            }
+
+            box_rec.is_locked = false;
+            box_rec.updated_at = now_str();
+            Ok(())
+        })
+        .await?;
+
+    Ok(Json(
+        serde_json::json!({ "box": BoxResponse::from(unlocked_box) }),
+    ))
+}
+
 // Helper function to update a guardian in a box
 // Returns (updated_box, was_guardian_updated)
 async fn update_or_add_guardian<S>(
@@ -183,35 +652,33 @@ async fn update_or_add_guardian<S>(
 where
     S: BoxStore,
 {
-    // Get the current box from store
-    let mut box_rec = store.get_box(box_id).await?;
-
-    // Check if the user is the owner
-    if box_rec.owner_id != owner_id {
-        return Err(AppError::unauthorized(
-            "You don't have permission to update this box".into(),
-        ));
-    }
-
-    // Check if the guardian already exists in the box
-    let guardian_index = box_rec.guardians.iter().position(|g| g.id == guardian.id);
-
-    let was_updated = if let Some(index) = guardian_index {
-        // Update existing guardian
-        box_rec.guardians[index] = guardian.clone();
-        true
-    } else {
-        // Add new guardian
-        box_rec.guardians.push(guardian.clone());
-        true
-    };
-
-    box_rec.updated_at = now_str();
+    let updated_box = update_box_with_retry(store, box_id, DEFAULT_UPDATE_ATTEMPTS, |box_rec| {
+        // Check if the user is the owner
+        if box_rec.owner_id != owner_id {
+            return Err(AppError::forbidden(
+                "You don't have permission to update this box".into(),
+            ));
+        }
 
-    // Save the updated box
-    let updated_box = store.update_box(box_rec).await?;
+        // Update the guardian if it already exists in the box, otherwise add it
+        let mut guardian = guardian.clone();
+        guardian.normalize()?;
+        let previous = box_rec.guardians.clone();
+        let index = box_rec.guardians.iter().position(|g| g.id == guardian.id);
+        ensure_email_free(&box_rec.guardians, index, &guardian)?;
+        match index {
+            Some(index) => box_rec.guardians[index] = guardian,
+            None => {
+                box_rec.guardians.push(guardian);
+                ensure_guardian_limit(box_rec, box_rec.guardians.len() - 1)?;
+            }
+        }
+        carry_over_views(&mut box_rec.guardians, &previous);
+        Ok(())
+    })
+    .await?;
 
-    Ok((updated_box, was_updated))
+    Ok((updated_box, true))
 }
 
 // PATCH /boxes/owned/:id/guardian
@@ -220,20 +687,21 @@ pub async fn update_guardian<S>(
     State(store): State<Arc<S>>,
     Path(box_id): Path<String>,
     Extension(user_id): Extension<String>,
-    Json(payload): Json<GuardianUpdateRequest>,
+    JsonBody(payload): JsonBody<GuardianUpdateRequest>,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
+    let guardian = Guardian::from(payload.guardian);
+
     // Let the helper function do the work
-    let (updated_box, _) =
-        update_or_add_guardian(&*store, &box_id, &user_id, &payload.guardian).await?;
+    let (updated_box, _) = update_or_add_guardian(&*store, &box_id, &user_id, &guardian).await?;
 
     // Find the updated guardian in the updated box
     let updated_guardian = updated_box
         .guardians
         .iter()
-        .find(|g| g.id == payload.guardian.id)
+        .find(|g| g.id == guardian.id)
         .ok_or_else(|| {
             AppError::internal_server_error("Updated guardian not found in response".into())
         })?;
@@ -246,6 +714,314 @@ where
         lead_guardian: updated_guardian.lead_guardian,
         added_at: updated_guardian.added_at.clone(),
         invitation_id: updated_guardian.invitation_id.clone(),
+        email: updated_guardian.email.clone(),
+        relationship: updated_guardian.relationship.clone(),
+        note: updated_guardian.note.clone(),
+        all_guardians: updated_box.guardians.clone(),
+        updated_at: updated_box.updated_at.clone(),
+    };
+
+    Ok(Json(serde_json::json!({ "guardian": response })))
+}
+
+// PUT /boxes/owned/:id/guardians
+// Sets several guardians in a single update, matching existing ones by id or invitation id
+pub async fn update_guardians<S>(
+    State(store): State<Arc<S>>,
+    Path(box_id): Path<String>,
+    Extension(user_id): Extension<String>,
+    JsonBody(payload): JsonBody<GuardiansUpdateRequest>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    payload.validate().map_err(AppError::FieldValidation)?;
+
+    let updated_box = update_box_with_retry(
+        store.as_ref(),
+        &box_id,
+        DEFAULT_UPDATE_ATTEMPTS,
+        |box_rec| {
+            if box_rec.owner_id != user_id {
+                return Err(AppError::forbidden(
+                    "You don't have permission to update this box".into(),
+                ));
+            }
+
+            let had_lead = has_active_lead(box_rec);
+            let previous = box_rec.guardians.clone();
+            let previous_count = previous.len();
+            if payload.replace {
+                box_rec.guardians.clear();
+            }
+            for guardian in &payload.guardians {
+                merge_guardian(&mut box_rec.guardians, guardian.clone().into())?;
+            }
+            carry_over_views(&mut box_rec.guardians, &previous);
+
+            validate_merged_guardians(&box_rec.guardians, &previous)
+                .map_err(AppError::FieldValidation)?;
+            ensure_guardian_limit(box_rec, previous_count)?;
+            ensure_lead_remains(box_rec, had_lead)
+        },
+    )
+    .await?;
+
+    let response = GuardiansUpdateResponse {
+        guardians: updated_box.guardians,
+        updated_at: updated_box.updated_at,
+    };
+
+    Ok(Json(serde_json::json!(response)))
+}
+
+// Adds the guardian, or overwrites the entry with the same id or invitation
+fn merge_guardian(guardians: &mut Vec<Guardian>, mut guardian: Guardian) -> Result<()> {
+    guardian.normalize()?;
+    let index = guardians.iter().position(|g| g.is_same_guardian(&guardian));
+    ensure_email_free(guardians, index, &guardian)?;
+    match index {
+        Some(index) => guardians[index] = guardian,
+        None => guardians.push(guardian),
+    }
+    Ok(())
+}
+
+// The payload is validated on its own up front; this checks the list it leaves
+// on the box. Boxes that already had several leads keep them, but an update
+// can't add another.
+fn validate_merged_guardians(
+    guardians: &[Guardian],
+    previous: &[Guardian],
+) -> std::result::Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let mut seen_ids = std::collections::HashSet::new();
+    let mut seen_invitations = std::collections::HashSet::new();
+
+    for guardian in guardians {
+        if !seen_ids.insert(guardian.id.as_str()) {
+            errors.push(FieldError::new(
+                "guardians",
+                &format!("guardian {} would appear more than once", guardian.id),
+            ));
+        }
+        if !seen_invitations.insert(guardian.invitation_id.as_str()) {
+            errors.push(FieldError::new(
+                "guardians",
+                &format!(
+                    "invitation {} would belong to more than one guardian",
+                    guardian.invitation_id
+                ),
+            ));
+        }
+    }
+
+    let leads = guardians.iter().filter(|g| g.lead_guardian).count();
+    let previous_leads = previous.iter().filter(|g| g.lead_guardian).count();
+    if leads > 1 && leads > previous_leads {
+        errors.push(FieldError::new(
+            "guardians",
+            "at most one guardian can be the lead guardian",
+        ));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+// An email identifies one guardian per box; `index` is the entry being replaced, if any
+fn ensure_email_free(
+    guardians: &[Guardian],
+    index: Option<usize>,
+    guardian: &Guardian,
+) -> Result<()> {
+    if guardians
+        .iter()
+        .enumerate()
+        .any(|(i, g)| Some(i) != index && g.has_same_email(guardian))
+    {
+        return Err(AppError::conflict(
+            "Another guardian in this box already uses that email".into(),
+        ));
+    }
+    Ok(())
+}
+
+// lastViewedAt is recorded by the service, so client updates neither set nor
+// clear it: each guardian keeps whatever was stored for the same user
+fn carry_over_views(guardians: &mut [Guardian], previous: &[Guardian]) {
+    for guardian in guardians {
+        guardian.last_viewed_at = previous
+            .iter()
+            .find(|p| p.id == guardian.id)
+            .and_then(|p| p.last_viewed_at.clone());
+    }
+}
+
+// Rejects updates that grow the guardian list past the cap; boxes already over
+// it (from before the cap or a lowered limit) can still be edited or trimmed
+fn ensure_guardian_limit(box_rec: &BoxRecord, previous_count: usize) -> Result<()> {
+    let max_guardians = max_guardians_per_box();
+    let count = box_rec.guardians.len();
+    if count > max_guardians && count > previous_count {
+        return Err(AppError::unprocessable(format!(
+            "A box can have at most {} guardians",
+            max_guardians
+        )));
+    }
+    Ok(())
+}
+
+// Without an accepted lead nobody could request an unlock, and without
+// instructions the guardians wouldn't know what to do once it is granted
+fn ensure_lockable(box_rec: &BoxRecord) -> Result<()> {
+    if !has_accepted_lead(box_rec) {
+        return Err(AppError::conflict(
+            "Locking needs a lead guardian who has accepted their invitation".into(),
+        ));
+    }
+
+    let has_instructions = box_rec
+        .unlock_instructions
+        .as_deref()
+        .is_some_and(|instructions| !instructions.trim().is_empty());
+    if !has_instructions {
+        return Err(AppError::conflict(
+            "Locking needs unlock instructions for the guardians".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn has_accepted_lead(box_rec: &BoxRecord) -> bool {
+    box_rec
+        .guardians
+        .iter()
+        .any(|g| g.lead_guardian && g.status == GuardianStatus::Accepted)
+}
+
+fn has_active_lead(box_rec: &BoxRecord) -> bool {
+    !box_rec.lead_guardian_ids().is_empty()
+}
+
+// A locked box needs a lead guardian, otherwise nobody can ever request an unlock
+fn ensure_lead_remains(box_rec: &BoxRecord, had_lead: bool) -> Result<()> {
+    if box_rec.is_locked && had_lead && !has_active_lead(box_rec) {
+        return Err(AppError::conflict(
+            "Cannot remove the last lead guardian of a locked box; promote another guardian to lead first".into(),
+        ));
+    }
+    Ok(())
+}
+
+// PATCH /boxes/owned/:id/guardian/:guardian_id
+// Merges the given fields onto an existing guardian instead of replacing it
+pub async fn patch_guardian<S>(
+    State(store): State<Arc<S>>,
+    Path((box_id, guardian_id)): Path<(String, String)>,
+    Extension(user_id): Extension<String>,
+    JsonBody(payload): JsonBody<GuardianPatchRequest>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    let updated_box = update_box_with_retry(
+        store.as_ref(),
+        &box_id,
+        DEFAULT_UPDATE_ATTEMPTS,
+        |box_rec| {
+            if box_rec.owner_id != user_id {
+                return Err(AppError::forbidden(
+                    "You don't have permission to update this box".into(),
+                ));
+            }
+
+            let had_lead = has_active_lead(box_rec);
+            let index = box_rec
+                .guardians
+                .iter()
+                .position(|g| g.id == guardian_id)
+                .ok_or_else(|| {
+                    AppError::not_found(format!(
+                        "Guardian with ID {} not found in box {}",
+                        guardian_id, box_id
+                    ))
+                })?;
+            let guardian = &mut box_rec.guardians[index];
+
+            // None of the guardian fields are nullable, so an explicit null is a client error
+            match &payload.name {
+                Some(OptionalField::Value(name)) => guardian.name = name.clone(),
+                Some(OptionalField::Null) => {
+                    return Err(AppError::bad_request("name cannot be null".into()))
+                }
+                None => {}
+            }
+            match &payload.lead_guardian {
+                Some(OptionalField::Value(lead)) => guardian.lead_guardian = *lead,
+                Some(OptionalField::Null) => {
+                    return Err(AppError::bad_request("leadGuardian cannot be null".into()))
+                }
+                None => {}
+            }
+            match &payload.status {
+                Some(OptionalField::Value(status)) => guardian.status = status.clone(),
+                Some(OptionalField::Null) => {
+                    return Err(AppError::bad_request("status cannot be null".into()))
+                }
+                None => {}
+            }
+            // Email is the one optional field, so null clears it
+            match &payload.email {
+                Some(OptionalField::Value(email)) => guardian.email = Some(email.clone()),
+                Some(OptionalField::Null) => guardian.email = None,
+                None => {}
+            }
+            guardian.normalize_email();
+            // The owner's annotations are optional too, so null clears them
+            match &payload.relationship {
+                Some(OptionalField::Value(relationship)) => {
+                    guardian.relationship = Some(relationship.clone())
+                }
+                Some(OptionalField::Null) => guardian.relationship = None,
+                None => {}
+            }
+            match &payload.note {
+                Some(OptionalField::Value(note)) => guardian.note = Some(note.clone()),
+                Some(OptionalField::Null) => guardian.note = None,
+                None => {}
+            }
+            guardian.normalize_annotations();
+
+            let patched = guardian.clone();
+            ensure_email_free(&box_rec.guardians, Some(index), &patched)?;
+
+            ensure_lead_remains(box_rec, had_lead)
+        },
+    )
+    .await?;
+
+    let updated_guardian = updated_box
+        .guardians
+        .iter()
+        .find(|g| g.id == guardian_id)
+        .ok_or_else(|| {
+            AppError::internal_server_error("Updated guardian not found in response".into())
+        })?;
+
+    let response = GuardianUpdateResponse {
+        id: updated_guardian.id.clone(),
+        name: updated_guardian.name.clone(),
+        status: updated_guardian.status.to_string(),
+        lead_guardian: updated_guardian.lead_guardian,
+        added_at: updated_guardian.added_at.clone(),
+        invitation_id: updated_guardian.invitation_id.clone(),
+        email: updated_guardian.email.clone(),
+        relationship: updated_guardian.relationship.clone(),
+        note: updated_guardian.note.clone(),
         all_guardians: updated_box.guardians.clone(),
         updated_at: updated_box.updated_at.clone(),
     };
@@ -265,28 +1041,77 @@ where
     S: BoxStore,
 {
     // Get the current box from store
-    let mut box_rec = store.get_box(box_id).await?;
+    let mut box_rec = store.get_box_consistent(box_id).await?;
 
     // Check if the user is the owner
     if box_rec.owner_id != owner_id {
-        return Err(AppError::unauthorized(
+        return Err(AppError::forbidden(
             "You don't have permission to update this box".into(),
         ));
     }
 
+    // Content types are matched case-insensitively and stored lowercased
+    let content_type = document.content_type.trim().to_lowercase();
+    if !ALLOWED_DOCUMENT_CONTENT_TYPES.contains(&content_type.as_str()) {
+        return Err(AppError::unprocessable(format!(
+            "contentType must be one of {}, got {}",
+            ALLOWED_DOCUMENT_CONTENT_TYPES.join(", "),
+            document.content_type
+        )));
+    }
+
+    // S3 documents may only point at objects belonging to this box
+    if document.storage == DocumentStorage::S3 && !is_box_document_key(box_id, &document.content) {
+        return Err(AppError::unprocessable(format!(
+            "S3 document content must be an object key under {}",
+            document_key_prefix(box_id)
+        )));
+    }
+
+    let max_bytes = max_document_bytes();
+    if document.content.len() > max_bytes {
+        return Err(AppError::unprocessable(format!(
+            "Document content is {} bytes, which exceeds the limit of {} bytes",
+            document.content.len(),
+            max_bytes
+        )));
+    }
+
     // Check if the document already exists in the box
     let document_index = box_rec.documents.iter().position(|d| d.id == document.id);
 
+    let max_documents = max_documents_per_box();
+    if document_index.is_none() && box_rec.documents.len() >= max_documents {
+        return Err(AppError::unprocessable(format!(
+            "Box already holds the maximum of {} documents",
+            max_documents
+        )));
+    }
+
+    // Client-supplied timestamps are stored in UTC
+    let mut document = document.clone();
+    document.created_at = normalize_rfc3339(&document.created_at)?;
+    document.content_type = content_type;
+
     let was_updated = if let Some(index) = document_index {
         // Update existing document
-        box_rec.documents[index] = document.clone();
+        box_rec.documents[index] = document;
         true
     } else {
         // Add new document
-        box_rec.documents.push(document.clone());
+        box_rec.documents.push(document);
         true
     };
 
+    // Fail clearly here rather than with an opaque DynamoDB item size error
+    let box_bytes = serde_json::to_vec(&box_rec)?.len();
+    if box_bytes > MAX_BOX_ITEM_BYTES {
+        return Err(AppError::payload_too_large(format!(
+            "Box would be {} bytes after this change, over the {} byte limit",
+            box_bytes, MAX_BOX_ITEM_BYTES
+        )));
+    }
+
     box_rec.updated_at = now_str();
 
     // Save the updated box
@@ -301,14 +1126,14 @@ pub async fn update_document<S>(
     State(store): State<Arc<S>>,
     Path(box_id): Path<String>,
     Extension(user_id): Extension<String>,
-    Json(payload): Json<DocumentUpdateRequest>,
+    JsonBody(payload): JsonBody<DocumentUpdateRequest>,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
     // Let the helper function do the work
-    let (updated_box, _) =
-        update_or_add_document(&*store, &box_id, &user_id, &payload.document).await?;
+    let document = Document::from(payload.document);
+    let (updated_box, _) = update_or_add_document(&*store, &box_id, &user_id, &document).await?;
 
     // Create a specialized response with all documents
     let response = DocumentUpdateResponse {
@@ -331,11 +1156,11 @@ where
     S: BoxStore,
 {
     // Get the current box from store
-    let mut box_rec = store.get_box(box_id).await?;
+    let mut box_rec = store.get_box_consistent(box_id).await?;
 
     // Check if the user is the owner
     if box_rec.owner_id != owner_id {
-        return Err(AppError::unauthorized(
+        return Err(AppError::forbidden(
             "You don't have permission to delete documents from this box".into(),
         ));
     }
@@ -386,46 +1211,103 @@ where
     })))
 }
 
-// Helper function to delete a guardian from a box
-// Returns updated box after deletion
-async fn delete_guardian_from_box<S>(
-    store: &S,
-    box_id: &str,
-    owner_id: &str,
-    guardian_id: &str,
-) -> Result<BoxRecord>
+// GET /boxes/owned/:id/document/:document_id/url
+// Short-lived download URL for a document stored in S3
+pub async fn get_document_url<S>(
+    State(store): State<Arc<S>>,
+    Path((box_id, document_id)): Path<(String, String)>,
+    Extension(user_id): Extension<String>,
+    presigner: Option<Extension<Arc<dyn DocumentPresigner>>>,
+) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    // Get the current box from store
-    let mut box_rec = store.get_box(box_id).await?;
+    let box_rec = store.get_box(&box_id).await?;
 
-    // Check if the user is the owner
-    if box_rec.owner_id != owner_id {
-        return Err(AppError::unauthorized(
-            "You don't have permission to delete guardians from this box".into(),
+    if box_rec.owner_id != user_id {
+        return Err(AppError::forbidden(
+            "You don't have permission to access documents in this box".into(),
         ));
     }
 
-    // Check if the guardian exists in the box
-    let guardian_index = box_rec.guardians.iter().position(|g| g.id == guardian_id);
+    let document = box_rec
+        .documents
+        .iter()
+        .find(|d| d.id == document_id)
+        .ok_or_else(|| {
+            AppError::not_found(format!(
+                "Document with ID {} not found in box {}",
+                document_id, box_id
+            ))
+        })?;
 
-    // Return not found if guardian doesn't exist
-    if guardian_index.is_none() {
-        return Err(AppError::not_found(format!(
-            "Guardian with ID {} not found in box {}",
-            guardian_id, box_id
+    if document.storage != DocumentStorage::S3 {
+        return Err(AppError::conflict(format!(
+            "Document {} is stored inline and has no download URL",
+            document_id
         )));
     }
 
-    // Remove the guardian
-    box_rec.guardians.remove(guardian_index.unwrap());
-    box_rec.updated_at = now_str();
+    // Documents stored before keys were checked could point anywhere in the bucket
+    if !is_box_document_key(&box_id, &document.content) {
+        return Err(AppError::forbidden(format!(
+            "Document {} points outside this box's storage",
+            document_id
+        )));
+    }
 
-    // Save the updated box
-    let updated_box = store.update_box(box_rec).await?;
+    let Some(Extension(presigner)) = presigner else {
+        return Err(AppError::ServiceUnavailable(
+            "Document downloads are not configured".into(),
+        ));
+    };
 
-    Ok(updated_box)
+    let expires_in = document_url_ttl();
+    let url = presigner.presign_get(&document.content, expires_in).await?;
+
+    Ok(Json(serde_json::json!({
+        "url": url,
+        "expiresIn": expires_in.as_secs(),
+    })))
+}
+
+// Helper function to delete a guardian from a box
+// Returns updated box after deletion
+async fn delete_guardian_from_box<S>(
+    store: &S,
+    box_id: &str,
+    owner_id: &str,
+    guardian_id: &str,
+) -> Result<BoxRecord>
+where
+    S: BoxStore,
+{
+    update_box_with_retry(store, box_id, DEFAULT_UPDATE_ATTEMPTS, |box_rec| {
+        // Check if the user is the owner
+        if box_rec.owner_id != owner_id {
+            return Err(AppError::forbidden(
+                "You don't have permission to delete guardians from this box".into(),
+            ));
+        }
+
+        // Return not found if guardian doesn't exist
+        let guardian_index = box_rec
+            .guardians
+            .iter()
+            .position(|g| g.id == guardian_id)
+            .ok_or_else(|| {
+                AppError::not_found(format!(
+                    "Guardian with ID {} not found in box {}",
+                    guardian_id, box_id
+                ))
+            })?;
+
+        // Remove the guardian
+        let had_lead = has_active_lead(box_rec);
+        box_rec.guardians.remove(guardian_index);
+        ensure_lead_remains(box_rec, had_lead)
+    })
+    .await
 }
 
 // DELETE /boxes/owned/:id/guardian/:guardian_id
@@ -439,7 +1321,7 @@ where
     S: BoxStore,
 {
     // Get the guardian details before deletion
-    let box_rec_before = store.get_box(&box_id).await?;
+    let box_rec_before = store.get_box_consistent(&box_id).await?;
     let guardian_before = box_rec_before
         .guardians
         .iter()
@@ -458,6 +1340,9 @@ where
         lead_guardian: guardian_before.lead_guardian,
         added_at: guardian_before.added_at,
         invitation_id: guardian_before.invitation_id,
+        email: guardian_before.email,
+        relationship: guardian_before.relationship,
+        note: guardian_before.note,
         all_guardians: updated_box.guardians,
         updated_at: updated_box.updated_at,
     };
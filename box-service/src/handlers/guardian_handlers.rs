@@ -2,38 +2,59 @@ use axum::{
     extract::{Extension, Path, State},
     Json,
 };
-use log::{debug, trace, warn};
+use chrono::{Duration, Utc};
+use log::{debug, error, trace, warn};
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::{
     error::{AppError, Result},
+    events::publish_invitation_event,
+    handlers::box_handlers::{box_sort_field, sort_boxes},
     models::{
-        now_str, GuardianInvitationResponse, GuardianResponseRequest, LeadGuardianUpdateRequest,
+        CancelUnlockRequest, GuardianInvitationResponse, GuardianResponseRequest,
+        InviteCodeResponseRequest, LeadGuardianUpdateRequest, DEFAULT_PAGE_SIZE,
     },
 };
 
 use lockbox_shared::{
-    models::{GuardianStatus, UnlockRequest, UnlockRequestStatus},
-    store::{convert_to_guardian_box, BoxStore},
+    extract::{JsonBody, ListParams},
+    models::{
+        now_str, unlock_request_expires_at, unlock_request_ttl_hours, BoxRecord, GuardianStatus,
+        InvitationStatus, UnlockRequest, UnlockRequestStatus,
+    },
+    store::{
+        convert_to_guardian_box, update_box_with_retry, BoxStore, InvitationStore,
+        DEFAULT_UPDATE_ATTEMPTS,
+    },
 };
 
 // GET /guardianBoxes
 pub async fn get_guardian_boxes<S>(
     State(store): State<Arc<S>>,
     Extension(user_id): Extension<String>,
+    list: ListParams,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    // TODO: For now, we'd need to fetch all boxes and filter on the guardian
-    // In a real app, we'd want to add a secondary index in DynamoDB for guardian lookups
-
-    // This is a simplified approach - in production, you would want pagination or a GSI
-    let guardian_boxes = store
-        .get_boxes_by_guardian_id(&user_id)
-        .await
-        .unwrap_or_default();
+    let sort = box_sort_field(list.sort.as_deref())?;
+
+    // Without limit/cursor keep returning every box in one response
+    let (mut guardian_boxes, next_cursor) = if !list.is_paginated() {
+        let boxes = store
+            .get_boxes_by_guardian_id(&user_id)
+            .await
+            .unwrap_or_default();
+        (boxes, None)
+    } else {
+        let limit = list.limit.map_or(DEFAULT_PAGE_SIZE, |limit| limit as usize);
+        store
+            .get_boxes_by_guardian_id_paged(&user_id, limit, list.cursor)
+            .await?
+    };
+    // As on /boxes/owned, sorting applies within a page when paginating
+    sort_boxes(&mut guardian_boxes, sort, list.order);
 
     // Convert BoxRecords to GuardianBox format
     let guardian_boxes: Vec<_> = guardian_boxes
@@ -42,7 +63,10 @@ where
         .map(crate::models::GuardianBoxResponse::from)
         .collect();
 
-    Ok(Json(serde_json::json!({ "boxes": guardian_boxes })))
+    Ok(Json(serde_json::json!({
+        "boxes": guardian_boxes,
+        "nextCursor": next_cursor
+    })))
 }
 
 // GET /guardianBoxes/:id
@@ -56,7 +80,7 @@ where
 {
     trace!("Fetching guardian box with id: {}", id);
     // Fetch the box from store
-    let box_rec = store.get_box(&id).await?;
+    let box_rec = store.get_box_consistent(&id).await?;
     debug!(
         "Fetched box record for guardian: box_id={}, box_rec={:?}",
         id, box_rec
@@ -64,14 +88,70 @@ where
 
     // TODO: query DB with filters instead
     if let Some(guardian_box) = convert_to_guardian_box(&box_rec, &user_id) {
+        // Recording the view is best effort; a failed write shouldn't hide the box
+        let guardian_box = if needs_view_record(&box_rec, &user_id) {
+            match store.record_guardian_view(&id, &user_id, &now_str()).await {
+                Ok(viewed_box) => {
+                    convert_to_guardian_box(&viewed_box, &user_id).unwrap_or(guardian_box)
+                }
+                Err(err) => {
+                    warn!(
+                        "Failed to record view of box {} by guardian {}: {:?}",
+                        id, user_id, err
+                    );
+                    guardian_box
+                }
+            }
+        } else {
+            guardian_box
+        };
         return Ok(Json(
             serde_json::json!({ "box": crate::models::GuardianBoxResponse::from(guardian_box) }),
         ));
     }
 
-    Err(AppError::unauthorized(
-        "Unauthorized or Box not found".into(),
-    ))
+    // A guardian who was removed still appears on the box as rejected; tell them their
+    // access is gone instead of pretending the box doesn't exist
+    let is_rejected = box_rec
+        .guardians
+        .iter()
+        .any(|g| g.id == user_id && g.status == GuardianStatus::Rejected);
+    if is_rejected {
+        warn!("Rejected guardian {} tried to access box {}", user_id, id);
+        return Err(AppError::forbidden(
+            "Guardian access to this box has been revoked".into(),
+        ));
+    }
+
+    Err(AppError::not_found(format!("Box not found: {}", id)))
+}
+
+// How stale a guardian's lastViewedAt can get before another open of the box rewrites it
+const VIEW_RECORD_INTERVAL_MINUTES: i64 = 15;
+
+// A first-time viewer is always recorded; after that only once per interval
+fn needs_view_record(box_rec: &BoxRecord, user_id: &str) -> bool {
+    let Some(guardian) = box_rec
+        .guardians
+        .iter()
+        .find(|g| g.id == user_id && g.status != GuardianStatus::Rejected)
+    else {
+        return false;
+    };
+    if guardian.status == GuardianStatus::Invited {
+        return true;
+    }
+
+    let last_viewed_at = guardian
+        .last_viewed_at
+        .as_deref()
+        .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok());
+    match last_viewed_at {
+        Some(at) => {
+            Utc::now() - at.with_timezone(&Utc) >= Duration::minutes(VIEW_RECORD_INTERVAL_MINUTES)
+        }
+        None => true,
+    }
 }
 
 // PATCH /boxes/guardian/:id/request - For lead guardian to initiate unlock request
@@ -79,64 +159,73 @@ pub async fn request_unlock<S>(
     State(store): State<Arc<S>>,
     Path(box_id): Path<String>,
     Extension(user_id): Extension<String>,
-    Json(payload): Json<LeadGuardianUpdateRequest>,
+    JsonBody(payload): JsonBody<LeadGuardianUpdateRequest>,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    // Get the box from store
-    let mut box_record = store.get_box(&box_id).await?;
-
-    // TODO: query DB with filters instead
-    let is_guardian = box_record
-        .guardians
-        .iter()
-        .find(|g| g.id == user_id && g.status != GuardianStatus::Rejected)
-        .is_some();
-
-    if !is_guardian {
-        warn!("User {} is not a guardian for box {}", user_id, box_id);
-        return Err(AppError::unauthorized("Not a guardian for this box".into()));
-    }
+    let updated_box = update_box_with_retry(
+        store.as_ref(),
+        &box_id,
+        DEFAULT_UPDATE_ATTEMPTS,
+        |box_record| {
+            // TODO: query DB with filters instead
+            let is_guardian = box_record
+                .guardians
+                .iter()
+                .any(|g| g.id == user_id && g.status != GuardianStatus::Rejected);
+
+            if !is_guardian {
+                warn!("User {} is not a guardian for box {}", user_id, box_id);
+                return Err(AppError::unauthorized("Not a guardian for this box".into()));
+            }
 
-    // Check if user is a lead guardian by checking the flag in the guardians list
-    let is_lead = box_record
-        .guardians
-        .iter()
-        .any(|g| g.id == user_id && g.lead_guardian);
-
-    if is_lead {
-        // Lead guardian is initiating an unlock request
-        let new_unlock = UnlockRequest {
-            id: Uuid::new_v4().to_string(),
-            requested_at: now_str(),
-            status: UnlockRequestStatus::Requested,
-            message: Some(payload.message),
-            initiated_by: Some(user_id.clone()),
-            approved_by: vec![],
-            rejected_by: vec![],
-        };
+            if !box_record.lead_guardian_ids().contains(&user_id.as_str()) {
+                return Err(AppError::forbidden(
+                    "Only lead guardians may request unlock".into(),
+                ));
+            }
 
-        box_record.unlock_request = Some(new_unlock);
-        box_record.updated_at = now_str();
+            // One open request per lead guardian; requests from other lead
+            // guardians stay open alongside it
+            if let Some(open) = box_record.unlock_requests.iter().find(|request| {
+                request.initiated_by.as_deref() == Some(user_id.as_str())
+                    && request.status == UnlockRequestStatus::Requested
+                    && !request.is_expired()
+            }) {
+                return Err(AppError::conflict(format!(
+                    "You already have an open unlock request ({}); cancel it before requesting again",
+                    open.id
+                )));
+            }
 
-        // Update the box in store
-        let updated_box = store.update_box(box_record).await?;
+            box_record.unlock_requests.push(UnlockRequest {
+                id: Uuid::new_v4().to_string(),
+                requested_at: now_str(),
+                status: UnlockRequestStatus::Requested,
+                message: Some(payload.message.clone()),
+                initiated_by: Some(user_id.clone()),
+                approved_by: vec![],
+                rejected_by: vec![],
+                expires_at: Some(unlock_request_expires_at(
+                    Utc::now(),
+                    unlock_request_ttl_hours(),
+                )),
+            });
+            Ok(())
+        },
+    )
+    .await?;
 
-        if let Some(guard_box) = convert_to_guardian_box(&updated_box, &user_id) {
-            return Ok(Json(
-                serde_json::json!({ "box": crate::models::GuardianBoxResponse::from(guard_box) }),
-            ));
-        } else {
-            return Err(AppError::internal_server_error(
-                "Failed to render guardian box".into(),
-            ));
-        }
+    if let Some(guard_box) = convert_to_guardian_box(&updated_box, &user_id) {
+        Ok(Json(
+            serde_json::json!({ "box": crate::models::GuardianBoxResponse::from(guard_box) }),
+        ))
+    } else {
+        Err(AppError::internal_server_error(
+            "Failed to render guardian box".into(),
+        ))
     }
-
-    Err(AppError::bad_request(
-        "User is not a lead guardian for this box".into(),
-    ))
 }
 
 // PATCH /boxes/guardian/:id/respond - For guardians to respond to unlock request
@@ -144,69 +233,203 @@ pub async fn respond_to_unlock_request<S>(
     State(store): State<Arc<S>>,
     Path(box_id): Path<String>,
     Extension(user_id): Extension<String>,
-    Json(payload): Json<GuardianResponseRequest>,
+    JsonBody(payload): JsonBody<GuardianResponseRequest>,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    // Get the box from store
-    let mut box_record = store.get_box(&box_id).await?;
+    // Votes are re-applied to the latest copy of the box, so concurrent responses
+    // from other guardians aren't lost
+    let updated_box = update_box_with_retry(
+        store.as_ref(),
+        &box_id,
+        DEFAULT_UPDATE_ATTEMPTS,
+        |box_record| record_unlock_vote(box_record, &user_id, &payload),
+    )
+    .await?;
+
+    let unlock_status = match &payload.request_id {
+        Some(request_id) => updated_box
+            .unlock_requests
+            .iter()
+            .find(|unlock| &unlock.id == request_id),
+        None => updated_box.latest_unlock_request(),
+    }
+    .map(|unlock| unlock.status.clone());
+
+    if let Some(guard_box) = convert_to_guardian_box(&updated_box, &user_id) {
+        Ok(Json(serde_json::json!({
+            "box": crate::models::GuardianBoxResponse::from(guard_box),
+            "unlockStatus": unlock_status,
+        })))
+    } else {
+        Err(AppError::internal_server_error(
+            "Failed to render guardian box".into(),
+        ))
+    }
+}
 
+fn record_unlock_vote(
+    box_record: &mut BoxRecord,
+    user_id: &str,
+    payload: &GuardianResponseRequest,
+) -> Result<()> {
     // TODO: query DB with filters instead
-    if box_record
+    let guardian = box_record
         .guardians
         .iter()
         .find(|g| g.id == user_id && g.status != GuardianStatus::Rejected)
-        .is_none()
-    {
-        return Err(AppError::unauthorized("Not a guardian for this box".into()));
+        .ok_or_else(|| AppError::unauthorized("Not a guardian for this box".into()))?;
+    // Only guardians who accepted are counted by unlock_outcome, so only they may vote
+    if guardian.status != GuardianStatus::Accepted {
+        return Err(AppError::forbidden(
+            "Accept the guardian invitation before voting on unlock requests".into(),
+        ));
     }
-
-    // Check if there's an unlock request to respond to
-    if box_record.unlock_request.is_none() {
+    if payload.approve == Some(true) && payload.reject == Some(true) {
         return Err(AppError::bad_request(
-            "No unlock request exists to update".into(),
+            "An unlock request can't be approved and rejected at once".into(),
         ));
     }
 
-    if let Some(unlock) = &mut box_record.unlock_request {
-        let mut updated = false;
-
-        if let Some(approve) = payload.approve {
-            if approve && !unlock.approved_by.contains(&user_id) {
-                unlock.approved_by.push(user_id.clone());
-                updated = true;
-            }
+    // Find the unlock request being responded to
+    let unlock = match select_unlock_request(box_record, payload.request_id.as_deref())? {
+        None => {
+            return Err(AppError::bad_request(
+                "No unlock request exists to update".into(),
+            ));
         }
-
-        if let Some(reject) = payload.reject {
-            if reject && !unlock.rejected_by.contains(&user_id) {
-                unlock.rejected_by.push(user_id.clone());
-                updated = true;
-            }
+        Some(unlock) if unlock.is_expired() => {
+            return Err(AppError::gone("Unlock request has expired".into()));
         }
-
-        if !updated {
+        Some(unlock) if unlock.status == UnlockRequestStatus::Cancelled => {
             return Err(AppError::bad_request(
-                "No valid update field provided".into(),
+                "Unlock request has been cancelled".into(),
             ));
         }
+        Some(unlock) => unlock,
+    };
+
+    // A guardian holds one vote; a new vote replaces the opposite one
+    let mut updated = false;
+
+    if payload.approve == Some(true) && !unlock.approved_by.iter().any(|id| id == user_id) {
+        unlock.rejected_by.retain(|id| id != user_id);
+        unlock.approved_by.push(user_id.to_string());
+        updated = true;
     }
 
-    box_record.updated_at = now_str();
+    if payload.reject == Some(true) && !unlock.rejected_by.iter().any(|id| id == user_id) {
+        unlock.approved_by.retain(|id| id != user_id);
+        unlock.rejected_by.push(user_id.to_string());
+        updated = true;
+    }
 
-    // Update the box in store
-    let updated_box = store.update_box(box_record).await?;
+    if !updated {
+        return Err(AppError::bad_request(
+            "No valid update field provided".into(),
+        ));
+    }
+
+    // Only a pending request moves; once decided, further votes are just recorded
+    if unlock.status == UnlockRequestStatus::Requested {
+        let unlock = unlock.clone();
+        if let Some(status) = box_record.unlock_outcome(&unlock) {
+            if let Some(pending) = box_record.unlock_request_mut(&unlock.id) {
+                pending.status = status;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// The unlock request with `request_id`, or the latest one when no id is given.
+// An id that isn't on the box is a 404; a box with no requests gives None.
+fn select_unlock_request<'a>(
+    box_record: &'a mut BoxRecord,
+    request_id: Option<&str>,
+) -> Result<Option<&'a mut UnlockRequest>> {
+    match request_id {
+        Some(request_id) => match box_record.unlock_request_mut(request_id) {
+            Some(unlock) => Ok(Some(unlock)),
+            None => Err(AppError::not_found(format!(
+                "Unlock request not found: {}",
+                request_id
+            ))),
+        },
+        None => Ok(box_record.unlock_requests.last_mut()),
+    }
+}
+
+// PATCH /boxes/guardian/:id/cancel - For the initiating lead guardian to withdraw an unlock request
+pub async fn cancel_unlock_request<S>(
+    State(store): State<Arc<S>>,
+    Path(box_id): Path<String>,
+    Extension(user_id): Extension<String>,
+    JsonBody(payload): JsonBody<CancelUnlockRequest>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    let updated_box = update_box_with_retry(
+        store.as_ref(),
+        &box_id,
+        DEFAULT_UPDATE_ATTEMPTS,
+        |box_record| cancel_unlock(box_record, &user_id, payload.request_id.as_deref()),
+    )
+    .await?;
 
     if let Some(guard_box) = convert_to_guardian_box(&updated_box, &user_id) {
-        return Ok(Json(
-            serde_json::json!({ "box": crate::models::GuardianBoxResponse::from(guard_box) }),
-        ));
+        Ok(Json(serde_json::json!({
+            "box": crate::models::GuardianBoxResponse::from(guard_box),
+            "unlockStatus": UnlockRequestStatus::Cancelled,
+        })))
     } else {
-        return Err(AppError::internal_server_error(
+        Err(AppError::internal_server_error(
             "Failed to render guardian box".into(),
+        ))
+    }
+}
+
+fn cancel_unlock(
+    box_record: &mut BoxRecord,
+    user_id: &str,
+    request_id: Option<&str>,
+) -> Result<()> {
+    if !box_record
+        .guardians
+        .iter()
+        .any(|g| g.id == user_id && g.status != GuardianStatus::Rejected)
+    {
+        return Err(AppError::unauthorized("Not a guardian for this box".into()));
+    }
+
+    let unlock = match select_unlock_request(box_record, request_id)? {
+        Some(unlock) => unlock,
+        None => {
+            return Err(AppError::bad_request(
+                "No unlock request exists to cancel".into(),
+            ));
+        }
+    };
+
+    if unlock.initiated_by.as_deref() != Some(user_id) {
+        return Err(AppError::forbidden(
+            "Only the guardian who requested the unlock may cancel it".into(),
         ));
     }
+
+    // Decided requests are part of the box's history and stay as they are
+    if unlock.status != UnlockRequestStatus::Requested {
+        return Err(AppError::conflict(format!(
+            "Unlock request is already {}",
+            unlock.status
+        )));
+    }
+
+    unlock.status = UnlockRequestStatus::Cancelled;
+    Ok(())
 }
 
 // PATCH /boxes/guardian/:id/invitation - For accepting/rejecting a guardian invitation
@@ -214,54 +437,158 @@ pub async fn respond_to_invitation<S>(
     State(store): State<Arc<S>>,
     Path(box_id): Path<String>,
     Extension(user_id): Extension<String>,
-    Json(payload): Json<GuardianInvitationResponse>,
+    JsonBody(payload): JsonBody<GuardianInvitationResponse>,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    // Get the box from store
-    let mut box_record = store.get_box(&box_id).await?;
-
-    // Find if user is a guardian with pending status
-    let guardian_index = box_record
-        .guardians
-        .iter()
-        .position(|g| g.id == user_id && g.status == GuardianStatus::Invited);
-
-    if let Some(index) = guardian_index {
-        // Update the guardian status based on the acceptance
-        if payload.accept {
-            box_record.guardians[index].status = GuardianStatus::Accepted;
-            box_record.updated_at = now_str();
-
-            // Update the box in store
-            let updated_box = store.update_box(box_record).await?;
-
-            if let Some(guard_box) = convert_to_guardian_box(&updated_box, &user_id) {
-                return Ok(Json(serde_json::json!({
-                    "message": "Guardian invitation accepted successfully",
-                    "box": crate::models::GuardianBoxResponse::from(guard_box)
-                })));
+    let updated_box = update_box_with_retry(
+        store.as_ref(),
+        &box_id,
+        DEFAULT_UPDATE_ATTEMPTS,
+        |box_record| -> Result<()> {
+            // Find if user is a guardian with pending status; opening the box moves
+            // them from invited to viewed, and either can still respond
+            let guardian = box_record
+                .guardians
+                .iter_mut()
+                .find(|g| {
+                    g.id == user_id
+                        && matches!(g.status, GuardianStatus::Invited | GuardianStatus::Viewed)
+                })
+                .ok_or_else(|| {
+                    AppError::bad_request("No pending invitation found for this user".into())
+                })?;
+
+            // Update the guardian status based on the acceptance
+            guardian.status = if payload.accept {
+                GuardianStatus::Accepted
             } else {
-                return Err(AppError::internal_server_error(
-                    "Failed to render guardian box".into(),
+                GuardianStatus::Rejected
+            };
+            Ok(())
+        },
+    )
+    .await?;
+
+    if !payload.accept {
+        return Ok(Json(serde_json::json!({
+            "message": "Guardian invitation rejected successfully"
+        })));
+    }
+
+    if let Some(guard_box) = convert_to_guardian_box(&updated_box, &user_id) {
+        Ok(Json(serde_json::json!({
+            "message": "Guardian invitation accepted successfully",
+            "box": crate::models::GuardianBoxResponse::from(guard_box)
+        })))
+    } else {
+        Err(AppError::internal_server_error(
+            "Failed to render guardian box".into(),
+        ))
+    }
+}
+
+// PATCH /boxes/guardian/invitation - Accept/reject an invitation by its invite code,
+// for guardians who haven't been linked to the box yet
+pub async fn respond_to_invitation_by_code<S>(
+    State(store): State<Arc<S>>,
+    Extension(invitation_store): Extension<Arc<dyn InvitationStore>>,
+    Extension(user_id): Extension<String>,
+    JsonBody(payload): JsonBody<InviteCodeResponseRequest>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    // Unknown and expired codes surface as 404/422 through the StoreError conversion
+    let mut invitation = invitation_store
+        .get_invitation_by_code(&payload.invite_code)
+        .await?;
+
+    if let Some(linked_user_id) = &invitation.linked_user_id {
+        if linked_user_id != &user_id {
+            return Err(AppError::forbidden(
+                "Invitation belongs to another user".into(),
+            ));
+        }
+    }
+    if matches!(
+        invitation.status,
+        InvitationStatus::Accepted | InvitationStatus::Rejected
+    ) {
+        return Err(AppError::conflict(
+            "Invitation has already been responded to".into(),
+        ));
+    }
+
+    let updated_box = update_box_with_retry(
+        store.as_ref(),
+        &invitation.box_id,
+        DEFAULT_UPDATE_ATTEMPTS,
+        |box_record| -> Result<()> {
+            let guardian = box_record
+                .guardians
+                .iter_mut()
+                .find(|g| g.invitation_id == invitation.id)
+                .ok_or_else(|| {
+                    AppError::not_found("No guardian slot found for this invitation".into())
+                })?;
+
+            if !matches!(
+                guardian.status,
+                GuardianStatus::Invited | GuardianStatus::Viewed
+            ) {
+                return Err(AppError::conflict(
+                    "Invitation has already been responded to".into(),
                 ));
             }
-        } else {
-            // User is rejecting the invitation
-            box_record.guardians[index].status = GuardianStatus::Rejected;
-            box_record.updated_at = now_str();
 
-            // Update the box in store
-            let _updated_box = store.update_box(box_record).await?;
+            // Link the slot to the caller in case invitation_viewed hasn't been processed yet
+            guardian.id = user_id.clone();
+            guardian.status = if payload.accept {
+                GuardianStatus::Accepted
+            } else {
+                GuardianStatus::Rejected
+            };
+            Ok(())
+        },
+    )
+    .await?;
+
+    invitation.opened = true;
+    invitation.linked_user_id = Some(user_id.clone());
+    invitation.status = if payload.accept {
+        InvitationStatus::Accepted
+    } else {
+        InvitationStatus::Rejected
+    };
+    let invitation = invitation_store.update_invitation(invitation).await?;
 
-            return Ok(Json(serde_json::json!({
-                "message": "Guardian invitation rejected successfully"
-            })));
-        }
+    let event_type = if payload.accept {
+        "invitation_accepted"
+    } else {
+        "invitation_rejected"
+    };
+    // The box is already updated, so a failed publish isn't worth failing the request
+    if let Err(err) = publish_invitation_event(&invitation, event_type).await {
+        error!("Failed to publish {} event: {:?}", event_type, err);
+    }
+
+    if !payload.accept {
+        return Ok(Json(serde_json::json!({
+            "message": "Guardian invitation rejected successfully",
+            "boxId": updated_box.id,
+        })));
     }
 
-    Err(AppError::bad_request(
-        "No pending invitation found for this user".into(),
-    ))
+    if let Some(guard_box) = convert_to_guardian_box(&updated_box, &user_id) {
+        Ok(Json(serde_json::json!({
+            "message": "Guardian invitation accepted successfully",
+            "box": crate::models::GuardianBoxResponse::from(guard_box)
+        })))
+    } else {
+        Err(AppError::internal_server_error(
+            "Failed to render guardian box".into(),
+        ))
+    }
 }
@@ -0,0 +1,38 @@
+use axum::{extract::State, http::StatusCode, Json};
+use log::warn;
+use std::sync::Arc;
+
+use lockbox_shared::store::BoxStore;
+
+// GET /health - unauthenticated liveness check
+pub async fn health() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "status": "ok",
+        "service": "box-service",
+        "version": env!("CARGO_PKG_VERSION"),
+    }))
+}
+
+// GET /ready - unauthenticated readiness check; 503 while the box store can't be reached
+pub async fn ready<S>(State(store): State<Arc<S>>) -> (StatusCode, Json<serde_json::Value>)
+where
+    S: BoxStore,
+{
+    let (status, state) = match store.ping().await {
+        Ok(()) => (StatusCode::OK, "ready"),
+        Err(e) => {
+            // The store error can name tables, so it is only logged
+            warn!("Readiness check failed: {}", e);
+            (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+        }
+    };
+
+    (
+        status,
+        Json(serde_json::json!({
+            "status": state,
+            "service": "box-service",
+            "version": env!("CARGO_PKG_VERSION"),
+        })),
+    )
+}
@@ -1,2 +1,4 @@
+pub mod auth_handlers;
 pub mod box_handlers;
 pub mod guardian_handlers;
+pub mod health_handlers;
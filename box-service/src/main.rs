@@ -1,4 +1,6 @@
+mod documents;
 mod error;
+mod events;
 mod handlers;
 // Keep models for request/response types
 mod models;
@@ -7,22 +9,27 @@ mod routes;
 #[cfg(test)]
 mod tests;
 
-use axum::{body::Body, extract::Request, response::Response, Router};
-use env_logger;
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header::CONTENT_TYPE, HeaderValue},
+    response::Response,
+    Router,
+};
 use http_body_util::BodyExt;
 use lambda_http::{
     run, service_fn, Body as LambdaBody, Error, Request as LambdaRequest,
     Response as LambdaResponse,
 };
-use log::{debug, error, info, trace};
-use once_cell::sync::OnceCell;
+use lockbox_shared::bootstrap::LazyInit;
+use lockbox_shared::shutdown::{serve_until, shutdown_signal};
+use log::{debug, error, info, trace, warn};
 use routes::create_router;
 use std::net::SocketAddr;
-use tokio::sync::Mutex;
 use tower::ServiceExt;
 
-// Router instance that will be initialized once
-static ROUTER: OnceCell<Mutex<Option<Router>>> = OnceCell::new();
+// Router instance, built on the first request
+static ROUTER: LazyInit<Router> = LazyInit::new();
 
 // The Lambda handler function
 async fn function_handler(event: LambdaRequest) -> Result<LambdaResponse<LambdaBody>, Error> {
@@ -34,22 +41,13 @@ async fn function_handler(event: LambdaRequest) -> Result<LambdaResponse<LambdaB
         event.uri().query()
     );
 
-    // Initialize the OnceCell if needed
-    if ROUTER.get().is_none() {
-        let _ = ROUTER.set(Mutex::new(None));
-    }
-
-    // Initialize the router if it hasn't been initialized yet
-    let mutex = ROUTER.get().unwrap();
-    let mut router_option = mutex.lock().await;
-
-    if router_option.is_none() {
-        info!("Initializing the Axum router");
-        *router_option = Some(create_router().await);
-    }
-
-    let app = router_option.as_ref().unwrap().clone();
-    drop(router_option); // Release lock as soon as possible
+    // Get or initialize the router
+    let app = ROUTER
+        .get_or_init(|| async {
+            info!("Initializing the Axum router");
+            create_router().await
+        })
+        .await;
 
     // Convert the Lambda event to an HTTP request for Axum
     let (parts, body) = event.into_parts();
@@ -128,31 +126,61 @@ async fn response_to_lambda(response: Response) -> Result<LambdaResponse<LambdaB
             builder.header(name, value)
         });
 
+    // lambda_http sets isBase64Encoded from the body variant, so Binary bodies are
+    // base64-encoded for API Gateway and Text bodies are passed through as-is
     let lambda_response = if bytes.is_empty() {
         debug!("Creating empty response body");
         builder_with_headers.body(LambdaBody::Empty)?
-    } else {
+    } else if is_text_content_type(parts.headers.get(CONTENT_TYPE)) {
         match String::from_utf8(bytes.to_vec()) {
             Ok(s) => {
                 debug!("Creating text response body");
                 builder_with_headers.body(LambdaBody::Text(s))?
             }
             Err(_) => {
-                debug!("Creating binary response body: {} bytes", bytes.len());
+                // Don't mangle the payload with a lossy conversion; send the raw bytes
+                warn!("Text response body is not valid UTF-8, sending it as binary");
                 builder_with_headers.body(LambdaBody::Binary(bytes.to_vec()))?
             }
         }
+    } else {
+        debug!("Creating binary response body: {} bytes", bytes.len());
+        builder_with_headers.body(LambdaBody::Binary(bytes.to_vec()))?
     };
 
     Ok(lambda_response)
 }
 
+// Whether a response with this content-type should go back to Lambda as text
+// (text/*, application/json and +json types such as the response envelope);
+// anything else, or a missing header, is binary
+fn is_text_content_type(content_type: Option<&HeaderValue>) -> bool {
+    let Some(mime) = content_type.and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    let mime = mime
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    mime.starts_with("text/") || mime == "application/json" || mime.ends_with("+json")
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    // Initialize env_logger instead of tracing_subscriber
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    // Initialize env_logger instead of tracing_subscriber (lines carry the request id;
+    // LOG_FORMAT=json switches them to JSON)
+    lockbox_shared::correlation::log_builder().init();
     info!("Logging initialized with env_logger");
 
+    // Refuse to start with ENFORCE_ID_TOKEN on but no app client to check audiences against
+    if let Err(msg) = lockbox_shared::auth::check_id_token_config() {
+        error!("{}", msg);
+        return Err(msg.into());
+    }
+
     // Check if running in Lambda environment
     if let Ok(function_name) = std::env::var("AWS_LAMBDA_FUNCTION_NAME") {
         info!(
@@ -169,7 +197,7 @@ async fn main() -> Result<(), Error> {
 
         let app = create_router().await;
         let listener = tokio::net::TcpListener::bind(&addr).await?;
-        axum::serve(listener, app.into_make_service()).await?;
+        serve_until(listener, app, shutdown_signal()).await?;
     }
 
     info!("Service finished");
@@ -1,17 +1,100 @@
-use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
 // Import shared models for direct use in response types
-use lockbox_shared::models::{Document, Guardian, UnlockRequest};
+use lockbox_shared::models::{
+    BoxRecord, Document, DocumentStorage, Guardian, GuardianDocument, GuardianStatus, UnlockPolicy,
+    UnlockRequest, DEFAULT_DOCUMENT_CONTENT_TYPE,
+};
 
-// Request DTOs
+use crate::error::FieldError;
+
+// Field limits enforced on box creation
+pub const MAX_BOX_NAME_LENGTH: usize = 100;
+pub const MAX_BOX_DESCRIPTION_LENGTH: usize = 1000;
+
+// Most ids accepted by POST /boxes/owned/batch-get
+pub const MAX_BATCH_GET_IDS: usize = 100;
+
+// Document limits, overridable via MAX_DOCUMENTS_PER_BOX and MAX_DOCUMENT_BYTES
+pub const DEFAULT_MAX_DOCUMENTS_PER_BOX: usize = 50;
+pub const DEFAULT_MAX_DOCUMENT_BYTES: usize = 256 * 1024;
+
+// Content types a document may be recorded as
+pub const ALLOWED_DOCUMENT_CONTENT_TYPES: &[&str] =
+    &["text/plain", "application/pdf", "application/json"];
+
+// Guardian limit, overridable via MAX_GUARDIANS_PER_BOX
+pub const DEFAULT_MAX_GUARDIANS_PER_BOX: usize = 25;
+
+// DynamoDB rejects items over 400KB; leave headroom for attribute names and the guardian list
+pub const MAX_BOX_ITEM_BYTES: usize = 350 * 1024;
+
+pub fn max_documents_per_box() -> usize {
+    env_limit("MAX_DOCUMENTS_PER_BOX", DEFAULT_MAX_DOCUMENTS_PER_BOX)
+}
+
+pub fn max_guardians_per_box() -> usize {
+    env_limit("MAX_GUARDIANS_PER_BOX", DEFAULT_MAX_GUARDIANS_PER_BOX)
+}
+
+pub fn max_document_bytes() -> usize {
+    env_limit("MAX_DOCUMENT_BYTES", DEFAULT_MAX_DOCUMENT_BYTES)
+}
+
+fn env_limit(var_name: &str, default: usize) -> usize {
+    match std::env::var(var_name)
+        .ok()
+        .map(|v| v.trim().parse::<usize>())
+    {
+        Some(Ok(limit)) if limit > 0 => limit,
+        Some(_) => {
+            log::warn!("Invalid {} value, using default of {}", var_name, default);
+            default
+        }
+        None => default,
+    }
+}
+
+// Request DTOs. Create/update bodies reject unknown keys so a typo'd field
+// is a 422 rather than a silently ignored change.
 #[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct CreateBoxRequest {
     pub name: String,
     pub description: String,
 }
 
+impl CreateBoxRequest {
+    // Collects every failing field rather than stopping at the first one
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+
+        if self.name.trim().is_empty() {
+            errors.push(FieldError::new("name", "must not be empty"));
+        } else if self.name.chars().count() > MAX_BOX_NAME_LENGTH {
+            errors.push(FieldError::new(
+                "name",
+                &format!("must be at most {} characters", MAX_BOX_NAME_LENGTH),
+            ));
+        }
+
+        if self.description.chars().count() > MAX_BOX_DESCRIPTION_LENGTH {
+            errors.push(FieldError::new(
+                "description",
+                &format!("must be at most {} characters", MAX_BOX_DESCRIPTION_LENGTH),
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct UpdateBoxRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
@@ -26,16 +109,232 @@ pub struct UpdateBoxRequest {
     pub unlock_instructions: Option<OptionalField<String>>,
     #[serde(rename = "isLocked", skip_serializing_if = "Option::is_none")]
     pub is_locked: Option<bool>,
+    #[serde(
+        rename = "requiredApprovals",
+        skip_serializing_if = "Option::is_none",
+        default,
+        with = "optional_field_serde"
+    )]
+    pub required_approvals: Option<OptionalField<usize>>,
+    #[serde(rename = "unlockPolicy", skip_serializing_if = "Option::is_none")]
+    pub unlock_policy: Option<UnlockPolicy>,
 }
 
 #[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct DocumentUpdateRequest {
-    pub document: Document,
+    pub document: DocumentInput,
 }
 
 #[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct GuardianUpdateRequest {
-    pub guardian: Guardian,
+    pub guardian: GuardianInput,
+}
+
+// Request-side copy of the stored Document, so typos inside the nested
+// object are rejected too without making stored items strict
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct DocumentInput {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+    #[serde(rename = "createdAt", alias = "created_at")]
+    pub created_at: String,
+    #[serde(default)]
+    pub storage: DocumentStorage,
+    #[serde(
+        rename = "contentType",
+        alias = "content_type",
+        default = "default_content_type"
+    )]
+    pub content_type: String,
+}
+
+fn default_content_type() -> String {
+    DEFAULT_DOCUMENT_CONTENT_TYPE.to_string()
+}
+
+impl From<DocumentInput> for Document {
+    fn from(input: DocumentInput) -> Self {
+        Document {
+            id: input.id,
+            title: input.title,
+            content: input.content,
+            created_at: input.created_at,
+            storage: input.storage,
+            content_type: input.content_type,
+        }
+    }
+}
+
+// Request-side copy of the stored Guardian. Clients often echo back a guardian
+// they fetched, so lastViewedAt is accepted but dropped; only the service sets it.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct GuardianInput {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "leadGuardian", alias = "lead_guardian")]
+    pub lead_guardian: bool,
+    pub status: GuardianStatus,
+    #[serde(rename = "addedAt", alias = "added_at")]
+    pub added_at: String,
+    #[serde(rename = "invitationId", alias = "invitation_id")]
+    pub invitation_id: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub relationship: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(rename = "lastViewedAt", alias = "last_viewed_at", default)]
+    _last_viewed_at: Option<serde::de::IgnoredAny>,
+}
+
+impl From<GuardianInput> for Guardian {
+    fn from(input: GuardianInput) -> Self {
+        Guardian {
+            id: input.id,
+            name: input.name,
+            lead_guardian: input.lead_guardian,
+            status: input.status,
+            added_at: input.added_at,
+            invitation_id: input.invitation_id,
+            email: input.email,
+            relationship: input.relationship,
+            note: input.note,
+            last_viewed_at: None,
+        }
+    }
+}
+
+// Partial guardian update; absent fields are left as they are
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct GuardianPatchRequest {
+    #[serde(default, with = "optional_field_serde")]
+    pub name: Option<OptionalField<String>>,
+    #[serde(rename = "leadGuardian", default, with = "optional_field_serde")]
+    pub lead_guardian: Option<OptionalField<bool>>,
+    #[serde(default, with = "optional_field_serde")]
+    pub status: Option<OptionalField<GuardianStatus>>,
+    #[serde(default, with = "optional_field_serde")]
+    pub email: Option<OptionalField<String>>,
+    #[serde(default, with = "optional_field_serde")]
+    pub relationship: Option<OptionalField<String>>,
+    #[serde(default, with = "optional_field_serde")]
+    pub note: Option<OptionalField<String>>,
+}
+
+// Guardians to set in one go; merged by invitation id or email unless `replace` is set
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct GuardiansUpdateRequest {
+    pub guardians: Vec<GuardianInput>,
+    #[serde(default)]
+    pub replace: bool,
+}
+
+impl GuardiansUpdateRequest {
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        let mut seen_invitations = std::collections::HashSet::new();
+
+        for (i, guardian) in self.guardians.iter().enumerate() {
+            if guardian.name.trim().is_empty() {
+                errors.push(FieldError::new(
+                    &format!("guardians[{}].name", i),
+                    "must not be empty",
+                ));
+            }
+            if guardian.invitation_id.trim().is_empty() {
+                errors.push(FieldError::new(
+                    &format!("guardians[{}].invitationId", i),
+                    "must not be empty",
+                ));
+            } else if !seen_invitations.insert(guardian.invitation_id.as_str()) {
+                errors.push(FieldError::new(
+                    &format!("guardians[{}].invitationId", i),
+                    "duplicates an earlier guardian",
+                ));
+            }
+        }
+
+        if self.guardians.iter().filter(|g| g.lead_guardian).count() > 1 {
+            errors.push(FieldError::new(
+                "guardians",
+                "at most one guardian can be the lead guardian",
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BatchGetBoxesRequest {
+    pub ids: Vec<String>,
+}
+
+// Query parameters for PATCH /boxes/owned/:id
+#[derive(Deserialize, Debug, Default)]
+pub struct UpdateBoxParams {
+    // Validate and return the projected box without saving it
+    #[serde(rename = "dryRun", default)]
+    pub dry_run: bool,
+}
+
+// Page size for GET /boxes/owned and /boxes/guardian when a cursor is given
+// without a limit; the upper bound is enforced by ListParams
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+// Bounds for GET /boxes/owned/search
+pub const MIN_SEARCH_QUERY_LENGTH: usize = 2;
+pub const MAX_SEARCH_RESULTS: usize = 50;
+
+// Query parameters for GET /boxes/owned/search
+#[derive(Deserialize, Debug, Default)]
+pub struct SearchBoxesParams {
+    pub q: Option<String>,
+}
+
+// Filters for GET /boxes/owned; pagination and sorting come from ListParams
+#[derive(Deserialize, Debug, Default)]
+pub struct ListBoxesParams {
+    pub locked: Option<bool>,
+}
+
+// Fields GET /boxes/owned and /boxes/guardian can sort by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoxSortField {
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl BoxSortField {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "name" => Some(Self::Name),
+            "createdAt" => Some(Self::CreatedAt),
+            "updatedAt" => Some(Self::UpdatedAt),
+            _ => None,
+        }
+    }
+
+    pub fn compare(self, a: &BoxRecord, b: &BoxRecord) -> std::cmp::Ordering {
+        match self {
+            Self::Name => a.name.cmp(&b.name),
+            Self::CreatedAt => a.created_at.cmp(&b.created_at),
+            Self::UpdatedAt => a.updated_at.cmp(&b.updated_at),
+        }
+    }
 }
 
 // Response DTOs
@@ -58,12 +357,21 @@ pub struct BoxResponse {
     pub owner_id: String,
     #[serde(rename = "ownerName")]
     pub owner_name: Option<String>,
+    #[serde(rename = "unlockRequests")]
+    pub unlock_requests: Vec<UnlockRequest>,
+    // Deprecated: the latest entry of unlockRequests, kept for older clients
     #[serde(rename = "unlockRequest")]
     pub unlock_request: Option<UnlockRequest>,
+    #[serde(rename = "requiredApprovals")]
+    pub required_approvals: usize,
+    #[serde(rename = "unlockPolicy")]
+    pub unlock_policy: UnlockPolicy,
 }
 
 impl From<lockbox_shared::models::BoxRecord> for BoxResponse {
     fn from(box_rec: lockbox_shared::models::BoxRecord) -> Self {
+        let required_approvals = box_rec.approval_threshold();
+        let unlock_request = box_rec.latest_unlock_request().cloned();
         Self {
             id: box_rec.id,
             name: box_rec.name,
@@ -76,7 +384,10 @@ impl From<lockbox_shared::models::BoxRecord> for BoxResponse {
             guardians: box_rec.guardians,
             owner_id: box_rec.owner_id,
             owner_name: box_rec.owner_name,
-            unlock_request: box_rec.unlock_request,
+            unlock_requests: box_rec.unlock_requests,
+            unlock_request,
+            required_approvals,
+            unlock_policy: box_rec.unlock_policy,
         }
     }
 }
@@ -88,6 +399,13 @@ pub struct DocumentUpdateResponse {
     pub updated_at: String,
 }
 
+#[derive(Serialize, Debug)]
+pub struct GuardiansUpdateResponse {
+    pub guardians: Vec<Guardian>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+}
+
 #[derive(Serialize, Debug)]
 pub struct GuardianUpdateResponse {
     pub id: String,
@@ -99,6 +417,12 @@ pub struct GuardianUpdateResponse {
     pub added_at: String,
     #[serde(rename = "invitationId")]
     pub invitation_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relationship: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
     #[serde(rename = "allGuardians")]
     pub all_guardians: Vec<Guardian>,
     #[serde(rename = "updatedAt")]
@@ -152,8 +476,18 @@ pub struct LeadGuardianUpdateRequest {
     pub message: String,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct CancelUnlockRequest {
+    // Which unlock request to cancel; defaults to the latest one
+    #[serde(rename = "requestId")]
+    pub request_id: Option<String>,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct GuardianResponseRequest {
+    // Which unlock request the vote is for; defaults to the latest one
+    #[serde(rename = "requestId")]
+    pub request_id: Option<String>,
     pub approve: Option<bool>,
     pub reject: Option<bool>,
 }
@@ -163,6 +497,13 @@ pub struct GuardianInvitationResponse {
     pub accept: bool,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct InviteCodeResponseRequest {
+    #[serde(rename = "inviteCode")]
+    pub invite_code: String,
+    pub accept: bool,
+}
+
 #[derive(Serialize)]
 pub struct ErrorResponse {
     pub error: String,
@@ -193,20 +534,29 @@ pub struct GuardianBoxResponse {
     pub owner_name: Option<String>,
     #[serde(rename = "unlockInstructions")]
     pub unlock_instructions: Option<String>,
+    #[serde(rename = "unlockRequests")]
+    pub unlock_requests: Vec<UnlockRequest>,
+    // Deprecated: the latest entry of unlockRequests, kept for older clients
     #[serde(rename = "unlockRequest")]
     pub unlock_request: Option<UnlockRequest>,
+    // True when the latest unlock request has expired and can no longer be responded to
+    pub expired: bool,
     #[serde(rename = "pendingGuardianApproval")]
     pub pending_guardian_approval: Option<bool>,
     #[serde(rename = "guardiansCount")]
     pub guardians_count: usize,
     #[serde(rename = "isLeadGuardian")]
     pub is_lead_guardian: bool,
-    pub documents: Vec<Document>,
+    pub documents: Vec<GuardianDocument>,
     pub guardians: Vec<Guardian>,
 }
 
 impl From<lockbox_shared::models::GuardianBox> for GuardianBoxResponse {
     fn from(guard_box: lockbox_shared::models::GuardianBox) -> Self {
+        let unlock_request = guard_box.unlock_requests.last().cloned();
+        let expired = unlock_request
+            .as_ref()
+            .is_some_and(|unlock| unlock.is_expired());
         Self {
             id: guard_box.id,
             name: guard_box.name,
@@ -217,7 +567,9 @@ impl From<lockbox_shared::models::GuardianBox> for GuardianBoxResponse {
             owner_id: guard_box.owner_id,
             owner_name: guard_box.owner_name,
             unlock_instructions: guard_box.unlock_instructions,
-            unlock_request: guard_box.unlock_request,
+            unlock_requests: guard_box.unlock_requests,
+            unlock_request,
+            expired,
             pending_guardian_approval: guard_box.pending_guardian_approval,
             guardians_count: guard_box.guardians_count,
             is_lead_guardian: guard_box.is_lead_guardian,
@@ -226,8 +578,3 @@ impl From<lockbox_shared::models::GuardianBox> for GuardianBoxResponse {
         }
     }
 }
-
-// Utility functions
-pub fn now_str() -> String {
-    Utc::now().to_rfc3339()
-}
@@ -1,61 +1,103 @@
 use axum::{
-    extract::Request,
+    extract::{DefaultBodyLimit, Extension, Request},
     middleware,
-    routing::{get, patch},
+    routing::{get, patch, post, put},
     Router,
 };
 use log::{info, warn};
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::limit::RequestBodyLimitLayer;
 
+use crate::documents::{DocumentPresigner, S3Presigner};
 use crate::handlers::{
+    auth_handlers::whoami,
     box_handlers::{
-        create_box, delete_box, delete_document, delete_guardian, get_box, get_boxes, update_box,
-        update_document, update_guardian,
+        batch_get_boxes, clone_box, count_boxes, create_box, delete_box, delete_document,
+        delete_guardian, get_box, get_boxes, get_document_url, lock_box, patch_guardian,
+        restore_box, search_boxes, unlock_box, update_box, update_document, update_guardian,
+        update_guardians,
     },
     guardian_handlers::{
-        get_guardian_box, get_guardian_boxes, request_unlock, respond_to_invitation,
-        respond_to_unlock_request,
+        cancel_unlock_request, get_guardian_box, get_guardian_boxes, request_unlock,
+        respond_to_invitation, respond_to_invitation_by_code, respond_to_unlock_request,
     },
+    health_handlers::{health, ready},
+};
+use lockbox_shared::idempotency::idempotency_store_from_env;
+use lockbox_shared::store::{
+    dynamo::{DynamoBoxStore, DynamoInvitationStore},
+    memory::MemoryInvitationStore,
+    metrics::{emf_metrics_enabled, MetricsBoxStore, MetricsInvitationStore},
+    tracing::TracingBoxStore,
+    BoxStore, InvitationStore,
 };
-use lockbox_shared::store::{dynamo::DynamoBoxStore, BoxStore};
 
 // Import shared auth middleware
 use lockbox_shared::auth::auth_middleware;
+use lockbox_shared::base_path::api_base_path;
+use lockbox_shared::body_limit::max_body_bytes;
+use lockbox_shared::correlation::correlation_id_middleware;
+use lockbox_shared::cors::cors_layer_from_env;
+use lockbox_shared::envelope::response_envelope_middleware;
+use lockbox_shared::fallback::{method_not_allowed_fallback, not_found_fallback};
 
 /// Creates a router with the default store
 pub async fn create_router() -> Router {
     info!("Creating router with DynamoDB store");
 
-    // Create the DynamoDB store
-    let dynamo_store = Arc::new(DynamoBoxStore::new().await);
+    // Create the DynamoDB store, logging each call at debug level
+    let dynamo_store = Arc::new(TracingBoxStore::new(DynamoBoxStore::new().await));
 
-    // Check if we should remove the base path prefix
-    let remove_base_path = std::env::var("REMOVE_BASE_PATH")
-        .map(|v| v.to_lowercase() == "true")
-        .unwrap_or(false);
+    // Invite codes are resolved against the invitation table when responding by code
+    let invitation_store: Arc<dyn InvitationStore> =
+        if std::env::var("DYNAMODB_INVITATION_TABLE").is_ok() {
+            Arc::new(DynamoInvitationStore::new().await)
+        } else {
+            warn!("DYNAMODB_INVITATION_TABLE is not set, using in-memory invitation store");
+            Arc::new(MemoryInvitationStore::new())
+        };
 
-    // If REMOVE_BASE_PATH is set to true, don't add the /Prod prefix
-    let prefix = if remove_base_path { "" } else { "/Prod" };
+    // API_BASE_PATH (default /Prod), or no prefix when REMOVE_BASE_PATH=true
+    let prefix = api_base_path();
     info!("Using API route prefix: {}", prefix);
 
-    create_router_with_store(dynamo_store, prefix)
+    let router = if emf_metrics_enabled() {
+        info!("EMF_METRICS is set, emitting store metrics");
+        let invitation_store = Arc::new(MetricsInvitationStore::new(invitation_store));
+        create_router_with_store(
+            Arc::new(MetricsBoxStore::new(dynamo_store)),
+            invitation_store,
+            &prefix,
+        )
+    } else {
+        create_router_with_store(dynamo_store, invitation_store, &prefix)
+    };
+
+    // Download URLs for S3-backed documents need DOCUMENTS_BUCKET
+    let router = match S3Presigner::from_env().await {
+        Some(presigner) => {
+            router.layer(Extension(Arc::new(presigner) as Arc<dyn DocumentPresigner>))
+        }
+        None => router,
+    };
+
+    // Remembers Idempotency-Key headers on box creation (IDEMPOTENCY_TABLE)
+    router.layer(Extension(idempotency_store_from_env().await))
 }
 
-/// Creates a router with a given store implementation
-pub fn create_router_with_store<S>(store: Arc<S>, prefix: &str) -> Router
+/// Creates a router with the given box and invitation store implementations
+pub fn create_router_with_store<S>(
+    store: Arc<S>,
+    invitation_store: Arc<dyn InvitationStore>,
+    prefix: &str,
+) -> Router
 where
     S: BoxStore + 'static,
 {
     info!("Setting up API routes with prefix: '{}'", prefix);
 
-    // Configure CORS
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
-
-    info!("CORS configured for all origins, methods and headers");
+    // Configure CORS from CORS_ALLOWED_ORIGINS/METHODS/HEADERS
+    let cors = cors_layer_from_env();
 
     // Logging middleware to trace all requests
     async fn logging_middleware(
@@ -73,20 +115,32 @@ where
     // Create the API routes
     let api_routes = Router::new()
         .route("/boxes/owned", get(get_boxes).post(create_box))
+        .route("/boxes/owned/batch-get", post(batch_get_boxes))
+        .route("/boxes/owned/search", get(search_boxes))
+        .route("/boxes/owned/count", get(count_boxes))
         .route(
             "/boxes/owned/:id",
             get(get_box).patch(update_box).delete(delete_box),
         )
+        .route("/boxes/owned/:id/restore", post(restore_box))
+        .route("/boxes/owned/:id/clone", post(clone_box))
+        .route("/boxes/owned/:id/lock", post(lock_box))
+        .route("/boxes/owned/:id/unlock", post(unlock_box))
         .route("/boxes/owned/:id/guardian", patch(update_guardian))
+        .route("/boxes/owned/:id/guardians", put(update_guardians))
         .route(
             "/boxes/owned/:id/guardian/:guardian_id",
-            axum::routing::delete(delete_guardian),
+            patch(patch_guardian).delete(delete_guardian),
         )
         .route("/boxes/owned/:id/document", patch(update_document))
         .route(
             "/boxes/owned/:id/document/:document_id",
             axum::routing::delete(delete_document),
         )
+        .route(
+            "/boxes/owned/:id/document/:document_id/url",
+            get(get_document_url),
+        )
         .route("/boxes/guardian", get(get_guardian_boxes))
         .route("/boxes/guardian/:id", get(get_guardian_box))
         .route("/boxes/guardian/:id/request", patch(request_unlock))
@@ -94,25 +148,46 @@ where
             "/boxes/guardian/:id/respond",
             patch(respond_to_unlock_request),
         )
+        .route("/boxes/guardian/:id/cancel", patch(cancel_unlock_request))
         .route(
             "/boxes/guardian/:id/invitation",
             patch(respond_to_invitation),
         )
+        .route(
+            "/boxes/guardian/invitation",
+            patch(respond_to_invitation_by_code),
+        )
+        .route("/whoami", get(whoami))
+        .layer(Extension(invitation_store))
         .layer(middleware::from_fn(auth_middleware))
-        .with_state(store);
+        // Oversized bodies get a 413 before anything buffers them (MAX_BODY_BYTES)
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(max_body_bytes()))
+        // Registered after the auth layer so health checks don't need a token
+        .route("/ready", get(ready::<S>))
+        .with_state(store)
+        .route("/health", get(health))
+        // Known paths called with the wrong method get a JSON 405
+        .method_not_allowed_fallback(method_not_allowed_fallback);
 
     // Create the main router
     let router = if prefix.is_empty() {
         // For tests or when no prefix is needed, don't nest the routes
         api_routes
+            // Opt-in {"data", "meta"} envelope for Accept: application/vnd.lockbox.v2+json
+            .layer(middleware::from_fn(response_envelope_middleware))
             .layer(cors)
             .layer(middleware::from_fn(logging_middleware))
+            // Outermost, so the request id is set for every log line below it
+            .layer(middleware::from_fn(correlation_id_middleware))
     } else {
         // For production, nest the routes under the prefix
         Router::new()
             .nest(prefix, api_routes)
+            .layer(middleware::from_fn(response_envelope_middleware))
             .layer(cors)
             .layer(middleware::from_fn(logging_middleware))
+            .layer(middleware::from_fn(correlation_id_middleware))
     };
 
     info!(
@@ -121,11 +196,5 @@ where
     );
 
     // Add a fallback handler for 404s
-    router.fallback(|req: Request| async move {
-        warn!("No route matched for: {} {}", req.method(), req.uri());
-        (
-            axum::http::StatusCode::NOT_FOUND,
-            "The requested resource was not found".to_string(),
-        )
-    })
+    router.fallback(not_found_fallback)
 }
@@ -1,11 +1,14 @@
 use axum::{
     body::Body,
     http::{Request, StatusCode},
-    Router,
+    Extension, Router,
 };
-use lockbox_shared::auth::create_test_request;
+use lockbox_shared::auth::{create_jwt_token, create_test_request};
+use lockbox_shared::body_limit::DEFAULT_MAX_BODY_BYTES;
+use lockbox_shared::envelope::ENVELOPE_MEDIA_TYPE;
 use lockbox_shared::store::dynamo::DynamoBoxStore;
-use lockbox_shared::store::BoxStore;
+use lockbox_shared::store::memory::{MemoryIdempotencyStore, MemoryInvitationStore};
+use lockbox_shared::store::{BoxStore, IdempotencyStore};
 use lockbox_shared::test_utils::dynamo_test_utils::{
     clear_dynamo_table, create_box_table, create_dynamo_client, use_dynamodb,
 };
@@ -16,8 +19,15 @@ use serde_json::json;
 use std::sync::Arc;
 use tower::ServiceExt;
 
+use crate::documents::{DocumentPresigner, S3Presigner};
+use crate::models::{
+    DEFAULT_MAX_DOCUMENTS_PER_BOX, DEFAULT_MAX_DOCUMENT_BYTES, DEFAULT_MAX_GUARDIANS_PER_BOX,
+    MAX_BOX_DESCRIPTION_LENGTH, MAX_BOX_ITEM_BYTES, MAX_BOX_NAME_LENGTH,
+};
 use crate::routes;
-use lockbox_shared::models::{now_str, BoxRecord, Guardian, GuardianStatus};
+use lockbox_shared::models::{
+    now_str, BoxRecord, Document, DocumentStorage, Guardian, GuardianStatus, UnlockPolicy,
+};
 
 // Constants for DynamoDB tests
 const TEST_TABLE_NAME: &str = "box-test-table";
@@ -29,6 +39,11 @@ enum TestStore {
 
 // Helper for setting up test router with appropriate store
 async fn create_test_app() -> (Router, TestStore) {
+    create_test_app_with_soft_delete(false).await
+}
+
+// Same as create_test_app, with the store's soft delete mode set explicitly
+async fn create_test_app_with_soft_delete(soft_delete: bool) -> (Router, TestStore) {
     // Initialize logging for tests
     lockbox_shared::test_utils::test_logging::init_test_logging();
 
@@ -56,20 +71,28 @@ async fn create_test_app() -> (Router, TestStore) {
         let _ = clear_dynamo_table(&client, TEST_TABLE_NAME).await;
 
         // Create the store with the test table
-        let store = Arc::new(DynamoBoxStore::with_client_and_table(
-            client.clone(),
-            TEST_TABLE_NAME.to_string(),
-        ));
+        let store = Arc::new(
+            DynamoBoxStore::with_client_and_table(client.clone(), TEST_TABLE_NAME.to_string())
+                .with_soft_delete(soft_delete),
+        );
 
         debug!("DynamoDB test setup complete");
-        let app = routes::create_router_with_store(store.clone(), "");
+        let app = routes::create_router_with_store(
+            store.clone(),
+            Arc::new(MemoryInvitationStore::new()),
+            "",
+        );
         debug!("Router created with empty prefix");
         (app, TestStore::DynamoDB(store))
     } else {
         debug!("Using mock store for tests");
         // Use empty mock store (data will be added in each test)
-        let store = Arc::new(MockBoxStore::new());
-        let app = routes::create_router_with_store(store.clone(), "");
+        let store = Arc::new(MockBoxStore::new().with_soft_delete(soft_delete));
+        let app = routes::create_router_with_store(
+            store.clone(),
+            Arc::new(MemoryInvitationStore::new()),
+            "",
+        );
         debug!("Router created with empty prefix");
         (app, TestStore::Mock(store))
     }
@@ -116,7 +139,10 @@ fn create_test_boxes(now: &str) -> Vec<BoxRecord> {
         documents: vec![],
         guardians: vec![],
         unlock_instructions: None,
-        unlock_request: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
         version: 0,
     };
 
@@ -132,7 +158,10 @@ fn create_test_boxes(now: &str) -> Vec<BoxRecord> {
         documents: vec![],
         guardians: vec![],
         unlock_instructions: None,
-        unlock_request: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
         version: 0,
     };
 
@@ -193,6 +222,167 @@ async fn test_get_boxes() {
     }
 }
 
+// Seeds three boxes for sort_user as (id, name, locked, updated_at)
+async fn seed_sortable_boxes(store: &TestStore) {
+    let seeds = [
+        ("sort_b", "Bravo", true, "2024-03-01T00:00:00+00:00"),
+        ("sort_a", "Alpha", false, "2024-01-01T00:00:00+00:00"),
+        ("sort_c", "Charlie", true, "2024-02-01T00:00:00+00:00"),
+    ];
+    for (id, name, locked, updated_at) in seeds {
+        let box_record = BoxRecord {
+            id: id.into(),
+            name: name.into(),
+            description: "Sortable box".into(),
+            is_locked: locked,
+            created_at: "2023-12-01T00:00:00+00:00".into(),
+            updated_at: updated_at.into(),
+            owner_id: "sort_user".into(),
+            owner_name: None,
+            documents: vec![],
+            guardians: vec![],
+            unlock_instructions: None,
+            unlock_requests: vec![],
+            required_approvals: None,
+            unlock_policy: UnlockPolicy::Majority,
+            deleted_at: None,
+            version: 0,
+        };
+        match store {
+            TestStore::Mock(mock) => mock.create_box(box_record).await.unwrap(),
+            TestStore::DynamoDB(dynamo) => dynamo.create_box(box_record).await.unwrap(),
+        };
+    }
+
+    if matches!(store, TestStore::DynamoDB(_)) {
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+    }
+}
+
+async fn list_sorted_box_ids(app: &Router, query: &str) -> Vec<String> {
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned?{}", query),
+            "sort_user",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    response_to_json(response).await["boxes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| b["id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_get_boxes_response_envelope() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    // Legacy shape unless the client asks for the envelope
+    let response = app
+        .clone()
+        .oneshot(create_test_request("GET", "/boxes/owned", "user_1", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let legacy = response_to_json(response).await;
+    assert!(legacy["boxes"].is_array());
+    assert!(legacy.get("data").is_none());
+
+    let mut request = create_test_request("GET", "/boxes/owned", "user_1", None);
+    request.headers_mut().insert(
+        axum::http::header::ACCEPT,
+        ENVELOPE_MEDIA_TYPE.parse().unwrap(),
+    );
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[axum::http::header::CONTENT_TYPE],
+        ENVELOPE_MEDIA_TYPE
+    );
+    let enveloped = response_to_json(response).await;
+    assert_eq!(enveloped["data"], legacy);
+    assert_eq!(enveloped["meta"]["status"], 200);
+    assert!(enveloped["meta"]["requestId"].is_string());
+
+    // Errors keep their body under "error"
+    let mut request = create_test_request("GET", "/boxes/owned/missing", "user_1", None);
+    request.headers_mut().insert(
+        axum::http::header::ACCEPT,
+        ENVELOPE_MEDIA_TYPE.parse().unwrap(),
+    );
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let enveloped = response_to_json(response).await;
+    assert!(enveloped["data"].is_null());
+    assert!(enveloped["error"]["error"].is_string());
+    assert_eq!(enveloped["meta"]["status"], 404);
+}
+
+#[tokio::test]
+async fn test_get_boxes_filter_locked() {
+    let (app, store) = create_test_app().await;
+    seed_sortable_boxes(&store).await;
+
+    let mut locked = list_sorted_box_ids(&app, "locked=true").await;
+    locked.sort();
+    assert_eq!(locked, vec!["sort_b", "sort_c"]);
+
+    assert_eq!(
+        list_sorted_box_ids(&app, "locked=false").await,
+        vec!["sort_a"]
+    );
+}
+
+#[tokio::test]
+async fn test_get_boxes_sorted() {
+    let (app, store) = create_test_app().await;
+    seed_sortable_boxes(&store).await;
+
+    assert_eq!(
+        list_sorted_box_ids(&app, "sort=name").await,
+        vec!["sort_a", "sort_b", "sort_c"]
+    );
+    assert_eq!(
+        list_sorted_box_ids(&app, "sort=name&order=desc").await,
+        vec!["sort_c", "sort_b", "sort_a"]
+    );
+    assert_eq!(
+        list_sorted_box_ids(&app, "sort=updatedAt&order=asc").await,
+        vec!["sort_a", "sort_c", "sort_b"]
+    );
+    assert_eq!(
+        list_sorted_box_ids(&app, "locked=true&sort=updatedAt&order=desc").await,
+        vec!["sort_b", "sort_c"]
+    );
+}
+
+#[tokio::test]
+async fn test_get_boxes_unknown_sort_field() {
+    let (app, _store) = create_test_app().await;
+
+    for query in ["sort=ownerId", "sort=name&order=sideways"] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "GET",
+                &format!("/boxes/owned?{}", query),
+                "sort_user",
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
+
 #[tokio::test]
 async fn test_get_box_success() {
     let (app, store) = create_test_app().await;
@@ -221,8 +411,8 @@ async fn test_get_box_success() {
 
     // Get the box directly from the store for comparison
     let store_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     // Verify that API and store data match
@@ -262,6 +452,38 @@ async fn test_get_box_not_found() {
     assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
 
+#[tokio::test]
+async fn test_missing_box_is_not_found_for_any_user() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    // A box that doesn't exist is a 404, even for a caller who owns nothing
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "DELETE",
+            "/boxes/owned/no_such_box",
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // An existing box owned by someone else is a 403
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "DELETE",
+            "/boxes/owned/box_2",
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
 #[tokio::test]
 async fn test_get_box_unauthorized() {
     let (app, store) = create_test_app().await;
@@ -307,126 +529,548 @@ async fn test_get_boxes_missing_authorization() {
 }
 
 #[tokio::test]
-async fn test_create_box() {
-    let (app, store) = create_test_app().await;
-
-    // Prepare test data
-    let box_payload = json!({
-        "name": "New Test Box",
-        "description": "Created during test"
-    });
+async fn test_health_check_without_authorization() {
+    let (app, _store) = create_test_app().await;
 
-    // Execute
+    // No authorization header - health checks must bypass auth
     let response = app
-        .clone()
-        .oneshot(create_test_request(
-            "POST",
-            "/boxes/owned",
-            "new_user",
-            Some(box_payload),
-        ))
+        .oneshot(
+            Request::builder()
+                .uri("/health")
+                .method("GET")
+                .body(Body::empty())
+                .unwrap(),
+        )
         .await
         .unwrap();
 
-    // Verify
-    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(response.status(), StatusCode::OK);
 
-    let body = response_to_json(response).await;
-    assert!(body.get("box").is_some());
-    let box_id = body["box"]["id"].as_str().unwrap().to_string();
-    assert_eq!(body["box"]["name"].as_str().unwrap(), "New Test Box");
+    let json_response = response_to_json(response).await;
+    assert_eq!(json_response["status"], "ok");
+    assert_eq!(json_response["service"], "box-service");
+    assert_eq!(json_response["version"], env!("CARGO_PKG_VERSION"));
+}
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
+#[tokio::test]
+async fn test_readiness_reflects_store_availability() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let store = Arc::new(MockBoxStore::new());
+    let app =
+        routes::create_router_with_store(store.clone(), Arc::new(MemoryInvitationStore::new()), "");
 
-    // Verify directly in the store
-    let stored_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+    // Unauthenticated, like /health
+    let ready = || {
+        app.clone().oneshot(
+            Request::builder()
+                .uri("/ready")
+                .method("GET")
+                .body(Body::empty())
+                .unwrap(),
+        )
     };
 
-    assert_eq!(stored_box.name, "New Test Box");
-    assert_eq!(stored_box.description, "Created during test");
-    assert_eq!(stored_box.owner_id, "new_user");
+    let response = ready().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response_to_json(response).await["status"], "ready");
+
+    store.set_unavailable(true);
+    let response = ready().await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response_to_json(response).await["status"], "unavailable");
+
+    store.set_unavailable(false);
+    assert_eq!(ready().await.unwrap().status(), StatusCode::OK);
 }
 
 #[tokio::test]
-async fn test_create_box_invalid_payload() {
-    // Setup
+async fn test_routes_nested_under_custom_prefix() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let store = Arc::new(MockBoxStore::with_data(create_test_boxes(&now_str())));
+    let app =
+        routes::create_router_with_store(store, Arc::new(MemoryInvitationStore::new()), "/Staging");
+
+    let get = |uri: &str| create_test_request("GET", uri, "user_1", None);
+
+    let response = app
+        .clone()
+        .oneshot(get("/Staging/boxes/owned"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert!(!body["boxes"].as_array().unwrap().is_empty());
+
+    let response = app.clone().oneshot(get("/Staging/health")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Unprefixed and unknown paths fall through to the 404 fallback
+    for uri in ["/boxes/owned", "/Prod/boxes/owned", "/Staging/nowhere"] {
+        let response = app.clone().oneshot(get(uri)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn test_unmatched_requests_get_json_errors() {
     let (app, _store) = create_test_app().await;
 
-    // Execute with invalid payload (missing name)
+    let response = app
+        .clone()
+        .oneshot(create_test_request("GET", "/no/such/path", "user_1", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let body = response_to_json(response).await;
+    assert_eq!(
+        body,
+        json!({ "error": "not_found", "path": "/no/such/path" })
+    );
+
+    // /boxes/owned only takes GET and POST
     let response = app
         .oneshot(create_test_request(
-            "POST",
+            "DELETE",
             "/boxes/owned",
-            "test_user",
-            Some(json!({
-                "description": "Missing name field"
-            })),
+            "user_1",
+            None,
         ))
         .await
         .unwrap();
-
-    // Verify
-    assert!(response.status().is_client_error());
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let body = response_to_json(response).await;
+    assert_eq!(body["error"], "method_not_allowed");
+    assert_eq!(body["method"], "DELETE");
+    assert_eq!(body["path"], "/boxes/owned");
 }
 
 #[tokio::test]
-async fn test_get_box_not_owned() {
+async fn test_get_boxes_paginated() {
     let (app, store) = create_test_app().await;
 
-    // Add test data to the store
+    // Three boxes for the same owner
+    let now = now_str();
+    for i in 0..3 {
+        let mut box_record = create_test_boxes(&now).remove(0);
+        box_record.id = format!("paged_box_{}", i);
+        match &store {
+            TestStore::Mock(mock) => {
+                mock.create_box(box_record).await.unwrap();
+            }
+            TestStore::DynamoDB(dynamo) => {
+                dynamo.create_box(box_record).await.unwrap();
+            }
+        }
+    }
+
+    // Follow nextCursor until the last page
+    let mut seen_ids = Vec::new();
+    let mut path = "/boxes/owned?limit=2".to_string();
+    loop {
+        let response = app
+            .clone()
+            .oneshot(create_test_request("GET", &path, "user_1", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response_to_json(response).await;
+        let boxes = body["boxes"].as_array().unwrap();
+        assert!(boxes.len() <= 2);
+        seen_ids.extend(boxes.iter().map(|b| b["id"].as_str().unwrap().to_string()));
+
+        match body["nextCursor"].as_str() {
+            Some(cursor) => path = format!("/boxes/owned?limit=2&cursor={}", cursor),
+            None => break,
+        }
+    }
+
+    seen_ids.sort();
+    assert_eq!(seen_ids, vec!["paged_box_0", "paged_box_1", "paged_box_2"]);
+}
+
+#[tokio::test]
+async fn test_get_boxes_invalid_pagination() {
+    let (app, store) = create_test_app().await;
     add_test_data_to_store(&store).await;
 
-    // Try to access a box owned by user_2 as user_1
+    // A limit outside 1-100 is rejected by ListParams
     let response = app
         .clone()
         .oneshot(create_test_request(
             "GET",
-            "/boxes/owned/box_2", // This box is owned by user_2
-            "user_1",             // But we're accessing as user_1
+            "/boxes/owned?limit=0",
+            "user_1",
             None,
         ))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response_to_json(response).await["field"], "limit");
 
-    // Verify status is UNAUTHORIZED (401)
-    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
-
-    // Verify response JSON
-    let body = response_to_json(response).await;
-    assert!(body.as_object().unwrap().contains_key("error"));
+    // A cursor that wasn't issued by the API is rejected
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned?cursor=not-a-cursor!",
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
 
 #[tokio::test]
-async fn test_update_box() {
+async fn test_create_box() {
     let (app, store) = create_test_app().await;
 
-    // Add test data to the store
-    add_test_data_to_store(&store).await;
-
-    // Get box directly from store
-    let box_id = "box_1";
-
-    // Prepare update data
-    let updated_box = json!({
-        "name": "Updated Box Name",
-        "description": "This description has been updated",
-        "isLocked": true,
+    // Prepare test data
+    let box_payload = json!({
+        "name": "New Test Box",
+        "description": "Created during test"
     });
 
-    // Execute update via API
-    let update_response = app
+    // Execute
+    let response = app
         .clone()
         .oneshot(create_test_request(
-            "PATCH",
-            &format!("/boxes/owned/{}", box_id),
-            "user_1",
-            Some(updated_box),
+            "POST",
+            "/boxes/owned",
+            "new_user",
+            Some(box_payload),
+        ))
+        .await
+        .unwrap();
+
+    // Verify
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let body = response_to_json(response).await;
+    assert!(body.get("box").is_some());
+    let box_id = body["box"]["id"].as_str().unwrap().to_string();
+    assert_eq!(body["box"]["name"].as_str().unwrap(), "New Test Box");
+
+    // Verify directly in the store
+    let stored_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
+    };
+
+    assert_eq!(stored_box.name, "New Test Box");
+    assert_eq!(stored_box.description, "Created during test");
+    assert_eq!(stored_box.owner_id, "new_user");
+}
+
+// POST /boxes/owned as idempotent_user, optionally with an Idempotency-Key
+async fn create_box_with_key(
+    app: &Router,
+    name: &str,
+    key: Option<&str>,
+) -> (StatusCode, serde_json::Value) {
+    let mut request = create_test_request(
+        "POST",
+        "/boxes/owned",
+        "idempotent_user",
+        Some(json!({ "name": name, "description": "Retried create" })),
+    );
+    if let Some(key) = key {
+        request
+            .headers_mut()
+            .insert("idempotency-key", key.parse().unwrap());
+    }
+    let response = app.clone().oneshot(request).await.unwrap();
+    (response.status(), response_to_json(response).await)
+}
+
+#[tokio::test]
+async fn test_create_box_idempotency_key() {
+    let (app, store) = create_test_app().await;
+    let app = app.layer(Extension(
+        Arc::new(MemoryIdempotencyStore::new()) as Arc<dyn IdempotencyStore>
+    ));
+
+    let (status, first) = create_box_with_key(&app, "Retried Box", Some("retry-1")).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // The retry gets the same box back instead of a second one
+    let (status, second) = create_box_with_key(&app, "Retried Box", Some("retry-1")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(second, first);
+
+    let owned = match &store {
+        TestStore::Mock(mock) => mock.get_boxes_by_owner("idempotent_user").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_boxes_by_owner("idempotent_user").await.unwrap(),
+    };
+    assert_eq!(owned.len(), 1);
+
+    // A different key, or none at all, still creates a new box
+    let (status, other) = create_box_with_key(&app, "Retried Box", Some("retry-2")).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_ne!(other["box"]["id"], first["box"]["id"]);
+    let (status, _) = create_box_with_key(&app, "Retried Box", None).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, _) = create_box_with_key(&app, "Retried Box", Some(" ")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_create_box_idempotency_key_in_progress() {
+    let (app, _store) = create_test_app().await;
+    let idempotency = Arc::new(MemoryIdempotencyStore::new());
+    let app = app.layer(Extension(idempotency.clone() as Arc<dyn IdempotencyStore>));
+
+    // The first request has claimed the key but not written its box yet
+    idempotency
+        .claim(
+            "create_box#idempotent_user#retry-pending",
+            "box-not-written-yet",
+            std::time::Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+
+    let (status, body) = create_box_with_key(&app, "Retried Box", Some("retry-pending")).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body["error"].as_str().unwrap().contains("in progress"));
+}
+
+#[tokio::test]
+async fn test_create_box_sets_owner_name_from_token() {
+    let (app, store) = create_test_app().await;
+
+    // Test tokens carry the user id as their cognito:username
+    let request = Request::builder()
+        .method("POST")
+        .uri("/boxes/owned")
+        .header(
+            "authorization",
+            format!("Bearer {}", create_jwt_token("named_owner")),
+        )
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "name": "Named Box", "description": "Has an owner name" }).to_string(),
+        ))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let body = response_to_json(response).await;
+    assert_eq!(body["box"]["ownerName"], "named_owner");
+
+    let box_id = body["box"]["id"].as_str().unwrap();
+    let stored_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert_eq!(stored_box.owner_name.as_deref(), Some("named_owner"));
+}
+
+#[tokio::test]
+async fn test_create_box_invalid_payload() {
+    // Setup
+    let (app, _store) = create_test_app().await;
+
+    // Execute with invalid payload (missing name)
+    let response = app
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned",
+            "test_user",
+            Some(json!({
+                "description": "Missing name field"
+            })),
+        ))
+        .await
+        .unwrap();
+
+    // Verify
+    assert!(response.status().is_client_error());
+}
+
+// Helper to POST a new box and return the status and JSON body
+async fn post_box(app: &Router, payload: serde_json::Value) -> (StatusCode, serde_json::Value) {
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned",
+            "test_user",
+            Some(payload),
+        ))
+        .await
+        .unwrap();
+    let status = response.status();
+    (status, response_to_json(response).await)
+}
+
+#[tokio::test]
+async fn test_create_box_empty_name() {
+    let (app, _store) = create_test_app().await;
+
+    let (status, body) = post_box(&app, json!({ "name": "", "description": "No name" })).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "Validation failed");
+    assert_eq!(body["fields"][0]["field"], "name");
+    assert_eq!(body["fields"][0]["reason"], "must not be empty");
+}
+
+#[tokio::test]
+async fn test_create_box_name_too_long() {
+    let (app, _store) = create_test_app().await;
+
+    // Exactly at the limit is fine
+    let (status, _) = post_box(
+        &app,
+        json!({ "name": "n".repeat(MAX_BOX_NAME_LENGTH), "description": "" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = post_box(
+        &app,
+        json!({ "name": "n".repeat(MAX_BOX_NAME_LENGTH + 1), "description": "" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let fields = body["fields"].as_array().unwrap();
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0]["field"], "name");
+}
+
+#[tokio::test]
+async fn test_create_box_description_too_long() {
+    let (app, _store) = create_test_app().await;
+
+    let (status, body) = post_box(
+        &app,
+        json!({
+            "name": "",
+            "description": "d".repeat(MAX_BOX_DESCRIPTION_LENGTH + 1)
+        }),
+    )
+    .await;
+
+    // Every failing field is reported, not just the first
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let fields: Vec<&str> = body["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, vec!["name", "description"]);
+}
+
+#[tokio::test]
+async fn test_create_box_rejects_unknown_field() {
+    let (app, _store) = create_test_app().await;
+
+    let (status, body) = post_box(
+        &app,
+        json!({ "name": "Typo box", "descripton": "Lost description" }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "validation");
+    assert_eq!(body["field"], "descripton");
+}
+
+#[tokio::test]
+async fn test_update_box_rejects_unknown_field() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let patch = |payload: serde_json::Value| {
+        app.clone().oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1",
+            "user_1",
+            Some(payload),
+        ))
+    };
+
+    let response = patch(json!({ "unlockInstruction": "Call my sister" }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = response_to_json(response).await;
+    assert_eq!(body["field"], "unlockInstruction");
+
+    // Null still clears an optional field and absent fields are left alone
+    let response = patch(json!({ "unlockInstructions": "Call my sister" }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = patch(json!({ "unlockInstructions": null })).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert!(body["box"]["unlockInstructions"].is_null());
+    assert_eq!(body["box"]["name"], "Test Box 1");
+}
+
+#[tokio::test]
+async fn test_get_box_not_owned() {
+    let (app, store) = create_test_app().await;
+
+    // Add test data to the store
+    add_test_data_to_store(&store).await;
+
+    // Try to access a box owned by user_2 as user_1
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned/box_2", // This box is owned by user_2
+            "user_1",             // But we're accessing as user_1
+            None,
+        ))
+        .await
+        .unwrap();
+
+    // Verify status is FORBIDDEN (403)
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Verify response JSON
+    let body = response_to_json(response).await;
+    assert!(body.as_object().unwrap().contains_key("error"));
+}
+
+#[tokio::test]
+async fn test_update_box() {
+    let (app, store) = create_test_app().await;
+
+    // Add test data to the store
+    add_test_data_to_store(&store).await;
+
+    // Get box directly from store
+    let box_id = "box_1";
+
+    // Locking needs an accepted lead guardian and unlock instructions
+    seed_lockable_box_1(&store).await;
+
+    // Prepare update data
+    let updated_box = json!({
+        "name": "Updated Box Name",
+        "description": "This description has been updated",
+        "isLocked": true,
+    });
+
+    // Execute update via API
+    let update_response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}", box_id),
+            "user_1",
+            Some(updated_box),
         ))
         .await
         .unwrap();
@@ -445,16 +1089,10 @@ async fn test_update_box() {
     );
     assert_eq!(update_body["box"]["isLocked"].as_bool().unwrap(), true);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify directly in the store
     let stored_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     assert_eq!(stored_box.name, "Updated Box Name");
@@ -473,8 +1111,8 @@ async fn test_update_box_partial() {
     // Get a box to update directly from the store
     let box_id = "box_1";
     let initial_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     let initial_description = initial_box.description.clone();
@@ -498,16 +1136,10 @@ async fn test_update_box_partial() {
 
     assert_eq!(response.status(), StatusCode::OK);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Get the box directly from store to confirm partial update
     let updated_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     // Name should be updated, description should remain the same
@@ -528,8 +1160,8 @@ async fn test_update_box_not_owned() {
 
     // Get the initial state directly from store
     let initial_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     let initial_name = initial_box.name.clone();
@@ -555,18 +1187,12 @@ async fn test_update_box_not_owned() {
         .await
         .unwrap();
 
-    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
-
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
 
     // Verify the box is unchanged directly from the store
     let final_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     // Box should remain unchanged
@@ -597,23 +1223,17 @@ async fn test_delete_box() {
     // Verify response
     assert_eq!(delete_response.status(), StatusCode::OK);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify box has been deleted by trying to get it from the store
     match &store {
         TestStore::Mock(mock) => {
-            let result = mock.get_box(&box_id).await;
+            let result = mock.get_box_consistent(&box_id).await;
             assert!(
                 result.is_err() || result.unwrap().id.is_empty(),
                 "Box should not exist in store after deletion"
             );
         }
         TestStore::DynamoDB(dynamo) => {
-            let result = dynamo.get_box(&box_id).await;
+            let result = dynamo.get_box_consistent(&box_id).await;
             assert!(
                 result.is_err() || result.unwrap().id.is_empty(),
                 "Box should not exist in store after deletion"
@@ -677,16 +1297,10 @@ async fn test_update_box_add_documents() {
 
     assert_eq!(response.status(), StatusCode::OK);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Get the updated box directly from the store
     let updated_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     // Check that the document was added
@@ -706,6 +1320,83 @@ async fn test_update_box_add_documents() {
     }
 }
 
+#[tokio::test]
+async fn test_update_guardian_missing_field_detail() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let response = app
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1/guardian",
+            "user_1",
+            Some(json!({})),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = response_to_json(response).await;
+    assert_eq!(body["error"], "validation");
+    assert_eq!(body["detail"], "missing field `guardian`");
+}
+
+#[tokio::test]
+async fn test_update_guardian_rejects_nested_unknown_field() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let patch = |uri: &str, payload: serde_json::Value| {
+        app.clone()
+            .oneshot(create_test_request("PATCH", uri, "user_1", Some(payload)))
+    };
+
+    let response = patch(
+        "/boxes/owned/box_1/guardian",
+        json!({
+            "guardian": {
+                "id": "typo_guardian",
+                "name": "Typo Guardian",
+                "emial": "typo@example.com",
+                "leadGuardian": false,
+                "status": "invited",
+                "addedAt": "2024-01-01T00:00:00Z",
+                "invitationId": "typo_invitation"
+            }
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = response_to_json(response).await;
+    assert_eq!(body["error"], "validation");
+    assert_eq!(body["field"], "guardian.emial");
+
+    let response = patch(
+        "/boxes/owned/box_1/document",
+        json!({
+            "document": {
+                "id": "typo_doc",
+                "title": "Typo",
+                "contnet": "Lost content",
+                "createdAt": "2024-01-01T00:00:00Z"
+            }
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = response_to_json(response).await;
+    assert_eq!(body["field"], "document.contnet");
+
+    let box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert!(box_record.guardians.iter().all(|g| g.id != "typo_guardian"));
+    assert!(box_record.documents.iter().all(|d| d.id != "typo_doc"));
+}
+
 #[tokio::test]
 async fn test_update_box_add_guardians() {
     let (app, store) = create_test_app().await;
@@ -760,6 +1451,9 @@ async fn test_update_box_lock() {
     // Use an existing box from the test data
     let box_id = "box_1";
 
+    // Only boxes that meet the locking rules can be locked
+    seed_lockable_box_1(&store).await;
+
     // Create a complete update payload with all fields using camelCase for JSON API
     let payload = json!({
         "isLocked": true,  // We're changing this field - uses camelCase for JSON API
@@ -794,22 +1488,150 @@ async fn test_update_box_lock() {
     // Verify update was successful with the real status code
     assert_eq!(status, StatusCode::OK);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Get the box from store to verify the update was received
     let updated_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     // Verify is_locked was updated
     assert_eq!(updated_box.is_locked, true);
 }
 
+async fn patch_box_1_with_if_match(app: &Router, if_match: Option<&str>) -> StatusCode {
+    let mut request = create_test_request(
+        "PATCH",
+        "/boxes/owned/box_1",
+        "user_1",
+        Some(json!({ "name": "Edited" })),
+    );
+    if let Some(if_match) = if_match {
+        request
+            .headers_mut()
+            .insert("if-match", if_match.parse().unwrap());
+    }
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_update_box_if_match() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned/box_1",
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    assert_eq!(etag, "\"0\"");
+
+    // The current tag is accepted and the response carries the next one
+    let mut request = create_test_request(
+        "PATCH",
+        "/boxes/owned/box_1",
+        "user_1",
+        Some(json!({ "name": "Edited" })),
+    );
+    request
+        .headers_mut()
+        .insert("if-match", etag.parse().unwrap());
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["etag"], "\"1\"");
+
+    // Reusing the old tag means another edit happened in between
+    assert_eq!(
+        patch_box_1_with_if_match(&app, Some(&etag)).await,
+        StatusCode::PRECONDITION_FAILED
+    );
+    let box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert_eq!(box_record.version, 1);
+}
+
+#[tokio::test]
+async fn test_update_box_without_if_match_last_write_wins() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    assert_eq!(patch_box_1_with_if_match(&app, None).await, StatusCode::OK);
+    assert_eq!(patch_box_1_with_if_match(&app, None).await, StatusCode::OK);
+    assert_eq!(
+        patch_box_1_with_if_match(&app, Some("*")).await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn test_update_box_dry_run() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    seed_lockable_box_1(&store).await;
+
+    let before = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1?dryRun=true",
+            "user_1",
+            Some(json!({ "name": "Renamed", "isLocked": true, "unlockInstructions": "Ring the lawyer" })),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_response = response_to_json(response).await;
+    assert_eq!(json_response["dryRun"], true);
+    assert_eq!(json_response["box"]["name"], "Renamed");
+    assert_eq!(json_response["box"]["isLocked"], true);
+    assert_eq!(
+        json_response["box"]["unlockInstructions"],
+        "Ring the lawyer"
+    );
+
+    // The stored box is untouched
+    let after = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert_eq!(after.name, before.name);
+    assert_eq!(after.is_locked, before.is_locked);
+    assert_eq!(after.version, before.version);
+    assert_eq!(after.updated_at, before.updated_at);
+}
+
+#[tokio::test]
+async fn test_update_box_dry_run_still_checks_ownership() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let response = app
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1?dryRun=true",
+            "user_2",
+            Some(json!({ "name": "Hijacked" })),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
 #[tokio::test]
 async fn test_update_box_unlock_instructions() {
     // Setup with mock data
@@ -839,16 +1661,10 @@ async fn test_update_box_unlock_instructions() {
     // Verify update was successful
     assert_eq!(response.status(), StatusCode::OK);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Get the box from store to verify the update
     let updated_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     // Verify unlock_instructions was updated
@@ -872,8 +1688,8 @@ async fn test_update_box_clear_unlock_instructions() {
 
     // Get the initial box directly from store
     let mut box_record = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     // Directly set unlock instructions in the box record
@@ -886,16 +1702,10 @@ async fn test_update_box_clear_unlock_instructions() {
         TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify the instructions were set by checking directly in the store
     let box_with_instructions = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     assert!(box_with_instructions.unlock_instructions.is_some());
@@ -921,16 +1731,10 @@ async fn test_update_box_clear_unlock_instructions() {
     // Verify update was successful
     assert_eq!(response.status(), StatusCode::OK);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Get the box from store to verify the update
     let updated_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     // Verify unlock_instructions was cleared
@@ -954,8 +1758,8 @@ async fn test_update_single_guardian() {
 
     // First get the box directly from store
     let mut box_record = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     // Add a guardian directly to the box
@@ -967,6 +1771,10 @@ async fn test_update_single_guardian() {
         status: GuardianStatus::Invited,
         added_at: "2023-01-01T12:00:00Z".to_string(),
         invitation_id: "inv-guardian-a".to_string(),
+        email: None,
+        relationship: None,
+        note: None,
+        last_viewed_at: None,
     };
 
     box_record.guardians.push(guardian_record);
@@ -977,16 +1785,10 @@ async fn test_update_single_guardian() {
         TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify the guardian was added directly in the store
     let box_with_guardian = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     let initial_guardian = box_with_guardian
@@ -1031,400 +1833,1867 @@ async fn test_update_single_guardian() {
     // The rest of the test is skipped as the request is expected to fail
 }
 
+// Helper to lock box_1 and give it the listed (guardian id, is lead) guardians
+async fn seed_box_1_guardians(store: &TestStore, guardians: &[(&str, bool)]) {
+    let mut box_record = match store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    box_record.is_locked = true;
+    box_record.guardians = guardians
+        .iter()
+        .map(|(id, lead)| Guardian {
+            id: id.to_string(),
+            name: format!("Guardian {}", id),
+            lead_guardian: *lead,
+            status: GuardianStatus::Accepted,
+            added_at: "2023-01-01T12:00:00Z".to_string(),
+            invitation_id: format!("inv-{}", id),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
+        })
+        .collect();
+    match store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+}
+
+// An unlocked box_1 that meets the locking rules: an accepted lead and instructions
+async fn seed_lockable_box_1(store: &TestStore) {
+    seed_box_1_guardians(store, &[("lead", true)]).await;
+    let mut box_record = match store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    box_record.is_locked = false;
+    box_record.unlock_instructions = Some("Call my sister".into());
+    match store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+}
+
+async fn delete_box_1_guardian(app: &Router, guardian_id: &str) -> StatusCode {
+    app.clone()
+        .oneshot(create_test_request(
+            "DELETE",
+            &format!("/boxes/owned/box_1/guardian/{}", guardian_id),
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap()
+        .status()
+}
+
 #[tokio::test]
-async fn test_update_guardian_invalid_payload() {
-    // Setup with mock data
-    let (app, _store) = create_test_app().await;
+async fn test_delete_non_lead_guardian() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    seed_box_1_guardians(&store, &[("lead", true), ("helper", false)]).await;
 
-    // Use an existing box from the test data
-    let box_id = "box_1";
+    assert_eq!(delete_box_1_guardian(&app, "helper").await, StatusCode::OK);
+}
 
-    // Create an invalid payload (missing required fields)
-    let invalid_payload = json!({
-        // Missing the "guardian" field
-        "some_other_field": "value"
-    });
+#[tokio::test]
+async fn test_delete_redundant_lead_guardian() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    seed_box_1_guardians(&store, &[("lead_a", true), ("lead_b", true)]).await;
 
-    // Make the request with an invalid payload
+    assert_eq!(delete_box_1_guardian(&app, "lead_a").await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_delete_last_lead_guardian_of_locked_box() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    seed_box_1_guardians(&store, &[("lead", true), ("helper", false)]).await;
+
+    assert_eq!(
+        delete_box_1_guardian(&app, "lead").await,
+        StatusCode::CONFLICT
+    );
+
+    // The lead is still on the box
+    let box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert!(box_record.guardians.iter().any(|g| g.id == "lead"));
+}
+
+fn bulk_guardian(id: &str, invitation_id: &str, lead: bool) -> serde_json::Value {
+    json!({
+        "id": id,
+        "name": format!("Guardian {}", id),
+        "leadGuardian": lead,
+        "status": "invited",
+        "addedAt": "2023-01-01T12:00:00Z",
+        "invitationId": invitation_id
+    })
+}
+
+#[tokio::test]
+async fn test_update_guardians_bulk() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    seed_box_1_guardians(&store, &[("lead", true)]).await;
+
+    // One existing guardian updated by invitation id, two new ones added
+    let payload = json!({
+        "guardians": [
+            bulk_guardian("lead", "inv-lead", true),
+            bulk_guardian("helper_a", "inv-helper-a", false),
+            bulk_guardian("helper_b", "inv-helper-b", false),
+        ]
+    });
     let response = app
         .clone()
         .oneshot(create_test_request(
-            "PATCH",
-            &format!("/boxes/owned/{}/guardian", box_id),
+            "PUT",
+            "/boxes/owned/box_1/guardians",
             "user_1",
-            Some(invalid_payload),
+            Some(payload),
         ))
         .await
         .unwrap();
 
-    // Verify unprocessable entity status
-    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_response = response_to_json(response).await;
+    let ids: Vec<&str> = json_response["guardians"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|g| g["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, vec!["lead", "helper_a", "helper_b"]);
+
+    // All three landed in a single version bump
+    let box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert_eq!(box_record.guardians.len(), 3);
+    assert_eq!(box_record.guardians[0].status, GuardianStatus::Invited);
+    assert_eq!(box_record.version, 2);
 }
 
 #[tokio::test]
-async fn test_update_document_invalid_payload() {
-    // Setup with mock data
-    let (app, _store) = create_test_app().await;
-
-    // Use an existing box from the test data
-    let box_id = "box_1";
+async fn test_update_guardians_duplicate_invitation_id() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
 
-    // Create an invalid payload (missing required fields)
-    let invalid_payload = json!({
-        // Missing the "document" field
-        "some_other_field": "value"
+    let payload = json!({
+        "guardians": [
+            bulk_guardian("helper_a", "inv-shared", false),
+            bulk_guardian("helper_b", "inv-shared", false),
+        ]
     });
-
-    // Make the request with an invalid payload
     let response = app
         .clone()
         .oneshot(create_test_request(
-            "PATCH",
-            &format!("/boxes/owned/{}/document", box_id),
+            "PUT",
+            "/boxes/owned/box_1/guardians",
             "user_1",
-            Some(invalid_payload),
+            Some(payload),
         ))
         .await
         .unwrap();
 
-    // Verify unprocessable entity status
     assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json_response = response_to_json(response).await;
+    assert_eq!(
+        json_response["fields"][0]["field"],
+        "guardians[1].invitationId"
+    );
+
+    // Nothing was written
+    let box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert!(box_record.guardians.is_empty());
 }
 
 #[tokio::test]
-async fn test_update_existing_document() {
-    // Setup with mock data
+async fn test_update_guardians_validates_merged_list() {
     let (app, store) = create_test_app().await;
-
-    // Add test data to the store
     add_test_data_to_store(&store).await;
+    seed_box_1_guardians(&store, &[("lead", true), ("helper_a", false)]).await;
 
-    // Use an existing box from the test data
-    let box_id = "box_1";
+    let put = |guardians: serde_json::Value| {
+        app.clone().oneshot(create_test_request(
+            "PUT",
+            "/boxes/owned/box_1/guardians",
+            "user_1",
+            Some(json!({ "guardians": guardians })),
+        ))
+    };
 
-    // First add a document
-    let initial_document = json!({
-        "document": {
-            "id": "doc_to_update",
-            "title": "Initial Title",
-            "content": "Initial content",
-            "createdAt": "2023-01-01T12:00:00Z"
-        }
-    });
+    // One lead in the payload is fine on its own, but not next to the box's lead
+    let response = put(json!([bulk_guardian("helper_b", "inv-helper-b", true)]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json_response = response_to_json(response).await;
+    assert_eq!(json_response["fields"][0]["field"], "guardians");
 
-    let initial_response = app
-        .clone()
-        .oneshot(create_test_request(
-            "PATCH",
-            &format!("/boxes/owned/{}/document", box_id),
-            "user_1", // Box owner
-            Some(initial_document),
-        ))
+    // Moving lead's invitation onto helper_a would leave it on two guardians
+    let response = put(json!([bulk_guardian("helper_a", "inv-lead", false)]))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
 
-    assert_eq!(initial_response.status(), StatusCode::OK);
+    // Nothing was written
+    let box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    let guardians: Vec<(&str, &str, bool)> = box_record
+        .guardians
+        .iter()
+        .map(|g| (g.id.as_str(), g.invitation_id.as_str(), g.lead_guardian))
+        .collect();
+    assert_eq!(
+        guardians,
+        vec![
+            ("lead", "inv-lead", true),
+            ("helper_a", "inv-helper_a", false)
+        ]
+    );
 
-    // Now update the same document
-    let updated_document = json!({
-        "document": {
-            "id": "doc_to_update",
-            "title": "Updated Title",
-            "content": "Updated content with more information",
-            "createdAt": "2023-01-01T12:00:00Z"
-        }
-    });
+    // Handing the lead over in the same request is allowed
+    let response = put(json!([
+        bulk_guardian("lead", "inv-lead", false),
+        bulk_guardian("helper_a", "inv-helper_a", true),
+    ]))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
 
-    let update_response = app
+#[tokio::test]
+async fn test_update_guardians_rejects_email_collisions() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let put = |guardians: serde_json::Value| {
+        app.clone().oneshot(create_test_request(
+            "PUT",
+            "/boxes/owned/box_1/guardians",
+            "user_1",
+            Some(json!({ "guardians": guardians })),
+        ))
+    };
+
+    let mut alice = bulk_guardian("helper_a", "inv-helper-a", false);
+    alice["email"] = json!("Alice@x.com");
+    let response = put(json!([alice])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // A different guardian with the same email, in any casing, is a conflict
+    // rather than a silent overwrite of the first
+    let mut other = bulk_guardian("helper_b", "inv-helper-b", false);
+    other["email"] = json!("  alice@X.COM ");
+    let response = put(json!([other])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // The same goes for two entries in one request
+    let mut first = bulk_guardian("helper_c", "inv-helper-c", false);
+    first["email"] = json!("carol@x.com");
+    let mut second = bulk_guardian("helper_d", "inv-helper-d", false);
+    second["email"] = json!("Carol@x.com");
+    let response = put(json!([first, second])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // So is adding one through the single guardian endpoint
+    let mut single = bulk_guardian("helper_e", "inv-helper-e", false);
+    single["email"] = json!("ALICE@x.com");
+    let response = app
         .clone()
         .oneshot(create_test_request(
             "PATCH",
-            &format!("/boxes/owned/{}/document", box_id),
-            "user_1", // Box owner
-            Some(updated_document),
+            "/boxes/owned/box_1/guardian",
+            "user_1",
+            Some(json!({ "guardian": single })),
         ))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
 
-    assert_eq!(update_response.status(), StatusCode::OK);
-
-    // Verify the updated document info in the response
-    let json_response = response_to_json(update_response).await;
-    let document_response = json_response["document"].as_object().unwrap();
-
-    // Get the documents array
-    let documents = document_response["documents"].as_array().unwrap();
-
-    // Find our updated document
-    let updated_doc = documents
-        .iter()
-        .find(|d| d["id"].as_str().unwrap() == "doc_to_update")
-        .expect("Updated document should be in the response");
-
-    // Verify each field was updated correctly
-    assert_eq!(updated_doc["title"].as_str().unwrap(), "Updated Title");
+    let box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert_eq!(box_record.guardians.len(), 1);
+    assert_eq!(box_record.guardians[0].id, "helper_a");
     assert_eq!(
-        updated_doc["content"].as_str().unwrap(),
-        "Updated content with more information"
+        box_record.guardians[0].email.as_deref(),
+        Some("alice@x.com")
     );
+
+    // The guardian who owns the email can still update themselves by id
+    let mut again = bulk_guardian("helper_a", "inv-helper-a-again", true);
+    again["email"] = json!("ALICE@x.com");
+    let response = put(json!([again])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_response = response_to_json(response).await;
+    let guardians = json_response["guardians"].as_array().unwrap();
+    assert_eq!(guardians.len(), 1);
+    assert_eq!(guardians[0]["email"], "alice@x.com");
+    assert_eq!(guardians[0]["invitationId"], "inv-helper-a-again");
+    assert_eq!(guardians[0]["leadGuardian"], true);
 }
 
 #[tokio::test]
-async fn test_update_document_unauthorized() {
-    // Setup with mock data
+async fn test_client_timestamps_are_stored_in_utc() {
     let (app, store) = create_test_app().await;
-
-    // Add test data to the store
     add_test_data_to_store(&store).await;
 
-    // Use an existing box from the test data
-    let box_id = "box_2"; // box_2 is owned by user_2
+    let mut guardian = bulk_guardian("helper_a", "inv-helper-a", false);
+    guardian["addedAt"] = json!("2024-01-01T17:30:00+05:30");
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PUT",
+            "/boxes/owned/box_1/guardians",
+            "user_1",
+            Some(json!({ "guardians": [guardian] })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
 
-    // Try to add a document as a non-owner
     let document = json!({
         "document": {
-            "id": "unauthorized_doc",
-            "title": "Unauthorized Document",
-            "content": "This should fail",
-            "createdAt": "2023-01-01T12:00:00Z"
+            "id": "doc_tz",
+            "title": "Offset",
+            "content": "Written in Kolkata",
+            "createdAt": "2024-01-01T17:30:00+05:30"
         }
     });
-
-    // Make the request with a user who is not the owner
     let response = app
         .clone()
         .oneshot(create_test_request(
             "PATCH",
-            &format!("/boxes/owned/{}/document", box_id),
-            "user_1", // Not the owner of box_2
+            "/boxes/owned/box_1/document",
+            "user_1",
             Some(document),
         ))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
 
-    // Verify unauthorized status
-    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert_eq!(box_record.guardians[0].added_at, "2024-01-01T12:00:00Z");
+    let stored_document = box_record
+        .documents
+        .iter()
+        .find(|d| d.id == "doc_tz")
+        .unwrap();
+    assert_eq!(stored_document.created_at, "2024-01-01T12:00:00Z");
+
+    // Anything that isn't RFC 3339 is rejected rather than stored as is
+    let mut guardian = bulk_guardian("helper_b", "inv-helper-b", false);
+    guardian["addedAt"] = json!("last tuesday");
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PUT",
+            "/boxes/owned/box_1/guardians",
+            "user_1",
+            Some(json!({ "guardians": [guardian] })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
 
 #[tokio::test]
-async fn test_delete_document() {
-    // Setup with mock data
+async fn test_patch_guardian_email_conflict() {
     let (app, store) = create_test_app().await;
-
-    // Add test data to the store
     add_test_data_to_store(&store).await;
 
-    // Use an existing box from the test data
-    let box_id = "box_1";
-
-    // First add a document
-    let document = json!({
-        "document": {
-            "id": "doc_to_delete",
-            "title": "Document to Delete",
-            "content": "This document will be deleted",
-            "createdAt": "2023-01-01T12:00:00Z"
-        }
+    let mut first = bulk_guardian("helper_a", "inv-helper-a", false);
+    first["email"] = json!("alice@x.com");
+    let payload = json!({
+        "guardians": [first, bulk_guardian("helper_b", "inv-helper-b", false)]
     });
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PUT",
+            "/boxes/owned/box_1/guardians",
+            "user_1",
+            Some(payload),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
 
-    // Make the request to add a document
-    let add_response = app
+    let response = app
         .clone()
         .oneshot(create_test_request(
             "PATCH",
-            &format!("/boxes/owned/{}/document", box_id),
-            "user_1", // Box owner
-            Some(document),
+            "/boxes/owned/box_1/guardian/helper_b",
+            "user_1",
+            Some(json!({ "email": " Alice@X.com" })),
         ))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
 
-    // Verify update was successful
-    assert_eq!(add_response.status(), StatusCode::OK);
-
-    // Now delete the document
-    let delete_response = app
+    let response = app
         .clone()
         .oneshot(create_test_request(
-            "DELETE",
-            &format!("/boxes/owned/{}/document/doc_to_delete", box_id),
-            "user_1", // Box owner
-            None,
+            "PATCH",
+            "/boxes/owned/box_1/guardian/helper_b",
+            "user_1",
+            Some(json!({ "email": " Bob@X.com" })),
         ))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_response = response_to_json(response).await;
+    assert_eq!(json_response["guardian"]["email"], "bob@x.com");
+}
 
-    // Verify delete was successful
-    assert_eq!(delete_response.status(), StatusCode::OK);
-
-    // Verify the response structure
-    let json_response = response_to_json(delete_response).await;
-    assert!(
-        json_response.get("message").is_some(),
-        "Response should contain a 'message' field"
-    );
-    assert!(
-        json_response.get("document").is_some(),
-        "Response should contain a 'document' field"
-    );
-
-    // Get the box to confirm the document was deleted
-    let get_response = app
+async fn patch_box_1_guardian(
+    app: &Router,
+    guardian_id: &str,
+    payload: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let response = app
+        .clone()
         .oneshot(create_test_request(
-            "GET",
-            &format!("/boxes/owned/{}", box_id),
+            "PATCH",
+            &format!("/boxes/owned/box_1/guardian/{}", guardian_id),
             "user_1",
-            None,
+            Some(payload),
         ))
         .await
         .unwrap();
+    let status = response.status();
+    (status, response_to_json(response).await)
+}
 
-    assert_eq!(get_response.status(), StatusCode::OK);
+#[tokio::test]
+async fn test_patch_guardian_status_only() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    seed_box_1_guardians(&store, &[("lead", true), ("helper", false)]).await;
 
-    // Verify the document is not in the box
-    let box_json = response_to_json(get_response).await;
-    let docs = box_json["box"]["documents"].as_array().unwrap();
-    let deleted_doc = docs
+    let (status, body) =
+        patch_box_1_guardian(&app, "helper", json!({ "status": "rejected" })).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Everything other than status is untouched
+    let guardian = &body["guardian"];
+    assert_eq!(guardian["status"], "rejected");
+    assert_eq!(guardian["name"], "Guardian helper");
+    assert_eq!(guardian["leadGuardian"], false);
+    assert_eq!(guardian["addedAt"], "2023-01-01T12:00:00Z");
+    assert_eq!(guardian["invitationId"], "inv-helper");
+}
+
+#[tokio::test]
+async fn test_patch_guardian_name_only() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    seed_box_1_guardians(&store, &[("lead", true), ("helper", false)]).await;
+
+    let (status, _body) =
+        patch_box_1_guardian(&app, "helper", json!({ "name": "Renamed Helper" })).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    let helper = box_record
+        .guardians
         .iter()
-        .find(|d| d["id"].as_str().unwrap() == "doc_to_delete");
+        .find(|g| g.id == "helper")
+        .unwrap();
+    assert_eq!(helper.name, "Renamed Helper");
+    assert_eq!(helper.status, GuardianStatus::Accepted);
+    assert!(!helper.lead_guardian);
+    assert_eq!(helper.invitation_id, "inv-helper");
+}
 
-    assert!(deleted_doc.is_none(), "Document should be deleted");
+#[tokio::test]
+async fn test_patch_guardian_rejects_null_and_unknown_guardian() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    seed_box_1_guardians(&store, &[("lead", true), ("helper", false)]).await;
+
+    let (status, _body) = patch_box_1_guardian(&app, "helper", json!({ "name": null })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _body) = patch_box_1_guardian(&app, "nobody", json!({ "name": "Nobody" })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Demoting the only lead of a locked box hits the same guard as deleting it
+    let (status, _body) =
+        patch_box_1_guardian(&app, "lead", json!({ "leadGuardian": false })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
 }
 
 #[tokio::test]
-async fn test_delete_document_nonexistent() {
-    // Setup with test data
+async fn test_update_guardian_invalid_payload() {
+    // Setup with mock data
     let (app, _store) = create_test_app().await;
 
     // Use an existing box from the test data
     let box_id = "box_1";
-    let nonexistent_doc_id = "nonexistent_doc";
 
-    // Try to delete a document that doesn't exist
+    // Create an invalid payload (missing required fields)
+    let invalid_payload = json!({
+        // Missing the "guardian" field
+        "some_other_field": "value"
+    });
+
+    // Make the request with an invalid payload
     let response = app
+        .clone()
         .oneshot(create_test_request(
-            "DELETE",
-            &format!("/boxes/owned/{}/document/{}", box_id, nonexistent_doc_id),
-            "user_1", // Box owner
-            None,
+            "PATCH",
+            &format!("/boxes/owned/{}/guardian", box_id),
+            "user_1",
+            Some(invalid_payload),
         ))
         .await
         .unwrap();
 
-    // Verify not found status
-    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    // Verify unprocessable entity status
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
 }
 
 #[tokio::test]
-async fn test_delete_document_unauthorized() {
-    // Setup with test data
+async fn test_update_document_invalid_payload() {
+    // Setup with mock data
+    let (app, _store) = create_test_app().await;
+
+    // Use an existing box from the test data
+    let box_id = "box_1";
+
+    // Create an invalid payload (missing required fields)
+    let invalid_payload = json!({
+        // Missing the "document" field
+        "some_other_field": "value"
+    });
+
+    // Make the request with an invalid payload
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}/document", box_id),
+            "user_1",
+            Some(invalid_payload),
+        ))
+        .await
+        .unwrap();
+
+    // Verify unprocessable entity status
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_update_existing_document() {
+    // Setup with mock data
     let (app, store) = create_test_app().await;
 
     // Add test data to the store
     add_test_data_to_store(&store).await;
 
     // Use an existing box from the test data
-    let box_id = "box_2"; // box_2 is owned by user_2
+    let box_id = "box_1";
 
-    // First add a document as the owner
-    let document = json!({
+    // First add a document
+    let initial_document = json!({
         "document": {
-            "id": "doc_in_box_2",
-            "title": "Document in Box 2",
-            "content": "This document belongs to user_2's box",
+            "id": "doc_to_update",
+            "title": "Initial Title",
+            "content": "Initial content",
             "createdAt": "2023-01-01T12:00:00Z"
         }
     });
 
-    // Make the request to add a document as the owner
-    let add_response = app
+    let initial_response = app
         .clone()
         .oneshot(create_test_request(
             "PATCH",
             &format!("/boxes/owned/{}/document", box_id),
-            "user_2", // Box owner
-            Some(document),
+            "user_1", // Box owner
+            Some(initial_document),
         ))
         .await
         .unwrap();
 
-    // Verify update was successful
-    assert_eq!(add_response.status(), StatusCode::OK);
+    assert_eq!(initial_response.status(), StatusCode::OK);
 
-    // Try to delete the document as a non-owner
-    let delete_response = app
+    // Now update the same document
+    let updated_document = json!({
+        "document": {
+            "id": "doc_to_update",
+            "title": "Updated Title",
+            "content": "Updated content with more information",
+            "createdAt": "2023-01-01T12:00:00Z"
+        }
+    });
+
+    let update_response = app
         .clone()
         .oneshot(create_test_request(
-            "DELETE",
-            &format!("/boxes/owned/{}/document/doc_in_box_2", box_id),
+            "PATCH",
+            &format!("/boxes/owned/{}/document", box_id),
+            "user_1", // Box owner
+            Some(updated_document),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(update_response.status(), StatusCode::OK);
+
+    // Verify the updated document info in the response
+    let json_response = response_to_json(update_response).await;
+    let document_response = json_response["document"].as_object().unwrap();
+
+    // Get the documents array
+    let documents = document_response["documents"].as_array().unwrap();
+
+    // Find our updated document
+    let updated_doc = documents
+        .iter()
+        .find(|d| d["id"].as_str().unwrap() == "doc_to_update")
+        .expect("Updated document should be in the response");
+
+    // Verify each field was updated correctly
+    assert_eq!(updated_doc["title"].as_str().unwrap(), "Updated Title");
+    assert_eq!(
+        updated_doc["content"].as_str().unwrap(),
+        "Updated content with more information"
+    );
+}
+
+// Helper to add a document to box_2 as its owner and return the response status
+async fn add_document_to_box_2(app: &Router, doc_id: &str, content: String) -> StatusCode {
+    app.clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_2/document",
+            "user_2",
+            Some(json!({
+                "document": {
+                    "id": doc_id,
+                    "title": "Capped Document",
+                    "content": content,
+                    "createdAt": "2023-01-01T12:00:00Z"
+                }
+            })),
+        ))
+        .await
+        .unwrap()
+        .status()
+}
+
+async fn add_document_with_content_type(
+    app: &Router,
+    doc_id: &str,
+    content_type: Option<&str>,
+) -> axum::response::Response {
+    let mut document = json!({
+        "id": doc_id,
+        "title": "Typed Document",
+        "content": "contents",
+        "createdAt": "2023-01-01T12:00:00Z"
+    });
+    if let Some(content_type) = content_type {
+        document["contentType"] = json!(content_type);
+    }
+    app.clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_2/document",
+            "user_2",
+            Some(json!({ "document": document })),
+        ))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_update_document_content_type() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    // Allowed types are accepted whatever their case and stored lowercased
+    let response = add_document_with_content_type(&app, "pdf_doc", Some("Application/PDF")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    let documents = body["document"]["documents"].as_array().unwrap();
+    let pdf = documents.iter().find(|d| d["id"] == "pdf_doc").unwrap();
+    assert_eq!(pdf["contentType"], "application/pdf");
+
+    // Documents sent without a type are plain text
+    let response = add_document_with_content_type(&app, "untyped_doc", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Anything off the allowlist is rejected and not stored
+    let response =
+        add_document_with_content_type(&app, "exe_doc", Some("application/x-msdownload")).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = response_to_json(response).await;
+    assert!(body["error"].as_str().unwrap().contains("contentType"));
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_2").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_2").await.unwrap(),
+    };
+    let content_type = |id: &str| {
+        stored
+            .documents
+            .iter()
+            .find(|d| d.id == id)
+            .map(|d| d.content_type.clone())
+    };
+    assert_eq!(content_type("pdf_doc").as_deref(), Some("application/pdf"));
+    assert_eq!(content_type("untyped_doc").as_deref(), Some("text/plain"));
+    assert_eq!(content_type("exe_doc"), None);
+}
+
+#[tokio::test]
+async fn test_update_document_count_cap() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let existing = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_2").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_2").await.unwrap(),
+    }
+    .documents
+    .len();
+
+    // Fill the box up to the cap
+    for i in existing..DEFAULT_MAX_DOCUMENTS_PER_BOX {
+        let status = add_document_to_box_2(&app, &format!("doc_{}", i), "x".into()).await;
+        assert_eq!(status, StatusCode::OK, "document {} should fit", i);
+    }
+
+    // One more new document is rejected
+    let status = add_document_to_box_2(&app, "one_too_many", "x".into()).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Updating an existing document at the cap is still allowed
+    let status = add_document_to_box_2(&app, "doc_0", "updated".into()).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_update_document_content_too_large() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let status =
+        add_document_to_box_2(&app, "huge_doc", "x".repeat(DEFAULT_MAX_DOCUMENT_BYTES + 1)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_update_document_box_too_large() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    // Each document is under the per-document limit, but together they outgrow the item limit
+    let content_size = 100 * 1024;
+    let fits = MAX_BOX_ITEM_BYTES / content_size;
+    for i in 0..fits {
+        let status =
+            add_document_to_box_2(&app, &format!("big_{}", i), "x".repeat(content_size)).await;
+        assert_eq!(status, StatusCode::OK, "document {} should fit", i);
+    }
+
+    let status = add_document_to_box_2(&app, "overflow", "x".repeat(content_size)).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_update_document_body_over_request_limit() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    // Rejected by the router's body limit before the handler's own checks run
+    let status =
+        add_document_to_box_2(&app, "huge_body", "x".repeat(DEFAULT_MAX_BODY_BYTES + 1)).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_update_document_unauthorized() {
+    // Setup with mock data
+    let (app, store) = create_test_app().await;
+
+    // Add test data to the store
+    add_test_data_to_store(&store).await;
+
+    // Use an existing box from the test data
+    let box_id = "box_2"; // box_2 is owned by user_2
+
+    // Try to add a document as a non-owner
+    let document = json!({
+        "document": {
+            "id": "unauthorized_doc",
+            "title": "Unauthorized Document",
+            "content": "This should fail",
+            "createdAt": "2023-01-01T12:00:00Z"
+        }
+    });
+
+    // Make the request with a user who is not the owner
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}/document", box_id),
+            "user_1", // Not the owner of box_2
+            Some(document),
+        ))
+        .await
+        .unwrap();
+
+    // Verify forbidden status
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_delete_document() {
+    // Setup with mock data
+    let (app, store) = create_test_app().await;
+
+    // Add test data to the store
+    add_test_data_to_store(&store).await;
+
+    // Use an existing box from the test data
+    let box_id = "box_1";
+
+    // First add a document
+    let document = json!({
+        "document": {
+            "id": "doc_to_delete",
+            "title": "Document to Delete",
+            "content": "This document will be deleted",
+            "createdAt": "2023-01-01T12:00:00Z"
+        }
+    });
+
+    // Make the request to add a document
+    let add_response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}/document", box_id),
+            "user_1", // Box owner
+            Some(document),
+        ))
+        .await
+        .unwrap();
+
+    // Verify update was successful
+    assert_eq!(add_response.status(), StatusCode::OK);
+
+    // Now delete the document
+    let delete_response = app
+        .clone()
+        .oneshot(create_test_request(
+            "DELETE",
+            &format!("/boxes/owned/{}/document/doc_to_delete", box_id),
+            "user_1", // Box owner
+            None,
+        ))
+        .await
+        .unwrap();
+
+    // Verify delete was successful
+    assert_eq!(delete_response.status(), StatusCode::OK);
+
+    // Verify the response structure
+    let json_response = response_to_json(delete_response).await;
+    assert!(
+        json_response.get("message").is_some(),
+        "Response should contain a 'message' field"
+    );
+    assert!(
+        json_response.get("document").is_some(),
+        "Response should contain a 'document' field"
+    );
+
+    // Get the box to confirm the document was deleted
+    let get_response = app
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/{}", box_id),
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(get_response.status(), StatusCode::OK);
+
+    // Verify the document is not in the box
+    let box_json = response_to_json(get_response).await;
+    let docs = box_json["box"]["documents"].as_array().unwrap();
+    let deleted_doc = docs
+        .iter()
+        .find(|d| d["id"].as_str().unwrap() == "doc_to_delete");
+
+    assert!(deleted_doc.is_none(), "Document should be deleted");
+}
+
+#[tokio::test]
+async fn test_delete_document_nonexistent() {
+    // Setup with test data
+    let (app, _store) = create_test_app().await;
+
+    // Use an existing box from the test data
+    let box_id = "box_1";
+    let nonexistent_doc_id = "nonexistent_doc";
+
+    // Try to delete a document that doesn't exist
+    let response = app
+        .oneshot(create_test_request(
+            "DELETE",
+            &format!("/boxes/owned/{}/document/{}", box_id, nonexistent_doc_id),
+            "user_1", // Box owner
+            None,
+        ))
+        .await
+        .unwrap();
+
+    // Verify not found status
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_delete_document_unauthorized() {
+    // Setup with test data
+    let (app, store) = create_test_app().await;
+
+    // Add test data to the store
+    add_test_data_to_store(&store).await;
+
+    // Use an existing box from the test data
+    let box_id = "box_2"; // box_2 is owned by user_2
+
+    // First add a document as the owner
+    let document = json!({
+        "document": {
+            "id": "doc_in_box_2",
+            "title": "Document in Box 2",
+            "content": "This document belongs to user_2's box",
+            "createdAt": "2023-01-01T12:00:00Z"
+        }
+    });
+
+    // Make the request to add a document as the owner
+    let add_response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}/document", box_id),
+            "user_2", // Box owner
+            Some(document),
+        ))
+        .await
+        .unwrap();
+
+    // Verify update was successful
+    assert_eq!(add_response.status(), StatusCode::OK);
+
+    // Try to delete the document as a non-owner
+    let delete_response = app
+        .clone()
+        .oneshot(create_test_request(
+            "DELETE",
+            &format!("/boxes/owned/{}/document/doc_in_box_2", box_id),
             "user_1", // Not the owner
             None,
         ))
         .await
         .unwrap();
 
-    // Verify unauthorized status
-    assert_eq!(delete_response.status(), StatusCode::UNAUTHORIZED);
+    // Verify forbidden status
+    assert_eq!(delete_response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_get_box_by_id() {
+    // Setup with test data
+    let (app, store) = create_test_app().await;
+
+    // Add test data to the store
+    add_test_data_to_store(&store).await;
+
+    // Find a box ID first
+    let response = app
+        .clone()
+        .oneshot(create_test_request("GET", "/boxes/owned", "user_1", None))
+        .await
+        .unwrap();
+
+    let body = response_to_json(response).await;
+    // Add more robust error handling
+    assert!(
+        body["boxes"].is_array(),
+        "Expected 'boxes' field to be an array"
+    );
+    assert!(
+        !body["boxes"].as_array().unwrap().is_empty(),
+        "Expected 'boxes' array to be non-empty"
+    );
+
+    let box_id = body["boxes"][0]["id"].as_str().unwrap();
+
+    // Get specific box by ID
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/{}", box_id),
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+
+    // Verify
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let box_data = response_to_json(response).await;
+    assert!(
+        box_data.get("box").is_some(),
+        "Expected 'box' field in response"
+    );
+
+    let box_obj = box_data["box"].as_object().unwrap();
+
+    assert_eq!(box_obj["id"].as_str().unwrap(), box_id);
+    assert!(box_obj.contains_key("name"));
+    assert!(box_obj.contains_key("description"));
+    assert!(box_obj.contains_key("createdAt"));
+    assert!(box_obj.contains_key("updatedAt"));
+    assert!(box_obj.contains_key("isLocked"));
+    assert!(box_obj.contains_key("documents"));
+    assert!(box_obj.contains_key("guardians"));
+    assert!(box_obj.contains_key("ownerId"));
+}
+
+// Helper to POST a batch-get as user_1 and return the status and JSON body
+async fn batch_get(app: &Router, ids: serde_json::Value) -> (StatusCode, serde_json::Value) {
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned/batch-get",
+            "user_1",
+            Some(json!({ "ids": ids })),
+        ))
+        .await
+        .unwrap();
+    let status = response.status();
+    (status, response_to_json(response).await)
+}
+
+#[tokio::test]
+async fn test_batch_get_boxes_returns_only_owned() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    // box_2 belongs to user_2 and missing_box doesn't exist; both are dropped
+    let (status, body) = batch_get(&app, json!(["box_1", "box_2", "missing_box"])).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let boxes = body["boxes"].as_array().unwrap();
+    assert_eq!(boxes.len(), 1);
+    assert_eq!(boxes[0]["id"], "box_1");
+}
+
+#[tokio::test]
+async fn test_batch_get_boxes_too_many_ids() {
+    let (app, _store) = create_test_app().await;
+
+    let ids: Vec<String> = (0..101).map(|i| format!("box_{}", i)).collect();
+    let (status, _body) = batch_get(&app, json!(ids)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Exactly at the cap is fine
+    let ids: Vec<String> = (0..100).map(|i| format!("box_{}", i)).collect();
+    let (status, _body) = batch_get(&app, json!(ids)).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_soft_delete_and_restore_box() {
+    let (app, store) = create_test_app_with_soft_delete(true).await;
+    add_test_data_to_store(&store).await;
+
+    let send = |method: &str, uri: &str, user: &str| {
+        app.clone()
+            .oneshot(create_test_request(method, uri, user, None))
+    };
+
+    // Delete hides the box from both single and list reads
+    let response = send("DELETE", "/boxes/owned/box_1", "user_1")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = send("GET", "/boxes/owned/box_1", "user_1").await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = send("GET", "/boxes/owned", "user_1").await.unwrap();
+    let body = response_to_json(response).await;
+    assert!(!body["boxes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|b| b["id"] == "box_1"));
+
+    // The record is still there, just marked deleted
+    let deleted = match &store {
+        TestStore::Mock(mock) => mock.get_deleted_box("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_deleted_box("box_1").await.unwrap(),
+    };
+    assert!(deleted.deleted_at.is_some());
+
+    // Only the owner can restore it
+    let response = send("POST", "/boxes/owned/box_1/restore", "user_2")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = send("POST", "/boxes/owned/box_1/restore", "user_1")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Visible again
+    let response = send("GET", "/boxes/owned/box_1", "user_1").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = send("GET", "/boxes/owned", "user_1").await.unwrap();
+    let body = response_to_json(response).await;
+    assert!(body["boxes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|b| b["id"] == "box_1"));
+
+    // Restoring a box that isn't deleted is a 404
+    let response = send("POST", "/boxes/owned/box_1/restore", "user_1")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+// Presigner that returns a recognisable fake URL instead of signing
+struct FakePresigner;
+
+#[async_trait::async_trait]
+impl DocumentPresigner for FakePresigner {
+    async fn presign_get(
+        &self,
+        key: &str,
+        expires_in: std::time::Duration,
+    ) -> crate::error::Result<String> {
+        Ok(format!(
+            "https://presigned.test/{}?expires={}",
+            key,
+            expires_in.as_secs()
+        ))
+    }
+}
+
+// Adds an inline and an S3-backed document to box_1 through the API
+async fn add_inline_and_s3_documents(app: &Router) {
+    for document in [
+        json!({ "id": "doc_inline", "title": "Notes", "content": "Inline text", "createdAt": now_str() }),
+        json!({ "id": "doc_s3", "title": "Will", "content": "boxes/box_1/will.pdf", "createdAt": now_str(), "storage": "s3" }),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "PATCH",
+                "/boxes/owned/box_1/document",
+                "user_1",
+                Some(json!({ "document": document })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
+
+async fn get_document_url_as(app: &Router, document_id: &str, user_id: &str) -> StatusCode {
+    app.clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/box_1/document/{}/url", document_id),
+            user_id,
+            None,
+        ))
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn test_document_url_for_s3_document() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let app = app.layer(Extension(
+        Arc::new(FakePresigner) as Arc<dyn DocumentPresigner>
+    ));
+    add_inline_and_s3_documents(&app).await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned/box_1/document/doc_s3/url",
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_response = response_to_json(response).await;
+    assert_eq!(
+        json_response["url"],
+        "https://presigned.test/boxes/box_1/will.pdf?expires=300"
+    );
+    assert_eq!(json_response["expiresIn"], 300);
+
+    // Inline documents have no object to link to
+    assert_eq!(
+        get_document_url_as(&app, "doc_inline", "user_1").await,
+        StatusCode::CONFLICT
+    );
+    // Only the owner may ask, as with deleting a document
+    assert_eq!(
+        get_document_url_as(&app, "doc_s3", "user_2").await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        get_document_url_as(&app, "missing", "user_1").await,
+        StatusCode::NOT_FOUND
+    );
 }
 
 #[tokio::test]
-async fn test_get_box_by_id() {
-    // Setup with test data
+async fn test_s3_document_key_must_belong_to_box() {
     let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let app = app.layer(Extension(
+        Arc::new(FakePresigner) as Arc<dyn DocumentPresigner>
+    ));
+
+    // Keys outside boxes/box_1/ would let the owner presign anyone's objects
+    for key in [
+        "boxes/box_2/will.pdf",
+        "boxes/box_1",
+        "boxes/box_1/../box_2/will.pdf",
+        "other/will.pdf",
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "PATCH",
+                "/boxes/owned/box_1/document",
+                "user_1",
+                Some(json!({ "document": {
+                    "id": "doc_s3", "title": "Will", "content": key,
+                    "createdAt": now_str(), "storage": "s3"
+                } })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.status(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{}",
+            key
+        );
+    }
 
-    // Add test data to the store
+    // A document stored before keys were checked gets no URL
+    let mut box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    box_record.documents.push(Document {
+        id: "doc_foreign".into(),
+        title: "Someone else's".into(),
+        content: "boxes/box_2/will.pdf".into(),
+        created_at: now_str(),
+        storage: DocumentStorage::S3,
+        content_type: "application/pdf".into(),
+    });
+    match &store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+    assert_eq!(
+        get_document_url_as(&app, "doc_foreign", "user_1").await,
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[tokio::test]
+async fn test_s3_presigner_signs_query_string() {
+    let credentials =
+        aws_credential_types::Credentials::new("AKIDEXAMPLE", "secret", None, None, "test");
+    let presigner = S3Presigner::new(
+        "lockbox-documents".into(),
+        "eu-west-2".into(),
+        aws_credential_types::provider::SharedCredentialsProvider::new(credentials),
+    );
+
+    let url = presigner
+        .presign_get("boxes/box 1/will.pdf", std::time::Duration::from_secs(300))
+        .await
+        .unwrap();
+    assert!(url.starts_with(
+        "https://lockbox-documents.s3.eu-west-2.amazonaws.com/boxes/box%201/will.pdf?"
+    ));
+    assert!(url.contains("X-Amz-Algorithm=AWS4-HMAC-SHA256"));
+    assert!(url.contains("X-Amz-Expires=300"));
+    assert!(url.contains("X-Amz-Signature="));
+}
+
+async fn add_box_1_guardian(app: &Router, id: &str, lead: bool) -> StatusCode {
+    app.clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1/guardian",
+            "user_1",
+            Some(json!({ "guardian": bulk_guardian(id, &format!("inv-{}", id), lead) })),
+        ))
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn test_guardian_count_cap() {
+    let (app, store) = create_test_app().await;
     add_test_data_to_store(&store).await;
 
-    // Find a box ID first
+    // Fill box_1 up to the cap, with the first guardian as lead
+    let ids: Vec<String> = (0..DEFAULT_MAX_GUARDIANS_PER_BOX)
+        .map(|i| format!("g{}", i))
+        .collect();
+    let guardians: Vec<(&str, bool)> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), i == 0))
+        .collect();
+    seed_box_1_guardians(&store, &guardians).await;
+
+    // One more guardian is rejected on both the single and the bulk path
+    assert_eq!(
+        add_box_1_guardian(&app, "one_too_many", false).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+    let bulk = |guardians: Vec<serde_json::Value>| {
+        app.clone().oneshot(create_test_request(
+            "PUT",
+            "/boxes/owned/box_1/guardians",
+            "user_1",
+            Some(json!({ "guardians": guardians })),
+        ))
+    };
+    let response = bulk(vec![bulk_guardian("one_too_many", "inv-extra", false)])
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Updating guardians already in a full box is still allowed
+    assert_eq!(add_box_1_guardian(&app, "g1", false).await, StatusCode::OK);
+    let response = bulk(vec![bulk_guardian("g2", "inv-g2", false)])
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert_eq!(stored.guardians.len(), DEFAULT_MAX_GUARDIANS_PER_BOX);
+}
+
+#[tokio::test]
+async fn test_lock_box_requires_lead_guardian() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let lock = || {
+        app.clone().oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1",
+            "user_1",
+            Some(json!({ "isLocked": true })),
+        ))
+    };
+
+    // No guardians at all
+    assert_eq!(lock().await.unwrap().status(), StatusCode::CONFLICT);
+
+    // Guardians, but none of them a lead
+    assert_eq!(
+        add_box_1_guardian(&app, "helper", false).await,
+        StatusCode::OK
+    );
+    let response = lock().await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body = response_to_json(response).await;
+    assert!(body["error"].as_str().unwrap().contains("lead guardian"));
+
+    // A lead who hasn't accepted their invitation yet doesn't count
+    assert_eq!(add_box_1_guardian(&app, "lead", true).await, StatusCode::OK);
+    assert_eq!(lock().await.unwrap().status(), StatusCode::CONFLICT);
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert!(!stored.is_locked);
+
+    // An accepted lead but no instructions
+    seed_lockable_box_1(&store).await;
     let response = app
         .clone()
-        .oneshot(create_test_request("GET", "/boxes/owned", "user_1", None))
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1",
+            "user_1",
+            Some(json!({ "isLocked": true, "unlockInstructions": null })),
+        ))
         .await
         .unwrap();
-
+    assert_eq!(response.status(), StatusCode::CONFLICT);
     let body = response_to_json(response).await;
-    // Add more robust error handling
-    assert!(
-        body["boxes"].is_array(),
-        "Expected 'boxes' field to be an array"
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .contains("unlock instructions"));
+
+    assert_eq!(lock().await.unwrap().status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_clone_box() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    seed_box_1_guardians(&store, &[("lead", true), ("helper", false)]).await;
+
+    // Give the source box instructions, an unlock policy, a document and an unlock request
+    let mut source = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    source.unlock_instructions = Some("Call the lawyer".into());
+    source.unlock_policy = UnlockPolicy::Threshold(2);
+    source.documents.push(
+        serde_json::from_value(json!({
+            "id": "doc_1",
+            "title": "Will",
+            "content": "Secret",
+            "createdAt": "2023-01-01T12:00:00Z"
+        }))
+        .unwrap(),
     );
-    assert!(
-        !body["boxes"].as_array().unwrap().is_empty(),
-        "Expected 'boxes' array to be non-empty"
+    source.unlock_requests.push(
+        serde_json::from_value(json!({
+            "id": "unlock_1",
+            "requestedAt": "2023-01-01T12:00:00Z",
+            "status": "requested",
+            "initiatedBy": "lead",
+            "approvedBy": [],
+            "rejectedBy": []
+        }))
+        .unwrap(),
     );
+    let source = match &store {
+        TestStore::Mock(mock) => mock.update_box(source).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(source).await.unwrap(),
+    };
 
-    let box_id = body["boxes"][0]["id"].as_str().unwrap();
+    let clone = |user: &str| {
+        app.clone().oneshot(create_test_request(
+            "POST",
+            "/boxes/owned/box_1/clone",
+            user,
+            None,
+        ))
+    };
 
-    // Get specific box by ID
+    // Only the owner can clone
+    assert_eq!(
+        clone("user_2").await.unwrap().status(),
+        StatusCode::FORBIDDEN
+    );
+
+    let response = clone("user_1").await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response_to_json(response).await;
+    let cloned_id = body["box"]["id"].as_str().unwrap().to_string();
+    assert_ne!(cloned_id, "box_1");
+
+    let cloned = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(&cloned_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&cloned_id).await.unwrap(),
+    };
+
+    // Metadata is copied
+    assert_eq!(cloned.name, "Test Box 1 (copy)");
+    assert_eq!(cloned.description, source.description);
+    assert_eq!(
+        cloned.unlock_instructions.as_deref(),
+        Some("Call the lawyer")
+    );
+    assert_eq!(cloned.owner_id, "user_1");
+    assert_eq!(cloned.version, 0);
+    assert!(!cloned.is_locked);
+
+    assert_eq!(cloned.unlock_policy, UnlockPolicy::Threshold(2));
+    assert_eq!(cloned.required_approvals, source.required_approvals);
+
+    // Guardians join only through invitations, so none are carried over
+    assert!(cloned.guardians.is_empty());
+    assert!(body["box"]["guardians"].as_array().unwrap().is_empty());
+
+    // Documents and unlock requests stay with the original
+    assert!(cloned.documents.is_empty());
+    assert!(cloned.unlock_requests.is_empty());
+    let original = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert_eq!(original.documents.len(), 1);
+    assert_eq!(original.unlock_requests.len(), 1);
+}
+
+#[tokio::test]
+async fn test_search_boxes() {
+    let (app, _store) = create_test_app().await;
+
+    for (name, description) in [
+        ("Family Will", "Copies for the solicitor"),
+        ("Passwords", "Bank and WILL portal logins"),
+        ("Photos", "Holiday albums"),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "POST",
+                "/boxes/owned",
+                "searcher",
+                Some(json!({ "name": name, "description": description })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+    // Someone else's box never shows up
+    app.clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned",
+            "someone_else",
+            Some(json!({ "name": "Other Will", "description": "" })),
+        ))
+        .await
+        .unwrap();
+
+    let search = |q: &str| {
+        app.clone().oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/search?q={}", q),
+            "searcher",
+            None,
+        ))
+    };
+    let names = |body: serde_json::Value| -> Vec<String> {
+        body["boxes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| b["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // By name
+    let response = search("photo").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(names(response_to_json(response).await), vec!["Photos"]);
+
+    // By description
+    let response = search("SOLICITOR").await.unwrap();
+    assert_eq!(names(response_to_json(response).await), vec!["Family Will"]);
+
+    // Name and description matches together, case-insensitively
+    let body = response_to_json(search("will").await.unwrap()).await;
+    assert_eq!(names(body.clone()), vec!["Family Will", "Passwords"]);
+    assert_eq!(body["truncated"], false);
+
+    // No match
+    let response = search("tax%20returns").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(names(response_to_json(response).await).is_empty());
+
+    // Too short a query
+    assert_eq!(search("w").await.unwrap().status(), StatusCode::BAD_REQUEST);
     let response = app
         .clone()
         .oneshot(create_test_request(
             "GET",
-            &format!("/boxes/owned/{}", box_id),
-            "user_1",
+            "/boxes/owned/search",
+            "searcher",
             None,
         ))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
 
-    // Verify
+async fn box_1_action(app: &Router, action: &str, user: &str) -> axum::response::Response {
+    app.clone()
+        .oneshot(create_test_request(
+            "POST",
+            &format!("/boxes/owned/box_1/{}", action),
+            user,
+            None,
+        ))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_lock_box_action_requires_prerequisites() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    // No guardians yet
+    let response = box_1_action(&app, "lock", "user_1").await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body = response_to_json(response).await;
+    assert!(body["error"].as_str().unwrap().contains("lead guardian"));
+
+    // A lead who hasn't accepted doesn't count
+    assert_eq!(add_box_1_guardian(&app, "lead", true).await, StatusCode::OK);
+    let response = box_1_action(&app, "lock", "user_1").await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // An accepted lead, but no unlock instructions
+    seed_box_1_guardians(&store, &[("lead", true)]).await;
+    assert_eq!(
+        box_1_action(&app, "unlock", "user_1").await.status(),
+        StatusCode::OK
+    );
+    let response = box_1_action(&app, "lock", "user_1").await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body = response_to_json(response).await;
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .contains("unlock instructions"));
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert!(!stored.is_locked);
+}
+
+#[tokio::test]
+async fn test_lock_box_action() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    seed_box_1_guardians(&store, &[("lead", true)]).await;
+    assert_eq!(
+        box_1_action(&app, "unlock", "user_1").await.status(),
+        StatusCode::OK
+    );
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1",
+            "user_1",
+            Some(json!({ "unlockInstructions": "Call the lawyer first" })),
+        ))
+        .await
+        .unwrap();
     assert_eq!(response.status(), StatusCode::OK);
 
-    let box_data = response_to_json(response).await;
-    assert!(
-        box_data.get("box").is_some(),
-        "Expected 'box' field in response"
+    // Only the owner may lock
+    assert_eq!(
+        box_1_action(&app, "lock", "user_2").await.status(),
+        StatusCode::FORBIDDEN
     );
 
-    let box_obj = box_data["box"].as_object().unwrap();
+    let response = box_1_action(&app, "lock", "user_1").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["box"]["isLocked"], true);
 
-    assert_eq!(box_obj["id"].as_str().unwrap(), box_id);
-    assert!(box_obj.contains_key("name"));
-    assert!(box_obj.contains_key("description"));
-    assert!(box_obj.contains_key("createdAt"));
-    assert!(box_obj.contains_key("updatedAt"));
-    assert!(box_obj.contains_key("isLocked"));
-    assert!(box_obj.contains_key("documents"));
-    assert!(box_obj.contains_key("guardians"));
-    assert!(box_obj.contains_key("ownerId"));
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert!(stored.is_locked);
+}
+
+#[tokio::test]
+async fn test_owner_unlock_cancels_pending_unlock_requests() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    seed_box_1_guardians(&store, &[("lead", true)]).await;
+
+    let mut box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    for (id, status) in [("old", "rejected"), ("open", "requested")] {
+        box_record.unlock_requests.push(
+            serde_json::from_value(json!({
+                "id": id,
+                "requestedAt": "2023-01-01T12:00:00Z",
+                "status": status,
+                "initiatedBy": "lead",
+                "approvedBy": [],
+                "rejectedBy": []
+            }))
+            .unwrap(),
+        );
+    }
+    match &store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+
+    assert_eq!(
+        box_1_action(&app, "unlock", "user_2").await.status(),
+        StatusCode::FORBIDDEN
+    );
+
+    let response = box_1_action(&app, "unlock", "user_1").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["box"]["isLocked"], false);
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert!(!stored.is_locked);
+    let status = |id: &str| {
+        stored
+            .unlock_requests
+            .iter()
+            .find(|unlock| unlock.id == id)
+            .unwrap()
+            .status
+            .to_string()
+    };
+    assert_eq!(status("open"), "cancelled");
+    assert_eq!(status("old"), "rejected");
+}
+
+#[tokio::test]
+async fn test_count_boxes() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let count = |user: &str| {
+        app.clone()
+            .oneshot(create_test_request("GET", "/boxes/owned/count", user, None))
+    };
+
+    // The seeded data gives user_1 a single box
+    let response = count("user_1").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response_to_json(response).await, json!({ "count": 1 }));
+
+    for name in ["Second", "Third"] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "POST",
+                "/boxes/owned",
+                "user_1",
+                Some(json!({ "name": name, "description": "" })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+    let body = response_to_json(count("user_1").await.unwrap()).await;
+    assert_eq!(body["count"], 3);
+
+    // Deleted boxes drop out of the count
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "DELETE",
+            "/boxes/owned/box_1",
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let body = response_to_json(count("user_1").await.unwrap()).await;
+    assert_eq!(body["count"], 2);
+
+    // A user with no boxes gets zero rather than an error
+    let body = response_to_json(count("nobody").await.unwrap()).await;
+    assert_eq!(body["count"], 0);
+}
+
+#[tokio::test]
+async fn test_whoami_returns_claims_without_token() {
+    let (app, _store) = create_test_app().await;
+
+    let token = create_jwt_token("whoami_user");
+    let request = Request::builder()
+        .method("GET")
+        .uri("/whoami")
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response_to_json(response).await;
+    assert_eq!(body["sub"], "whoami_user");
+    assert_eq!(body["email"], "test@example.com");
+    assert_eq!(body["cognitoUsername"], "whoami_user");
+    assert!(body["exp"].as_u64().is_some());
+
+    // Neither the token nor its signature may be echoed back
+    let text = body.to_string();
+    assert!(!text.contains(&token));
+    assert!(!text.contains(token.rsplit('.').next().unwrap()));
 }
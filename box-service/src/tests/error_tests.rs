@@ -0,0 +1,125 @@
+use axum::{http::StatusCode, response::IntoResponse};
+use lockbox_shared::correlation::with_correlation_id;
+use lockbox_shared::error::StoreError;
+use lockbox_shared::test_utils::http_test_utils::response_to_json;
+
+use crate::error::AppError;
+
+#[test]
+fn test_version_conflict_display() {
+    let err = StoreError::VersionConflict("box 1234 was modified".into());
+    assert_eq!(err.to_string(), "Version conflict: box 1234 was modified");
+}
+
+#[tokio::test]
+async fn test_version_conflict_maps_to_409() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+
+    let app_err = AppError::from(StoreError::VersionConflict("box 1234 was modified".into()));
+    assert!(matches!(app_err, AppError::Conflict(_)));
+
+    let response = app_err.into_response();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let json = response_to_json(response).await;
+    assert!(json["error"]
+        .as_str()
+        .unwrap()
+        .contains("box 1234 was modified"));
+}
+
+#[tokio::test]
+async fn test_app_error_status_codes() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+
+    let cases = vec![
+        (AppError::not_found("box".into()), StatusCode::NOT_FOUND),
+        (
+            AppError::unauthorized("token".into()),
+            StatusCode::UNAUTHORIZED,
+        ),
+        (AppError::forbidden("owner".into()), StatusCode::FORBIDDEN),
+        (
+            AppError::bad_request("name".into()),
+            StatusCode::BAD_REQUEST,
+        ),
+        (AppError::conflict("version".into()), StatusCode::CONFLICT),
+        (
+            AppError::precondition_failed("etag".into()),
+            StatusCode::PRECONDITION_FAILED,
+        ),
+        (
+            AppError::internal_server_error("boom".into()),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ),
+    ];
+
+    for (err, expected_status) in cases {
+        let message = err.to_string();
+        let response = err.into_response();
+        assert_eq!(response.status(), expected_status, "{}", message);
+
+        let json = response_to_json(response).await;
+        assert!(
+            json["error"].is_string(),
+            "{} should have an error body",
+            message
+        );
+    }
+}
+
+#[test]
+fn test_store_errors_map_to_app_errors() {
+    assert!(matches!(
+        AppError::from(StoreError::NotFound("box".into())),
+        AppError::NotFound(_)
+    ));
+    assert!(matches!(
+        AppError::from(StoreError::ValidationError("bad".into())),
+        AppError::Validation(_)
+    ));
+    assert!(matches!(
+        AppError::from(StoreError::AuthError("token".into())),
+        AppError::Unauthorized(_)
+    ));
+    assert!(matches!(
+        AppError::from(StoreError::InternalError("boom".into())),
+        AppError::Internal(_)
+    ));
+    assert!(matches!(
+        AppError::from(StoreError::AmbiguousInviteCode),
+        AppError::Conflict(_)
+    ));
+}
+
+#[tokio::test]
+async fn test_internal_error_body_hides_store_detail() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+
+    let raw = "DynamoDB get_item error: ResourceNotFoundException: table box-table not found";
+    let response = with_correlation_id("req-123".to_string(), async {
+        AppError::from(StoreError::InternalError(raw.into())).into_response()
+    })
+    .await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    let json = response_to_json(response).await;
+    assert_eq!(
+        json,
+        serde_json::json!({ "error": "internal_error", "requestId": "req-123" })
+    );
+    assert!(!json.to_string().contains("box-table"));
+}
+
+#[tokio::test]
+async fn test_throttled_body_hides_store_detail() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+
+    let raw = "box-table still throttled after 3 attempts: ProvisionedThroughputExceededException";
+    let response = AppError::from(StoreError::Throttled(raw.into())).into_response();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()["retry-after"], "1");
+
+    let json = response_to_json(response).await;
+    assert_eq!(json, serde_json::json!({ "error": "service unavailable" }));
+}
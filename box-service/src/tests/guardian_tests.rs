@@ -1,8 +1,10 @@
 use axum::http::StatusCode;
 use axum::Router;
 use lockbox_shared::auth::create_test_request;
+use lockbox_shared::error::StoreError;
 use lockbox_shared::store::dynamo::DynamoBoxStore;
-use lockbox_shared::store::BoxStore;
+use lockbox_shared::store::memory::MemoryInvitationStore;
+use lockbox_shared::store::{BoxStore, InvitationStore};
 use lockbox_shared::test_utils::dynamo_test_utils::{
     clear_dynamo_table, create_box_table, create_dynamo_client, use_dynamodb,
 };
@@ -14,9 +16,10 @@ use serde_json::json;
 use std::sync::Arc;
 use tower::ServiceExt;
 
-use crate::{models::now_str, routes};
+use crate::routes;
 use lockbox_shared::models::{
-    BoxRecord, Guardian, GuardianStatus, UnlockRequest, UnlockRequestStatus,
+    now_str, BoxRecord, Document, DocumentStorage, Guardian, GuardianStatus, Invitation,
+    InvitationStatus, UnlockPolicy, UnlockRequest, UnlockRequestStatus,
 };
 
 // Constants for DynamoDB tests
@@ -44,6 +47,10 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
                 status: GuardianStatus::Accepted,
                 added_at: now.to_string(),
                 invitation_id: "invitation_1".into(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
             },
             Guardian {
                 id: "guardian_2".into(),
@@ -52,6 +59,10 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
                 status: GuardianStatus::Accepted,
                 added_at: now.to_string(),
                 invitation_id: "invitation_2".into(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
             },
             Guardian {
                 id: "lead_guardian_1".into(),
@@ -60,10 +71,17 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
                 status: GuardianStatus::Accepted,
                 added_at: now.to_string(),
                 invitation_id: "invitation_3".into(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
             },
         ],
         unlock_instructions: Some("Contact all guardians".into()),
-        unlock_request: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
         version: 0,
     };
 
@@ -77,6 +95,7 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
         initiated_by: Some("lead_guardian_1".into()),
         approved_by: vec![],
         rejected_by: vec![],
+        expires_at: None,
     };
 
     let box_2 = BoxRecord {
@@ -97,6 +116,10 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
                 status: GuardianStatus::Accepted,
                 added_at: now.to_string(),
                 invitation_id: "invitation_5".into(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
             },
             Guardian {
                 id: "guardian_3".into(),
@@ -105,6 +128,10 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
                 status: GuardianStatus::Accepted,
                 added_at: now.to_string(),
                 invitation_id: "invitation_6".into(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
             },
             Guardian {
                 id: "lead_guardian_1".into(),
@@ -113,10 +140,17 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
                 status: GuardianStatus::Accepted,
                 added_at: now.to_string(),
                 invitation_id: "invitation_7".into(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
             },
         ],
         unlock_instructions: Some("Call emergency contact".into()),
-        unlock_request: Some(unlock_request),
+        unlock_requests: vec![unlock_request],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
         version: 0,
     };
 
@@ -139,9 +173,16 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
             status: GuardianStatus::Accepted,
             added_at: now.to_string(),
             invitation_id: "invitation_9".into(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
         }],
         unlock_instructions: None,
-        unlock_request: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
         version: 0,
     };
 
@@ -155,6 +196,13 @@ enum TestStore {
 
 // Create test app with either mock or DynamoDB store
 async fn create_test_app() -> (Router, TestStore) {
+    create_test_app_with_invitations(Arc::new(MemoryInvitationStore::new())).await
+}
+
+// Same as create_test_app, resolving invite codes against the given store
+async fn create_test_app_with_invitations(
+    invitation_store: Arc<dyn InvitationStore>,
+) -> (Router, TestStore) {
     // Initialize logging for tests
     init_test_logging();
 
@@ -180,13 +228,13 @@ async fn create_test_app() -> (Router, TestStore) {
             TEST_TABLE_NAME.to_string(),
         ));
 
-        let app = routes::create_router_with_store(store.clone(), "");
+        let app = routes::create_router_with_store(store.clone(), invitation_store, "");
         (app, TestStore::DynamoDB(store))
     } else {
         // Use mock store
         debug!("Using mock store for guardian tests");
         let store = Arc::new(MockBoxStore::new());
-        let app = routes::create_router_with_store(store.clone(), "");
+        let app = routes::create_router_with_store(store.clone(), invitation_store, "");
         (app, TestStore::Mock(store))
     }
 }
@@ -306,6 +354,90 @@ async fn test_get_guardian_boxes_empty_for_non_guardian() {
     assert!(boxes.is_empty());
 }
 
+#[tokio::test]
+async fn test_get_guardian_boxes_paginated() {
+    let (app, store) = create_test_app().await;
+
+    // Five boxes guarded by "pager", plus one where they were rejected
+    let template = create_test_data(&now_str()).remove(0);
+    for i in 0..6 {
+        let mut box_record = template.clone();
+        box_record.id = format!("paged-box-{}", i);
+        box_record.guardians = vec![Guardian {
+            id: "pager".into(),
+            name: "Pager".into(),
+            lead_guardian: false,
+            status: if i == 5 {
+                GuardianStatus::Rejected
+            } else {
+                GuardianStatus::Accepted
+            },
+            added_at: now_str(),
+            invitation_id: format!("paged-invitation-{}", i),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
+        }];
+        match &store {
+            TestStore::Mock(mock) => mock.create_box(box_record).await.unwrap(),
+            TestStore::DynamoDB(dynamo) => dynamo.create_box(box_record).await.unwrap(),
+        };
+    }
+
+    let list = |uri: String| {
+        app.clone()
+            .oneshot(create_test_request("GET", &uri, "pager", None))
+    };
+
+    // Walk every page, following nextCursor until it runs out
+    let mut seen = Vec::new();
+    let mut pages = 0;
+    let mut cursor: Option<String> = None;
+    loop {
+        let uri = match &cursor {
+            Some(cursor) => format!("/boxes/guardian?limit=2&cursor={}", cursor),
+            None => "/boxes/guardian?limit=2".to_string(),
+        };
+        let response = list(uri).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response_to_json(response).await;
+
+        let boxes = body["boxes"].as_array().unwrap();
+        assert!(boxes.len() <= 2);
+        seen.extend(boxes.iter().map(|b| b["id"].as_str().unwrap().to_string()));
+        pages += 1;
+
+        match body["nextCursor"].as_str() {
+            Some(next) => cursor = Some(next.to_string()),
+            None => break,
+        }
+    }
+
+    seen.sort();
+    let expected: Vec<String> = (0..5).map(|i| format!("paged-box-{}", i)).collect();
+    assert_eq!(seen, expected);
+    if matches!(store, TestStore::Mock(_)) {
+        assert_eq!(pages, 3);
+    }
+
+    // Without limit or cursor everything comes back in one page
+    let body = response_to_json(list("/boxes/guardian".to_string()).await.unwrap()).await;
+    assert_eq!(body["boxes"].as_array().unwrap().len(), 5);
+    assert!(body["nextCursor"].is_null());
+
+    let response = list("/boxes/guardian?limit=0".to_string()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = list("/boxes/guardian?sort=name&order=sideways".to_string())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = list("/boxes/guardian?cursor=not-a-cursor".to_string())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn test_get_guardian_box_found() {
     // Setup with test data
@@ -341,7 +473,400 @@ async fn test_get_guardian_box_found() {
 }
 
 #[tokio::test]
-async fn test_get_guardian_box_unauthorized() {
+async fn test_guardian_annotations_are_owner_only() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "11111111-1111-1111-1111-111111111111";
+
+    // Set through the full guardian update
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}/guardian", box_id),
+            "owner_1",
+            Some(json!({
+                "guardian": {
+                    "id": "guardian_1",
+                    "name": "Guardian One",
+                    "leadGuardian": false,
+                    "status": "accepted",
+                    "addedAt": "2024-01-01T00:00:00Z",
+                    "invitationId": "invitation_1",
+                    "relationship": "  spouse ",
+                    "note": "Call after 6pm"
+                }
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["guardian"]["relationship"], "spouse");
+    assert_eq!(body["guardian"]["note"], "Call after 6pm");
+
+    // And through the partial update
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}/guardian/guardian_2", box_id),
+            "owner_1",
+            Some(json!({ "relationship": "attorney", "note": "Holds the paper copy" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["guardian"]["relationship"], "attorney");
+
+    // The owner sees both annotations on the box
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/{}", box_id),
+            "owner_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    let body = response_to_json(response).await;
+    let owner_view = body["box"]["guardians"].as_array().unwrap();
+    let annotated: Vec<(&str, &str)> = owner_view
+        .iter()
+        .filter_map(|g| Some((g["relationship"].as_str()?, g["note"].as_str()?)))
+        .collect();
+    assert_eq!(
+        annotated,
+        vec![
+            ("spouse", "Call after 6pm"),
+            ("attorney", "Holds the paper copy")
+        ]
+    );
+
+    // Guardians get the same list with the annotations stripped
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/guardian/{}", box_id),
+            "guardian_2",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    let guardian_view = body["box"]["guardians"].as_array().unwrap();
+    assert_eq!(guardian_view.len(), owner_view.len());
+    for guardian in guardian_view {
+        assert!(guardian.get("relationship").is_none());
+        assert!(guardian.get("note").is_none());
+    }
+
+    // Null clears an annotation
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}/guardian/guardian_2", box_id),
+            "owner_1",
+            Some(json!({ "note": null })),
+        ))
+        .await
+        .unwrap();
+    let body = response_to_json(response).await;
+    assert_eq!(body["guardian"]["relationship"], "attorney");
+    assert!(body["guardian"].get("note").is_none());
+}
+
+#[tokio::test]
+async fn test_guardian_view_is_recorded() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "11111111-1111-1111-1111-111111111111";
+
+    // guardian_2 hasn't opened the box since being invited
+    let mut box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    box_record.guardians[1].status = GuardianStatus::Invited;
+    box_record.guardians[0].email = Some("guardian1@example.com".to_string());
+    match &store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+
+    let view_as_guardian = || {
+        app.clone().oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/guardian/{}", box_id),
+            "guardian_2",
+            None,
+        ))
+    };
+    // The owner's copy of guardian_2
+    let owner_view = || async {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "GET",
+                &format!("/boxes/owned/{}", box_id),
+                "owner_1",
+                None,
+            ))
+            .await
+            .unwrap();
+        let body = response_to_json(response).await;
+        body["box"]["guardians"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|g| g["id"] == "guardian_2")
+            .unwrap()
+            .clone()
+    };
+
+    let stored_box = || async {
+        match &store {
+            TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+            TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+        }
+    };
+
+    assert!(owner_view().await.get("lastViewedAt").is_none());
+    let before_view = stored_box().await;
+
+    // First view moves the guardian from invited to viewed
+    let response = view_as_guardian().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    // Guardians don't see each other's view history or email addresses
+    for guardian in body["box"]["guardians"].as_array().unwrap() {
+        assert!(guardian.get("lastViewedAt").is_none());
+        assert!(guardian.get("email").is_none());
+    }
+    assert_eq!(
+        stored_box().await.guardians[0].email.as_deref(),
+        Some("guardian1@example.com")
+    );
+
+    let guardian = owner_view().await;
+    assert_eq!(guardian["status"], "viewed");
+    let first_view = guardian["lastViewedAt"].as_str().unwrap().to_string();
+
+    // Recording the view leaves updatedAt alone but bumps the version, so a
+    // write based on the box as it was before the view can't erase it
+    let after_view = stored_box().await;
+    assert_eq!(after_view.version, before_view.version + 1);
+    assert_eq!(after_view.updated_at, before_view.updated_at);
+    let stale_write = match &store {
+        TestStore::Mock(mock) => mock.update_box(before_view).await,
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(before_view).await,
+    };
+    assert!(matches!(stale_write, Err(StoreError::VersionConflict(_))));
+    assert_eq!(owner_view().await["status"], "viewed");
+
+    // Opening the box again soon after doesn't write at all
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    assert_eq!(view_as_guardian().await.unwrap().status(), StatusCode::OK);
+    assert_eq!(owner_view().await["lastViewedAt"], first_view.as_str());
+
+    // Once the recorded view is stale, the next one moves the timestamp forward
+    let mut box_record = stored_box().await;
+    box_record.guardians[1].last_viewed_at = Some("2000-01-01T00:00:00Z".to_string());
+    match &store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+    assert_eq!(view_as_guardian().await.unwrap().status(), StatusCode::OK);
+
+    let guardian = owner_view().await;
+    assert_eq!(guardian["status"], "viewed");
+    let second_view = guardian["lastViewedAt"].as_str().unwrap().to_string();
+    let parse = |ts: &str| chrono::DateTime::parse_from_rfc3339(ts).unwrap();
+    assert!(parse(&second_view) >= parse(&first_view));
+
+    // An accepted guardian keeps their status
+    assert_eq!(
+        app.clone()
+            .oneshot(create_test_request(
+                "GET",
+                &format!("/boxes/guardian/{}", box_id),
+                "guardian_1",
+                None,
+            ))
+            .await
+            .unwrap()
+            .status(),
+        StatusCode::OK
+    );
+    let box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert_eq!(box_record.guardians[0].status, GuardianStatus::Accepted);
+    assert!(box_record.guardians[0].last_viewed_at.is_some());
+
+    // A full guardian update from the owner neither sets nor clears it
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}/guardian", box_id),
+            "owner_1",
+            Some(json!({
+                "guardian": {
+                    "id": "guardian_2",
+                    "name": "Renamed Guardian",
+                    "leadGuardian": false,
+                    "status": "viewed",
+                    "addedAt": "2024-01-01T00:00:00Z",
+                    "invitationId": "invitation_2",
+                    "lastViewedAt": "2000-01-01T00:00:00Z"
+                }
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(owner_view().await["lastViewedAt"], second_view);
+}
+
+#[tokio::test]
+async fn test_view_then_accept_invitation() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "11111111-1111-1111-1111-111111111111";
+    let mut box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    box_record.guardians[1].status = GuardianStatus::Invited;
+    match &store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+
+    // Opening the box moves guardian_2 to viewed...
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/guardian/{}", box_id),
+            "guardian_2",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // ...and they can still accept the invitation afterwards
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/invitation", box_id),
+            "guardian_2",
+            Some(json!({ "accept": true })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let guardian = box_record
+        .guardians
+        .iter()
+        .find(|g| g.id == "guardian_2")
+        .unwrap();
+    assert_eq!(guardian.status, GuardianStatus::Accepted);
+}
+
+// Fetches box 1 as guardian_1 and returns its documents
+async fn guardian_documents(app: &Router) -> Vec<serde_json::Value> {
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/guardian/11111111-1111-1111-1111-111111111111",
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response_to_json(response).await["box"]["documents"]
+        .as_array()
+        .unwrap()
+        .clone()
+}
+
+#[tokio::test]
+async fn test_guardian_box_hides_document_content_until_unlocked() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    // Box 1 is locked; give it a document
+    let box_id = "11111111-1111-1111-1111-111111111111";
+    let mut box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    box_record.documents.push(Document {
+        id: "doc_1".into(),
+        title: "Will".into(),
+        content: "Secret contents".into(),
+        created_at: now_str(),
+        storage: DocumentStorage::Inline,
+        content_type: "text/plain".into(),
+    });
+    match &store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+
+    let documents = guardian_documents(&app).await;
+    assert_eq!(documents.len(), 1);
+    assert_eq!(documents[0]["id"], "doc_1");
+    assert_eq!(documents[0]["title"], "Will");
+    assert!(documents[0].get("content").is_none());
+
+    // Once an unlock request completes, the contents are shared. Re-read the
+    // box since the guardian's view above was recorded on it.
+    let mut box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    box_record.unlock_requests.push(UnlockRequest {
+        id: "unlock_1".into(),
+        requested_at: now_str(),
+        status: UnlockRequestStatus::Completed,
+        message: None,
+        initiated_by: Some("lead_guardian_1".into()),
+        approved_by: vec!["guardian_1".into(), "guardian_2".into()],
+        rejected_by: vec![],
+        expires_at: None,
+    });
+    match &store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+
+    let documents = guardian_documents(&app).await;
+    assert_eq!(documents[0]["content"], "Secret contents");
+}
+
+#[tokio::test]
+async fn test_get_guardian_box_not_found_for_non_guardian() {
     // Setup with test data
     let (app, store) = create_test_app().await;
 
@@ -361,8 +886,48 @@ async fn test_get_guardian_box_unauthorized() {
         .await
         .unwrap();
 
-    // Should be UNAUTHORIZED
-    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    // A user who was never a guardian can't tell the box exists
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_get_guardian_box_forbidden_for_rejected_guardian() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "11111111-1111-1111-1111-111111111111";
+
+    // Mark guardian_1 as rejected on the box
+    let mut box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    for guardian in box_record.guardians.iter_mut() {
+        if guardian.id == "guardian_1" {
+            guardian.status = GuardianStatus::Rejected;
+        }
+    }
+    match &store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/guardian/{}", box_id),
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let json_response = response_to_json(response).await;
+    assert_eq!(
+        json_response["error"],
+        "Guardian access to this box has been revoked"
+    );
 }
 
 #[tokio::test]
@@ -402,11 +967,11 @@ async fn test_lead_guardian_unlock_request() {
 
     // Verify box state before the test
     let initial_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
     assert!(
-        initial_box.unlock_request.is_none(),
+        initial_box.unlock_requests.is_empty(),
         "Box should not have unlock request before test"
     );
 
@@ -453,23 +1018,17 @@ async fn test_lead_guardian_unlock_request() {
         "lead_guardian_1"
     );
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify directly in the store
     let updated_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     assert!(
-        updated_box.unlock_request.is_some(),
+        updated_box.latest_unlock_request().is_some(),
         "Box should have unlock request in store"
     );
-    let store_unlock_request = updated_box.unlock_request.unwrap();
+    let store_unlock_request = updated_box.unlock_requests.last().unwrap();
     assert_eq!(store_unlock_request.status, UnlockRequestStatus::Requested);
     assert_eq!(
         store_unlock_request.message,
@@ -507,23 +1066,22 @@ async fn test_non_lead_guardian_cannot_initiate_unlock() {
         .await
         .unwrap();
 
-    // Should be BAD_REQUEST
-    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
-
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
+    // Only lead guardians may request an unlock
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let json_response = response_to_json(response).await;
+    assert_eq!(
+        json_response["error"],
+        "Only lead guardians may request unlock"
+    );
 
     // Verify the box still has no unlock request
     let final_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     assert!(
-        final_box.unlock_request.is_none(),
+        final_box.unlock_requests.is_empty(),
         "Box should still not have unlock request after failed attempt"
     );
 }
@@ -540,11 +1098,11 @@ async fn test_accept_unlock_request() {
 
     // Verify the box has an unlock request
     let initial_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
     assert!(
-        initial_box.unlock_request.is_some(),
+        initial_box.latest_unlock_request().is_some(),
         "Box should have unlock request before test"
     );
 
@@ -588,23 +1146,17 @@ async fn test_accept_unlock_request() {
         .iter()
         .any(|id| id.as_str().unwrap() == "guardian_1"));
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify directly in the store
     let updated_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     assert!(
-        updated_box.unlock_request.is_some(),
+        updated_box.latest_unlock_request().is_some(),
         "Box should have unlock request in store"
     );
-    let store_unlock_request = updated_box.unlock_request.unwrap();
+    let store_unlock_request = updated_box.unlock_requests.last().unwrap();
     assert!(
         store_unlock_request
             .approved_by
@@ -625,17 +1177,16 @@ async fn test_reject_unlock_request() {
 
     // Verify the box has an unlock request
     let initial_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
     assert!(
-        initial_box.unlock_request.is_some(),
+        initial_box.latest_unlock_request().is_some(),
         "Box should have unlock request before test"
     );
     assert!(
         initial_box
-            .unlock_request
-            .as_ref()
+            .latest_unlock_request()
             .unwrap()
             .rejected_by
             .is_empty(),
@@ -682,23 +1233,17 @@ async fn test_reject_unlock_request() {
         .iter()
         .any(|id| id.as_str().unwrap() == "guardian_1"));
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify directly in the store
     let updated_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     assert!(
-        updated_box.unlock_request.is_some(),
+        updated_box.latest_unlock_request().is_some(),
         "Box should have unlock request in store"
     );
-    let store_unlock_request = updated_box.unlock_request.unwrap();
+    let store_unlock_request = updated_box.unlock_requests.last().unwrap();
     assert!(
         store_unlock_request
             .rejected_by
@@ -734,23 +1279,17 @@ async fn test_respond_to_unlock_request_invalid_payload() {
     // Should result in a client error
     assert!(response.status().is_client_error());
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify the unlock request was not modified
     let final_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     assert!(
-        final_box.unlock_request.is_some(),
+        final_box.latest_unlock_request().is_some(),
         "Box should still have unlock request"
     );
-    let final_request = final_box.unlock_request.unwrap();
+    let final_request = final_box.unlock_requests.last().unwrap();
     assert!(
         final_request.approved_by.is_empty(),
         "Approved by should still be empty"
@@ -790,20 +1329,14 @@ async fn test_respond_without_unlock_request() {
     // Should return bad request since there's no unlock request
     assert!(response.status().is_client_error());
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify the box still has no unlock request
     let final_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     assert!(
-        final_box.unlock_request.is_none(),
+        final_box.unlock_requests.is_empty(),
         "Box should still not have unlock request after attempt"
     );
 }
@@ -837,23 +1370,17 @@ async fn test_non_guardian_cannot_respond() {
     // Should be UNAUTHORIZED
     assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify the unlock request was not modified
     let final_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     assert!(
-        final_box.unlock_request.is_some(),
+        final_box.latest_unlock_request().is_some(),
         "Box should still have unlock request"
     );
-    let final_request = final_box.unlock_request.unwrap();
+    let final_request = final_box.unlock_requests.last().unwrap();
 
     // Verify non-guardian was not added to approvers or rejecters
     assert!(
@@ -869,3 +1396,978 @@ async fn test_non_guardian_cannot_respond() {
         "not_a_guardian should not be in rejected_by list"
     );
 }
+
+// Helper to send an unlock response as the given guardian and return the status and JSON body
+async fn respond_as(
+    app: &Router,
+    box_id: &str,
+    guardian_id: &str,
+    payload: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/respond", box_id),
+            guardian_id,
+            Some(payload),
+        ))
+        .await
+        .unwrap();
+
+    let status = response.status();
+    (status, response_to_json(response).await)
+}
+
+#[tokio::test]
+async fn test_unlock_request_approved_at_threshold() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    // Box 2 has three accepted guardians, so a majority is two approvals
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    let (status, body) = respond_as(&app, box_id, "guardian_1", json!({ "approve": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["unlockStatus"], "requested");
+
+    let (status, body) = respond_as(&app, box_id, "guardian_3", json!({ "approve": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["unlockStatus"], "approved");
+    assert_eq!(body["box"]["unlockRequest"]["status"], "approved");
+
+    let updated_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let unlock = updated_box.unlock_requests.last().unwrap();
+    assert_eq!(unlock.status, UnlockRequestStatus::Approved);
+    assert_eq!(unlock.approved_by.len(), 2);
+}
+
+#[tokio::test]
+async fn test_unlock_vote_requires_accepted_guardian() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    // guardian_1 has opened box 2 but not accepted the invitation yet
+    let box_id = "22222222-2222-2222-2222-222222222222";
+    let mut box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    box_record
+        .guardians
+        .iter_mut()
+        .find(|g| g.id == "guardian_1")
+        .unwrap()
+        .status = GuardianStatus::Viewed;
+    match &store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+
+    let (status, _) = respond_as(&app, box_id, "guardian_1", json!({ "approve": true })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let unlock = box_record.unlock_requests.last().unwrap();
+    assert!(unlock.approved_by.is_empty());
+    assert_eq!(unlock.status, UnlockRequestStatus::Requested);
+}
+
+#[tokio::test]
+async fn test_unlock_vote_replaces_earlier_vote() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    // One request can't carry both votes
+    let (status, _) = respond_as(
+        &app,
+        box_id,
+        "guardian_1",
+        json!({ "approve": true, "reject": true }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Changing their mind moves guardian_1 from rejected_by to approved_by
+    let (status, _) = respond_as(&app, box_id, "guardian_1", json!({ "reject": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = respond_as(&app, box_id, "guardian_1", json!({ "approve": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["unlockStatus"], "requested");
+
+    let box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let unlock = box_record.unlock_requests.last().unwrap();
+    assert_eq!(unlock.approved_by, vec!["guardian_1".to_string()]);
+    assert!(unlock.rejected_by.is_empty());
+
+    // The switched vote counts once towards the majority of two
+    let (status, body) = respond_as(&app, box_id, "guardian_3", json!({ "approve": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["unlockStatus"], "approved");
+}
+
+#[tokio::test]
+async fn test_unlock_request_stays_approved_when_over_approved() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    for guardian_id in ["guardian_1", "guardian_3"] {
+        let (status, _) = respond_as(&app, box_id, guardian_id, json!({ "approve": true })).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    // A third approval past the threshold is recorded but the request stays approved
+    let (status, body) =
+        respond_as(&app, box_id, "lead_guardian_1", json!({ "approve": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["unlockStatus"], "approved");
+    assert_eq!(
+        body["box"]["unlockRequest"]["approvedBy"]
+            .as_array()
+            .unwrap()
+            .len(),
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_unlock_request_rejected_once_approval_impossible() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    // With three guardians and a threshold of two, one rejection still leaves room to approve
+    let (status, body) = respond_as(&app, box_id, "guardian_1", json!({ "reject": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["unlockStatus"], "requested");
+
+    // A second rejection means at most one guardian could still approve
+    let (status, body) = respond_as(&app, box_id, "guardian_3", json!({ "reject": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["unlockStatus"], "rejected");
+
+    let updated_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert_eq!(
+        updated_box.unlock_requests.last().unwrap().status,
+        UnlockRequestStatus::Rejected
+    );
+}
+
+#[tokio::test]
+async fn test_unlock_request_uses_configured_required_approvals() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    // Require every guardian to approve, so a single rejection ends the request
+    let mut box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    box_record.required_approvals = Some(3);
+    match &store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+
+    let (status, body) = respond_as(&app, box_id, "guardian_1", json!({ "reject": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["unlockStatus"], "rejected");
+}
+
+// Helper to set a box's unlock policy through the owner PATCH endpoint
+async fn set_unlock_policy(app: &Router, box_id: &str, policy: serde_json::Value) -> StatusCode {
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}", box_id),
+            "owner_1",
+            Some(json!({ "unlockPolicy": policy })),
+        ))
+        .await
+        .unwrap();
+    response.status()
+}
+
+// Each policy reaches approval on exactly the vote that satisfies it. With
+// lead_guardian_2 added, box 2 has four accepted guardians, two of them leads.
+#[tokio::test]
+async fn test_unlock_policies_reach_approval() {
+    let cases = [
+        (
+            json!({ "type": "majority" }),
+            vec!["guardian_1", "guardian_3", "lead_guardian_1"],
+        ),
+        (
+            json!({ "type": "threshold", "value": 2 }),
+            vec!["guardian_1", "guardian_3"],
+        ),
+        (
+            json!({ "type": "anyLead" }),
+            vec!["guardian_1", "lead_guardian_1"],
+        ),
+        (
+            json!({ "type": "allLeads" }),
+            vec!["guardian_1", "lead_guardian_1", "lead_guardian_2"],
+        ),
+    ];
+
+    for (policy, approvers) in cases {
+        let (app, store) = create_test_app().await;
+        add_test_data_to_store(&store).await;
+        let box_id = "22222222-2222-2222-2222-222222222222";
+
+        let mut box_record = match &store {
+            TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+            TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+        };
+        box_record.guardians.push(Guardian {
+            id: "lead_guardian_2".into(),
+            name: "Lead Guardian Two".into(),
+            lead_guardian: true,
+            status: GuardianStatus::Accepted,
+            added_at: now_str(),
+            invitation_id: "invitation_8".into(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
+        });
+        match &store {
+            TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+            TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+        };
+
+        assert_eq!(
+            set_unlock_policy(&app, box_id, policy.clone()).await,
+            StatusCode::OK
+        );
+
+        let (last, earlier) = approvers.split_last().unwrap();
+        for approver in earlier {
+            let (status, body) =
+                respond_as(&app, box_id, approver, json!({ "approve": true })).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(
+                body["unlockStatus"], "requested",
+                "{} after {}",
+                policy, approver
+            );
+        }
+
+        let (status, body) = respond_as(&app, box_id, last, json!({ "approve": true })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["unlockStatus"], "approved",
+            "{} after {}",
+            policy, last
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_all_leads_policy_rejected_by_one_lead() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    assert_eq!(
+        set_unlock_policy(&app, box_id, json!({ "type": "allLeads" })).await,
+        StatusCode::OK
+    );
+
+    // A non-lead rejection doesn't count against a lead-only policy
+    let (_, body) = respond_as(&app, box_id, "guardian_1", json!({ "reject": true })).await;
+    assert_eq!(body["unlockStatus"], "requested");
+
+    let (status, body) =
+        respond_as(&app, box_id, "lead_guardian_1", json!({ "reject": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["unlockStatus"], "rejected");
+}
+
+#[tokio::test]
+async fn test_update_box_unlock_policy() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}", box_id),
+            "owner_1",
+            Some(json!({ "unlockPolicy": { "type": "threshold", "value": 2 } })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_response = response_to_json(response).await;
+    assert_eq!(
+        json_response["box"]["unlockPolicy"],
+        json!({ "type": "threshold", "value": 2 })
+    );
+    assert_eq!(json_response["box"]["requiredApprovals"], 2);
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert_eq!(stored.unlock_policy, UnlockPolicy::Threshold(2));
+
+    // A threshold of zero would approve requests nobody voted on
+    assert_eq!(
+        set_unlock_policy(&app, box_id, json!({ "type": "threshold", "value": 0 })).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        set_unlock_policy(&app, box_id, json!({ "type": "quorum" })).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+}
+
+#[tokio::test]
+async fn test_update_box_rejects_unreachable_unlock_policy() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    let patch = |box_id: &str, owner: &str, payload: serde_json::Value| {
+        app.clone().oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}", box_id),
+            owner,
+            Some(payload),
+        ))
+    };
+
+    // requiredApprovals would be ignored under a threshold policy
+    let response = patch(
+        box_id,
+        "owner_1",
+        json!({
+            "requiredApprovals": 2,
+            "unlockPolicy": { "type": "threshold", "value": 3 }
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Box 2 has three accepted guardians
+    assert_eq!(
+        set_unlock_policy(&app, box_id, json!({ "type": "threshold", "value": 4 })).await,
+        StatusCode::UNPROCESSABLE_ENTITY
+    );
+    assert_eq!(
+        set_unlock_policy(&app, box_id, json!({ "type": "threshold", "value": 3 })).await,
+        StatusCode::OK
+    );
+
+    // Once a threshold policy is in place, requiredApprovals alone is refused too
+    let response = patch(box_id, "owner_1", json!({ "requiredApprovals": 2 }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Box 3 has no lead guardian to approve under a lead policy
+    let box_3 = "33333333-3333-3333-3333-333333333333";
+    for policy in [json!({ "type": "allLeads" }), json!({ "type": "anyLead" })] {
+        let response = patch(box_3, "owner_2", json!({ "unlockPolicy": policy }))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    // nor does a lead who hasn't accepted their invitation yet
+    let mut box_3_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_3).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_3).await.unwrap(),
+    };
+    let mut invited_lead = box_3_record.guardians[0].clone();
+    invited_lead.id = "invited_lead".into();
+    invited_lead.invitation_id = "invitation_invited_lead".into();
+    invited_lead.lead_guardian = true;
+    invited_lead.status = GuardianStatus::Invited;
+    box_3_record.guardians.push(invited_lead);
+    match &store {
+        TestStore::Mock(mock) => mock.update_box(box_3_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_3_record).await.unwrap(),
+    };
+    let response = patch(
+        box_3,
+        "owner_2",
+        json!({ "unlockPolicy": { "type": "anyLead" } }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert_eq!(stored.unlock_policy, UnlockPolicy::Threshold(3));
+    assert_eq!(stored.required_approvals, None);
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_3).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_3).await.unwrap(),
+    };
+    assert_eq!(stored.unlock_policy, UnlockPolicy::Majority);
+}
+
+// Replace box 2's unlock request with one that expired an hour ago
+async fn seed_expired_unlock_request(store: &TestStore, box_id: &str) {
+    let mut box_record = match store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let expired_at = (chrono::Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+    box_record.unlock_requests.last_mut().unwrap().expires_at = Some(expired_at);
+    match store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+}
+
+#[tokio::test]
+async fn test_respond_to_expired_unlock_request_is_gone() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "22222222-2222-2222-2222-222222222222";
+    seed_expired_unlock_request(&store, box_id).await;
+
+    let (status, body) = respond_as(&app, box_id, "guardian_1", json!({ "approve": true })).await;
+    assert_eq!(status, StatusCode::GONE);
+    assert_eq!(body["error"], "Unlock request has expired");
+
+    // The vote must not have been recorded
+    let final_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert!(final_box
+        .unlock_requests
+        .last()
+        .unwrap()
+        .approved_by
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_get_guardian_box_reports_expired_unlock_request() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/guardian/{}", box_id),
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_response = response_to_json(response).await;
+    assert_eq!(json_response["box"]["expired"], false);
+
+    seed_expired_unlock_request(&store, box_id).await;
+
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/guardian/{}", box_id),
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_response = response_to_json(response).await;
+    assert_eq!(json_response["box"]["expired"], true);
+}
+
+#[tokio::test]
+async fn test_request_unlock_sets_expiry() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let response = app
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/guardian/11111111-1111-1111-1111-111111111111/request",
+            "lead_guardian_1",
+            Some(json!({ "message": "Please unlock" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json_response = response_to_json(response).await;
+    let expires_at = json_response["box"]["unlockRequest"]["expiresAt"]
+        .as_str()
+        .expect("New unlock requests should carry an expiry");
+    let expires_at = chrono::DateTime::parse_from_rfc3339(expires_at).unwrap();
+    assert!(expires_at > chrono::Utc::now());
+    assert_eq!(json_response["box"]["expired"], false);
+}
+
+#[tokio::test]
+async fn test_respond_to_unlock_request_retries_on_conflict() {
+    // Concurrent writers can only be simulated with the mock store
+    if use_dynamodb() {
+        return;
+    }
+    init_test_logging();
+
+    // Another guardian's vote lands between our read and write
+    let store =
+        Arc::new(MockBoxStore::with_data(create_test_data(&now_str())).with_concurrent_writes(1));
+    let app =
+        routes::create_router_with_store(store.clone(), Arc::new(MemoryInvitationStore::new()), "");
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    let (status, body) = respond_as(&app, box_id, "guardian_1", json!({ "approve": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["unlockStatus"], "requested");
+
+    let updated_box = store.get_box(box_id).await.unwrap();
+    assert_eq!(updated_box.version, 2);
+    assert_eq!(
+        updated_box.unlock_requests.last().unwrap().approved_by,
+        vec!["guardian_1".to_string()]
+    );
+}
+
+// Makes guardian_2 a second lead guardian on box 1 and has both leads request an
+// unlock, returning the two request ids in the order they were made
+async fn seed_two_unlock_requests(app: &Router, store: &TestStore) -> (String, String) {
+    let box_id = "11111111-1111-1111-1111-111111111111";
+    let mut box_record = match store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    box_record
+        .guardians
+        .iter_mut()
+        .find(|g| g.id == "guardian_2")
+        .unwrap()
+        .lead_guardian = true;
+    match store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+
+    let mut request_ids = Vec::new();
+    for lead in ["lead_guardian_1", "guardian_2"] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "PATCH",
+                &format!("/boxes/guardian/{}/request", box_id),
+                lead,
+                Some(json!({ "message": format!("Unlock requested by {}", lead) })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json_response = response_to_json(response).await;
+        request_ids.push(
+            json_response["box"]["unlockRequest"]["id"]
+                .as_str()
+                .unwrap()
+                .to_string(),
+        );
+    }
+    (request_ids[0].clone(), request_ids[1].clone())
+}
+
+#[tokio::test]
+async fn test_request_unlock_rejects_second_open_request() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "11111111-1111-1111-1111-111111111111";
+
+    let request_unlock = || {
+        app.clone().oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/request", box_id),
+            "lead_guardian_1",
+            Some(json!({ "message": "Please unlock" })),
+        ))
+    };
+
+    assert_eq!(request_unlock().await.unwrap().status(), StatusCode::OK);
+    assert_eq!(
+        request_unlock().await.unwrap().status(),
+        StatusCode::CONFLICT
+    );
+
+    let mut box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert_eq!(box_record.unlock_requests.len(), 1);
+
+    // Once the open request has expired a new one can be made
+    let expired_at = (chrono::Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+    box_record.unlock_requests[0].expires_at = Some(expired_at);
+    match &store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+    assert_eq!(request_unlock().await.unwrap().status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_simultaneous_unlock_requests_are_kept() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "11111111-1111-1111-1111-111111111111";
+
+    let (first_id, second_id) = seed_two_unlock_requests(&app, &store).await;
+    assert_ne!(first_id, second_id);
+
+    let updated_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let initiators: Vec<_> = updated_box
+        .unlock_requests
+        .iter()
+        .map(|request| request.initiated_by.clone().unwrap())
+        .collect();
+    assert_eq!(initiators, vec!["lead_guardian_1", "guardian_2"]);
+    assert!(updated_box
+        .unlock_requests
+        .iter()
+        .all(|request| request.status == UnlockRequestStatus::Requested));
+
+    // The deprecated singular field reports the latest request
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/guardian/{}", box_id),
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    let json_response = response_to_json(response).await;
+    assert_eq!(
+        json_response["box"]["unlockRequests"]
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+    assert_eq!(json_response["box"]["unlockRequest"]["id"], second_id);
+}
+
+#[tokio::test]
+async fn test_respond_to_specific_unlock_request() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "11111111-1111-1111-1111-111111111111";
+
+    let (first_id, second_id) = seed_two_unlock_requests(&app, &store).await;
+
+    // Vote on the older request rather than the latest
+    let (status, body) = respond_as(
+        &app,
+        box_id,
+        "guardian_1",
+        json!({ "requestId": first_id, "approve": true }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["unlockStatus"], "requested");
+
+    let updated_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let find = |id: &str| {
+        updated_box
+            .unlock_requests
+            .iter()
+            .find(|request| request.id == id)
+            .unwrap()
+    };
+    assert_eq!(find(&first_id).approved_by, vec!["guardian_1".to_string()]);
+    assert!(find(&second_id).approved_by.is_empty());
+
+    // An id that doesn't belong to the box is reported as not found
+    let (status, _) = respond_as(
+        &app,
+        box_id,
+        "guardian_1",
+        json!({ "requestId": "no-such-request", "approve": true }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+async fn cancel_as(
+    app: &Router,
+    box_id: &str,
+    guardian_id: &str,
+    payload: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/cancel", box_id),
+            guardian_id,
+            Some(payload),
+        ))
+        .await
+        .unwrap();
+
+    let status = response.status();
+    (status, response_to_json(response).await)
+}
+
+#[tokio::test]
+async fn test_initiator_can_cancel_unlock_request() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    let (status, body) = cancel_as(&app, box_id, "lead_guardian_1", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["unlockStatus"], "cancelled");
+    assert_eq!(body["box"]["unlockRequest"]["status"], "cancelled");
+
+    let updated_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert_eq!(
+        updated_box.latest_unlock_request().unwrap().status,
+        UnlockRequestStatus::Cancelled
+    );
+
+    // Guardians can no longer vote on it, and it can't be cancelled twice
+    let (status, _) = respond_as(&app, box_id, "guardian_1", json!({ "approve": true })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = cancel_as(&app, box_id, "lead_guardian_1", json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_other_guardian_cannot_cancel_unlock_request() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    let (status, body) = cancel_as(
+        &app,
+        box_id,
+        "guardian_1",
+        json!({ "requestId": "unlock-111" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(
+        body["error"],
+        "Only the guardian who requested the unlock may cancel it"
+    );
+
+    let final_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert_eq!(
+        final_box.latest_unlock_request().unwrap().status,
+        UnlockRequestStatus::Requested
+    );
+}
+
+#[tokio::test]
+async fn test_cancel_without_unlock_request() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    // Box 1 has no unlock request
+    let (status, body) = cancel_as(
+        &app,
+        "11111111-1111-1111-1111-111111111111",
+        "lead_guardian_1",
+        json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "No unlock request exists to cancel");
+}
+
+// Seeds a box whose only guardian slot is still waiting on the invitation with
+// code `invite_code`, and returns the box id
+async fn seed_pending_invitation(
+    store: &TestStore,
+    invitation_store: &MemoryInvitationStore,
+    invite_code: &str,
+) -> String {
+    let now = now_str();
+    let box_id = format!("box-for-{}", invite_code);
+    let invitation_id = format!("invitation-{}", invite_code);
+
+    let box_record = BoxRecord {
+        id: box_id.clone(),
+        name: "Invite Code Box".into(),
+        description: "Box for invite code responses".into(),
+        is_locked: false,
+        created_at: now.clone(),
+        updated_at: now.clone(),
+        owner_id: "owner_1".into(),
+        owner_name: Some("Owner One".into()),
+        documents: vec![],
+        guardians: vec![Guardian {
+            id: "placeholder".into(),
+            name: "Future Guardian".into(),
+            lead_guardian: false,
+            status: GuardianStatus::Invited,
+            added_at: now.clone(),
+            invitation_id: invitation_id.clone(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
+        }],
+        unlock_instructions: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
+        version: 0,
+    };
+    match store {
+        TestStore::Mock(mock) => mock.create_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.create_box(box_record).await.unwrap(),
+    };
+
+    invitation_store
+        .create_invitation(Invitation {
+            id: invitation_id,
+            invite_code: invite_code.to_string(),
+            invited_name: "Future Guardian".into(),
+            box_id: box_id.clone(),
+            created_at: String::new(),
+            expires_at: String::new(),
+            opened: false,
+            status: InvitationStatus::Invited,
+            linked_user_id: None,
+            creator_id: "owner_1".into(),
+        })
+        .await
+        .unwrap();
+
+    box_id
+}
+
+async fn respond_by_code(
+    app: &Router,
+    user_id: &str,
+    invite_code: &str,
+    accept: bool,
+) -> (StatusCode, serde_json::Value) {
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/guardian/invitation",
+            user_id,
+            Some(json!({ "inviteCode": invite_code, "accept": accept })),
+        ))
+        .await
+        .unwrap();
+    let status = response.status();
+    (status, response_to_json(response).await)
+}
+
+#[tokio::test]
+async fn test_accept_invitation_by_code() {
+    std::env::set_var("TEST_SNS", "true");
+    let invitation_store = Arc::new(MemoryInvitationStore::new());
+    let (app, store) = create_test_app_with_invitations(invitation_store.clone()).await;
+    let box_id = seed_pending_invitation(&store, &invitation_store, "ACCEPT01").await;
+
+    let (status, body) = respond_by_code(&app, "new_guardian", "ACCEPT01", true).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["box"]["id"], box_id);
+
+    // The slot is linked to the caller even before invitation_viewed is processed
+    let updated_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
+    };
+    assert_eq!(updated_box.guardians[0].id, "new_guardian");
+    assert_eq!(updated_box.guardians[0].status, GuardianStatus::Accepted);
+
+    let invitation = invitation_store
+        .get_invitation_by_code("ACCEPT01")
+        .await
+        .unwrap();
+    assert_eq!(invitation.status, InvitationStatus::Accepted);
+    assert_eq!(invitation.linked_user_id.as_deref(), Some("new_guardian"));
+
+    // Someone else can't reuse the code
+    let (status, _) = respond_by_code(&app, "other_user", "ACCEPT01", true).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_reject_invitation_by_code() {
+    std::env::set_var("TEST_SNS", "true");
+    let invitation_store = Arc::new(MemoryInvitationStore::new());
+    let (app, store) = create_test_app_with_invitations(invitation_store.clone()).await;
+    let box_id = seed_pending_invitation(&store, &invitation_store, "REJECT01").await;
+
+    let (status, body) = respond_by_code(&app, "new_guardian", "REJECT01", false).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["boxId"], box_id);
+
+    let updated_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
+    };
+    assert_eq!(updated_box.guardians[0].status, GuardianStatus::Rejected);
+
+    let invitation = invitation_store
+        .get_invitation_by_code("REJECT01")
+        .await
+        .unwrap();
+    assert_eq!(invitation.status, InvitationStatus::Rejected);
+
+    // A second answer is refused, and unknown codes are 404
+    let (status, _) = respond_by_code(&app, "new_guardian", "REJECT01", true).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = respond_by_code(&app, "new_guardian", "NOSUCHCD", true).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,90 @@
+use axum::{
+    body::Body,
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::Response,
+};
+use lambda_http::Body as LambdaBody;
+
+use crate::response_to_lambda;
+
+// PNG signature followed by the start of the IHDR chunk
+const PNG_BYTES: &[u8] = &[
+    0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44, 0x52,
+];
+
+#[tokio::test]
+async fn test_json_response_is_sent_as_text() {
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json; charset=utf-8")
+        .body(Body::from(r#"{"count":3}"#))
+        .unwrap();
+
+    let lambda_response = response_to_lambda(response).await.unwrap();
+
+    assert_eq!(lambda_response.status(), StatusCode::OK);
+    assert_eq!(
+        lambda_response.headers().get(CONTENT_TYPE).unwrap(),
+        "application/json; charset=utf-8"
+    );
+    match lambda_response.body() {
+        LambdaBody::Text(text) => assert_eq!(text, r#"{"count":3}"#),
+        other => panic!("expected a text body, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_envelope_response_is_sent_as_text() {
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/vnd.lockbox.v2+json")
+        .body(Body::from(r#"{"data":[],"meta":{}}"#))
+        .unwrap();
+
+    let lambda_response = response_to_lambda(response).await.unwrap();
+
+    assert!(matches!(lambda_response.body(), LambdaBody::Text(_)));
+}
+
+#[tokio::test]
+async fn test_png_response_is_sent_as_binary() {
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "image/png")
+        .body(Body::from(PNG_BYTES))
+        .unwrap();
+
+    let lambda_response = response_to_lambda(response).await.unwrap();
+
+    match lambda_response.body() {
+        LambdaBody::Binary(bytes) => assert_eq!(bytes.as_slice(), PNG_BYTES),
+        other => panic!("expected a binary body, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_utf8_body_without_text_content_type_is_binary() {
+    // Valid UTF-8 must not be enough to send the body as text
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .body(Body::from("plain ascii bytes"))
+        .unwrap();
+
+    let lambda_response = response_to_lambda(response).await.unwrap();
+
+    assert!(matches!(lambda_response.body(), LambdaBody::Binary(_)));
+}
+
+#[tokio::test]
+async fn test_empty_response_body() {
+    let response = Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap();
+
+    let lambda_response = response_to_lambda(response).await.unwrap();
+
+    assert_eq!(lambda_response.status(), StatusCode::NO_CONTENT);
+    assert!(matches!(lambda_response.body(), LambdaBody::Empty));
+}
@@ -1,2 +1,4 @@
 pub mod box_tests;
+pub mod error_tests;
 pub mod guardian_tests;
+pub mod lambda_tests;
@@ -21,15 +21,30 @@ pub enum AppError {
     VersionConflict(String),
     GuardianNotFound(String),
     BoxNotFound(String),
+    // The event can't be processed as sent, e.g. a required field is missing
+    InvalidEvent(String),
     InternalError(String),
 }
 
+impl AppError {
+    /// Whether redelivering the event could succeed. A missing box or guardian
+    /// and a malformed event stay that way, so only store failures and
+    /// version conflicts are worth another attempt.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            AppError::VersionConflict(_) | AppError::InternalError(_)
+        )
+    }
+}
+
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             AppError::VersionConflict(msg) => write!(f, "Version conflict: {}", msg),
             AppError::GuardianNotFound(msg) => write!(f, "Guardian not found: {}", msg),
             AppError::BoxNotFound(msg) => write!(f, "Box not found: {}", msg),
+            AppError::InvalidEvent(msg) => write!(f, "Invalid event: {}", msg),
             AppError::InternalError(msg) => write!(f, "Internal error: {}", msg),
         }
     }
@@ -46,6 +61,7 @@ impl From<anyhow::Error> for AppError {
                 AppError::GuardianNotFound(msg) => AppError::GuardianNotFound(msg.clone()),
                 AppError::BoxNotFound(msg) => AppError::BoxNotFound(msg.clone()),
                 AppError::VersionConflict(msg) => AppError::VersionConflict(msg.clone()),
+                AppError::InvalidEvent(msg) => AppError::InvalidEvent(msg.clone()),
                 AppError::InternalError(msg) => AppError::InternalError(msg.clone()),
             }
         } else {
@@ -71,7 +87,7 @@ impl From<InvitationEventError> for AppError {
         match err {
             InvitationEventError::BoxNotFound(msg) => Self::BoxNotFound(msg),
             InvitationEventError::MissingField(field) => {
-                Self::InternalError(format!("Missing required field: {}", field))
+                Self::InvalidEvent(format!("Missing required field: {}", field))
             }
             InvitationEventError::UpdateError(msg) => {
                 Self::InternalError(format!("Update error: {}", msg))
@@ -1,7 +1,7 @@
 // Import shared models and store
 use lockbox_shared::models::events::InvitationEvent;
-use lockbox_shared::models::GuardianStatus;
-use lockbox_shared::store::BoxStore;
+use lockbox_shared::models::{now_str, BoxRecord, Guardian, GuardianStatus};
+use lockbox_shared::store::{update_box_with_retry, BoxStore};
 use std::sync::Arc; // Add Arc for shared state
 
 use log::{error, info, warn}; // Add log import
@@ -40,39 +40,97 @@ pub async fn handle_invitation_opened(
     // We don't need to extract box_id separately since we use event.box_id directly
 
     // Extract user_id from event
-    let user_id = match &event.user_id {
-        Some(id) => id,
+    let result = match &event.user_id {
+        Some(user_id) => {
+            // Process invitation viewing (connecting user to invitation)
+            process_invitation_viewing(state, &event.box_id, &event.invitation_id, user_id).await
+        }
         None => {
             error!("User ID is missing in the event");
-            return Err(InvitationEventError::MissingField("user_id".to_string()).into());
+            Err(InvitationEventError::MissingField("user_id".to_string()).into())
         }
     };
 
-    // Process invitation viewing (connecting user to invitation)
-    match process_invitation_viewing(state, &event.box_id, &event.invitation_id, user_id).await {
-        Ok(_) => Ok(()),
-        Err(app_error) => {
-            // Only propagate critical errors, log and ignore others
-            // This is important for resilience - if a box doesn't exist or guardian not found,
-            // we don't want to crash the handler
-            match &app_error {
-                AppError::GuardianNotFound(msg) => {
-                    warn!("Ignoring event for non-existent guardian: {}", msg);
-                    Ok(())
-                }
-                AppError::BoxNotFound(msg) => {
-                    warn!("Ignoring event for non-existent box: {}", msg);
-                    Ok(())
-                }
-                _ => Err(app_error),
-            }
+    drop_terminal_error(event, result)
+}
+
+// Handler for invitation_rejected events
+pub async fn handle_invitation_rejected(
+    state: SharedBoxStore,
+    event: &InvitationEvent,
+) -> Result<(), AppError> {
+    info!(
+        "Processing invitation_rejected event for box_id={}",
+        event.box_id
+    );
+
+    let result = process_invitation_rejection(state, &event.box_id, &event.invitation_id).await;
+    drop_terminal_error(event, result)
+}
+
+// Handler for invitation_expired events
+pub async fn handle_invitation_expired(
+    state: SharedBoxStore,
+    event: &InvitationEvent,
+) -> Result<(), AppError> {
+    info!(
+        "Processing invitation_expired event for box_id={}",
+        event.box_id
+    );
+
+    let result = process_invitation_expiry(state, &event.box_id, &event.invitation_id).await;
+    drop_terminal_error(event, result)
+}
+
+// Handler for invitation_revoked events
+pub async fn handle_invitation_revoked(
+    state: SharedBoxStore,
+    event: &InvitationEvent,
+) -> Result<(), AppError> {
+    info!(
+        "Processing invitation_revoked event for box_id={}",
+        event.box_id
+    );
+
+    let result = process_invitation_revocation(state, &event.box_id, &event.invitation_id).await;
+    drop_terminal_error(event, result)
+}
+
+// Errors that redelivery can't fix (the box or guardian is gone, or the event
+// itself is bad) are logged and dropped, so SNS doesn't retry them forever.
+// Retryable ones propagate and the record is reported as a batch failure.
+fn drop_terminal_error(
+    event: &InvitationEvent,
+    result: Result<(), AppError>,
+) -> Result<(), AppError> {
+    match result {
+        Err(err) if !err.is_retryable() => {
+            warn!(
+                "Dropping {} event for invitation {} on box {}: {}",
+                event.event_type, event.invitation_id, event.box_id, err
+            );
+            Ok(())
         }
+        result => result,
     }
 }
 
 // Reasonable retry limit
 const MAX_RETRIES: usize = 5;
 
+// Exponential backoff with jitter between optimistic-locking retries
+fn retry_delay_ms(retries: usize) -> u64 {
+    let base_delay_ms = 50 * (1 << retries); // 50, 100, 200, 400, 800
+    let jitter = ((retries as f64 * 0.1) * base_delay_ms as f64) as u64;
+    base_delay_ms + fastrand::u64(0..=jitter)
+}
+
+// SNS delivers at least once, so a redelivered event can find the guardian already
+// linked to this user. Anything past Invited for the same user means there's nothing to do.
+fn is_already_linked(guardian: &Guardian, user_id: &str) -> bool {
+    guardian.id == user_id && guardian.status != GuardianStatus::Invited
+}
+
 pub async fn process_invitation_viewing(
     store: SharedBoxStore,
     box_id: &str,
@@ -85,17 +143,17 @@ pub async fn process_invitation_viewing(
     );
 
     if user_id.is_empty() {
-        return Err(AppError::from(anyhow::anyhow!("User ID cannot be empty")));
+        return Err(AppError::InvalidEvent(
+            "User ID cannot be empty".to_string(),
+        ));
     }
 
-    // First check if the box exists
-    let box_result = store.get_box(box_id).await;
-    if let Err(e) = box_result {
-        return Err(AppError::BoxNotFound(format!(
-            "Box not found: {}, error: {}",
-            box_id, e
-        )));
-    }
+    // First check if the box exists. A consistent read means NotFound is final,
+    // while other store errors stay retryable.
+    store
+        .get_box_consistent(box_id)
+        .await
+        .map_err(AppError::from)?;
 
     let mut retries = 0;
     let mut last_error = None;
@@ -110,12 +168,17 @@ pub async fn process_invitation_viewing(
                 return Ok(());
             }
             Err(err) => {
+                // Retrying can't bring back a missing guardian
+                if let Some(app_err) = err.downcast_ref::<AppError>() {
+                    if !app_err.is_retryable() {
+                        return Err(AppError::from(err));
+                    }
+                }
+
                 retries += 1;
                 last_error = Some(err);
 
-                let base_delay_ms = 50 * (1 << retries); // 50, 100, 200, 400, 800
-                let jitter = ((retries as f64 * 0.1) * base_delay_ms as f64) as u64;
-                let delay_ms = base_delay_ms + fastrand::u64(0..=jitter);
+                let delay_ms = retry_delay_ms(retries);
 
                 info!(
                     "Error updating guardian (retry {}/{}): box_id={}, invitation_id={}, waiting {}ms",
@@ -144,7 +207,7 @@ pub async fn process_invitation_viewing(
                     .find(|g| g.invitation_id == invitation_id);
                 match guardian {
                     Some(g) => {
-                        if g.id == user_id && g.status == GuardianStatus::Viewed {
+                        if is_already_linked(g, user_id) {
                             info!(
                                 "Guardian was actually updated by another process: box_id={}, invitation_id={}, user_id={}",
                                 box_id, invitation_id, user_id
@@ -203,10 +266,11 @@ async fn update_specific_guardian(
     let guardian_idx = guardian_idx.unwrap();
     let guardian = &box_record.guardians[guardian_idx];
 
-    // Skip if already updated to viewed status with correct user ID
-    if guardian.status == GuardianStatus::Viewed && guardian.id == user_id {
+    // Skip duplicate deliveries without writing, so updated_at and version stay put
+    if is_already_linked(guardian, user_id) {
         log::info!(
-            "Guardian already updated, skipping: box_id={}, invitation_id={}, user_id={}",
+            "Guardian already linked (status {}), skipping: box_id={}, invitation_id={}, user_id={}",
+            guardian.status,
             box_id,
             invitation_id,
             user_id
@@ -217,7 +281,7 @@ async fn update_specific_guardian(
     // Only update if the guardian is still in "invited" state
     if guardian.status == GuardianStatus::Invited {
         // Make a minimal update - only update this one guardian
-        let now = chrono::Utc::now().to_rfc3339();
+        let now = now_str();
         box_record.guardians[guardian_idx].id = user_id.to_string();
         box_record.guardians[guardian_idx].status = GuardianStatus::Viewed;
         box_record.updated_at = now;
@@ -251,3 +315,144 @@ async fn update_specific_guardian(
         Ok(())
     }
 }
+
+// Aborts update_box_with_retry before it writes: either there is nothing to
+// change for this guardian or the update failed outright
+enum GuardianUpdate {
+    Unchanged,
+    Failed(AppError),
+}
+
+impl From<lockbox_shared::error::StoreError> for GuardianUpdate {
+    fn from(err: lockbox_shared::error::StoreError) -> Self {
+        Self::Failed(AppError::from(err))
+    }
+}
+
+// Applies `update` to the guardian holding `invitation_id`, retrying on version
+// conflicts. `update` returns None when the guardian is already in the wanted state,
+// in which case nothing is written. Returns whether the box was written.
+async fn update_guardian_by_invitation<F>(
+    store: &SharedBoxStore,
+    box_id: &str,
+    invitation_id: &str,
+    mut update: F,
+) -> Result<bool, AppError>
+where
+    F: FnMut(&mut BoxRecord, usize) -> Option<()>,
+{
+    let result = update_box_with_retry(store.as_ref(), box_id, MAX_RETRIES, |box_record| {
+        let guardian_idx = box_record
+            .guardians
+            .iter()
+            .position(|g| g.invitation_id == invitation_id)
+            .ok_or_else(|| {
+                GuardianUpdate::Failed(AppError::GuardianNotFound(format!(
+                    "No guardian found with invitation ID: {}",
+                    invitation_id
+                )))
+            })?;
+        update(box_record, guardian_idx).ok_or(GuardianUpdate::Unchanged)
+    })
+    .await;
+
+    match result {
+        Ok(_) => Ok(true),
+        Err(GuardianUpdate::Unchanged) => Ok(false),
+        Err(GuardianUpdate::Failed(e)) => Err(e),
+    }
+}
+
+pub async fn process_invitation_rejection(
+    store: SharedBoxStore,
+    box_id: &str,
+    invitation_id: &str,
+) -> Result<(), AppError> {
+    let written =
+        update_guardian_by_invitation(&store, box_id, invitation_id, |box_record, idx| {
+            // Redelivered events find the guardian already rejected
+            if box_record.guardians[idx].status == GuardianStatus::Rejected {
+                return None;
+            }
+            box_record.guardians[idx].status = GuardianStatus::Rejected;
+            Some(())
+        })
+        .await
+        .inspect_err(|e| {
+            error!(
+                "Failed to reject guardian: box_id={}, invitation_id={}, error={}",
+                box_id, invitation_id, e
+            )
+        })?;
+
+    if written {
+        info!(
+            "Marked guardian as rejected: box_id={}, invitation_id={}",
+            box_id, invitation_id
+        );
+    } else {
+        info!(
+            "Guardian already rejected, skipping: box_id={}, invitation_id={}",
+            box_id, invitation_id
+        );
+    }
+    Ok(())
+}
+
+// Drops the guardian placeholder left behind by an invitation that expired
+// without being answered. Guardians that already responded are kept as they are.
+pub async fn process_invitation_expiry(
+    store: SharedBoxStore,
+    box_id: &str,
+    invitation_id: &str,
+) -> Result<(), AppError> {
+    remove_invited_guardian(store, box_id, invitation_id, "expired").await
+}
+
+// Drops the guardian placeholder for an invitation its creator revoked. Revoking is
+// only allowed before the invitation is used, so the guardian is normally still invited.
+pub async fn process_invitation_revocation(
+    store: SharedBoxStore,
+    box_id: &str,
+    invitation_id: &str,
+) -> Result<(), AppError> {
+    remove_invited_guardian(store, box_id, invitation_id, "revoked").await
+}
+
+// `reason` is only used in log messages
+async fn remove_invited_guardian(
+    store: SharedBoxStore,
+    box_id: &str,
+    invitation_id: &str,
+    reason: &str,
+) -> Result<(), AppError> {
+    let written =
+        update_guardian_by_invitation(&store, box_id, invitation_id, |box_record, idx| {
+            // The invitation was viewed or answered in the meantime
+            if box_record.guardians[idx].status != GuardianStatus::Invited {
+                return None;
+            }
+            box_record.guardians.remove(idx);
+            Some(())
+        })
+        .await
+        .inspect_err(|e| {
+            error!(
+                "Failed to remove {} guardian: box_id={}, invitation_id={}, error={}",
+                reason, box_id, invitation_id, e
+            )
+        })?;
+
+    if written {
+        info!(
+            "Removed {} guardian placeholder: box_id={}, invitation_id={}",
+            reason, box_id, invitation_id
+        );
+    } else {
+        info!(
+            "Guardian is no longer invited, keeping it: box_id={}, invitation_id={}",
+            box_id, invitation_id
+        );
+    }
+    Ok(())
+}
@@ -1,9 +1,11 @@
 use aws_lambda_events::event::sns::SnsEvent;
-use env_logger;
 use lambda_runtime::{service_fn, Error, LambdaEvent};
-use lockbox_shared::models::events::InvitationEvent;
+use lockbox_shared::correlation::with_correlation_id;
+use lockbox_shared::models::events::{EventError, InvitationEvent};
+use lockbox_shared::store::metrics::{emf_metrics_enabled, MetricsBoxStore};
 use lockbox_shared::store::{dynamo::DynamoBoxStore, BoxStore};
-use log::{error, info};
+use log::{error, info, warn};
+use serde::Serialize;
 use std::sync::Arc;
 
 // Import the handlers module
@@ -16,8 +18,8 @@ mod tests;
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    // Initialize env_logger
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    // Initialize env_logger, tagging lines with the event's correlation id (JSON with LOG_FORMAT=json)
+    lockbox_shared::correlation::log_builder().init();
 
     info!("Logging initialized with env_logger");
 
@@ -27,41 +29,110 @@ async fn main() -> Result<(), Error> {
     let dynamo_store = Arc::new(DynamoBoxStore::new().await);
 
     // Run the Lambda service function with the store
-    lambda_runtime::run(service_fn(|event| handler(event, dynamo_store.clone()))).await?;
+    if emf_metrics_enabled() {
+        info!("EMF_METRICS is set, emitting store metrics");
+        let store = Arc::new(MetricsBoxStore::new(dynamo_store));
+        lambda_runtime::run(service_fn(|event| handler(event, store.clone()))).await?;
+    } else {
+        lambda_runtime::run(service_fn(|event| handler(event, dynamo_store.clone()))).await?;
+    }
     Ok(())
 }
 
+// Partial batch failure response, so only the failed records are retried
+#[derive(Serialize, Debug, Default)]
+pub struct BatchResponse {
+    #[serde(rename = "batchItemFailures")]
+    pub batch_item_failures: Vec<BatchItemFailure>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct BatchItemFailure {
+    #[serde(rename = "itemIdentifier")]
+    pub item_identifier: String,
+}
+
 // Lambda handler function - make this public for testing
-pub async fn handler<S>(event: LambdaEvent<SnsEvent>, store: Arc<S>) -> Result<(), Error>
+pub async fn handler<S>(event: LambdaEvent<SnsEvent>, store: Arc<S>) -> Result<BatchResponse, Error>
 where
     S: BoxStore + Send + Sync + 'static,
 {
     // Get the SNS event
     let sns_event = event.payload;
+    let mut response = BatchResponse::default();
 
-    // Process each record (message) in the SNS event
+    // Process each record (message) in the SNS event, isolating failures per record
     for record in sns_event.records {
-        // Extract and parse the SNS message
         let message = record.sns;
 
-        // Try to parse the message as an InvitationEvent
-        if let Ok(invitation_event) = serde_json::from_str::<InvitationEvent>(&message.message) {
-            match invitation_event.event_type.as_str() {
-                "invitation_created" => {
-                    handlers::handle_invitation_created(store.clone(), &invitation_event).await?
-                }
-                "invitation_viewed" => {
-                    handlers::handle_invitation_opened(store.clone(), &invitation_event).await?
-                }
-                _ => {
-                    error!("Unknown event type: {}", invitation_event.event_type);
-                }
-            }
-        } else {
-            error!("Failed to parse SNS message: {}", message.message);
-            // Continue processing remaining records; rely on SNS DLQ for this one
-            continue;
+        if let Err(e) = process_message(&message.message, store.clone()).await {
+            error!(
+                "Failed to process SNS message {}: {}",
+                message.message_id, e
+            );
+            response.batch_item_failures.push(BatchItemFailure {
+                item_identifier: message.message_id,
+            });
+        }
+    }
+
+    Ok(response)
+}
+
+async fn process_message<S>(message: &str, store: Arc<S>) -> Result<(), Error>
+where
+    S: BoxStore + Send + Sync + 'static,
+{
+    let invitation_event = match InvitationEvent::from_json(message) {
+        Ok(event) => event,
+        // Retrying won't help an event type we don't know, so don't report it as failed
+        Err(EventError::UnknownEventType(event_type)) => {
+            warn!("Ignoring unknown event type: {}", event_type);
+            return Ok(());
+        }
+        // Malformed and newer-version messages are reported so they end up in the DLQ
+        Err(e) => return Err(format!("Failed to parse SNS message: {}", e).into()),
+    };
+
+    info!(
+        "Received {} event for invitation {} (correlation id: {})",
+        invitation_event.event_type,
+        invitation_event.invitation_id,
+        invitation_event.correlation_id.as_deref().unwrap_or("none")
+    );
+
+    // Handle the event under the publishing request's id so its logs line up
+    match invitation_event.correlation_id.clone() {
+        Some(id) => with_correlation_id(id, dispatch_event(&invitation_event, store)).await,
+        None => dispatch_event(&invitation_event, store).await,
+    }
+}
+
+async fn dispatch_event<S>(invitation_event: &InvitationEvent, store: Arc<S>) -> Result<(), Error>
+where
+    S: BoxStore + Send + Sync + 'static,
+{
+    match invitation_event.event_type.as_str() {
+        "invitation_created" => {
+            handlers::handle_invitation_created(store, invitation_event).await?
+        }
+        "invitation_viewed" => handlers::handle_invitation_opened(store, invitation_event).await?,
+        // The box service updates the guardian itself before publishing this
+        "invitation_accepted" => info!(
+            "Invitation {} accepted for box {}",
+            invitation_event.invitation_id, invitation_event.box_id
+        ),
+        "invitation_rejected" => {
+            handlers::handle_invitation_rejected(store, invitation_event).await?
+        }
+        "invitation_expired" => {
+            handlers::handle_invitation_expired(store, invitation_event).await?
+        }
+        "invitation_revoked" => {
+            handlers::handle_invitation_revoked(store, invitation_event).await?
         }
+        // from_json only accepts the types above
+        other => warn!("Ignoring unhandled event type: {}", other),
     }
 
     Ok(())
@@ -5,8 +5,8 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use lockbox_shared::error::Result as StoreResult;
-use lockbox_shared::models::events::InvitationEvent;
-use lockbox_shared::models::GuardianStatus;
+use lockbox_shared::models::events::{InvitationEvent, INVITATION_EVENT_VERSION};
+use lockbox_shared::models::{GuardianStatus, UnlockPolicy};
 use lockbox_shared::store::dynamo::DynamoBoxStore;
 use lockbox_shared::store::BoxStore;
 use lockbox_shared::test_utils::dynamo_test_utils::{
@@ -15,7 +15,7 @@ use lockbox_shared::test_utils::dynamo_test_utils::{
 use lockbox_shared::test_utils::mock_box_store::MockBoxStore;
 use lockbox_shared::test_utils::test_logging;
 
-use crate::handler;
+use crate::{handler, BatchItemFailure, BatchResponse};
 
 // Constants for DynamoDB tests
 const TEST_TABLE_NAME: &str = "box-invitation-test-table";
@@ -50,7 +50,7 @@ impl TestStore {
     async fn handle_event(
         &self,
         event: LambdaEvent<SnsEvent>,
-    ) -> Result<(), lambda_runtime::Error> {
+    ) -> Result<BatchResponse, lambda_runtime::Error> {
         match self {
             TestStore::Mock(store) => handler(event, store.clone()).await,
             TestStore::DynamoDB(store) => handler(event, store.clone()).await,
@@ -100,11 +100,13 @@ fn create_test_sns_event(
     // Create invitation event
     let invitation_event = InvitationEvent {
         event_type: event_type.to_string(),
+        event_version: INVITATION_EVENT_VERSION,
         invitation_id: invitation_id.to_string(),
         box_id: box_id.to_string(),
         timestamp: chrono::Utc::now().to_rfc3339(),
         user_id: Some(user_id.to_string()),
         invite_code: "test-code".to_string(),
+        correlation_id: None,
     };
 
     // Serialize to JSON
@@ -170,7 +172,10 @@ async fn test_invitation_viewed_handler() {
         documents: vec![],
         guardians: vec![],
         unlock_instructions: None,
-        unlock_request: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
         version: 0,
     };
 
@@ -183,6 +188,10 @@ async fn test_invitation_viewed_handler() {
         status: GuardianStatus::Invited,
         added_at: "2023-01-01T00:00:00Z".to_string(),
         invitation_id: invitation_id.to_string(), // Use the same invitation_id as in the event
+        email: None,
+        relationship: None,
+        note: None,
+        last_viewed_at: None,
     };
 
     box_record.guardians.push(guardian);
@@ -226,6 +235,68 @@ async fn test_invitation_viewed_handler() {
     );
 }
 
+#[tokio::test]
+async fn test_duplicate_invitation_viewed_is_idempotent() {
+    let store = create_test_store().await;
+
+    let box_id = "test_box_duplicate";
+    let invitation_id = "test_invitation_duplicate";
+    let user_id = "test_user_duplicate";
+
+    let box_record = lockbox_shared::models::BoxRecord {
+        id: box_id.to_string(),
+        name: "Test Box".to_string(),
+        description: "Test Description".to_string(),
+        is_locked: false,
+        created_at: "2023-01-01T00:00:00Z".to_string(),
+        updated_at: "2023-01-01T00:00:00Z".to_string(),
+        owner_id: "test_owner".to_string(),
+        owner_name: Some("Test Owner".to_string()),
+        documents: vec![],
+        guardians: vec![lockbox_shared::models::Guardian {
+            id: "placeholder_id".to_string(),
+            name: "Test Guardian".to_string(),
+            lead_guardian: false,
+            status: GuardianStatus::Invited,
+            added_at: "2023-01-01T00:00:00Z".to_string(),
+            invitation_id: invitation_id.to_string(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
+        }],
+        unlock_instructions: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
+        version: 0,
+    };
+    let _ = store.create_box(box_record).await.unwrap();
+
+    // First delivery links the guardian
+    let event = create_test_sns_event("invitation_viewed", invitation_id, box_id, user_id);
+    let result = store.handle_event(event).await;
+    assert!(result.is_ok(), "First delivery failed: {:?}", result.err());
+    let after_first = store.get_box(box_id).await.unwrap();
+
+    // SNS redelivers the same message
+    let event = create_test_sns_event("invitation_viewed", invitation_id, box_id, user_id);
+    let result = store.handle_event(event).await;
+    assert!(
+        result.is_ok(),
+        "Duplicate delivery failed: {:?}",
+        result.err()
+    );
+    let after_second = store.get_box(box_id).await.unwrap();
+
+    // Only the first delivery wrote to the store
+    assert_eq!(after_second.version, after_first.version);
+    assert_eq!(after_second.updated_at, after_first.updated_at);
+    assert_eq!(after_second.guardians[0].id, user_id);
+    assert_eq!(after_second.guardians[0].status, GuardianStatus::Viewed);
+}
+
 #[tokio::test]
 async fn test_no_matching_guardian() {
     // Create test store
@@ -254,9 +325,16 @@ async fn test_no_matching_guardian() {
             status: GuardianStatus::Invited,
             added_at: "2023-01-01T00:00:00Z".to_string(),
             invitation_id: "different_invitation_id".to_string(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
         }],
         unlock_instructions: None,
-        unlock_request: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
         version: 0,
     };
 
@@ -318,7 +396,10 @@ async fn test_concurrent_updates() {
         documents: vec![],
         guardians: vec![],
         unlock_instructions: None,
-        unlock_request: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
         version: 0,
     };
 
@@ -330,6 +411,10 @@ async fn test_concurrent_updates() {
         status: GuardianStatus::Invited,
         added_at: "2023-01-01T00:00:00Z".to_string(),
         invitation_id: invitation_id1.to_string(),
+        email: None,
+        relationship: None,
+        note: None,
+        last_viewed_at: None,
     };
 
     let guardian2 = lockbox_shared::models::Guardian {
@@ -339,6 +424,10 @@ async fn test_concurrent_updates() {
         status: GuardianStatus::Invited,
         added_at: "2023-01-01T00:00:00Z".to_string(),
         invitation_id: invitation_id2.to_string(),
+        email: None,
+        relationship: None,
+        note: None,
+        last_viewed_at: None,
     };
 
     let guardian3 = lockbox_shared::models::Guardian {
@@ -348,6 +437,10 @@ async fn test_concurrent_updates() {
         status: GuardianStatus::Invited,
         added_at: "2023-01-01T00:00:00Z".to_string(),
         invitation_id: invitation_id3.to_string(),
+        email: None,
+        relationship: None,
+        note: None,
+        last_viewed_at: None,
     };
 
     box_record.guardians.push(guardian1);
@@ -448,9 +541,16 @@ async fn test_malformed_event() {
             status: GuardianStatus::Invited,
             added_at: "2023-01-01T00:00:00Z".to_string(),
             invitation_id: invitation_id.to_string(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
         }],
         unlock_instructions: None,
-        unlock_request: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
         version: 0,
     };
 
@@ -503,6 +603,14 @@ async fn test_malformed_event() {
         "Handler should continue processing even with malformed event"
     );
 
+    // The malformed record is reported so it can be retried on its own
+    assert_eq!(
+        result.unwrap().batch_item_failures,
+        vec![BatchItemFailure {
+            item_identifier: "test-message-id".to_string()
+        }]
+    );
+
     // Verify the box data wasn't changed
     let box_result = store.get_box(box_id).await;
     assert!(
@@ -524,3 +632,543 @@ async fn test_malformed_event() {
     assert_eq!(box_record.description, original_box.description);
     assert_eq!(box_record.is_locked, original_box.is_locked);
 }
+
+#[tokio::test]
+async fn test_batch_reports_only_failed_records() {
+    let store = create_test_store().await;
+
+    let box_id = "test_box_batch";
+    let invitation_id = "test_invitation_batch";
+    let user_id = "test_user_batch";
+
+    let box_record = lockbox_shared::models::BoxRecord {
+        id: box_id.to_string(),
+        name: "Test Box".to_string(),
+        description: "Test Description".to_string(),
+        is_locked: false,
+        created_at: "2023-01-01T00:00:00Z".to_string(),
+        updated_at: "2023-01-01T00:00:00Z".to_string(),
+        owner_id: "test_owner".to_string(),
+        owner_name: Some("Test Owner".to_string()),
+        documents: vec![],
+        guardians: vec![lockbox_shared::models::Guardian {
+            id: "placeholder_id".to_string(),
+            name: "Test Guardian".to_string(),
+            lead_guardian: false,
+            status: GuardianStatus::Invited,
+            added_at: "2023-01-01T00:00:00Z".to_string(),
+            invitation_id: invitation_id.to_string(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
+        }],
+        unlock_instructions: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
+        version: 0,
+    };
+    let _ = store.create_box(box_record).await.unwrap();
+
+    // One valid record and one with an unparseable body in the same batch
+    let mut event = create_test_sns_event("invitation_viewed", invitation_id, box_id, user_id);
+    event.payload.records[0].sns.message_id = "good-message".to_string();
+
+    let mut bad_record = event.payload.records[0].clone();
+    bad_record.sns.message_id = "bad-message".to_string();
+    bad_record.sns.message = "{not valid json".to_string();
+    event.payload.records.push(bad_record);
+
+    let response = store.handle_event(event).await.unwrap();
+    assert_eq!(
+        response.batch_item_failures,
+        vec![BatchItemFailure {
+            item_identifier: "bad-message".to_string()
+        }]
+    );
+
+    // The good record was still applied
+    let box_record = store.get_box(box_id).await.unwrap();
+    assert_eq!(box_record.guardians[0].id, user_id);
+    assert_eq!(box_record.guardians[0].status, GuardianStatus::Viewed);
+
+    // The serialized shape matches the Lambda partial batch response contract
+    let json = serde_json::to_value(&response).unwrap();
+    assert_eq!(
+        json["batchItemFailures"][0]["itemIdentifier"],
+        "bad-message"
+    );
+}
+
+#[tokio::test]
+async fn test_invitation_rejected_handler() {
+    let store = create_test_store().await;
+
+    let box_id = "test_box_rejected";
+    let invitation_id = "test_invitation_rejected";
+    let original_updated_at = "2023-01-01T00:00:00Z";
+
+    let box_record = lockbox_shared::models::BoxRecord {
+        id: box_id.to_string(),
+        name: "Test Box".to_string(),
+        description: "Test Description".to_string(),
+        is_locked: false,
+        created_at: original_updated_at.to_string(),
+        updated_at: original_updated_at.to_string(),
+        owner_id: "test_owner".to_string(),
+        owner_name: Some("Test Owner".to_string()),
+        documents: vec![],
+        guardians: vec![
+            lockbox_shared::models::Guardian {
+                id: "declining_user".to_string(),
+                name: "Declining Guardian".to_string(),
+                lead_guardian: false,
+                status: GuardianStatus::Viewed,
+                added_at: original_updated_at.to_string(),
+                invitation_id: invitation_id.to_string(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
+            },
+            lockbox_shared::models::Guardian {
+                id: "other_user".to_string(),
+                name: "Other Guardian".to_string(),
+                lead_guardian: false,
+                status: GuardianStatus::Accepted,
+                added_at: original_updated_at.to_string(),
+                invitation_id: "other_invitation".to_string(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
+            },
+        ],
+        unlock_instructions: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
+        version: 0,
+    };
+    let _ = store.create_box(box_record).await.unwrap();
+
+    let event = create_test_sns_event(
+        "invitation_rejected",
+        invitation_id,
+        box_id,
+        "declining_user",
+    );
+    let response = store.handle_event(event).await.unwrap();
+    assert!(response.batch_item_failures.is_empty());
+
+    let box_record = store.get_box(box_id).await.unwrap();
+    let rejected = box_record
+        .guardians
+        .iter()
+        .find(|g| g.invitation_id == invitation_id)
+        .unwrap();
+    assert_eq!(rejected.status, GuardianStatus::Rejected);
+
+    // Other guardians are untouched
+    let other = box_record
+        .guardians
+        .iter()
+        .find(|g| g.invitation_id == "other_invitation")
+        .unwrap();
+    assert_eq!(other.status, GuardianStatus::Accepted);
+
+    // updated_at moves forward
+    let original = chrono::DateTime::parse_from_rfc3339(original_updated_at).unwrap();
+    let updated = chrono::DateTime::parse_from_rfc3339(&box_record.updated_at).unwrap();
+    assert!(updated > original);
+}
+
+#[tokio::test]
+async fn test_invitation_expired_removes_placeholder_guardian() {
+    let store = create_test_store().await;
+
+    let box_id = "test_box_expired";
+    let invitation_id = "test_invitation_expired";
+    let original_updated_at = "2023-01-01T00:00:00Z";
+
+    let box_record = lockbox_shared::models::BoxRecord {
+        id: box_id.to_string(),
+        name: "Test Box".to_string(),
+        description: "Test Description".to_string(),
+        is_locked: false,
+        created_at: original_updated_at.to_string(),
+        updated_at: original_updated_at.to_string(),
+        owner_id: "test_owner".to_string(),
+        owner_name: Some("Test Owner".to_string()),
+        documents: vec![],
+        guardians: vec![
+            lockbox_shared::models::Guardian {
+                id: "placeholder_id".to_string(),
+                name: "Invited Guardian".to_string(),
+                lead_guardian: false,
+                status: GuardianStatus::Invited,
+                added_at: original_updated_at.to_string(),
+                invitation_id: invitation_id.to_string(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
+            },
+            lockbox_shared::models::Guardian {
+                id: "viewing_user".to_string(),
+                name: "Viewing Guardian".to_string(),
+                lead_guardian: false,
+                status: GuardianStatus::Viewed,
+                added_at: original_updated_at.to_string(),
+                invitation_id: "viewed_invitation".to_string(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
+            },
+            lockbox_shared::models::Guardian {
+                id: "other_user".to_string(),
+                name: "Other Guardian".to_string(),
+                lead_guardian: false,
+                status: GuardianStatus::Accepted,
+                added_at: original_updated_at.to_string(),
+                invitation_id: "other_invitation".to_string(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
+            },
+        ],
+        unlock_instructions: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
+        version: 0,
+    };
+    let _ = store.create_box(box_record).await.unwrap();
+
+    let event = create_test_sns_event("invitation_expired", invitation_id, box_id, "nobody");
+    let response = store.handle_event(event).await.unwrap();
+    assert!(response.batch_item_failures.is_empty());
+
+    // A guardian who viewed the invitation before it expired is kept
+    let event = create_test_sns_event("invitation_expired", "viewed_invitation", box_id, "nobody");
+    let response = store.handle_event(event).await.unwrap();
+    assert!(response.batch_item_failures.is_empty());
+
+    let box_record = store.get_box(box_id).await.unwrap();
+    let remaining: Vec<&str> = box_record
+        .guardians
+        .iter()
+        .map(|g| g.invitation_id.as_str())
+        .collect();
+    assert_eq!(remaining, vec!["viewed_invitation", "other_invitation"]);
+    assert_eq!(box_record.guardians[0].status, GuardianStatus::Viewed);
+    assert_eq!(box_record.guardians[1].status, GuardianStatus::Accepted);
+
+    let original = chrono::DateTime::parse_from_rfc3339(original_updated_at).unwrap();
+    let updated = chrono::DateTime::parse_from_rfc3339(&box_record.updated_at).unwrap();
+    assert!(updated > original);
+}
+
+#[tokio::test]
+async fn test_invitation_revoked_removes_placeholder_guardian() {
+    let store = create_test_store().await;
+
+    let box_id = "test_box_revoked";
+    let invitation_id = "test_invitation_revoked";
+    let original_updated_at = "2023-01-01T00:00:00Z";
+
+    let guardian =
+        |id: &str, invitation_id: &str, status: GuardianStatus| lockbox_shared::models::Guardian {
+            id: id.to_string(),
+            name: "Guardian".to_string(),
+            lead_guardian: false,
+            status,
+            added_at: original_updated_at.to_string(),
+            invitation_id: invitation_id.to_string(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
+        };
+    let box_record = lockbox_shared::models::BoxRecord {
+        id: box_id.to_string(),
+        name: "Test Box".to_string(),
+        description: "Test Description".to_string(),
+        is_locked: false,
+        created_at: original_updated_at.to_string(),
+        updated_at: original_updated_at.to_string(),
+        owner_id: "test_owner".to_string(),
+        owner_name: Some("Test Owner".to_string()),
+        documents: vec![],
+        guardians: vec![
+            guardian("placeholder_id", invitation_id, GuardianStatus::Invited),
+            guardian("other_user", "other_invitation", GuardianStatus::Accepted),
+        ],
+        unlock_instructions: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
+        version: 0,
+    };
+    let _ = store.create_box(box_record).await.unwrap();
+
+    let event = create_test_sns_event("invitation_revoked", invitation_id, box_id, "nobody");
+    let response = store.handle_event(event).await.unwrap();
+    assert!(response.batch_item_failures.is_empty());
+
+    let box_record = store.get_box(box_id).await.unwrap();
+    assert_eq!(box_record.guardians.len(), 1);
+    assert_eq!(box_record.guardians[0].invitation_id, "other_invitation");
+
+    // Redelivery finds nothing left to remove and is dropped
+    let event = create_test_sns_event("invitation_revoked", invitation_id, box_id, "nobody");
+    let response = store.handle_event(event).await.unwrap();
+    assert!(response.batch_item_failures.is_empty());
+    assert_eq!(store.get_box(box_id).await.unwrap().guardians.len(), 1);
+}
+
+#[tokio::test]
+async fn test_invitation_expired_without_matching_guardian() {
+    let store = create_test_store().await;
+
+    let box_id = "test_box_expired_no_match";
+    let original_updated_at = "2023-01-01T00:00:00Z";
+
+    let box_record = lockbox_shared::models::BoxRecord {
+        id: box_id.to_string(),
+        name: "Test Box".to_string(),
+        description: "Test Description".to_string(),
+        is_locked: false,
+        created_at: original_updated_at.to_string(),
+        updated_at: original_updated_at.to_string(),
+        owner_id: "test_owner".to_string(),
+        owner_name: Some("Test Owner".to_string()),
+        documents: vec![],
+        guardians: vec![lockbox_shared::models::Guardian {
+            id: "placeholder_id".to_string(),
+            name: "Invited Guardian".to_string(),
+            lead_guardian: false,
+            status: GuardianStatus::Invited,
+            added_at: original_updated_at.to_string(),
+            invitation_id: "different_invitation_id".to_string(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
+        }],
+        unlock_instructions: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
+        version: 0,
+    };
+    let _ = store.create_box(box_record).await.unwrap();
+
+    // Redelivered or already-cleaned-up events are dropped rather than retried
+    let event = create_test_sns_event("invitation_expired", "gone_invitation", box_id, "nobody");
+    let response = store.handle_event(event).await.unwrap();
+    assert!(response.batch_item_failures.is_empty());
+
+    let box_record = store.get_box(box_id).await.unwrap();
+    assert_eq!(box_record.guardians.len(), 1);
+    assert_eq!(
+        box_record.guardians[0].invitation_id,
+        "different_invitation_id"
+    );
+    assert_eq!(box_record.guardians[0].status, GuardianStatus::Invited);
+    assert_eq!(box_record.updated_at, original_updated_at);
+}
+
+#[tokio::test]
+async fn test_unknown_type_ignored_and_newer_version_reported() {
+    let store = create_test_store().await;
+
+    let mut event = create_test_sns_event("invitation_exploded", "inv", "box", "user");
+    event.payload.records[0].sns.message_id = "unknown-type".to_string();
+
+    // A version this consumer can't read goes to the DLQ instead of being dropped
+    let mut newer = event.payload.records[0].clone();
+    newer.sns.message_id = "newer-version".to_string();
+    let mut payload: serde_json::Value = serde_json::from_str(&newer.sns.message).unwrap();
+    payload["event_type"] = "invitation_viewed".into();
+    payload["event_version"] = (INVITATION_EVENT_VERSION + 1).into();
+    newer.sns.message = payload.to_string();
+    event.payload.records.push(newer);
+
+    let response = store.handle_event(event).await.unwrap();
+    assert_eq!(
+        response.batch_item_failures,
+        vec![BatchItemFailure {
+            item_identifier: "newer-version".to_string()
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_transient_store_error_is_retried() {
+    test_logging::init_test_logging();
+
+    // The first read is throttled, as if DynamoDB were struggling
+    let store = Arc::new(MockBoxStore::new().with_throttled_reads(1));
+
+    let box_id = "test_box_transient";
+    let invitation_id = "test_invitation_transient";
+    let user_id = "test_user_transient";
+    store
+        .create_box(lockbox_shared::models::BoxRecord {
+            id: box_id.to_string(),
+            name: "Test Box".to_string(),
+            description: "Test Description".to_string(),
+            is_locked: false,
+            created_at: "2023-01-01T00:00:00Z".to_string(),
+            updated_at: "2023-01-01T00:00:00Z".to_string(),
+            owner_id: "test_owner".to_string(),
+            owner_name: Some("Test Owner".to_string()),
+            documents: vec![],
+            guardians: vec![lockbox_shared::models::Guardian {
+                id: "placeholder_id".to_string(),
+                name: "Test Guardian".to_string(),
+                lead_guardian: false,
+                status: GuardianStatus::Invited,
+                added_at: "2023-01-01T00:00:00Z".to_string(),
+                invitation_id: invitation_id.to_string(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
+            }],
+            unlock_instructions: None,
+            unlock_requests: vec![],
+            required_approvals: None,
+            unlock_policy: UnlockPolicy::Majority,
+            deleted_at: None,
+            version: 0,
+        })
+        .await
+        .unwrap();
+
+    // The error propagates, so the record is reported for SNS to redeliver
+    let event = create_test_sns_event("invitation_viewed", invitation_id, box_id, user_id);
+    let response = handler(event, store.clone()).await.unwrap();
+    assert_eq!(
+        response.batch_item_failures,
+        vec![BatchItemFailure {
+            item_identifier: "test-message-id".to_string()
+        }]
+    );
+    let box_record = store.get_box(box_id).await.unwrap();
+    assert_eq!(box_record.guardians[0].status, GuardianStatus::Invited);
+
+    // The redelivery goes through
+    let event = create_test_sns_event("invitation_viewed", invitation_id, box_id, user_id);
+    let response = handler(event, store.clone()).await.unwrap();
+    assert!(response.batch_item_failures.is_empty());
+    let box_record = store.get_box(box_id).await.unwrap();
+    assert_eq!(box_record.guardians[0].id, user_id);
+    assert_eq!(box_record.guardians[0].status, GuardianStatus::Viewed);
+}
+
+#[tokio::test]
+async fn test_missing_box_is_dropped() {
+    let store = create_test_store().await;
+
+    // No redelivery will make the box appear, so neither event is reported
+    for event_type in ["invitation_viewed", "invitation_rejected"] {
+        let event = create_test_sns_event(
+            event_type,
+            "test_invitation_missing_box",
+            "test_box_that_does_not_exist",
+            "test_user_missing_box",
+        );
+        let response = store.handle_event(event).await.unwrap();
+        assert!(
+            response.batch_item_failures.is_empty(),
+            "{} for a missing box should be dropped",
+            event_type
+        );
+    }
+
+    // Nor is an event missing the user it's for
+    let mut event = create_test_sns_event(
+        "invitation_viewed",
+        "test_invitation_missing_box",
+        "test_box_that_does_not_exist",
+        "test_user_missing_box",
+    );
+    let mut invitation_event: serde_json::Value =
+        serde_json::from_str(&event.payload.records[0].sns.message).unwrap();
+    invitation_event["user_id"] = serde_json::Value::Null;
+    event.payload.records[0].sns.message = invitation_event.to_string();
+    let response = store.handle_event(event).await.unwrap();
+    assert!(response.batch_item_failures.is_empty());
+}
+
+#[tokio::test]
+async fn test_event_updates_retry_after_version_conflict() {
+    test_logging::init_test_logging();
+
+    for (event_type, expected_status) in [
+        ("invitation_viewed", GuardianStatus::Viewed),
+        ("invitation_rejected", GuardianStatus::Rejected),
+    ] {
+        // An owner edit lands between the handler's first read and its write
+        let store = Arc::new(MockBoxStore::new().with_concurrent_writes(1));
+
+        let box_id = "test_box_conflict";
+        let invitation_id = "test_invitation_conflict";
+        store
+            .create_box(lockbox_shared::models::BoxRecord {
+                id: box_id.to_string(),
+                name: "Test Box".to_string(),
+                description: "Test Description".to_string(),
+                is_locked: false,
+                created_at: "2023-01-01T00:00:00Z".to_string(),
+                updated_at: "2023-01-01T00:00:00Z".to_string(),
+                owner_id: "test_owner".to_string(),
+                owner_name: Some("Test Owner".to_string()),
+                documents: vec![],
+                guardians: vec![lockbox_shared::models::Guardian {
+                    id: "placeholder_id".to_string(),
+                    name: "Test Guardian".to_string(),
+                    lead_guardian: false,
+                    status: GuardianStatus::Invited,
+                    added_at: "2023-01-01T00:00:00Z".to_string(),
+                    invitation_id: invitation_id.to_string(),
+                    email: None,
+                    relationship: None,
+                    note: None,
+                    last_viewed_at: None,
+                }],
+                unlock_instructions: None,
+                unlock_requests: vec![],
+                required_approvals: None,
+                unlock_policy: UnlockPolicy::Majority,
+                deleted_at: None,
+                version: 0,
+            })
+            .await
+            .unwrap();
+
+        // The first write conflicts; the handler re-reads and the second write succeeds
+        let event = create_test_sns_event(event_type, invitation_id, box_id, "test_user_conflict");
+        let response = handler(event, store.clone()).await.unwrap();
+        assert!(
+            response.batch_item_failures.is_empty(),
+            "{} should succeed after one conflict",
+            event_type
+        );
+
+        // Applied on top of the concurrent write's version
+        let box_record = store.get_box(box_id).await.unwrap();
+        assert_eq!(box_record.guardians[0].status, expected_status);
+        assert_eq!(box_record.version, 2);
+    }
+}
@@ -1,12 +1,32 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+use lockbox_shared::error::{
+    internal_error_body, SERVICE_UNAVAILABLE_MESSAGE, THROTTLED_RETRY_AFTER_SECS,
+};
 use log::{error, warn};
+use serde::Serialize;
 use serde_json::json;
 use thiserror::Error;
 
+// A single failed field check, reported back to the client in 422 responses
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub reason: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, reason: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Not found: {0}")]
@@ -21,17 +41,28 @@ pub enum AppError {
     #[error("Invitation expired")]
     InvitationExpired,
 
+    #[error("Validation failed: {0:?}")]
+    FieldValidation(Vec<FieldError>),
+
     #[error("Internal server error: {0}")]
     InternalServerError(String),
 
     #[error("Forbidden: {0}")]
     Forbidden(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
 
+    #[error("Too many requests, retry after {0}s")]
+    TooManyRequests(u64),
+
+    #[error("Store throttled: {0}")]
+    Throttled(String),
+
     #[error("Bad gateway: {0}")]
-    #[allow(dead_code)]
     BadGateway(String),
 }
 
@@ -73,6 +104,16 @@ impl AppError {
     }
 
     #[allow(dead_code)]
+    pub fn conflict(msg: String) -> Self {
+        warn!("Conflict error: {}", msg);
+        Self::Conflict(msg)
+    }
+
+    pub fn too_many_requests(retry_after_secs: u64) -> Self {
+        warn!("Rate limited, retry after {}s", retry_after_secs);
+        Self::TooManyRequests(retry_after_secs)
+    }
+
     pub fn bad_gateway(msg: String) -> Self {
         warn!("Bad gateway error: {}", msg);
         Self::BadGateway(msg)
@@ -81,25 +122,81 @@ impl AppError {
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let retry_after = match &self {
+            AppError::TooManyRequests(secs) => Some(*secs),
+            AppError::Throttled(_) => Some(THROTTLED_RETRY_AFTER_SECS),
+            _ => None,
+        };
+
         let (status, error_message) = match self {
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             AppError::InvitationExpired => (StatusCode::GONE, "Invitation has expired".to_string()),
-            AppError::InternalServerError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            AppError::FieldValidation(field_errors) => {
+                warn!("Validation failed: {:?}", field_errors);
+                return (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Json(field_validation_body(&field_errors)),
+                )
+                    .into_response();
+            }
+            AppError::InternalServerError(msg) => {
+                // Logged in full here; the response never carries the detail
+                error!("Internal server error: {}", msg);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(internal_error_body()),
+                )
+                    .into_response();
+            }
             AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
             AppError::SerializationError(err) => {
                 warn!("Serialization error: {}", err);
                 (StatusCode::BAD_REQUEST, err.to_string())
             }
             AppError::BadGateway(msg) => (StatusCode::BAD_GATEWAY, msg),
+            AppError::TooManyRequests(_) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many requests".to_string(),
+            ),
+            AppError::Throttled(msg) => {
+                // The detail names tables and SDK errors, so it stays in the logs
+                warn!("Store throttled: {}", msg);
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    SERVICE_UNAVAILABLE_MESSAGE.to_string(),
+                )
+            }
         };
 
-        let body = Json(json!({ "error": error_message }));
-        (status, body).into_response()
+        let body = json!({ "error": error_message });
+        let mut response = (status, Json(body)).into_response();
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
+// The same 422 shape as the shared body and query extractors: the first failing
+// field, with every reason given for it
+fn field_validation_body(field_errors: &[FieldError]) -> serde_json::Value {
+    let Some(first) = field_errors.first() else {
+        return json!({ "error": "validation" });
+    };
+    let detail = field_errors
+        .iter()
+        .filter(|e| e.field == first.field)
+        .map(|e| e.reason.as_str())
+        .collect::<Vec<_>>()
+        .join("; ");
+    json!({ "error": "validation", "field": first.field, "detail": detail })
+}
+
 // Helper function to map DynamoDB errors to our application errors
 pub fn map_dynamo_error(operation: &str, err: impl std::fmt::Display) -> AppError {
     AppError::InternalServerError(format!("DynamoDB {} error: {}", operation, err))
@@ -118,7 +215,11 @@ impl From<lockbox_shared::error::StoreError> for AppError {
             lockbox_shared::error::StoreError::InvitationExpired => AppError::InvitationExpired,
             lockbox_shared::error::StoreError::AuthError(msg) => AppError::Unauthorized(msg),
             lockbox_shared::error::StoreError::VersionConflict(msg) => {
-                AppError::BadRequest(format!("Concurrent modification detected: {}", msg))
+                AppError::Conflict(format!("Concurrent modification detected: {}", msg))
+            }
+            lockbox_shared::error::StoreError::Throttled(msg) => AppError::Throttled(msg),
+            err @ lockbox_shared::error::StoreError::AmbiguousInviteCode => {
+                AppError::Conflict(err.to_string())
             }
         }
     }
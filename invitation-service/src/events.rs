@@ -0,0 +1,295 @@
+use async_trait::async_trait;
+use aws_sdk_sns::Client as SnsClient;
+use bytes::Bytes;
+use hmac::{Hmac, Mac};
+use http_body_util::{BodyExt, Full};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::{
+    client::legacy::{connect::HttpConnector, Client},
+    rt::TokioExecutor,
+};
+use log::{info, warn};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use lockbox_shared::models::events::InvitationEvent;
+
+use crate::error::{AppError, Result};
+
+// Header carrying `sha256=<hex HMAC of the body>` on webhook deliveries
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-lockbox-signature";
+// Header carrying the event type, so receivers can route without parsing the body
+pub const WEBHOOK_EVENT_HEADER: &str = "x-lockbox-event";
+
+// Most entries SNS accepts in one PublishBatch call
+pub const SNS_MAX_BATCH_SIZE: usize = 10;
+// How long one webhook delivery may take, overridable via WEBHOOK_TIMEOUT_MS
+pub const DEFAULT_WEBHOOK_TIMEOUT_MS: u64 = 5_000;
+
+fn webhook_timeout() -> Duration {
+    let millis = env::var("WEBHOOK_TIMEOUT_MS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .filter(|millis| *millis > 0)
+        .unwrap_or(DEFAULT_WEBHOOK_TIMEOUT_MS);
+    Duration::from_millis(millis)
+}
+
+/// Where invitation events go once the invitation has been saved
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    async fn publish(&self, event: &InvitationEvent) -> Result<()>;
+
+    /// Publishes several events, stopping at the first failure. Sinks without
+    /// a batch API publish them one at a time.
+    async fn publish_batch(&self, events: &[InvitationEvent]) -> Result<()> {
+        for event in events {
+            self.publish(event).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits events into the groups sent in each SNS PublishBatch call
+pub fn sns_batches(events: &[InvitationEvent]) -> std::slice::Chunks<'_, InvitationEvent> {
+    events.chunks(SNS_MAX_BATCH_SIZE)
+}
+
+// The eventType message attribute subscription filters match on
+fn event_type_attribute(
+    event: &InvitationEvent,
+) -> Result<aws_sdk_sns::types::MessageAttributeValue> {
+    aws_sdk_sns::types::MessageAttributeValue::builder()
+        .data_type("String")
+        .string_value(&event.event_type)
+        .build()
+        .map_err(|e| AppError::InternalServerError(format!("Failed to build SNS attribute: {}", e)))
+}
+
+/// Publishes events to the SNS topic in SNS_TOPIC_ARN, with the event type
+/// as the `eventType` message attribute
+pub struct SnsSink {
+    client: SnsClient,
+    topic_arn: String,
+}
+
+impl SnsSink {
+    pub fn new(client: SnsClient, topic_arn: String) -> Self {
+        Self { client, topic_arn }
+    }
+
+    /// None when SNS_TOPIC_ARN isn't set
+    pub async fn from_env() -> Option<Self> {
+        let Some(topic_arn) = env::var("SNS_TOPIC_ARN").ok().filter(|arn| !arn.is_empty()) else {
+            warn!("SNS_TOPIC_ARN is not set, invitation events will not be published");
+            return None;
+        };
+
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        info!("Publishing invitation events to SNS topic {}", topic_arn);
+        Some(Self::new(SnsClient::new(&config), topic_arn))
+    }
+}
+
+#[async_trait]
+impl EventSink for SnsSink {
+    async fn publish(&self, event: &InvitationEvent) -> Result<()> {
+        let message = serde_json::to_string(event)?;
+
+        let mut message_attributes = HashMap::new();
+        message_attributes.insert("eventType".to_string(), event_type_attribute(event)?);
+
+        self.client
+            .publish()
+            .topic_arn(&self.topic_arn)
+            .message(message)
+            .subject("Invitation Viewed")
+            .set_message_attributes(Some(message_attributes))
+            .send()
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("SNS publish failed: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// One PublishBatch call per 10 events instead of one Publish per event
+    async fn publish_batch(&self, events: &[InvitationEvent]) -> Result<()> {
+        for batch in sns_batches(events) {
+            let mut entries = Vec::with_capacity(batch.len());
+            for (i, event) in batch.iter().enumerate() {
+                // Ids only need to be unique within the batch
+                let entry = aws_sdk_sns::types::PublishBatchRequestEntry::builder()
+                    .id(i.to_string())
+                    .message(serde_json::to_string(event)?)
+                    .subject("Invitation Viewed")
+                    .message_attributes("eventType", event_type_attribute(event)?)
+                    .build()
+                    .map_err(|e| {
+                        AppError::InternalServerError(format!("Failed to build SNS entry: {}", e))
+                    })?;
+                entries.push(entry);
+            }
+
+            let response = self
+                .client
+                .publish_batch()
+                .topic_arn(&self.topic_arn)
+                .set_publish_batch_request_entries(Some(entries))
+                .send()
+                .await
+                .map_err(|e| {
+                    AppError::InternalServerError(format!("SNS publish batch failed: {}", e))
+                })?;
+
+            // A batch call succeeds even when some of its entries don't
+            if let Some(failed) = response.failed().first() {
+                return Err(AppError::InternalServerError(format!(
+                    "SNS rejected {} of {} batched events (first: {}: {})",
+                    response.failed().len(),
+                    batch.len(),
+                    failed.code(),
+                    failed.message().unwrap_or_default()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// POSTs each event as JSON to EVENT_WEBHOOK_URL, signed with an HMAC-SHA256
+/// of the body keyed by EVENT_WEBHOOK_SECRET
+pub struct WebhookSink {
+    url: String,
+    secret: String,
+    client: Client<HttpsConnector<HttpConnector>, Full<Bytes>>,
+    timeout: Duration,
+}
+
+impl WebhookSink {
+    // Fails only when the platform's root certificates can't be loaded
+    pub fn new(url: String, secret: String, timeout: Duration) -> std::io::Result<Self> {
+        let connector = HttpsConnectorBuilder::new()
+            .with_native_roots()?
+            .https_or_http()
+            .enable_http1()
+            .build();
+
+        Ok(Self {
+            url,
+            secret,
+            client: Client::builder(TokioExecutor::new()).build(connector),
+            timeout,
+        })
+    }
+
+    /// None unless both EVENT_WEBHOOK_URL and EVENT_WEBHOOK_SECRET are set
+    pub fn from_env() -> Option<Self> {
+        let url = env::var("EVENT_WEBHOOK_URL").ok().filter(|u| !u.is_empty());
+        let secret = env::var("EVENT_WEBHOOK_SECRET")
+            .ok()
+            .filter(|s| !s.is_empty());
+        let (Some(url), Some(secret)) = (url, secret) else {
+            warn!(
+                "EVENT_WEBHOOK_URL and EVENT_WEBHOOK_SECRET must both be set for the webhook sink"
+            );
+            return None;
+        };
+
+        match Self::new(url.clone(), secret, webhook_timeout()) {
+            Ok(sink) => {
+                info!("Publishing invitation events to webhook {}", url);
+                Some(sink)
+            }
+            Err(e) => {
+                warn!("Could not set up the webhook client: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// `sha256=<hex>` signature of `body` under `secret`, as sent in the
+/// x-lockbox-signature header
+pub fn webhook_signature(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[async_trait]
+impl EventSink for WebhookSink {
+    async fn publish(&self, event: &InvitationEvent) -> Result<()> {
+        let body = serde_json::to_vec(event)?;
+        let signature = webhook_signature(&self.secret, &body);
+
+        let request = http::Request::post(&self.url)
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .header(WEBHOOK_EVENT_HEADER, &event.event_type)
+            .header(WEBHOOK_SIGNATURE_HEADER, signature)
+            .body(Full::new(Bytes::from(body)))
+            .map_err(|e| {
+                AppError::InternalServerError(format!("Invalid webhook request: {}", e))
+            })?;
+
+        // A receiver that never answers would otherwise hold the request open
+        let delivery = async {
+            let response = self.client.request(request).await.map_err(|e| {
+                AppError::InternalServerError(format!("Webhook delivery failed: {}", e))
+            })?;
+
+            let status = response.status();
+            if !status.is_success() {
+                // The body is only read for the error message
+                let detail = response
+                    .into_body()
+                    .collect()
+                    .await
+                    .map(|collected| String::from_utf8_lossy(&collected.to_bytes()).into_owned())
+                    .unwrap_or_default();
+                return Err(AppError::InternalServerError(format!(
+                    "Webhook responded with {}: {}",
+                    status, detail
+                )));
+            }
+            Ok(())
+        };
+
+        tokio::time::timeout(self.timeout, delivery)
+            .await
+            .map_err(|_| {
+                AppError::InternalServerError(format!(
+                    "Webhook delivery timed out after {}ms",
+                    self.timeout.as_millis()
+                ))
+            })?
+    }
+}
+
+/// Sink picked by EVENT_SINK (`sns`, the default, or `webhook`). None when
+/// the chosen sink isn't configured or TEST_SNS=true, in which case events
+/// are skipped.
+pub async fn event_sink_from_env() -> Option<Arc<dyn EventSink>> {
+    if env::var("TEST_SNS").is_ok_and(|value| value == "true") {
+        info!("TEST_SNS is set, invitation events will not be published");
+        return None;
+    }
+
+    match env::var("EVENT_SINK").as_deref().unwrap_or("sns") {
+        "sns" => SnsSink::from_env()
+            .await
+            .map(|sink| Arc::new(sink) as Arc<dyn EventSink>),
+        "webhook" => WebhookSink::from_env().map(|sink| Arc::new(sink) as Arc<dyn EventSink>),
+        other => {
+            warn!(
+                "Unknown EVENT_SINK {:?}, invitation events will not be published",
+                other
+            );
+            None
+        }
+    }
+}
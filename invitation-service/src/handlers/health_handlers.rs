@@ -0,0 +1,10 @@
+use axum::Json;
+
+// GET /health - unauthenticated liveness check
+pub async fn health() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "status": "ok",
+        "service": "invitation-service",
+        "version": env!("CARGO_PKG_VERSION"),
+    }))
+}
@@ -1,74 +1,200 @@
-use aws_sdk_sns::Client as SnsClient;
 use axum::{
-    extract::{Extension, Path, State},
+    extract::{Extension, Path, Query, State},
+    http::{HeaderMap, HeaderValue},
     Json,
 };
-use chrono::{Duration, Utc};
+use chrono::Utc;
 use log::{debug, error, info};
-use serde_json::json;
-use std::env;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use lockbox_shared::{models::Invitation, store::InvitationStore};
+use lockbox_shared::{
+    auth::UserGroups,
+    error::StoreError,
+    extract::{JsonBody, ListParams, NEXT_CURSOR_HEADER},
+    idempotency::{claim_idempotency_key, idempotency_key, IdempotencyClaim, REQUEST_IN_PROGRESS},
+    models::{
+        events::InvitationEvent, generate_invite_code, invitation_expires_at, invitation_ttl_hours,
+        now_str, utc_rfc3339, Guardian, GuardianStatus, Invitation, InvitationStatus,
+    },
+    store::{
+        BoxStore, IdempotencyStore, InvitationBoxTransaction, InvitationStore,
+        SequentialInvitationBoxTransaction, DEFAULT_UPDATE_ATTEMPTS,
+    },
+};
 
 use crate::{
-    error::{map_dynamo_error, AppError, Result},
-    models::{ConnectToUserRequest, CreateInvitationRequest, MessageResponse},
+    error::{map_dynamo_error, AppError, FieldError, Result},
+    events::EventSink,
+    models::{
+        BoxInvitationsQuery, ConnectToUserRequest, CreateInvitationRequest,
+        CreateInvitationResponse, InvitationPreviewResponse, InvitationSortField, MessageResponse,
+        PurgeExpiredResponse, DEFAULT_PAGE_SIZE,
+    },
 };
 
-// Alphabet for user-friendly invitation codes (uppercase letters only)
-const CODE_ALPHABET: [char; 26] = [
-    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
-    'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
-];
-
-// POST /invitation - Create a new invitation
+// POST /invitation - Create a new invitation for a box the caller owns
+#[allow(clippy::too_many_arguments)] // one per axum extractor
 pub async fn create_invitation<S: InvitationStore + ?Sized>(
     State(store): State<Arc<S>>,
+    Extension(box_store): Extension<Arc<dyn BoxStore>>,
     Extension(user_id): Extension<String>,
-    Json(create_request): Json<CreateInvitationRequest>,
-) -> Result<Json<Invitation>> {
-    // Generate a user-friendly code for the invitation (8 characters)
-    let invite_code = nanoid::nanoid!(8, &CODE_ALPHABET);
+    transaction: Option<Extension<Arc<dyn InvitationBoxTransaction>>>,
+    sink: Option<Extension<Arc<dyn EventSink>>>,
+    idempotency: Option<Extension<Arc<dyn IdempotencyStore>>>,
+    headers: HeaderMap,
+    JsonBody(create_request): JsonBody<CreateInvitationRequest>,
+) -> Result<Json<CreateInvitationResponse>> {
+    create_request
+        .validate()
+        .map_err(AppError::FieldValidation)?;
+    let key = idempotency_key(&headers).map_err(AppError::bad_request)?;
+
+    // Missing boxes surface as 404 through the StoreError conversion
+    let box_record = box_store.get_box(&create_request.box_id).await?;
+    if box_record.owner_id != user_id {
+        return Err(AppError::forbidden(format!(
+            "You don't have permission to invite guardians to box {}",
+            create_request.box_id
+        )));
+    }
+
+    // A retry with the same Idempotency-Key gets the invitation the first request created
+    let invitation_id = Uuid::new_v4().to_string();
+    let claim = claim_idempotency_key(
+        idempotency.as_deref(),
+        key.as_deref(),
+        "create_invitation",
+        &user_id,
+        &invitation_id,
+    )
+    .await?;
+    if let IdempotencyClaim::Replay(existing_id) = claim {
+        // Until the first request has written the invitation there's nothing to replay
+        let invitation = match store.get_invitation(&existing_id).await {
+            Err(StoreError::NotFound(_)) => {
+                return Err(AppError::conflict(REQUEST_IN_PROGRESS.into()))
+            }
+            result => result?,
+        };
+        let ttl_hours = invitation_ttl_hours();
+        return Ok(Json(CreateInvitationResponse {
+            invitation,
+            ttl_hours,
+        }));
+    }
+
+    // Generate a user-friendly code for the invitation; the store swaps it out
+    // if it's already taken
+    let invite_code = generate_invite_code();
 
-    // Set expiration to 48 hours from now
-    let created_at = Utc::now().to_rfc3339();
-    let expires_at = (Utc::now() + Duration::hours(48)).to_rfc3339();
+    // Set expiration using the configured TTL (INVITATION_TTL_HOURS, default 48)
+    let now = Utc::now();
+    let ttl_hours = invitation_ttl_hours();
+    let created_at = utc_rfc3339(now);
+    let expires_at = invitation_expires_at(now, ttl_hours);
 
     // Create the invitation
     let invitation = Invitation {
-        id: Uuid::new_v4().to_string(),
+        id: invitation_id,
         invite_code,
-        invited_name: create_request.invited_name,
+        invited_name: create_request.invited_name.trim().to_string(),
         box_id: create_request.box_id,
         created_at,
         expires_at,
         opened: false,
+        status: InvitationStatus::Invited,
         linked_user_id: None,
         creator_id: user_id,
     };
 
-    // Save to database
-    let saved_invitation = store
-        .create_invitation(invitation)
-        .await
-        .map_err(|e| map_dynamo_error("create_invitation", e))?;
-
-    // Publish event to SNS
-    if let Err(err) = publish_invitation_event(&saved_invitation, "invitation_created").await {
+    // The invitation and its guardian placeholder on the box are written together
+    // (one DynamoDB transaction when configured), freeing the key again if that fails
+    let transaction = match transaction {
+        Some(Extension(transaction)) => transaction,
+        None => Arc::new(SequentialInvitationBoxTransaction::new(
+            store.clone(),
+            box_store.clone(),
+        )),
+    };
+    let saved_invitation =
+        match create_invitation_with_guardian(transaction.as_ref(), box_store.as_ref(), invitation)
+            .await
+        {
+            Ok(saved_invitation) => saved_invitation,
+            Err(e) => {
+                if let IdempotencyClaim::Claimed(claimed) = claim {
+                    claimed.release().await;
+                }
+                return Err(AppError::from(e));
+            }
+        };
+
+    // Publish event to the configured sink
+    if let Err(err) =
+        publish_invitation_event(sink.as_deref(), &saved_invitation, "invitation_created").await
+    {
         error!("Failed to publish invitation event: {:?}", err);
     }
 
-    // Return the full invitation object
-    Ok(Json(saved_invitation))
+    // Return the full invitation object along with its effective TTL
+    Ok(Json(CreateInvitationResponse {
+        invitation: saved_invitation,
+        ttl_hours,
+    }))
+}
+
+// Adds the invited guardian to the latest copy of the box and writes it with the
+// invitation, starting over from a fresh read when another writer got in first
+async fn create_invitation_with_guardian(
+    transaction: &dyn InvitationBoxTransaction,
+    box_store: &dyn BoxStore,
+    invitation: Invitation,
+) -> std::result::Result<Invitation, StoreError> {
+    let mut attempt = 1;
+    loop {
+        let mut box_record = box_store.get_box_consistent(&invitation.box_id).await?;
+        box_record.guardians.push(invited_guardian(&invitation));
+
+        match transaction
+            .create_invitation_with_box(invitation.clone(), box_record)
+            .await
+        {
+            Err(StoreError::VersionConflict(msg)) if attempt < DEFAULT_UPDATE_ATTEMPTS => {
+                debug!(
+                    "Version conflict adding invitation {} to box {} (attempt {}/{}): {}",
+                    invitation.id, invitation.box_id, attempt, DEFAULT_UPDATE_ATTEMPTS, msg
+                );
+                attempt += 1;
+            }
+            result => return result.map(|(invitation, _)| invitation),
+        }
+    }
+}
+
+// Placeholder guardian for a new invitation; the invitation id stands in for the
+// user id until the invitee accepts and the box is linked to their account
+fn invited_guardian(invitation: &Invitation) -> Guardian {
+    Guardian {
+        id: invitation.id.clone(),
+        name: invitation.invited_name.clone(),
+        lead_guardian: false,
+        status: GuardianStatus::Invited,
+        added_at: now_str(),
+        invitation_id: invitation.id.clone(),
+        email: None,
+        relationship: None,
+        note: None,
+        last_viewed_at: None,
+    }
 }
 
 // PUT /invitation/handle - Connect invitation to user
 pub async fn handle_invitation<S: InvitationStore + ?Sized>(
     State(store): State<Arc<S>>,
     Extension(auth_user_id): Extension<String>,
-    Json(mut request): Json<ConnectToUserRequest>,
+    sink: Option<Extension<Arc<dyn EventSink>>>,
+    JsonBody(mut request): JsonBody<ConnectToUserRequest>,
 ) -> Result<Json<MessageResponse>> {
     // Overwrite payload userId with authenticated user
     request.user_id = auth_user_id.clone();
@@ -76,7 +202,7 @@ pub async fn handle_invitation<S: InvitationStore + ?Sized>(
     let mut invitation = store.get_invitation_by_code(&request.invite_code).await?;
 
     // Prevent replay if the invitation has already been opened or linked
-    if invitation.opened || invitation.linked_user_id.is_some() {
+    if invitation.status != InvitationStatus::Invited || invitation.linked_user_id.is_some() {
         return Err(AppError::Forbidden(format!(
             "Invitation with code {} has already been used",
             request.invite_code
@@ -85,13 +211,16 @@ pub async fn handle_invitation<S: InvitationStore + ?Sized>(
 
     // Set as opened and connect to authenticated user
     invitation.opened = true;
+    invitation.status = InvitationStatus::Opened;
     invitation.linked_user_id = Some(auth_user_id.clone());
 
     // Save the updated invitation
     let updated_invitation = store.update_invitation(invitation.clone()).await?;
 
-    // Publish event to SNS
-    if let Err(err) = publish_invitation_event(&updated_invitation, "invitation_viewed").await {
+    // Publish event to the configured sink
+    if let Err(err) =
+        publish_invitation_event(sink.as_deref(), &updated_invitation, "invitation_viewed").await
+    {
         error!("Failed to publish invitation event: {:?}", err);
     }
 
@@ -107,85 +236,55 @@ pub async fn handle_invitation<S: InvitationStore + ?Sized>(
     Ok(Json(response))
 }
 
-// Helper function to publish an invitation event to SNS
-pub async fn publish_invitation_event(invitation: &Invitation, event_type: &str) -> Result<()> {
+// Helper function to hand an invitation event to the configured sink (SNS or
+// webhook, see EVENT_SINK); skipped when no sink is configured
+pub async fn publish_invitation_event(
+    sink: Option<&Arc<dyn EventSink>>,
+    invitation: &Invitation,
+    event_type: &str,
+) -> Result<()> {
     debug!(
         "publish_invitation_event called for event_type={}, invitation_id={}",
         event_type, invitation.id
     );
 
-    // Check if we're in test mode
-    if let Ok(test_sns) = env::var("TEST_SNS") {
-        if test_sns == "true" {
-            // Skip actual SNS publishing in test mode
-            debug!(
-                "Test mode: Skipping SNS publishing for event_type={}, invitation_id={}",
-                event_type, invitation.id
-            );
-            return Ok(());
-        }
-    }
-
-    // Get SNS topic ARN from environment variable
-    let topic_arn =
-        env::var("SNS_TOPIC_ARN").map_err(|e| map_dynamo_error("get_sns_topic_arn", e))?;
-
-    // Create SNS client
-    let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
-    let sns_client = SnsClient::new(&config);
+    let Some(sink) = sink else {
+        debug!(
+            "No event sink configured: skipping event_type={}, invitation_id={}",
+            event_type, invitation.id
+        );
+        return Ok(());
+    };
 
-    // Call the internal implementation with the client
-    publish_invitation_event_with_client(invitation, sns_client, &topic_arn, event_type).await
+    sink.publish(&InvitationEvent::for_invitation(invitation, event_type))
+        .await
 }
 
-// Internal implementation that can be mocked for testing
-pub async fn publish_invitation_event_with_client(
-    invitation: &Invitation,
-    sns_client: SnsClient,
-    topic_arn: &str,
+// Batched form of publish_invitation_event for handlers that emit one event per
+// invitation; SNS sends these in PublishBatch calls of up to 10
+pub async fn publish_invitation_events(
+    sink: Option<&Arc<dyn EventSink>>,
+    invitations: &[Invitation],
     event_type: &str,
 ) -> Result<()> {
-    // Create the event payload
-    let event_payload = json!({
-        "event_type": event_type,
-        "invitation_id": invitation.id,
-        "box_id": invitation.box_id,
-        "user_id": invitation.linked_user_id,
-        "invite_code": invitation.invite_code,
-        "timestamp": Utc::now().to_rfc3339()
-    });
-
-    // Convert to string
-    let message = serde_json::to_string(&event_payload)
-        .map_err(|e| map_dynamo_error("serialize_event_payload", e))?;
-
-    // Create message attribute
-    let message_attribute = aws_sdk_sns::types::MessageAttributeValue::builder()
-        .data_type("String")
-        .string_value(event_type)
-        .build()
-        .map_err(|e| map_dynamo_error("build_message_attribute", e))?;
-
-    // Add to HashMap for message attributes
-    let mut message_attributes = std::collections::HashMap::new();
-    message_attributes.insert("eventType".to_string(), message_attribute);
-
-    let mut publish_request = sns_client
-        .publish()
-        .topic_arn(topic_arn)
-        .message(message)
-        .subject("Invitation Viewed");
-
-    // Add message attributes with the proper method
-    publish_request = publish_request.set_message_attributes(Some(message_attributes));
-
-    // Send the request
-    publish_request
-        .send()
-        .await
-        .map_err(|e| map_dynamo_error("publish_to_sns", e))?;
+    if invitations.is_empty() {
+        return Ok(());
+    }
 
-    Ok(())
+    let Some(sink) = sink else {
+        debug!(
+            "No event sink configured: skipping {} {} events",
+            invitations.len(),
+            event_type
+        );
+        return Ok(());
+    };
+
+    let events: Vec<InvitationEvent> = invitations
+        .iter()
+        .map(|invitation| InvitationEvent::for_invitation(invitation, event_type))
+        .collect();
+    sink.publish_batch(&events).await
 }
 
 // POST /invitations/:inviteId/refresh - Refresh the invitation
@@ -195,7 +294,7 @@ pub async fn refresh_invitation<S: InvitationStore + ?Sized>(
     Path(invite_id): Path<String>,
 ) -> Result<Json<Invitation>> {
     // Directly fetch the invitation by ID
-    let mut invitation = store.get_invitation(&invite_id).await?;
+    let invitation = store.get_invitation(&invite_id).await?;
 
     // Verify that the current user is the creator of this invitation
     if invitation.creator_id != user_id {
@@ -205,41 +304,286 @@ pub async fn refresh_invitation<S: InvitationStore + ?Sized>(
         )));
     }
 
+    let updated_invitation = reissue_invitation(store.as_ref(), invitation).await?;
+
+    // Return the full updated invitation object
+    Ok(Json(updated_invitation))
+}
+
+// GET /invitations/preview/:inviteCode - What a deep link shows before the user logs in
+pub async fn preview_invitation<S: InvitationStore + ?Sized>(
+    State(store): State<Arc<S>>,
+    Extension(box_store): Extension<Arc<dyn BoxStore>>,
+    Path(invite_code): Path<String>,
+) -> Result<Json<InvitationPreviewResponse>> {
+    let invitation = store.get_invitation_by_code(&invite_code).await?;
+
+    // Every store rejects expired codes on lookup, but don't rely on it here
+    if invitation.is_expired() {
+        return Err(AppError::invitation_expired());
+    }
+
+    // The box name is optional; a deleted or unreadable box still gets a preview
+    let box_name = match box_store.get_box(&invitation.box_id).await {
+        Ok(box_record) => Some(box_record.name),
+        Err(err) => {
+            debug!(
+                "No box name for invitation preview of box {}: {:?}",
+                invitation.box_id, err
+            );
+            None
+        }
+    };
+
+    Ok(Json(InvitationPreviewResponse {
+        invited_name: invitation.invited_name,
+        box_name,
+        expires_at: invitation.expires_at,
+        expired: false,
+    }))
+}
+
+// PATCH /invitations/code/:inviteCode/refresh - Refresh an invitation found by its code
+pub async fn refresh_invitation_by_code<S: InvitationStore + ?Sized>(
+    State(store): State<Arc<S>>,
+    Extension(user_id): Extension<String>,
+    Path(invite_code): Path<String>,
+) -> Result<Json<Invitation>> {
+    let invitation = match store.get_invitation_by_code(&invite_code).await {
+        Ok(invitation) => invitation,
+        // Refreshing is how an expired code gets fixed, so look it up among the
+        // caller's own invitations, which the creator index returns expired or not
+        Err(StoreError::InvitationExpired) => store
+            .get_invitations_by_creator_id(&user_id)
+            .await?
+            .into_iter()
+            .find(|inv| inv.invite_code == invite_code)
+            .ok_or_else(|| {
+                AppError::forbidden(format!(
+                    "Invitation with code {} is not owned by user",
+                    invite_code
+                ))
+            })?,
+        Err(err) => return Err(err.into()),
+    };
+
+    if invitation.creator_id != user_id {
+        return Err(AppError::forbidden(format!(
+            "Invitation with code {} is not owned by user",
+            invite_code
+        )));
+    }
+
+    let updated_invitation = reissue_invitation(store.as_ref(), invitation).await?;
+    Ok(Json(updated_invitation))
+}
+
+// Gives an unused invitation a new code and expiry
+async fn reissue_invitation<S: InvitationStore + ?Sized>(
+    store: &S,
+    mut invitation: Invitation,
+) -> Result<Invitation> {
     // Check if the invitation has already been opened or linked
-    if invitation.opened || invitation.linked_user_id.is_some() {
+    if invitation.status != InvitationStatus::Invited || invitation.linked_user_id.is_some() {
         return Err(AppError::Forbidden(format!(
             "Invitation {} has already been used and cannot be refreshed.",
-            invite_id
+            invitation.id
         )));
     }
 
-    // Generate a new user-friendly invite code (8 characters)
-    invitation.invite_code = nanoid::nanoid!(8, &CODE_ALPHABET);
+    // Generate a new user-friendly invite code
+    invitation.invite_code = generate_invite_code();
 
-    // Set new expiration date (48 hours from now)
-    invitation.expires_at = (Utc::now() + Duration::hours(48)).to_rfc3339();
+    // Set new expiration date using the configured TTL
+    invitation.expires_at = invitation_expires_at(Utc::now(), invitation_ttl_hours());
 
     // Save the updated invitation
-    let updated_invitation = store.update_invitation(invitation).await?;
+    Ok(store.update_invitation(invitation).await?)
+}
 
-    // Return the full updated invitation object
-    Ok(Json(updated_invitation))
+// DELETE /invitations/:inviteId - Revoke an invitation the current user created
+pub async fn revoke_invitation<S: InvitationStore + ?Sized>(
+    State(store): State<Arc<S>>,
+    Extension(user_id): Extension<String>,
+    sink: Option<Extension<Arc<dyn EventSink>>>,
+    Path(invite_id): Path<String>,
+) -> Result<Json<MessageResponse>> {
+    let invitation = store.get_invitation(&invite_id).await?;
+
+    if invitation.creator_id != user_id {
+        return Err(AppError::forbidden(format!(
+            "Invitation {} is not owned by user",
+            invite_id
+        )));
+    }
+
+    // Once used, the guardian slot belongs to the invitee; the owner removes them instead
+    if invitation.status != InvitationStatus::Invited || invitation.linked_user_id.is_some() {
+        return Err(AppError::conflict(format!(
+            "Invitation {} has already been used and cannot be revoked",
+            invite_id
+        )));
+    }
+
+    store.delete_invitation(&invite_id).await?;
+
+    // Lets the box service drop the guardian placeholder tied to this invitation
+    if let Err(err) =
+        publish_invitation_event(sink.as_deref(), &invitation, "invitation_revoked").await
+    {
+        error!("Failed to publish invitation event: {:?}", err);
+    }
+
+    Ok(Json(MessageResponse {
+        message: format!("Invitation {} revoked", invite_id),
+        box_id: Some(invitation.box_id),
+    }))
 }
 
 // GET /invitations/me - Get all invitations created by the current user
 pub async fn get_my_invitations<S: InvitationStore + ?Sized>(
     State(store): State<Arc<S>>,
     Extension(user_id): Extension<String>,
-) -> Result<Json<Vec<Invitation>>> {
+    list: ListParams,
+) -> Result<(HeaderMap, Json<Vec<Invitation>>)> {
     info!("get_my_invitations called with user_id: {}", user_id);
     
-    // Fetch all invitations created by this user
-    let invitations = store
-        .get_invitations_by_creator_id(&user_id)
-        .await
-        .map_err(|e| map_dynamo_error("get_invitations_by_creator_id", e))?;
+    // Reject bad sort options before touching the store
+    let sort = list
+        .sort
+        .as_deref()
+        .map(|field| {
+            InvitationSortField::parse(field).ok_or_else(|| {
+                AppError::FieldValidation(vec![FieldError::new(
+                    "sort",
+                    "must be createdAt or expiresAt",
+                )])
+            })
+        })
+        .transpose()?;
+
+    // Without limit/cursor keep returning every invitation in one response
+    let (mut invitations, next_cursor) = if !list.is_paginated() {
+        let invitations = store
+            .get_invitations_by_creator_id(&user_id)
+            .await
+            .map_err(|e| map_dynamo_error("get_invitations_by_creator_id", e))?;
+        (invitations, None)
+    } else {
+        let limit = list.limit.map_or(DEFAULT_PAGE_SIZE, |limit| limit as usize);
+        store
+            .get_invitations_by_creator_id_paged(&user_id, limit, list.cursor)
+            .await?
+    };
+
+    // Sorting applies within a page when paginating
+    if let Some(sort) = sort {
+        invitations.sort_by(|a, b| list.order.apply(sort.compare(a, b)));
+    }
+
+    // The body stays a bare array, so the next page's cursor goes in a header
+    let mut headers = HeaderMap::new();
+    if let Some(cursor) = next_cursor {
+        let cursor = HeaderValue::from_str(&cursor)
+            .map_err(|e| AppError::internal_server_error(format!("Unusable page cursor: {}", e)))?;
+        headers.insert(NEXT_CURSOR_HEADER, cursor);
+    }
 
     info!("get_my_invitations returning {} invitations for user_id: {}", invitations.len(), user_id);
     
+    Ok((headers, Json(invitations)))
+}
+
+// GET /invitations/linked - Invitations the current user has opened or responded to
+pub async fn get_linked_invitations<S: InvitationStore + ?Sized>(
+    State(store): State<Arc<S>>,
+    Extension(user_id): Extension<String>,
+) -> Result<Json<Vec<Invitation>>> {
+    let mut invitations = store.get_invitations_by_linked_user(&user_id).await?;
+    invitations.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+    debug!(
+        "get_linked_invitations returning {} invitations for user {}",
+        invitations.len(),
+        user_id
+    );
+
     Ok(Json(invitations))
 }
+
+// GET /invitations/box/:boxId - Invitations the caller created for one of their boxes
+pub async fn get_box_invitations<S: InvitationStore + ?Sized>(
+    State(store): State<Arc<S>>,
+    Path(box_id): Path<String>,
+    Extension(user_id): Extension<String>,
+    Query(params): Query<BoxInvitationsQuery>,
+) -> Result<Json<Vec<Invitation>>> {
+    let invitations = store.get_invitations_by_box_id(&box_id).await?;
+
+    // Only the box owner creates its invitations, so someone with no invitations
+    // on a box that has some isn't its owner
+    if !invitations.is_empty() && !invitations.iter().any(|inv| inv.creator_id == user_id) {
+        return Err(AppError::forbidden(format!(
+            "You don't have permission to view invitations for box {}",
+            box_id
+        )));
+    }
+
+    let mut invitations: Vec<Invitation> = invitations
+        .into_iter()
+        .filter(|inv| inv.creator_id == user_id)
+        .filter(|inv| params.include_expired || !inv.is_expired())
+        .collect();
+    invitations.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+    debug!(
+        "get_box_invitations returning {} invitations for box {}",
+        invitations.len(),
+        box_id
+    );
+
+    Ok(Json(invitations))
+}
+
+// POST /invitations/purge-expired - Admin-only cleanup of invitations past their expiry
+pub async fn purge_expired_invitations<S: InvitationStore + ?Sized>(
+    State(store): State<Arc<S>>,
+    Extension(user_id): Extension<String>,
+    Extension(groups): Extension<UserGroups>,
+    sink: Option<Extension<Arc<dyn EventSink>>>,
+) -> Result<Json<PurgeExpiredResponse>> {
+    if !groups.is_admin() {
+        return Err(AppError::forbidden(format!(
+            "User {} is not allowed to purge invitations",
+            user_id
+        )));
+    }
+
+    let expired = store.purge_expired_invitations().await?;
+    info!(
+        "User {} purged {} expired invitations",
+        user_id,
+        expired.len()
+    );
+
+    // Lets the box service drop the guardian placeholders these invitations left behind.
+    // The invitations are already gone, so a rerun can't resend these; the caller is
+    // told and the ids are logged so the placeholders can be cleared by hand.
+    if let Err(err) =
+        publish_invitation_events(sink.as_deref(), &expired, "invitation_expired").await
+    {
+        let ids: Vec<&str> = expired.iter().map(|i| i.id.as_str()).collect();
+        error!(
+            "Failed to publish invitation_expired events for purged invitations {:?}: {:?}",
+            ids, err
+        );
+        return Err(AppError::bad_gateway(format!(
+            "Purged {} expired invitations but could not publish their invitation_expired events",
+            expired.len()
+        )));
+    }
+
+    Ok(Json(PurgeExpiredResponse {
+        purged: expired.len(),
+    }))
+}
@@ -1 +1,2 @@
+pub mod health_handlers;
 pub mod invitation_handlers;
@@ -1,4 +1,5 @@
 mod error;
+mod events;
 mod handlers;
 mod models;
 mod routes;
@@ -6,19 +7,19 @@ mod routes;
 mod tests;
 
 use axum::{body::Body, extract::Request, response::Response, Router};
-use env_logger;
 use http_body_util::BodyExt;
 use lambda_http::{
     run, service_fn, Body as LambdaBody, Error, Request as LambdaRequest,
     Response as LambdaResponse,
 };
+use lockbox_shared::bootstrap::LazyInit;
+use lockbox_shared::shutdown::{serve_until, shutdown_signal};
 use log::{debug, error, info, trace};
 use std::net::SocketAddr;
-use tokio::sync::OnceCell;
 use tower::ServiceExt;
 
-// Router instance that will be initialized once
-static ROUTER: OnceCell<Router> = OnceCell::const_new();
+// Router instance, built on the first request
+static ROUTER: LazyInit<Router> = LazyInit::new();
 
 // The Lambda handler function
 async fn function_handler(event: LambdaRequest) -> Result<LambdaResponse<LambdaBody>, Error> {
@@ -31,9 +32,11 @@ async fn function_handler(event: LambdaRequest) -> Result<LambdaResponse<LambdaB
 
     // Get or initialize the router
     let app = ROUTER
-        .get_or_init(|| async { routes::create_router().await })
-        .await
-        .clone();
+        .get_or_init(|| async {
+            info!("Initializing the Axum router");
+            routes::create_router().await
+        })
+        .await;
 
     let (parts, body) = event.into_parts();
     let body = match body {
@@ -130,10 +133,16 @@ async fn response_to_lambda(response: Response) -> Result<LambdaResponse<LambdaB
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    // Initialize env_logger
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    // Initialize env_logger (lines carry the request id; LOG_FORMAT=json switches them to JSON)
+    lockbox_shared::correlation::log_builder().init();
     info!("Logging initialized with env_logger");
 
+    // Refuse to start with ENFORCE_ID_TOKEN on but no app client to check audiences against
+    if let Err(msg) = lockbox_shared::auth::check_id_token_config() {
+        error!("{}", msg);
+        return Err(msg.into());
+    }
+
     if let Ok(function_name) = std::env::var("AWS_LAMBDA_FUNCTION_NAME") {
         info!(
             "Running in AWS Lambda environment: {} (version: {})",
@@ -148,7 +157,7 @@ async fn main() -> Result<(), Error> {
 
         let app = routes::create_router().await;
         let listener = tokio::net::TcpListener::bind(&addr).await?;
-        axum::serve(listener, app.into_make_service()).await?;
+        serve_until(listener, app, shutdown_signal()).await?;
     }
 
     info!("Service finished");
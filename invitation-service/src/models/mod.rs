@@ -1,5 +1,11 @@
+use lockbox_shared::models::Invitation;
 use serde::{Deserialize, Serialize};
 
+use crate::error::FieldError;
+
+// Longest invited name, in characters, after trimming
+pub const MAX_INVITED_NAME_LENGTH: usize = 80;
+
 // Request DTOs
 #[derive(Deserialize, Debug)]
 pub struct CreateInvitationRequest {
@@ -9,6 +15,36 @@ pub struct CreateInvitationRequest {
     pub box_id: String,
 }
 
+impl CreateInvitationRequest {
+    // Checks the name as it will be stored, i.e. with surrounding whitespace trimmed
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        let invited_name = self.invited_name.trim();
+
+        if invited_name.is_empty() {
+            errors.push(FieldError::new("invitedName", "must not be empty"));
+        } else if invited_name.chars().count() > MAX_INVITED_NAME_LENGTH {
+            errors.push(FieldError::new(
+                "invitedName",
+                &format!("must be at most {} characters", MAX_INVITED_NAME_LENGTH),
+            ));
+        }
+
+        if invited_name.chars().any(char::is_control) {
+            errors.push(FieldError::new(
+                "invitedName",
+                "must not contain control characters",
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ConnectToUserRequest {
     #[serde(rename = "userId")]
@@ -17,9 +53,71 @@ pub struct ConnectToUserRequest {
     pub invite_code: String,
 }
 
+// Page size for GET /invitations/me when a cursor is given without a limit
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+// Fields GET /invitations/me can sort by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InvitationSortField {
+    CreatedAt,
+    ExpiresAt,
+}
+
+impl InvitationSortField {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "createdAt" => Some(Self::CreatedAt),
+            "expiresAt" => Some(Self::ExpiresAt),
+            _ => None,
+        }
+    }
+
+    pub fn compare(self, a: &Invitation, b: &Invitation) -> std::cmp::Ordering {
+        match self {
+            Self::CreatedAt => a.created_at.cmp(&b.created_at),
+            Self::ExpiresAt => a.expires_at.cmp(&b.expires_at),
+        }
+    }
+}
+
+// Query parameters for GET /invitations/box/:boxId
+#[derive(Deserialize, Debug, Default)]
+pub struct BoxInvitationsQuery {
+    #[serde(rename = "includeExpired", default)]
+    pub include_expired: bool,
+}
+
 #[derive(Serialize, Debug)]
 pub struct MessageResponse {
     pub message: String,
     #[serde(rename = "boxId", skip_serializing_if = "Option::is_none")]
     pub box_id: Option<String>,
 }
+
+// Create-invitation response: the invitation plus the TTL it was issued with
+#[derive(Serialize, Debug)]
+pub struct CreateInvitationResponse {
+    #[serde(flatten)]
+    pub invitation: Invitation,
+    #[serde(rename = "ttlHours")]
+    pub ttl_hours: i64,
+}
+
+// Response for GET /invitations/preview/:inviteCode. Served without a token, so
+// it carries nothing beyond what the deep link screen needs
+#[derive(Serialize, Debug)]
+pub struct InvitationPreviewResponse {
+    #[serde(rename = "invitedName")]
+    pub invited_name: String,
+    #[serde(rename = "boxName", skip_serializing_if = "Option::is_none")]
+    pub box_name: Option<String>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: String,
+    pub expired: bool,
+}
+
+// Response for POST /invitations/purge-expired
+#[derive(Serialize, Debug)]
+pub struct PurgeExpiredResponse {
+    pub purged: usize,
+}
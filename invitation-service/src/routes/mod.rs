@@ -1,53 +1,147 @@
 use axum::{
-    extract::Request,
+    extract::{DefaultBodyLimit, Extension, Request, State},
     middleware,
+    response::{IntoResponse, Response},
     routing::{get, patch, post, put},
     Router,
 };
 use log::{debug, info, warn};
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::limit::RequestBodyLimitLayer;
 
-use crate::handlers::invitation_handlers::{
-    create_invitation, get_my_invitations, handle_invitation, refresh_invitation,
+mod rate_limit;
+
+pub use rate_limit::RateLimiter;
+
+use crate::error::AppError;
+use crate::events::event_sink_from_env;
+use crate::handlers::{
+    health_handlers::health,
+    invitation_handlers::{
+        create_invitation, get_box_invitations, get_linked_invitations, get_my_invitations,
+        handle_invitation, preview_invitation, purge_expired_invitations, refresh_invitation,
+        refresh_invitation_by_code, revoke_invitation,
+    },
 };
 // Import shared auth middleware
 use lockbox_shared::auth::auth_middleware;
-use lockbox_shared::store::{dynamo::DynamoInvitationStore, InvitationStore};
+use lockbox_shared::base_path::api_base_path;
+use lockbox_shared::body_limit::max_body_bytes;
+use lockbox_shared::correlation::correlation_id_middleware;
+use lockbox_shared::cors::cors_layer_from_env;
+use lockbox_shared::envelope::response_envelope_middleware;
+use lockbox_shared::fallback::{method_not_allowed_fallback, not_found_fallback};
+use lockbox_shared::idempotency::idempotency_store_from_env;
+use lockbox_shared::store::{
+    dynamo::{DynamoBoxStore, DynamoInvitationBoxTransaction, DynamoInvitationStore},
+    memory::MemoryInvitationStore,
+    metrics::{emf_metrics_enabled, MetricsBoxStore, MetricsInvitationStore},
+    tracing::TracingBoxStore,
+    BoxStore, InvitationBoxTransaction, InvitationStore,
+};
+use lockbox_shared::test_utils::mock_box_store::MockBoxStore;
 
 /// Creates a router with the default store
+///
+/// Uses DynamoDB when DYNAMODB_INVITATION_TABLE is set, otherwise in-memory
+/// stores so the service can run locally without AWS.
 pub async fn create_router() -> Router {
-    info!("Creating router with DynamoDB store");
+    let use_dynamo = std::env::var("DYNAMODB_INVITATION_TABLE").is_ok();
 
-    // Create the DynamoDB store
-    let dynamo_store = Arc::new(DynamoInvitationStore::new().await);
+    // Box ownership is checked against the box table before creating invitations
+    let (mut store, mut box_store): (Arc<dyn InvitationStore>, Arc<dyn BoxStore>) = if use_dynamo {
+        info!("Creating router with DynamoDB store");
+        (
+            Arc::new(DynamoInvitationStore::new().await),
+            Arc::new(TracingBoxStore::new(DynamoBoxStore::new().await)),
+        )
+    } else {
+        warn!("DYNAMODB_INVITATION_TABLE is not set, using in-memory invitation and box stores");
+        (
+            Arc::new(MemoryInvitationStore::new()),
+            Arc::new(MockBoxStore::new()),
+        )
+    };
 
-    // Check if we should remove the base path prefix
-    let remove_base_path = std::env::var("REMOVE_BASE_PATH")
-        .map(|v| v.to_lowercase() == "true")
-        .unwrap_or(false);
+    if emf_metrics_enabled() {
+        info!("EMF_METRICS is set, emitting store metrics");
+        store = Arc::new(MetricsInvitationStore::new(store));
+        box_store = Arc::new(MetricsBoxStore::new(box_store));
+    }
 
-    // If REMOVE_BASE_PATH is set to true, don't add the /Prod prefix
-    let prefix = if remove_base_path { "" } else { "/Prod" };
+    // API_BASE_PATH (default /Prod), or no prefix when REMOVE_BASE_PATH=true
+    let prefix = api_base_path();
     info!("Using API route prefix: {}", prefix);
 
-    create_router_with_store(dynamo_store, prefix)
+    let router = create_router_with_store(store, box_store, &prefix);
+
+    // With both tables in DynamoDB, an invitation and its guardian placeholder on the
+    // box are written in one transaction; otherwise the handler writes them in turn
+    let router = if use_dynamo {
+        let transaction: Arc<dyn InvitationBoxTransaction> =
+            Arc::new(DynamoInvitationBoxTransaction::new(
+                DynamoInvitationStore::new().await,
+                DynamoBoxStore::new().await,
+            ));
+        router.layer(Extension(transaction))
+    } else {
+        router
+    };
+
+    // EVENT_SINK picks SNS (the default) or a signed webhook for invitation events
+    let router = match event_sink_from_env().await {
+        Some(sink) => router.layer(Extension(sink)),
+        None => router,
+    };
+
+    // Remembers Idempotency-Key headers on invitation creation (IDEMPOTENCY_TABLE)
+    router.layer(Extension(idempotency_store_from_env().await))
 }
 
-/// Creates a router with a given store implementation
-pub fn create_router_with_store<S>(store: Arc<S>, prefix: &str) -> Router
+/// Creates a router with the given invitation and box store implementations
+pub fn create_router_with_store<S>(
+    store: Arc<S>,
+    box_store: Arc<dyn BoxStore>,
+    prefix: &str,
+) -> Router
 where
     S: InvitationStore + ?Sized + 'static,
 {
-    info!("Setting up API routes with prefix: {}", prefix);
+    create_router_with_rate_limiter(store, box_store, prefix, Arc::new(RateLimiter::from_env()))
+}
 
-    // Configure CORS
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+// Rejects invitation creation once the caller has used up their bucket
+async fn invite_rate_limit_middleware(
+    State(limiter): State<Arc<RateLimiter>>,
+    Extension(user_id): Extension<String>,
+    req: Request,
+    next: axum::middleware::Next,
+) -> Response {
+    match limiter.try_acquire(&user_id) {
+        Ok(()) => next.run(req).await,
+        Err(wait) => {
+            warn!("Invitation rate limit exceeded for user {}", user_id);
+            // Round up so clients never retry before a token is back
+            let retry_after_secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            AppError::too_many_requests(retry_after_secs).into_response()
+        }
+    }
+}
 
-    debug!("CORS configured for all origins, methods and headers");
+/// Creates a router with the given stores and invitation rate limiter
+pub fn create_router_with_rate_limiter<S>(
+    store: Arc<S>,
+    box_store: Arc<dyn BoxStore>,
+    prefix: &str,
+    invite_limiter: Arc<RateLimiter>,
+) -> Router
+where
+    S: InvitationStore + ?Sized + 'static,
+{
+    info!("Setting up API routes with prefix: {}", prefix);
+
+    // Configure CORS from CORS_ALLOWED_ORIGINS/METHODS/HEADERS
+    let cors = cors_layer_from_env();
 
     // Logging middleware to trace all requests
     async fn logging_middleware(
@@ -65,20 +159,61 @@ where
         response
     }
 
+    // Deep link previews are opened before the user has logged in, so these
+    // routes sit outside the auth layer
+    let public_routes = Router::new()
+        .route("/invitations/preview/:inviteCode", get(preview_invitation))
+        .layer(Extension(box_store.clone()))
+        .with_state(store.clone());
+
     // Create the API routes
     let api_routes = Router::new()
-        .route("/invitations/new", post(create_invitation))
+        // route_layer runs inside the auth layer, so the user id is already set
+        .route(
+            "/invitations/new",
+            post(create_invitation).route_layer(middleware::from_fn_with_state(
+                invite_limiter,
+                invite_rate_limit_middleware,
+            )),
+        )
         .route("/invitations/handle", put(handle_invitation))
+        .route(
+            "/invitations/:inviteId",
+            axum::routing::delete(revoke_invitation),
+        )
         .route("/invitations/:inviteId/refresh", patch(refresh_invitation))
+        .route(
+            "/invitations/code/:inviteCode/refresh",
+            patch(refresh_invitation_by_code),
+        )
         .route("/invitations/me", get(get_my_invitations))
+        .route("/invitations/linked", get(get_linked_invitations))
+        .route("/invitations/box/:boxId", get(get_box_invitations))
+        .route(
+            "/invitations/purge-expired",
+            post(purge_expired_invitations),
+        )
+        .layer(Extension(box_store))
         .layer(middleware::from_fn(auth_middleware))
-        .with_state(store);
+        // Oversized bodies get a 413 before anything buffers them (MAX_BODY_BYTES)
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(max_body_bytes()))
+        .with_state(store)
+        // Registered after the auth layer so health checks don't need a token
+        .route("/health", get(health))
+        .merge(public_routes)
+        // Known paths called with the wrong method get a JSON 405
+        .method_not_allowed_fallback(method_not_allowed_fallback);
 
     // Create the main router with the prefix
     let router = Router::new()
         .nest(prefix, api_routes)
+        // Opt-in {"data", "meta"} envelope for Accept: application/vnd.lockbox.v2+json
+        .layer(middleware::from_fn(response_envelope_middleware))
         .layer(cors)
-        .layer(middleware::from_fn(logging_middleware));
+        .layer(middleware::from_fn(logging_middleware))
+        // Outermost, so the request id is set for every log line below it
+        .layer(middleware::from_fn(correlation_id_middleware));
 
     info!(
         "Router configured with all routes and middleware under prefix: {}",
@@ -86,11 +221,5 @@ where
     );
 
     // Add a fallback handler for 404s
-    router.fallback(|req: Request| async move {
-        warn!("No route matched for: {} {}", req.method(), req.uri());
-        (
-            axum::http::StatusCode::NOT_FOUND,
-            "The requested resource was not found".to_string(),
-        )
-    })
+    router.fallback(not_found_fallback)
 }
@@ -0,0 +1,91 @@
+use log::warn;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Invitation creations allowed per user per hour, overridable via INVITE_RATE_LIMIT
+pub const DEFAULT_INVITE_RATE_LIMIT: u32 = 20;
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+// Idle buckets are pruned once the map grows past this many users
+const MAX_TRACKED_USERS: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// In-memory token bucket keyed by user id.
+///
+/// State lives in the Lambda instance, so each warm instance enforces the limit
+/// on its own; a user spread across several instances can exceed it in total.
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Allows `capacity` requests per `window`, refilling continuously
+    pub fn new(capacity: u32, window: Duration) -> Self {
+        let capacity = capacity.max(1) as f64;
+        Self {
+            capacity,
+            refill_per_sec: capacity / window.as_secs_f64(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Per-hour limiter sized from INVITE_RATE_LIMIT
+    pub fn from_env() -> Self {
+        Self::new(invite_rate_limit(), RATE_LIMIT_WINDOW)
+    }
+
+    /// Takes a token for `key`, or returns how long until one is available
+    pub fn try_acquire(&self, key: &str) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= MAX_TRACKED_USERS && !buckets.contains_key(key) {
+            let (capacity, refill_per_sec) = (self.capacity, self.refill_per_sec);
+            buckets.retain(|_, bucket| {
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                bucket.tokens + elapsed * refill_per_sec < capacity
+            });
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.capacity,
+            last_refill: now,
+        });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = (1.0 - bucket.tokens) / self.refill_per_sec;
+            Err(Duration::from_secs_f64(wait))
+        }
+    }
+}
+
+pub fn invite_rate_limit() -> u32 {
+    match std::env::var("INVITE_RATE_LIMIT")
+        .ok()
+        .map(|v| v.trim().parse::<u32>())
+    {
+        Some(Ok(limit)) if limit > 0 => limit,
+        Some(_) => {
+            warn!(
+                "Invalid INVITE_RATE_LIMIT value, using default of {}",
+                DEFAULT_INVITE_RATE_LIMIT
+            );
+            DEFAULT_INVITE_RATE_LIMIT
+        }
+        None => DEFAULT_INVITE_RATE_LIMIT,
+    }
+}
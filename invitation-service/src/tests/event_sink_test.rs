@@ -0,0 +1,162 @@
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::events::{
+    sns_batches, webhook_signature, EventSink, WebhookSink, WEBHOOK_EVENT_HEADER,
+    WEBHOOK_SIGNATURE_HEADER,
+};
+use lockbox_shared::models::events::InvitationEvent;
+
+type Received = Arc<Mutex<Vec<(HeaderMap, Bytes)>>>;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn test_event() -> InvitationEvent {
+    InvitationEvent {
+        event_type: "invitation_created".to_string(),
+        event_version: 1,
+        invitation_id: "invitation-1".to_string(),
+        box_id: "box-1".to_string(),
+        user_id: None,
+        invite_code: "ABCD1234".to_string(),
+        timestamp: "2024-01-01T00:00:00Z".to_string(),
+        correlation_id: None,
+    }
+}
+
+// Local HTTP server that records every POST and answers with `status`
+async fn mock_webhook_server(status: StatusCode) -> (String, Received) {
+    async fn record(
+        State((received, status)): State<(Received, StatusCode)>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        received.lock().unwrap().push((headers, body));
+        status
+    }
+
+    let received: Received = Arc::default();
+    let app = Router::new()
+        .route("/hook", post(record))
+        .with_state((received.clone(), status));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (format!("http://{}/hook", addr), received)
+}
+
+#[test]
+fn test_webhook_signature_is_hmac_sha256() {
+    // RFC 4231 test case 2
+    assert_eq!(
+        webhook_signature("Jefe", b"what do ya want for nothing?"),
+        "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+#[tokio::test]
+async fn test_webhook_sink_posts_signed_payload() {
+    let (url, received) = mock_webhook_server(StatusCode::NO_CONTENT).await;
+    let sink = WebhookSink::new(url, "webhook-secret".to_string(), TIMEOUT).unwrap();
+
+    sink.publish(&test_event()).await.unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    let (headers, body) = &received[0];
+    assert_eq!(headers["content-type"], "application/json");
+    assert_eq!(headers[WEBHOOK_EVENT_HEADER], "invitation_created");
+
+    // The signature covers the exact bytes that were sent
+    assert_eq!(
+        headers[WEBHOOK_SIGNATURE_HEADER].to_str().unwrap(),
+        webhook_signature("webhook-secret", body)
+    );
+    assert_ne!(
+        headers[WEBHOOK_SIGNATURE_HEADER].to_str().unwrap(),
+        webhook_signature("another-secret", body)
+    );
+
+    let event: InvitationEvent = serde_json::from_slice(body).unwrap();
+    assert_eq!(event.invitation_id, "invitation-1");
+    assert_eq!(event.box_id, "box-1");
+    assert_eq!(event.invite_code, "ABCD1234");
+}
+
+#[tokio::test]
+async fn test_webhook_sink_fails_on_error_status() {
+    let (url, received) = mock_webhook_server(StatusCode::INTERNAL_SERVER_ERROR).await;
+    let sink = WebhookSink::new(url, "webhook-secret".to_string(), TIMEOUT).unwrap();
+
+    let result = sink.publish(&test_event()).await;
+
+    assert!(result.unwrap_err().to_string().contains("500"));
+    assert_eq!(received.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_webhook_sink_times_out() {
+    // Accepts connections but never answers
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let mut open = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            open.push(socket);
+        }
+    });
+    let sink = WebhookSink::new(
+        url,
+        "webhook-secret".to_string(),
+        Duration::from_millis(100),
+    )
+    .unwrap();
+
+    let result = sink.publish(&test_event()).await;
+
+    assert!(result.unwrap_err().to_string().contains("timed out"));
+}
+
+#[tokio::test]
+async fn test_webhook_sink_publishes_batch_one_by_one() {
+    let (url, received) = mock_webhook_server(StatusCode::NO_CONTENT).await;
+    let sink = WebhookSink::new(url, "webhook-secret".to_string(), TIMEOUT).unwrap();
+
+    sink.publish_batch(&[test_event(), test_event(), test_event()])
+        .await
+        .unwrap();
+
+    assert_eq!(received.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn test_sns_batches_chunk_by_ten() {
+    let events: Vec<InvitationEvent> = (0..23)
+        .map(|i| InvitationEvent {
+            invitation_id: format!("invitation-{}", i),
+            ..test_event()
+        })
+        .collect();
+
+    let batches: Vec<&[InvitationEvent]> = sns_batches(&events).collect();
+    let sizes: Vec<usize> = batches.iter().map(|batch| batch.len()).collect();
+    assert_eq!(sizes, vec![10, 10, 3]);
+
+    // Every event is sent once, in order
+    let ids: Vec<&str> = batches
+        .iter()
+        .flat_map(|batch| batch.iter().map(|event| event.invitation_id.as_str()))
+        .collect();
+    let expected: Vec<String> = (0..23).map(|i| format!("invitation-{}", i)).collect();
+    assert_eq!(ids, expected);
+
+    assert_eq!(sns_batches(&[]).count(), 0);
+}
@@ -4,16 +4,21 @@ use serde_json::json;
 use std::sync::Arc;
 use tower::ServiceExt;
 
-use crate::routes::create_router_with_store;
+use crate::routes::{create_router_with_rate_limiter, RateLimiter};
 use chrono::{DateTime, Duration, Utc};
-use lockbox_shared::auth::create_test_request;
-use lockbox_shared::models::Invitation;
+use lockbox_shared::auth::{create_test_request, create_test_request_with_groups};
+use lockbox_shared::body_limit::DEFAULT_MAX_BODY_BYTES;
+use lockbox_shared::envelope::ENVELOPE_MEDIA_TYPE;
+use lockbox_shared::models::{
+    now_str, BoxRecord, GuardianStatus, Invitation, InvitationStatus, UnlockPolicy,
+};
 use lockbox_shared::store::dynamo::DynamoInvitationStore;
-use lockbox_shared::store::InvitationStore;
+use lockbox_shared::store::{BoxStore, InvitationStore, DEFAULT_UPDATE_ATTEMPTS};
 use lockbox_shared::test_utils::dynamo_test_utils::{
     clear_dynamo_table, create_dynamo_client, create_invitation_table, use_dynamodb,
 };
 use lockbox_shared::test_utils::http_test_utils::response_to_json;
+use lockbox_shared::test_utils::mock_box_store::MockBoxStore;
 use lockbox_shared::test_utils::mock_invitation_store::MockInvitationStore;
 use lockbox_shared::test_utils::test_logging::init_test_logging;
 use std::env;
@@ -27,8 +32,44 @@ enum TestStore {
     DynamoDB(Arc<DynamoInvitationStore>),
 }
 
+fn owned_box(id: &str, owner_id: &str) -> BoxRecord {
+    let now = now_str();
+    BoxRecord {
+        id: id.to_string(),
+        name: format!("Box {}", id),
+        description: "Invitation test box".to_string(),
+        is_locked: false,
+        created_at: now.clone(),
+        updated_at: now,
+        owner_id: owner_id.to_string(),
+        owner_name: None,
+        documents: vec![],
+        guardians: vec![],
+        unlock_instructions: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
+        version: 0,
+    }
+}
+
+// Boxes invitations can be created for; box-789 belongs to someone else
+fn test_box_store() -> Arc<dyn BoxStore> {
+    Arc::new(MockBoxStore::with_data(vec![
+        owned_box("box-123", "test-user-id"),
+        owned_box("box-789", "other-user-id"),
+        owned_box("busy-box", "busy-user"),
+        owned_box("other-box", "other-user"),
+    ]))
+}
+
 // Helper to set up test application with the appropriate store based on environment
 async fn create_test_app() -> (Router, TestStore) {
+    create_test_app_with_rate_limiter(Arc::new(RateLimiter::from_env())).await
+}
+
+async fn create_test_app_with_rate_limiter(limiter: Arc<RateLimiter>) -> (Router, TestStore) {
     // Initialize logging for tests
     init_test_logging();
 
@@ -95,13 +136,13 @@ async fn create_test_app() -> (Router, TestStore) {
             TEST_TABLE_NAME.to_string(),
         ));
 
-        let app = create_router_with_store(store.clone(), "");
+        let app = create_router_with_rate_limiter(store.clone(), test_box_store(), "", limiter);
         (app, TestStore::DynamoDB(store))
     } else {
         // Use mock store
         debug!("Using mock store for invitation tests");
         let store = Arc::new(MockInvitationStore::new_with_expiry());
-        let app = create_router_with_store(store.clone(), "");
+        let app = create_router_with_rate_limiter(store.clone(), test_box_store(), "", limiter);
         (app, TestStore::Mock(store))
     }
 }
@@ -134,7 +175,11 @@ async fn test_create_invitation() {
     let invite_code = json_resp["inviteCode"].as_str().unwrap();
     let expires_at = json_resp["expiresAt"].as_str().unwrap();
     assert_eq!(invite_code.len(), 8);
+    assert_eq!(json_resp["status"], "invited");
     assert!(!expires_at.is_empty());
+    // Same UTC format as every other timestamp the services write
+    assert!(expires_at.ends_with('Z'), "{}", expires_at);
+    assert!(json_resp["createdAt"].as_str().unwrap().ends_with('Z'));
     let expires_at_dt = DateTime::parse_from_rfc3339(expires_at)
         .unwrap()
         .with_timezone(&Utc);
@@ -146,6 +191,13 @@ async fn test_create_invitation() {
         diff_secs
     );
 
+    // The effective TTL is exposed and matches the computed expiry
+    let ttl_hours = json_resp["ttlHours"].as_i64().unwrap();
+    let created_at_dt = DateTime::parse_from_rfc3339(json_resp["createdAt"].as_str().unwrap())
+        .unwrap()
+        .with_timezone(&Utc);
+    assert_eq!((expires_at_dt - created_at_dt).num_hours(), ttl_hours);
+
     // Verify additional fields in the full invitation response
     assert_eq!(json_resp["invitedName"], "Test User");
     assert_eq!(json_resp["boxId"], "box-123");
@@ -193,6 +245,102 @@ async fn test_create_invitation() {
     assert!(invitation.linked_user_id.is_none());
 }
 
+// POSTs an invitation for box-123 with the given name
+async fn create_named_invitation(app: Router, invited_name: &str) -> axum::response::Response {
+    app.oneshot(create_test_request(
+        "POST",
+        "/invitations/new",
+        "test-user-id",
+        Some(json!({ "invitedName": invited_name, "boxId": "box-123" })),
+    ))
+    .await
+    .unwrap()
+}
+
+async fn assert_invited_name_rejected(invited_name: &str, reason: &str) {
+    let (app, _store) = create_test_app().await;
+
+    let response = create_named_invitation(app, invited_name).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let json_response = response_to_json(response).await;
+    assert_eq!(
+        json_response,
+        json!({ "error": "validation", "field": "invitedName", "detail": reason })
+    );
+}
+
+#[tokio::test]
+async fn test_create_invitation_empty_name() {
+    assert_invited_name_rejected("", "must not be empty").await;
+    assert_invited_name_rejected("   ", "must not be empty").await;
+}
+
+#[tokio::test]
+async fn test_create_invitation_name_too_long() {
+    assert_invited_name_rejected(&"a".repeat(81), "must be at most 80 characters").await;
+}
+
+#[tokio::test]
+async fn test_create_invitation_name_with_control_characters() {
+    assert_invited_name_rejected("Jane\nDoe", "must not contain control characters").await;
+    assert_invited_name_rejected("Jane\u{0}", "must not contain control characters").await;
+}
+
+#[tokio::test]
+async fn test_create_invitation_trims_name() {
+    let (app, _store) = create_test_app().await;
+
+    // 80 characters once the padding is gone
+    let name = format!("  {}\t", "b".repeat(80));
+    let response = create_named_invitation(app, &name).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json_response = response_to_json(response).await;
+    assert_eq!(json_response["invitedName"], "b".repeat(80));
+}
+
+#[tokio::test]
+async fn test_create_invitation_box_not_found() {
+    let (app, _store) = create_test_app().await;
+
+    let response = app
+        .oneshot(create_test_request(
+            "POST",
+            "/invitations/new",
+            "test-user-id",
+            Some(json!({ "invitedName": "Test User", "boxId": "no-such-box" })),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_create_invitation_for_someone_elses_box() {
+    let (app, store) = create_test_app().await;
+
+    let response = app
+        .oneshot(create_test_request(
+            "POST",
+            "/invitations/new",
+            "test-user-id",
+            Some(json!({ "invitedName": "Test User", "boxId": "box-789" })),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Nothing was persisted for the box
+    let invitations = match &store {
+        TestStore::Mock(mock) => mock.get_invitations_by_box_id("box-789").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_invitations_by_box_id("box-789").await.unwrap(),
+    };
+    assert!(invitations.is_empty());
+}
+
 #[tokio::test]
 async fn test_handle_invitation() {
     let (app, store) = create_test_app().await;
@@ -209,6 +357,7 @@ async fn test_handle_invitation() {
         created_at: now.to_rfc3339(),
         expires_at: (now + Duration::hours(2)).to_rfc3339(),
         opened: false,
+        status: InvitationStatus::Invited,
         linked_user_id: None,
         creator_id: "creator-id".to_string(),
     };
@@ -244,6 +393,7 @@ async fn test_handle_invitation() {
     };
 
     assert!(updated_inv.opened);
+    assert_eq!(updated_inv.status, InvitationStatus::Opened);
     assert_eq!(updated_inv.linked_user_id, Some("user-456".to_string()));
 
     // Additional test for SNS event payload
@@ -282,6 +432,7 @@ async fn test_handle_invitation_expired_code() {
         created_at: now.to_rfc3339(),
         expires_at: (now - Duration::hours(1)).to_rfc3339(),
         opened: false,
+        status: InvitationStatus::Invited,
         linked_user_id: None,
         creator_id: "creator-id".to_string(),
     };
@@ -334,6 +485,7 @@ async fn test_refresh_invitation() {
         created_at: create_time.to_rfc3339(),
         expires_at: expiry_time.to_rfc3339(),
         opened: false,
+        status: InvitationStatus::Invited,
         linked_user_id: None,
         creator_id: "test-user-id".to_string(),
     };
@@ -415,6 +567,7 @@ async fn test_refresh_invitation_invalid_id() {
         created_at: now.to_rfc3339(),
         expires_at: (now + Duration::hours(2)).to_rfc3339(),
         opened: false,
+        status: InvitationStatus::Invited,
         linked_user_id: None,
         creator_id: "owner-id".to_string(),
     };
@@ -435,6 +588,103 @@ async fn test_refresh_invitation_invalid_id() {
     assert_eq!(response.status(), StatusCode::FORBIDDEN);
 }
 
+#[tokio::test]
+async fn test_refresh_invitation_by_code() {
+    let (app, store) = create_test_app().await;
+
+    // Already expired: refreshing is how the creator fixes a stale deep link
+    let now = Utc::now();
+    let id = Uuid::new_v4().to_string();
+    let invitation = Invitation {
+        id: id.clone(),
+        invite_code: "STALECDE".to_string(),
+        invited_name: "Test User".to_string(),
+        box_id: "box-123".to_string(),
+        created_at: (now - Duration::hours(50)).to_rfc3339(),
+        expires_at: (now - Duration::hours(2)).to_rfc3339(),
+        opened: false,
+        status: InvitationStatus::Invited,
+        linked_user_id: None,
+        creator_id: "test-user-id".to_string(),
+    };
+    match &store {
+        TestStore::Mock(mock) => mock.create_invitation(invitation).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation).await.unwrap(),
+    };
+
+    if matches!(store, TestStore::DynamoDB(_)) {
+        tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
+    }
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/invitations/code/STALECDE/refresh",
+            "test-user-id",
+            None,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_resp = response_to_json(response).await;
+    assert_eq!(json_resp["id"], id);
+    let new_code = json_resp["inviteCode"].as_str().unwrap();
+    assert_ne!(new_code, "STALECDE");
+    let expires_at = DateTime::parse_from_rfc3339(json_resp["expiresAt"].as_str().unwrap())
+        .unwrap()
+        .with_timezone(&Utc);
+    assert!(expires_at > Utc::now());
+
+    // The new code resolves to the same, now live, invitation
+    let refreshed = match &store {
+        TestStore::Mock(mock) => mock.get_invitation_by_code(new_code).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_invitation(&id).await.unwrap(),
+    };
+    assert_eq!(refreshed.id, id);
+    assert_eq!(refreshed.invite_code, new_code);
+}
+
+#[tokio::test]
+async fn test_refresh_invitation_by_code_not_creator() {
+    let (app, store) = create_test_app().await;
+
+    let now = Utc::now();
+    let invitation = Invitation {
+        id: Uuid::new_v4().to_string(),
+        invite_code: "OTHERSCD".to_string(),
+        invited_name: "Test User".to_string(),
+        box_id: "box-789".to_string(),
+        created_at: now.to_rfc3339(),
+        expires_at: (now + Duration::hours(2)).to_rfc3339(),
+        opened: false,
+        status: InvitationStatus::Invited,
+        linked_user_id: None,
+        creator_id: "other-user-id".to_string(),
+    };
+    match &store {
+        TestStore::Mock(mock) => mock.create_invitation(invitation).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation).await.unwrap(),
+    };
+
+    if matches!(store, TestStore::DynamoDB(_)) {
+        tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
+    }
+
+    let response = app
+        .oneshot(create_test_request(
+            "PATCH",
+            "/invitations/code/OTHERSCD/refresh",
+            "test-user-id",
+            None,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
 #[tokio::test]
 async fn test_handle_invitation_invalid_code() {
     let (app, store) = create_test_app().await;
@@ -449,6 +699,7 @@ async fn test_handle_invitation_invalid_code() {
         created_at: now.to_rfc3339(),
         expires_at: (now + Duration::hours(2)).to_rfc3339(),
         opened: false,
+        status: InvitationStatus::Invited,
         linked_user_id: None,
         creator_id: "creator-id".to_string(),
     };
@@ -509,6 +760,7 @@ async fn test_get_my_invitations() {
             created_at: now.to_rfc3339(),
             expires_at: (now + Duration::hours(48)).to_rfc3339(),
             opened: false,
+            status: InvitationStatus::Invited,
             linked_user_id: None,
             creator_id: creator.to_string(),
         };
@@ -550,22 +802,24 @@ async fn test_get_my_invitations() {
     let json_resp = response_to_json(response).await;
     let arr = json_resp.as_array().unwrap();
 
-    // With DynamoDB tests, our test workaround returns 0 invitations when GSI fails
-    // With mock tests, we should get 2 invitations
-    if matches!(store, TestStore::Mock(_)) {
-        // We should get only the invitations where test-user-id is the creator
-        assert_eq!(arr.len(), 2, "Expected 2 invitations with mock store");
-
-        // Verify each returned invitation has the correct creator_id
-        for item in arr {
-            assert_eq!(item["creatorId"], "test-user-id");
-        }
-    } else {
-        // In test mode with actual DynamoDB, GSI may not be ready
-        // Our handler workaround returns empty list
-        info!("DynamoDB test: Expected empty result due to GSI workaround");
-        // This is a special workaround for the test environment
-        assert!(arr.is_empty(), "DynamoDB test: Expected empty result");
+    // Only the invitations where test-user-id is the creator, whichever store backs the app
+    assert_eq!(arr.len(), 2, "Expected 2 invitations for test-user-id");
+
+    let mut returned: Vec<&str> = arr
+        .iter()
+        .map(|item| item["id"].as_str().unwrap())
+        .collect();
+    returned.sort();
+    let mut expected: Vec<&str> = ids
+        .iter()
+        .filter(|(_, creator)| creator == "test-user-id")
+        .map(|(id, _)| id.as_str())
+        .collect();
+    expected.sort();
+    assert_eq!(returned, expected);
+
+    for item in arr {
+        assert_eq!(item["creatorId"], "test-user-id");
     }
 }
 
@@ -588,3 +842,1014 @@ async fn test_get_my_invitations_empty() {
     let json_resp = response_to_json(response).await;
     assert!(json_resp.as_array().unwrap().is_empty());
 }
+
+#[tokio::test]
+async fn test_get_my_invitations_list_params() {
+    use lockbox_shared::extract::NEXT_CURSOR_HEADER;
+
+    let (app, store) = create_test_app().await;
+    let now = Utc::now();
+    let mut seeded = Vec::new();
+    for hours in [2, 3, 1] {
+        seeded.push(
+            seed_invitation_expiring(&store, "test-user-id", now + Duration::hours(hours)).await,
+        );
+    }
+
+    let get = |uri: String| {
+        app.clone()
+            .oneshot(create_test_request("GET", &uri, "test-user-id", None))
+    };
+    let ids = |body: serde_json::Value| -> Vec<String> {
+        body.as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // Sorted by expiry, latest first
+    let response = get("/invitations/me?sort=expiresAt&order=desc".to_string())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(NEXT_CURSOR_HEADER).is_none());
+    assert_eq!(
+        ids(response_to_json(response).await),
+        vec![seeded[1].clone(), seeded[0].clone(), seeded[2].clone()]
+    );
+
+    // Paging follows the cursor header until it runs out
+    let mut paged = Vec::new();
+    let mut uri = "/invitations/me?limit=2".to_string();
+    loop {
+        let response = get(uri).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let cursor = response
+            .headers()
+            .get(NEXT_CURSOR_HEADER)
+            .map(|value| value.to_str().unwrap().to_string());
+        let page = ids(response_to_json(response).await);
+        assert!(page.len() <= 2);
+        paged.extend(page);
+        match cursor {
+            Some(cursor) => uri = format!("/invitations/me?limit=2&cursor={}", cursor),
+            None => break,
+        }
+    }
+    paged.sort();
+    seeded.sort();
+    assert_eq!(paged, seeded);
+
+    // Bad list parameters are rejected with 422
+    for (query, field) in [
+        ("limit=0", "limit"),
+        ("limit=101", "limit"),
+        ("order=sideways", "order"),
+    ] {
+        let response = get(format!("/invitations/me?{}", query)).await.unwrap();
+        assert_eq!(
+            response.status(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{}",
+            query
+        );
+        assert_eq!(response_to_json(response).await["field"], field);
+    }
+    let response = get("/invitations/me?sort=invitedName".to_string())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response_to_json(response).await["field"], "sort");
+}
+
+#[tokio::test]
+async fn test_get_linked_invitations() {
+    let (app, store) = create_test_app().await;
+
+    let now = Utc::now();
+    for (code, linked_user_id) in [
+        ("LINKEDAA", Some("guardian-user")),
+        ("LINKEDBB", Some("someone-else")),
+        ("LINKEDCC", None),
+        ("LINKEDDD", Some("guardian-user")),
+    ] {
+        let invitation = Invitation {
+            id: Uuid::new_v4().to_string(),
+            invite_code: code.to_string(),
+            invited_name: "Linked Guardian".to_string(),
+            box_id: "box-123".to_string(),
+            created_at: now.to_rfc3339(),
+            expires_at: (now + Duration::hours(48)).to_rfc3339(),
+            opened: linked_user_id.is_some(),
+            status: if linked_user_id.is_some() {
+                InvitationStatus::Accepted
+            } else {
+                InvitationStatus::Invited
+            },
+            linked_user_id: linked_user_id.map(str::to_string),
+            creator_id: "test-user-id".to_string(),
+        };
+        match &store {
+            TestStore::Mock(mock) => mock.create_invitation(invitation).await.unwrap(),
+            TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation).await.unwrap(),
+        };
+    }
+
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            "/invitations/linked",
+            "guardian-user",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json_response = response_to_json(response).await;
+    let mut codes: Vec<&str> = json_response
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|inv| {
+            assert_eq!(inv["linkedUserId"], "guardian-user");
+            inv["inviteCode"].as_str().unwrap()
+        })
+        .collect();
+    codes.sort();
+    assert_eq!(codes, vec!["LINKEDAA", "LINKEDDD"]);
+}
+
+#[tokio::test]
+async fn test_get_my_invitations_response_envelope() {
+    let (app, store) = create_test_app().await;
+    seed_invitation(&store, "test-user-id").await;
+
+    // The legacy shape is a bare array
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/invitations/me",
+            "test-user-id",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let legacy = response_to_json(response).await;
+    assert_eq!(legacy.as_array().unwrap().len(), 1);
+
+    let mut request = create_test_request("GET", "/invitations/me", "test-user-id", None);
+    request.headers_mut().insert(
+        axum::http::header::ACCEPT,
+        ENVELOPE_MEDIA_TYPE.parse().unwrap(),
+    );
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let enveloped = response_to_json(response).await;
+    assert_eq!(enveloped["data"], legacy);
+    assert_eq!(enveloped["meta"]["status"], 200);
+}
+
+#[tokio::test]
+async fn test_health_check_without_authorization() {
+    let (app, _store) = create_test_app().await;
+
+    // No authorization header - health checks must bypass auth
+    let response = app
+        .oneshot(
+            axum::http::Request::builder()
+                .uri("/health")
+                .method("GET")
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json_response = response_to_json(response).await;
+    assert_eq!(json_response["status"], "ok");
+    assert_eq!(json_response["service"], "invitation-service");
+    assert_eq!(json_response["version"], env!("CARGO_PKG_VERSION"));
+}
+
+// Unauthenticated GET, as a deep link opened before login would send
+async fn get_preview(app: Router, invite_code: &str) -> axum::response::Response {
+    app.oneshot(
+        axum::http::Request::builder()
+            .uri(format!("/invitations/preview/{}", invite_code))
+            .method("GET")
+            .body(axum::body::Body::empty())
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+}
+
+async fn seed_preview_invitation(store: &TestStore, invite_code: &str, expires_at: DateTime<Utc>) {
+    let invitation = Invitation {
+        id: Uuid::new_v4().to_string(),
+        invite_code: invite_code.to_string(),
+        invited_name: "Deep Link".to_string(),
+        box_id: "box-123".to_string(),
+        created_at: Utc::now().to_rfc3339(),
+        expires_at: expires_at.to_rfc3339(),
+        opened: false,
+        status: InvitationStatus::Invited,
+        linked_user_id: Some("private-user".to_string()),
+        creator_id: "test-user-id".to_string(),
+    };
+    match store {
+        TestStore::Mock(mock) => mock.create_invitation(invitation).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation).await.unwrap(),
+    };
+}
+
+#[tokio::test]
+async fn test_preview_invitation_without_authorization() {
+    let (app, store) = create_test_app().await;
+    let expires_at = Utc::now() + Duration::hours(24);
+    seed_preview_invitation(&store, "PREVIEWS", expires_at).await;
+
+    let response = get_preview(app, "PREVIEWS").await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json_response = response_to_json(response).await;
+    assert_eq!(json_response["invitedName"], "Deep Link");
+    assert_eq!(json_response["boxName"], "Box box-123");
+    assert_eq!(json_response["expired"], false);
+    let returned_expiry =
+        DateTime::parse_from_rfc3339(json_response["expiresAt"].as_str().unwrap())
+            .unwrap()
+            .with_timezone(&Utc);
+    assert_eq!(returned_expiry.timestamp(), expires_at.timestamp());
+
+    // Nothing that identifies the people involved or the invitation itself
+    let fields = json_response.as_object().unwrap();
+    assert_eq!(fields.len(), 4);
+}
+
+#[tokio::test]
+async fn test_preview_invitation_expired_code() {
+    let (app, store) = create_test_app().await;
+    seed_preview_invitation(&store, "PREVIEWX", Utc::now() - Duration::hours(1)).await;
+
+    let response = get_preview(app, "PREVIEWX").await;
+    assert_eq!(response.status(), StatusCode::GONE);
+}
+
+#[tokio::test]
+async fn test_preview_invitation_unknown_code() {
+    let (app, _store) = create_test_app().await;
+
+    let response = get_preview(app, "NOSUCHCD").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_create_invitation_rate_limited() {
+    let limiter = Arc::new(RateLimiter::new(2, std::time::Duration::from_secs(3600)));
+    let (app, _store) = create_test_app_with_rate_limiter(limiter).await;
+
+    let create_as = |user_id: &str, box_id: &str| {
+        app.clone().oneshot(create_test_request(
+            "POST",
+            "/invitations/new",
+            user_id,
+            Some(json!({ "invitedName": "Spam", "boxId": box_id })),
+        ))
+    };
+
+    // Exhaust the bucket
+    for _ in 0..2 {
+        let response = create_as("busy-user", "busy-box").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let response = create_as("busy-user", "busy-box").await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    // One token refills every 30 minutes at 2 per hour
+    assert!(retry_after > 0 && retry_after <= 1800);
+
+    // Buckets are per user
+    let response = create_as("other-user", "other-box").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_create_invitation_body_too_large() {
+    let (app, _store) = create_test_app().await;
+
+    let payload = json!({
+        "invitedName": "x".repeat(DEFAULT_MAX_BODY_BYTES),
+        "boxId": "box-123"
+    });
+    let length = payload.to_string().len();
+    let mut request =
+        create_test_request("POST", "/invitations/new", "test-user-id", Some(payload));
+    request
+        .headers_mut()
+        .insert("content-length", length.to_string().parse().unwrap());
+
+    // A declared length over the limit is refused without reading the body
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_get_box_invitations() {
+    let (app, store) = create_test_app().await;
+
+    // (name, box, creator, hours until expiry, hours since creation)
+    let now = Utc::now();
+    let seeds = [
+        ("Second", "box-a", "owner-a", 24, 1),
+        ("First", "box-a", "owner-a", 24, 3),
+        ("Expired", "box-a", "owner-a", -1, 50),
+        ("Other box", "box-b", "owner-b", 24, 2),
+    ];
+    for (name, box_id, creator, expires_in, created_ago) in seeds {
+        let invitation = Invitation {
+            id: Uuid::new_v4().to_string(),
+            invite_code: Uuid::new_v4().to_string()[..8].to_uppercase(),
+            invited_name: name.to_string(),
+            box_id: box_id.to_string(),
+            created_at: (now - Duration::hours(created_ago)).to_rfc3339(),
+            expires_at: (now + Duration::hours(expires_in)).to_rfc3339(),
+            opened: false,
+            status: InvitationStatus::Invited,
+            linked_user_id: None,
+            creator_id: creator.to_string(),
+        };
+        match &store {
+            TestStore::Mock(mock) => mock.create_invitation(invitation).await.unwrap(),
+            TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation).await.unwrap(),
+        };
+    }
+
+    if matches!(store, TestStore::DynamoDB(_)) {
+        tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
+    }
+
+    let list = |uri: &str, user_id: &str| {
+        app.clone()
+            .oneshot(create_test_request("GET", uri, user_id, None))
+    };
+    let names = |json: &serde_json::Value| -> Vec<String> {
+        json.as_array()
+            .unwrap()
+            .iter()
+            .map(|inv| inv["invitedName"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // Only box-a's live invitations, oldest first
+    let response = list("/invitations/box/box-a", "owner-a").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        names(&response_to_json(response).await),
+        ["First", "Second"]
+    );
+
+    // The expired one comes back when asked for
+    let response = list("/invitations/box/box-a?includeExpired=true", "owner-a")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        names(&response_to_json(response).await),
+        ["Expired", "First", "Second"]
+    );
+
+    // Someone else's box is off limits
+    let response = list("/invitations/box/box-b", "owner-a").await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+// Helper to store a fresh invitation created by `creator_id` and return its id
+async fn seed_invitation(store: &TestStore, creator_id: &str) -> String {
+    seed_invitation_expiring(store, creator_id, Utc::now() + Duration::hours(48)).await
+}
+
+async fn seed_invitation_expiring(
+    store: &TestStore,
+    creator_id: &str,
+    expires_at: DateTime<Utc>,
+) -> String {
+    let now = Utc::now();
+    let invitation = Invitation {
+        id: Uuid::new_v4().to_string(),
+        invite_code: Uuid::new_v4().to_string()[..8].to_uppercase(),
+        invited_name: "Revokable".to_string(),
+        box_id: "box-123".to_string(),
+        created_at: now.to_rfc3339(),
+        expires_at: expires_at.to_rfc3339(),
+        opened: false,
+        status: InvitationStatus::Invited,
+        linked_user_id: None,
+        creator_id: creator_id.to_string(),
+    };
+    let id = invitation.id.clone();
+    match store {
+        TestStore::Mock(mock) => mock.create_invitation(invitation).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation).await.unwrap(),
+    };
+    id
+}
+
+#[tokio::test]
+async fn test_revoke_invitation() {
+    let (app, store) = create_test_app().await;
+    let id = seed_invitation(&store, "test-user-id").await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "DELETE",
+            &format!("/invitations/{}", id),
+            "test-user-id",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_resp = response_to_json(response).await;
+    assert_eq!(json_resp["boxId"], "box-123");
+
+    let lookup = match &store {
+        TestStore::Mock(mock) => mock.get_invitation(&id).await,
+        TestStore::DynamoDB(dynamo) => dynamo.get_invitation(&id).await,
+    };
+    assert!(lookup.is_err());
+}
+
+#[tokio::test]
+async fn test_revoke_invitation_not_creator() {
+    let (app, store) = create_test_app().await;
+    let id = seed_invitation(&store, "test-user-id").await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "DELETE",
+            &format!("/invitations/{}", id),
+            "someone-else",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Still there
+    let lookup = match &store {
+        TestStore::Mock(mock) => mock.get_invitation(&id).await,
+        TestStore::DynamoDB(dynamo) => dynamo.get_invitation(&id).await,
+    };
+    assert!(lookup.is_ok());
+}
+
+#[tokio::test]
+async fn test_revoke_used_invitation_conflicts() {
+    let (app, store) = create_test_app().await;
+    let id = seed_invitation(&store, "test-user-id").await;
+
+    // The invitee has already accepted
+    let mut invitation = match &store {
+        TestStore::Mock(mock) => mock.get_invitation(&id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_invitation(&id).await.unwrap(),
+    };
+    invitation.status = InvitationStatus::Accepted;
+    invitation.linked_user_id = Some("guardian-user".to_string());
+    match &store {
+        TestStore::Mock(mock) => mock.update_invitation(invitation).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_invitation(invitation).await.unwrap(),
+    };
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "DELETE",
+            &format!("/invitations/{}", id),
+            "test-user-id",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let lookup = match &store {
+        TestStore::Mock(mock) => mock.get_invitation(&id).await,
+        TestStore::DynamoDB(dynamo) => dynamo.get_invitation(&id).await,
+    };
+    assert!(lookup.is_ok());
+}
+
+#[tokio::test]
+async fn test_revoke_invitation_not_found() {
+    let (app, _store) = create_test_app().await;
+
+    let response = app
+        .oneshot(create_test_request(
+            "DELETE",
+            &format!("/invitations/{}", Uuid::new_v4()),
+            "test-user-id",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_handle_invitation_ambiguous_code() {
+    init_test_logging();
+    env::set_var("TEST_SNS", "true");
+
+    // Duplicate codes can only come from data written before codes were checked,
+    // so seed them directly into the mock
+    let now = Utc::now();
+    let invitation = |id: &str| Invitation {
+        id: id.to_string(),
+        invite_code: "DUPECODE".to_string(),
+        invited_name: "Duplicate".to_string(),
+        box_id: "box-123".to_string(),
+        created_at: now.to_rfc3339(),
+        expires_at: (now + Duration::hours(2)).to_rfc3339(),
+        opened: false,
+        status: InvitationStatus::Invited,
+        linked_user_id: None,
+        creator_id: "creator-id".to_string(),
+    };
+    let store = Arc::new(MockInvitationStore::with_data(vec![
+        invitation("dupe-1"),
+        invitation("dupe-2"),
+    ]));
+    let app = create_router_with_rate_limiter(
+        store,
+        test_box_store(),
+        "",
+        Arc::new(RateLimiter::from_env()),
+    );
+
+    let response = app
+        .oneshot(create_test_request(
+            "PUT",
+            "/invitations/handle",
+            "user-456",
+            Some(json!({ "userId": "user-456", "inviteCode": "DUPECODE" })),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_create_invitation_adds_guardian_placeholder() {
+    init_test_logging();
+
+    let store = Arc::new(MockInvitationStore::new());
+    let box_store = Arc::new(MockBoxStore::with_data(vec![owned_box(
+        "box-123",
+        "test-user-id",
+    )]));
+    let app = create_router_with_rate_limiter(
+        store.clone(),
+        box_store.clone(),
+        "",
+        Arc::new(RateLimiter::from_env()),
+    );
+
+    let response = app
+        .oneshot(create_test_request(
+            "POST",
+            "/invitations/new",
+            "test-user-id",
+            Some(json!({ "invitedName": "Placeholder", "boxId": "box-123" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let invitation_id = response_to_json(response).await["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // The guardian stands in for the invitee until they accept
+    let box_record = box_store.get_box("box-123").await.unwrap();
+    assert_eq!(box_record.guardians.len(), 1);
+    let guardian = &box_record.guardians[0];
+    assert_eq!(guardian.invitation_id, invitation_id);
+    assert_eq!(guardian.name, "Placeholder");
+    assert_eq!(guardian.status, GuardianStatus::Invited);
+    assert!(store.get_invitation(&invitation_id).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_create_invitation_rolls_back_when_box_write_fails() {
+    init_test_logging();
+
+    // Every attempt at the box write, the second item, loses to another writer
+    let store = Arc::new(MockInvitationStore::new());
+    let box_store = Arc::new(
+        MockBoxStore::with_data(vec![owned_box("box-123", "test-user-id")])
+            .with_concurrent_writes(DEFAULT_UPDATE_ATTEMPTS),
+    );
+    let app = create_router_with_rate_limiter(
+        store.clone(),
+        box_store.clone(),
+        "",
+        Arc::new(RateLimiter::from_env()),
+    );
+
+    let response = app
+        .oneshot(create_test_request(
+            "POST",
+            "/invitations/new",
+            "test-user-id",
+            Some(json!({ "invitedName": "Rolled Back", "boxId": "box-123" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // No invitation survives without its guardian, and vice versa
+    assert!(store
+        .get_invitations_by_box_id("box-123")
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(box_store
+        .get_box("box-123")
+        .await
+        .unwrap()
+        .guardians
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_invitation_event_carries_generated_request_id() {
+    use axum::{body::Body, middleware, routing::get, Json};
+    use lockbox_shared::correlation::{correlation_id_middleware, REQUEST_ID_HEADER};
+    use lockbox_shared::models::events::InvitationEvent;
+
+    // Stand-in route that returns the payload it would publish to SNS
+    let app = Router::new()
+        .route(
+            "/event",
+            get(|| async {
+                let now = now_str();
+                let invitation = Invitation {
+                    id: "inv-1".to_string(),
+                    invite_code: "CODE1234".to_string(),
+                    invited_name: "Guardian".to_string(),
+                    box_id: "box-123".to_string(),
+                    created_at: now.clone(),
+                    expires_at: now,
+                    opened: false,
+                    status: InvitationStatus::Invited,
+                    linked_user_id: None,
+                    creator_id: "test-user-id".to_string(),
+                };
+                Json(InvitationEvent::for_invitation(
+                    &invitation,
+                    "invitation_created",
+                ))
+            }),
+        )
+        .layer(middleware::from_fn(correlation_id_middleware));
+
+    let response = app
+        .oneshot(
+            axum::http::Request::builder()
+                .uri("/event")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let request_id = response.headers()[REQUEST_ID_HEADER]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(Uuid::parse_str(&request_id).is_ok());
+
+    let payload = response_to_json(response).await;
+    assert_eq!(payload["correlation_id"], request_id);
+}
+
+#[tokio::test]
+async fn test_responses_echo_request_id() {
+    let (app, _store) = create_test_app().await;
+
+    let mut request = create_test_request("GET", "/invitations/me", "test-user-id", None);
+    request
+        .headers_mut()
+        .insert("x-request-id", "client-req-42".parse().unwrap());
+    let response = app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-request-id"], "client-req-42");
+}
+
+#[tokio::test]
+async fn test_purge_expired_invitations() {
+    let (app, store) = create_test_app().await;
+    let expired_at = Utc::now() - Duration::hours(1);
+    seed_invitation_expiring(&store, "test-user-id", expired_at).await;
+    seed_invitation_expiring(&store, "other-user-id", expired_at).await;
+    let live_id = seed_invitation(&store, "test-user-id").await;
+
+    // Only members of the admin group may purge
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/invitations/purge-expired",
+            "test-user-id",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request_with_groups(
+            "POST",
+            "/invitations/purge-expired",
+            "admin-user-id",
+            &["admin"],
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_resp = response_to_json(response).await;
+    assert_eq!(json_resp["purged"], 2);
+
+    let remaining = match &store {
+        TestStore::Mock(mock) => mock.get_invitations_by_box_id("box-123").await,
+        TestStore::DynamoDB(dynamo) => dynamo.get_invitations_by_box_id("box-123").await,
+    }
+    .unwrap();
+    let remaining_ids: Vec<&str> = remaining.iter().map(|inv| inv.id.as_str()).collect();
+    assert_eq!(remaining_ids, vec![live_id.as_str()]);
+}
+
+#[tokio::test]
+async fn test_purge_expired_invitations_publishes_expired_events() {
+    use crate::events::EventSink;
+    use axum::Extension;
+    use lockbox_shared::models::events::InvitationEvent;
+    use std::sync::Mutex;
+
+    // Records (event type, invitation id) pairs instead of sending them anywhere
+    #[derive(Default)]
+    struct RecordingSink(Mutex<Vec<(String, String)>>);
+
+    #[async_trait::async_trait]
+    impl EventSink for RecordingSink {
+        async fn publish(&self, event: &InvitationEvent) -> crate::error::Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push((event.event_type.clone(), event.invitation_id.clone()));
+            Ok(())
+        }
+    }
+
+    let (app, store) = create_test_app().await;
+    let sink = Arc::new(RecordingSink::default());
+    let app = app.layer(Extension(sink.clone() as Arc<dyn EventSink>));
+    let expired_id =
+        seed_invitation_expiring(&store, "test-user-id", Utc::now() - Duration::hours(1)).await;
+    seed_invitation(&store, "test-user-id").await;
+
+    let response = app
+        .oneshot(create_test_request_with_groups(
+            "POST",
+            "/invitations/purge-expired",
+            "admin-user-id",
+            &["admin"],
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Only the purged invitation is announced
+    assert_eq!(
+        *sink.0.lock().unwrap(),
+        vec![("invitation_expired".to_string(), expired_id)]
+    );
+}
+
+#[tokio::test]
+async fn test_purge_expired_invitations_reports_publish_failure() {
+    use crate::error::AppError;
+    use crate::events::EventSink;
+    use axum::Extension;
+    use lockbox_shared::models::events::InvitationEvent;
+
+    struct FailingSink;
+
+    #[async_trait::async_trait]
+    impl EventSink for FailingSink {
+        async fn publish(&self, _event: &InvitationEvent) -> crate::error::Result<()> {
+            Err(AppError::InternalServerError("SNS is down".into()))
+        }
+    }
+
+    let (app, store) = create_test_app().await;
+    let app = app.layer(Extension(Arc::new(FailingSink) as Arc<dyn EventSink>));
+    let expired_id =
+        seed_invitation_expiring(&store, "test-user-id", Utc::now() - Duration::hours(1)).await;
+
+    let response = app
+        .oneshot(create_test_request_with_groups(
+            "POST",
+            "/invitations/purge-expired",
+            "admin-user-id",
+            &["admin"],
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+
+    // The purge itself went through
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_invitation(&expired_id).await,
+        TestStore::DynamoDB(dynamo) => dynamo.get_invitation(&expired_id).await,
+    };
+    assert!(matches!(
+        stored,
+        Err(lockbox_shared::error::StoreError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_internal_error_body_hides_store_detail() {
+    use crate::error::AppError;
+    use axum::response::IntoResponse;
+    use lockbox_shared::correlation::with_correlation_id;
+    use lockbox_shared::error::StoreError;
+
+    init_test_logging();
+
+    let raw = "DynamoDB query error: AccessDeniedException on invitation-table";
+    let response = with_correlation_id("req-456".to_string(), async {
+        AppError::from(StoreError::InternalError(raw.into())).into_response()
+    })
+    .await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    let json_resp = response_to_json(response).await;
+    assert_eq!(
+        json_resp,
+        json!({ "error": "internal_error", "requestId": "req-456" })
+    );
+    assert!(!json_resp.to_string().contains("invitation-table"));
+}
+
+#[tokio::test]
+async fn test_throttled_body_hides_store_detail() {
+    use crate::error::AppError;
+    use axum::response::IntoResponse;
+    use lockbox_shared::error::StoreError;
+
+    init_test_logging();
+
+    let raw = "invitation-table still throttled after 3 attempts";
+    let response = AppError::from(StoreError::Throttled(raw.into())).into_response();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()["retry-after"], "1");
+
+    let json_resp = response_to_json(response).await;
+    assert_eq!(json_resp, json!({ "error": "service unavailable" }));
+}
+
+#[tokio::test]
+async fn test_invitation_events_go_to_configured_sink() {
+    use crate::events::EventSink;
+    use axum::Extension;
+    use lockbox_shared::models::events::InvitationEvent;
+    use std::sync::Mutex;
+
+    // Records event types instead of sending them anywhere
+    #[derive(Default)]
+    struct RecordingSink(Mutex<Vec<String>>);
+
+    #[async_trait::async_trait]
+    impl EventSink for RecordingSink {
+        async fn publish(&self, event: &InvitationEvent) -> crate::error::Result<()> {
+            self.0.lock().unwrap().push(event.event_type.clone());
+            Ok(())
+        }
+    }
+
+    let (app, _store) = create_test_app().await;
+    let sink = Arc::new(RecordingSink::default());
+    let app = app.layer(Extension(sink.clone() as Arc<dyn EventSink>));
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/invitations/new",
+            "test-user-id",
+            Some(json!({ "invitedName": "Test User", "boxId": "box-123" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let invite_code = response_to_json(response).await["inviteCode"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PUT",
+            "/invitations/handle",
+            "guardian-user-id",
+            Some(json!({ "inviteCode": invite_code, "userId": "guardian-user-id" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    assert_eq!(
+        *sink.0.lock().unwrap(),
+        vec!["invitation_created", "invitation_viewed"]
+    );
+}
+
+#[tokio::test]
+async fn test_create_invitation_idempotency_key() {
+    use axum::Extension;
+    use lockbox_shared::store::memory::MemoryIdempotencyStore;
+    use lockbox_shared::store::IdempotencyStore;
+
+    let (app, store) = create_test_app().await;
+    let idempotency = Arc::new(MemoryIdempotencyStore::new());
+    let app = app.layer(Extension(idempotency.clone() as Arc<dyn IdempotencyStore>));
+
+    let create = |key: &'static str| {
+        let app = app.clone();
+        async move {
+            let mut request = create_test_request(
+                "POST",
+                "/invitations/new",
+                "test-user-id",
+                Some(json!({ "invitedName": "Test User", "boxId": "box-123" })),
+            );
+            request
+                .headers_mut()
+                .insert("idempotency-key", key.parse().unwrap());
+            let response = app.oneshot(request).await.unwrap();
+            (response.status(), response_to_json(response).await)
+        }
+    };
+
+    let (status, first) = create("retry-1").await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, second) = create("retry-1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(second, first);
+
+    let invitations = match &store {
+        TestStore::Mock(mock) => mock.get_invitations_by_box_id("box-123").await,
+        TestStore::DynamoDB(dynamo) => dynamo.get_invitations_by_box_id("box-123").await,
+    }
+    .unwrap();
+    assert_eq!(invitations.len(), 1);
+
+    let (status, other) = create("retry-2").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(other["id"], first["id"]);
+
+    // A retry racing a first request that hasn't written its invitation yet
+    idempotency
+        .claim(
+            "create_invitation#test-user-id#retry-pending",
+            "invitation-not-written-yet",
+            std::time::Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+    let (status, _) = create("retry-pending").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
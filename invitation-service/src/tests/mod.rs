@@ -1 +1,2 @@
+pub mod event_sink_test;
 pub mod invitation_handlers_test;
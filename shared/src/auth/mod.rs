@@ -1,12 +1,26 @@
-use axum::{extract::Request, middleware::Next, response::Response};
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::{request::Parts, StatusCode},
+    middleware::Next,
+    response::Response,
+};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::error::{Result, StoreError};
 
+// Default tolerance for clock skew between Cognito and the Lambda host
+const DEFAULT_JWT_LEEWAY_SECS: u64 = 60;
+
+// Claims masked in logs unless LOG_PII=true, and what they are replaced with
+const PII_CLAIMS: &[&str] = &["email", "cognito:username"];
+const REDACTED: &str = "[redacted]";
+
 // JWT claims structure - combines both services' implementations
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     #[serde(
@@ -37,6 +51,52 @@ pub struct Claims {
     pub jti: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub email: Option<String>,
+    #[serde(
+        rename = "cognito:groups",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cognito_groups: Option<Vec<String>>,
+}
+
+/// Extractor for the authenticated user's full JWT claims, as stored by
+/// `auth_middleware`. Rejects with 401 on routes the middleware doesn't cover.
+#[derive(Debug, Clone)]
+pub struct AuthClaims(pub Claims);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuthClaims {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Claims>()
+            .cloned()
+            .map(AuthClaims)
+            .ok_or((StatusCode::UNAUTHORIZED, "Missing authentication claims"))
+    }
+}
+
+/// Display name of the authenticated user (Cognito username, falling back to
+/// email), stored in request extensions next to the user id when the token has one
+#[derive(Debug, Clone)]
+pub struct UserName(pub String);
+
+/// Cognito groups of the authenticated user, stored in request extensions
+/// next to the user id
+#[derive(Debug, Clone, Default)]
+pub struct UserGroups(pub Vec<String>);
+
+impl UserGroups {
+    /// Whether the user is in the configured admin group (`ADMIN_GROUP`, default "admin")
+    pub fn is_admin(&self) -> bool {
+        let admin_group = std::env::var("ADMIN_GROUP").unwrap_or_else(|_| "admin".to_string());
+        self.0.contains(&admin_group)
+    }
 }
 
 // JWT decoder without verification - used since API Gateway already validated the token
@@ -72,7 +132,10 @@ pub fn decode_jwt_payload(token: &str) -> Result<Claims> {
 
             // Try to parse as generic JSON to see what fields are missing
             if let Ok(value) = serde_json::from_slice::<serde_json::Value>(&payload_data) {
-                debug!("Raw JWT payload: {:?}", value);
+                debug!(
+                    "Raw JWT payload: {:?}",
+                    redact_jwt_payload(value, log_pii_enabled())
+                );
             }
 
             Err(StoreError::AuthError("Could not parse JWT claims".into()))
@@ -80,6 +143,101 @@ pub fn decode_jwt_payload(token: &str) -> Result<Claims> {
     }
 }
 
+// Whether personal claims may appear in logs as they are, opt-in via LOG_PII=true
+fn log_pii_enabled() -> bool {
+    std::env::var("LOG_PII")
+        .map(|value| value.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+// Masks the claims that identify a person (email, cognito:username) in a JWT
+// payload before it is logged, unless `log_pii` is set
+fn redact_jwt_payload(mut payload: serde_json::Value, log_pii: bool) -> serde_json::Value {
+    if log_pii {
+        return payload;
+    }
+
+    if let Some(claims) = payload.as_object_mut() {
+        for claim in PII_CLAIMS {
+            if let Some(value) = claims.get_mut(*claim) {
+                *value = serde_json::Value::String(REDACTED.to_string());
+            }
+        }
+    }
+    payload
+}
+
+// The same masking for a single claim in a log line
+fn redact_claim(value: Option<&str>) -> Option<&str> {
+    if log_pii_enabled() {
+        value
+    } else {
+        value.map(|_| REDACTED)
+    }
+}
+
+// Clock skew leeway applied to the expiry check, configurable via JWT_LEEWAY_SECS
+fn jwt_leeway_secs() -> u64 {
+    std::env::var("JWT_LEEWAY_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_JWT_LEEWAY_SECS)
+}
+
+// Returns true if the token's exp (plus leeway) is already in the past
+pub fn is_token_expired(claims: &Claims) -> bool {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before Unix epoch")
+        .as_secs();
+
+    (claims.exp as u64).saturating_add(jwt_leeway_secs()) < now
+}
+
+// Whether token_use/aud checks are enforced, opt-in via ENFORCE_ID_TOKEN=true
+pub fn id_token_enforcement_enabled() -> bool {
+    std::env::var("ENFORCE_ID_TOKEN")
+        .map(|value| value.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Fails when ENFORCE_ID_TOKEN is on but COGNITO_APP_CLIENT_ID isn't set, since every
+/// token's audience would then be compared against "" and rejected. Services call
+/// this at startup so the misconfiguration stops the deploy instead of every request.
+pub fn check_id_token_config() -> std::result::Result<(), String> {
+    let client_id = std::env::var("COGNITO_APP_CLIENT_ID").unwrap_or_default();
+    id_token_config_error(id_token_enforcement_enabled(), &client_id).map_or(Ok(()), Err)
+}
+
+fn id_token_config_error(enforced: bool, client_id: &str) -> Option<String> {
+    (enforced && client_id.trim().is_empty()).then(|| {
+        "ENFORCE_ID_TOKEN is on but COGNITO_APP_CLIENT_ID is not set; every token would be rejected"
+            .to_string()
+    })
+}
+
+// Checks that the claims belong to a Cognito id token issued for our app client
+pub fn validate_id_token(claims: &Claims, client_id: &str) -> Result<()> {
+    match claims.token_use.as_deref() {
+        Some("id") => {}
+        Some(other) => {
+            return Err(StoreError::AuthError(format!(
+                "Invalid token_use '{}', expected 'id'",
+                other
+            )))
+        }
+        None => return Err(StoreError::AuthError("Missing token_use claim".into())),
+    }
+
+    if claims.aud != client_id {
+        return Err(StoreError::AuthError(
+            "Token audience does not match app client id".into(),
+        ));
+    }
+
+    Ok(())
+}
+
 // Auth middleware for both services
 pub async fn auth_middleware(mut request: Request, next: Next) -> Response {
     // Allow only health checks without authentication
@@ -147,12 +305,47 @@ pub async fn auth_middleware(mut request: Request, next: Next) -> Response {
         }
     };
 
-    let user_id = claims.sub;
+    if is_token_expired(&claims) {
+        warn!(
+            "Rejecting expired JWT: sub={}, exp={}",
+            claims.sub, claims.exp
+        );
+        return Response::builder()
+            .status(http::StatusCode::UNAUTHORIZED)
+            .body(axum::body::Body::from("Token expired"))
+            .unwrap();
+    }
+
+    if id_token_enforcement_enabled() {
+        let client_id = std::env::var("COGNITO_APP_CLIENT_ID").unwrap_or_default();
+        if let Err(StoreError::AuthError(msg)) = validate_id_token(&claims, &client_id) {
+            warn!("Rejecting JWT for sub={}: {}", claims.sub, msg);
+            return Response::builder()
+                .status(http::StatusCode::UNAUTHORIZED)
+                .body(axum::body::Body::from(msg))
+                .unwrap();
+        }
+    }
+
+    let user_id = claims.sub.clone();
     info!("Authenticated user ID: {}", user_id);
-    info!("JWT claims: sub={}, email={:?}, aud={}", user_id, claims.email, claims.aud);
+    info!(
+        "JWT claims: sub={}, email={:?}, aud={}",
+        user_id,
+        redact_claim(claims.email.as_deref()),
+        claims.aud
+    );
 
     // Store the user_id in the request extensions for later retrieval
     request.extensions_mut().insert(user_id.clone());
+    request.extensions_mut().insert(UserGroups(
+        claims.cognito_groups.clone().unwrap_or_default(),
+    ));
+    if let Some(name) = claims.cognito_username.clone().or(claims.email.clone()) {
+        request.extensions_mut().insert(UserName(name));
+    }
+    // The full claims too, for handlers that need more than the id (see AuthClaims)
+    request.extensions_mut().insert(claims);
     info!("Stored user_id in request extensions: {}", user_id);
 
     // Continue to the handler
@@ -165,17 +358,54 @@ pub async fn auth_middleware(mut request: Request, next: Next) -> Response {
 
 // Helper function to get the auth headers for testing
 pub fn create_jwt_token(user_id: &str) -> String {
-    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
-    use std::time::{SystemTime, UNIX_EPOCH};
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before Unix epoch")
+        .as_secs() as usize;
 
+    create_jwt_token_with_exp(user_id, now + 3600) // 1 hour in the future
+}
+
+// Helper function to create a test token with an explicit exp, e.g. one in the past
+pub fn create_jwt_token_with_exp(user_id: &str, exp: usize) -> String {
+    encode_test_claims(&test_claims(user_id, exp))
+}
+
+// Helper function to create a test token for a user in the given Cognito groups
+pub fn create_jwt_token_with_groups(user_id: &str, groups: &[&str]) -> String {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("System time is before Unix epoch")
         .as_secs() as usize;
 
-    let exp = now + 3600; // 1 hour in the future
+    let mut claims = test_claims(user_id, now + 3600);
+    claims.cognito_groups = Some(groups.iter().map(|group| group.to_string()).collect());
+    encode_test_claims(&claims)
+}
+
+fn encode_test_claims(claims: &Claims) -> String {
+    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+
+    // Create JWT header
+    let header = Header::new(Algorithm::HS256);
+
+    // In a real scenario, Cognito would use RS256 with a proper key pair
+    // For testing purposes, we use HS256 with a simple secret
+    let secret = "test_secret_key_for_jwt_encoding_in_tests";
+    let encoding_key = EncodingKey::from_secret(secret.as_bytes());
+
+    // Generate the JWT
+    encode(&header, claims, &encoding_key).expect("Failed to create JWT")
+}
 
-    let claims = Claims {
+// Cognito-shaped id token claims shared by the test token helpers
+pub(crate) fn test_claims(user_id: &str, exp: usize) -> Claims {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before Unix epoch")
+        .as_secs() as usize;
+
+    Claims {
         sub: user_id.to_string(),
         email_verified: Some(true),
         iss: "https://cognito-idp.eu-west-2.amazonaws.com/eu-west-2_rdkfPgGg4".to_string(),
@@ -189,18 +419,8 @@ pub fn create_jwt_token(user_id: &str) -> String {
         iat: now - 100,
         jti: Some("021ba19b-7fce-4bc0-b246-852346c43d4e".to_string()),
         email: Some("test@example.com".to_string()),
-    };
-
-    // Create JWT header
-    let header = Header::new(Algorithm::HS256);
-
-    // In a real scenario, Cognito would use RS256 with a proper key pair
-    // For testing purposes, we use HS256 with a simple secret
-    let secret = "test_secret_key_for_jwt_encoding_in_tests";
-    let encoding_key = EncodingKey::from_secret(secret.as_bytes());
-
-    // Generate the JWT
-    encode(&header, &claims, &encoding_key).expect("Failed to create JWT")
+        cognito_groups: None,
+    }
 }
 
 /// Helper function to create an authorization header with a bearer token for tests
@@ -215,11 +435,35 @@ pub fn create_test_request(
     path: &str,
     user_id: &str,
     body: Option<serde_json::Value>,
+) -> http::Request<axum::body::Body> {
+    let (auth_key, auth_value) = create_auth_header(user_id);
+    build_test_request(method, path, (auth_key, auth_value), body)
+}
+
+/// Helper function to create a test request for a user in the given Cognito groups
+pub fn create_test_request_with_groups(
+    method: &str,
+    path: &str,
+    user_id: &str,
+    groups: &[&str],
+    body: Option<serde_json::Value>,
+) -> http::Request<axum::body::Body> {
+    let auth_header = (
+        "authorization".to_string(),
+        format!("Bearer {}", create_jwt_token_with_groups(user_id, groups)),
+    );
+    build_test_request(method, path, auth_header, body)
+}
+
+fn build_test_request(
+    method: &str,
+    path: &str,
+    (auth_key, auth_value): (String, String),
+    body: Option<serde_json::Value>,
 ) -> http::Request<axum::body::Body> {
     let mut builder = http::Request::builder().method(method).uri(path);
 
     // Add authorization header with JWT
-    let (auth_key, auth_value) = create_auth_header(user_id);
     builder = builder.header(auth_key, auth_value);
 
     // Add content type if there is a body
@@ -282,6 +526,37 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn test_auth_claims_extractor() {
+        async fn email_handler(AuthClaims(claims): AuthClaims) -> String {
+            claims.email.unwrap_or_default()
+        }
+
+        let app = Router::new()
+            .route("/", get(email_handler))
+            .layer(axum::middleware::from_fn(auth_middleware));
+
+        let response = app
+            .oneshot(
+                HttpRequest::builder()
+                    .uri("/")
+                    .header(
+                        "authorization",
+                        format!("Bearer {}", create_jwt_token("claims-user")),
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"test@example.com");
+    }
+
     #[tokio::test]
     async fn test_auth_middleware_missing_header() {
         // Arrange: Router with middleware
@@ -319,4 +594,149 @@ mod tests {
         // Assert: Middleware returns unauthorized
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
+
+    fn unix_now() -> usize {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as usize
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_expired_token() {
+        // Arrange: Router with middleware
+        let app = Router::new()
+            .route("/", get(check_user_id_handler))
+            .layer(axum::middleware::from_fn(auth_middleware));
+
+        // Token expired an hour ago, well outside the default leeway
+        let token =
+            create_jwt_token_with_exp("56a20244-0061-708a-0441-62c42ace7b39", unix_now() - 3600);
+
+        let request = HttpRequest::builder()
+            .uri("/")
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+
+        // Act: Call the middleware
+        let response = app.oneshot(request).await.unwrap();
+
+        // Assert: Middleware rejects the stale token
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"Token expired");
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_expired_token_within_leeway() {
+        // Arrange: Router with middleware
+        let app = Router::new()
+            .route("/", get(check_user_id_handler))
+            .layer(axum::middleware::from_fn(auth_middleware));
+
+        // Token expired a few seconds ago, inside the default 60s leeway
+        let token =
+            create_jwt_token_with_exp("56a20244-0061-708a-0441-62c42ace7b39", unix_now() - 10);
+
+        let request = HttpRequest::builder()
+            .uri("/")
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+
+        // Act: Call the middleware
+        let response = app.oneshot(request).await.unwrap();
+
+        // Assert: Clock skew is tolerated
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_rs256_token_verifies_against_test_jwks() {
+        use crate::test_utils::jwt_test_utils::{
+            create_rs256_jwt_token, test_jwks, test_rs256_encoding_key, TEST_JWK_KID,
+        };
+        use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+
+        let token = create_rs256_jwt_token(
+            "56a20244-0061-708a-0441-62c42ace7b39",
+            &test_rs256_encoding_key(),
+            TEST_JWK_KID,
+        );
+
+        // Look the signing key up by kid, the way a JWKS verifier would
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.alg, Algorithm::RS256);
+        let jwks = test_jwks();
+        let jwk = jwks.find(header.kid.as_deref().unwrap()).unwrap();
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&["5pgt5gkfulqs0tkdi279c895gp"]);
+        let decoded =
+            decode::<Claims>(&token, &DecodingKey::from_jwk(jwk).unwrap(), &validation).unwrap();
+        assert_eq!(decoded.claims.sub, "56a20244-0061-708a-0441-62c42ace7b39");
+
+        // The unverified decoder used by the middleware reads it too
+        assert_eq!(
+            decode_jwt_payload(&token).unwrap().sub,
+            "56a20244-0061-708a-0441-62c42ace7b39"
+        );
+    }
+
+    #[test]
+    fn test_validate_id_token_matching_audience() {
+        let token = create_jwt_token("56a20244-0061-708a-0441-62c42ace7b39");
+        let claims = decode_jwt_payload(&token).unwrap();
+
+        assert!(validate_id_token(&claims, "5pgt5gkfulqs0tkdi279c895gp").is_ok());
+    }
+
+    #[test]
+    fn test_validate_id_token_mismatched_audience() {
+        let token = create_jwt_token("56a20244-0061-708a-0441-62c42ace7b39");
+        let claims = decode_jwt_payload(&token).unwrap();
+
+        let result = validate_id_token(&claims, "some-other-client-id");
+        assert!(matches!(result, Err(StoreError::AuthError(_))));
+    }
+
+    #[test]
+    fn test_validate_id_token_rejects_access_token() {
+        let token = create_jwt_token("56a20244-0061-708a-0441-62c42ace7b39");
+        let mut claims = decode_jwt_payload(&token).unwrap();
+        claims.token_use = Some("access".to_string());
+
+        let result = validate_id_token(&claims, "5pgt5gkfulqs0tkdi279c895gp");
+        assert!(matches!(result, Err(StoreError::AuthError(_))));
+    }
+
+    #[test]
+    fn test_id_token_enforcement_needs_client_id() {
+        assert!(id_token_config_error(true, "").is_some());
+        assert!(id_token_config_error(true, "  ").is_some());
+        assert!(id_token_config_error(true, "5pgt5gkfulqs0tkdi279c895gp").is_none());
+        assert!(id_token_config_error(false, "").is_none());
+    }
+
+    #[test]
+    fn test_jwt_payload_pii_is_redacted() {
+        let payload = serde_json::json!({
+            "sub": "56a20244-0061-708a-0441-62c42ace7b39",
+            "email": "someone@example.com",
+            "cognito:username": "someone",
+        });
+
+        let logged = format!("{:?}", redact_jwt_payload(payload.clone(), false));
+        assert!(!logged.contains("someone@example.com"));
+        assert!(!logged.contains("\"someone\""));
+        assert!(logged.contains(REDACTED));
+        // Non-personal claims are still there to debug with
+        assert!(logged.contains("56a20244-0061-708a-0441-62c42ace7b39"));
+
+        // LOG_PII=true leaves the payload alone
+        assert_eq!(redact_jwt_payload(payload.clone(), true), payload);
+    }
 }
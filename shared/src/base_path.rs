@@ -0,0 +1,48 @@
+use log::warn;
+
+// API Gateway stage the routes are nested under unless API_BASE_PATH says otherwise
+pub const DEFAULT_API_BASE_PATH: &str = "/Prod";
+
+/// Route prefix from `API_BASE_PATH` (default `/Prod`), or empty when
+/// `REMOVE_BASE_PATH=true`
+pub fn api_base_path() -> String {
+    let remove_base_path = std::env::var("REMOVE_BASE_PATH")
+        .map(|v| v.to_lowercase() == "true")
+        .unwrap_or(false);
+
+    base_path_from(std::env::var("API_BASE_PATH").ok(), remove_base_path)
+}
+
+fn base_path_from(configured: Option<String>, remove_base_path: bool) -> String {
+    if remove_base_path {
+        return String::new();
+    }
+
+    match configured.map(|v| v.trim().to_string()) {
+        // A trailing slash would leave every route with a double slash
+        Some(path) if path.starts_with('/') => path.trim_end_matches('/').to_string(),
+        Some(path) => {
+            warn!(
+                "API_BASE_PATH {:?} doesn't start with '/', using default of {}",
+                path, DEFAULT_API_BASE_PATH
+            );
+            DEFAULT_API_BASE_PATH.to_string()
+        }
+        None => DEFAULT_API_BASE_PATH.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_path_from() {
+        assert_eq!(base_path_from(None, false), "/Prod");
+        assert_eq!(base_path_from(Some("/Staging".into()), false), "/Staging");
+        assert_eq!(base_path_from(Some("/v1/".into()), false), "/v1");
+        assert_eq!(base_path_from(Some("/".into()), false), "");
+        assert_eq!(base_path_from(Some("v1".into()), false), "/Prod");
+        assert_eq!(base_path_from(Some("/v1".into()), true), "");
+    }
+}
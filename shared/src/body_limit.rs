@@ -0,0 +1,21 @@
+use log::warn;
+
+// Request bodies over this many bytes get a 413, overridable via MAX_BODY_BYTES
+pub const DEFAULT_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+pub fn max_body_bytes() -> usize {
+    match std::env::var("MAX_BODY_BYTES")
+        .ok()
+        .map(|v| v.trim().parse::<usize>())
+    {
+        Some(Ok(limit)) if limit > 0 => limit,
+        Some(_) => {
+            warn!(
+                "Invalid MAX_BODY_BYTES value, using default of {}",
+                DEFAULT_MAX_BODY_BYTES
+            );
+            DEFAULT_MAX_BODY_BYTES
+        }
+        None => DEFAULT_MAX_BODY_BYTES,
+    }
+}
@@ -0,0 +1,128 @@
+use std::future::Future;
+use std::sync::RwLock;
+use tokio::sync::Mutex;
+
+/// A value such as the service router, built by an async constructor on first
+/// use and cloned out to every caller after that. Once built, `get_or_init` only
+/// takes a shared read lock, so concurrent requests never queue behind each
+/// other. Only the first build (or a rebuild after `reset`) is serialized.
+pub struct LazyInit<T> {
+    value: RwLock<Option<T>>,
+    // Held while building, so concurrent first callers build the value once
+    init_lock: Mutex<()>,
+}
+
+impl<T: Clone> LazyInit<T> {
+    pub const fn new() -> Self {
+        Self {
+            value: RwLock::new(None),
+            init_lock: Mutex::const_new(()),
+        }
+    }
+
+    /// Returns the value, building it with `init` if there isn't one yet
+    pub async fn get_or_init<F, Fut>(&self, init: F) -> T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        if let Some(value) = self.current() {
+            return value;
+        }
+
+        let _guard = self.init_lock.lock().await;
+        // Another caller may have finished building while we waited
+        if let Some(value) = self.current() {
+            return value;
+        }
+
+        let value = init().await;
+        *self.value.write().unwrap() = Some(value.clone());
+        value
+    }
+
+    /// Drops the current value so the next `get_or_init` builds a fresh one,
+    /// e.g. after the configuration it was built from has changed
+    pub fn reset(&self) {
+        self.value.write().unwrap().take();
+    }
+
+    fn current(&self) -> Option<T> {
+        self.value.read().unwrap().clone()
+    }
+}
+
+impl<T: Clone> Default for LazyInit<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_first_calls_build_once() {
+        let cell = Arc::new(LazyInit::<usize>::new());
+        let builds = Arc::new(AtomicUsize::new(0));
+
+        let callers: Vec<_> = (0..8)
+            .map(|_| {
+                let cell = cell.clone();
+                let builds = builds.clone();
+                tokio::spawn(async move {
+                    cell.get_or_init(|| async move {
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                        builds.fetch_add(1, Ordering::SeqCst) + 1
+                    })
+                    .await
+                })
+            })
+            .collect();
+
+        for caller in callers {
+            assert_eq!(caller.await.unwrap(), 1);
+        }
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reads_do_not_wait_on_the_init_lock() {
+        let cell = Arc::new(LazyInit::new());
+        cell.get_or_init(|| async { "router" }).await;
+
+        // Hold the build lock as a slow rebuild would; readers must not queue behind it
+        let _building = cell.init_lock.lock().await;
+        let readers: Vec<_> = (0..16)
+            .map(|_| {
+                let cell = cell.clone();
+                tokio::spawn(async move {
+                    cell.get_or_init(|| async { unreachable!("already built") })
+                        .await
+                })
+            })
+            .collect();
+
+        for reader in readers {
+            let value = tokio::time::timeout(Duration::from_secs(1), reader)
+                .await
+                .expect("reads should not wait for the init lock")
+                .unwrap();
+            assert_eq!(value, "router");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reset_rebuilds_on_next_use() {
+        let cell = LazyInit::new();
+        assert_eq!(cell.get_or_init(|| async { 1 }).await, 1);
+        assert_eq!(cell.get_or_init(|| async { 2 }).await, 1);
+
+        cell.reset();
+        assert_eq!(cell.get_or_init(|| async { 2 }).await, 2);
+    }
+}
@@ -0,0 +1,240 @@
+use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+use std::future::Future;
+use std::io::Write;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// Longer or oddly formed client ids are replaced rather than echoed into logs
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Correlation id of the current request, available as a request extension
+#[derive(Clone, Debug, PartialEq)]
+pub struct CorrelationId(pub String);
+
+tokio::task_local! {
+    static CURRENT_CORRELATION_ID: String;
+}
+
+/// Correlation id of the request or event being handled on this task, if any
+pub fn current_correlation_id() -> Option<String> {
+    CURRENT_CORRELATION_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Runs `fut` with `id` as the current correlation id
+pub async fn with_correlation_id<F: Future>(id: String, fut: F) -> F::Output {
+    CURRENT_CORRELATION_ID.scope(id, fut).await
+}
+
+/// Takes the caller's X-Request-Id, or generates one, and makes it the
+/// correlation id for everything the request does. The id is echoed back in
+/// the response's X-Request-Id header.
+pub async fn correlation_id_middleware(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    req.extensions_mut().insert(CorrelationId(id.clone()));
+    let mut response = with_correlation_id(id.clone(), next.run(req)).await;
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LENGTH
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// Shape of each log line, picked by LOG_FORMAT
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    /// `[timestamp level target request_id=...] message`, the default
+    Text,
+    /// One JSON object per line, for log ingestion
+    Json,
+}
+
+impl LogFormat {
+    /// `json` (any case) selects JSON; anything else, or no value, is text
+    pub fn parse(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            Some("json") => LogFormat::Json,
+            _ => LogFormat::Text,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::parse(std::env::var("LOG_FORMAT").ok().as_deref())
+    }
+}
+
+/// env_logger builder (RUST_LOG, default info) whose lines carry the current
+/// correlation id, so one request can be followed across services. Lines are
+/// JSON when LOG_FORMAT=json and text otherwise.
+pub fn log_builder() -> env_logger::Builder {
+    log_builder_with_format(LogFormat::from_env())
+}
+
+pub fn log_builder_with_format(format: LogFormat) -> env_logger::Builder {
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let line = json_log_line(
+                &buf.timestamp().to_string(),
+                record.level(),
+                record.target(),
+                current_correlation_id().as_deref(),
+                &record.args().to_string(),
+            );
+            writeln!(buf, "{}", line)
+        });
+        return builder;
+    }
+
+    builder.format(|buf, record| {
+        let timestamp = buf.timestamp();
+        match current_correlation_id() {
+            Some(id) => writeln!(
+                buf,
+                "[{} {:<5} {} request_id={}] {}",
+                timestamp,
+                record.level(),
+                record.target(),
+                id,
+                record.args()
+            ),
+            None => writeln!(
+                buf,
+                "[{} {:<5} {}] {}",
+                timestamp,
+                record.level(),
+                record.target(),
+                record.args()
+            ),
+        }
+    });
+    builder
+}
+
+// A log line as a JSON object; requestId is left out outside a request
+fn json_log_line(
+    timestamp: &str,
+    level: log::Level,
+    target: &str,
+    request_id: Option<&str>,
+    message: &str,
+) -> String {
+    let mut line = serde_json::json!({
+        "timestamp": timestamp,
+        "level": level.as_str(),
+        "target": target,
+        "message": message,
+    });
+    if let Some(id) = request_id {
+        line["requestId"] = serde_json::json!(id);
+    }
+    line.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::util::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|| async { current_correlation_id().unwrap_or_default() }),
+            )
+            .layer(middleware::from_fn(correlation_id_middleware))
+    }
+
+    async fn call(request_id: Option<&str>) -> (String, String) {
+        let mut request = axum::http::Request::builder().uri("/");
+        if let Some(id) = request_id {
+            request = request.header(REQUEST_ID_HEADER, id);
+        }
+        let response = app()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let header = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (header, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_correlation_id_reuses_request_id() {
+        let (header, seen_by_handler) = call(Some("req-123")).await;
+        assert_eq!(header, "req-123");
+        assert_eq!(seen_by_handler, "req-123");
+    }
+
+    #[tokio::test]
+    async fn test_correlation_id_generated_when_missing_or_invalid() {
+        for request_id in [None, Some("bad id\twith spaces")] {
+            let (header, seen_by_handler) = call(request_id).await;
+            assert!(Uuid::parse_str(&header).is_ok(), "{}", header);
+            assert_eq!(seen_by_handler, header);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_no_correlation_id_outside_a_request() {
+        assert_eq!(current_correlation_id(), None);
+        let inside =
+            with_correlation_id("event-1".to_string(), async { current_correlation_id() }).await;
+        assert_eq!(inside.as_deref(), Some("event-1"));
+    }
+
+    #[test]
+    fn test_log_format_selects_json() {
+        assert_eq!(LogFormat::parse(Some("json")), LogFormat::Json);
+        assert_eq!(LogFormat::parse(Some(" JSON ")), LogFormat::Json);
+        assert_eq!(LogFormat::parse(Some("text")), LogFormat::Text);
+        assert_eq!(LogFormat::parse(Some("yaml")), LogFormat::Text);
+        assert_eq!(LogFormat::parse(None), LogFormat::Text);
+    }
+
+    #[test]
+    fn test_json_log_line() {
+        let line = json_log_line(
+            "2024-01-01T00:00:00Z",
+            log::Level::Warn,
+            "lockbox_box_service",
+            Some("req-123"),
+            "said \"hi\"\non two lines",
+        );
+        assert!(!line.contains('\n'));
+
+        let parsed: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed["level"], "WARN");
+        assert_eq!(parsed["target"], "lockbox_box_service");
+        assert_eq!(parsed["requestId"], "req-123");
+        assert_eq!(parsed["message"], "said \"hi\"\non two lines");
+
+        let line = json_log_line("t", log::Level::Info, "x", None, "m");
+        let parsed: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert!(parsed.get("requestId").is_none());
+    }
+}
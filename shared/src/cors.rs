@@ -0,0 +1,163 @@
+use http::{header, HeaderName, HeaderValue, Method};
+use log::{info, warn};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, Any, CorsLayer};
+
+/// Builds the CORS layer from CORS_ALLOWED_ORIGINS, CORS_ALLOWED_METHODS and
+/// CORS_ALLOWED_HEADERS (each comma-separated). Unset variables allow anything.
+pub fn cors_layer_from_env() -> CorsLayer {
+    let origins = std::env::var("CORS_ALLOWED_ORIGINS").ok();
+    let methods = std::env::var("CORS_ALLOWED_METHODS").ok();
+    let headers = std::env::var("CORS_ALLOWED_HEADERS").ok();
+
+    cors_layer(origins.as_deref(), methods.as_deref(), headers.as_deref())
+}
+
+/// Builds a CORS layer from comma-separated allow-lists, where None means any.
+/// Entries that aren't valid header values are skipped with a warning.
+pub fn cors_layer(
+    origins: Option<&str>,
+    methods: Option<&str>,
+    headers: Option<&str>,
+) -> CorsLayer {
+    let allow_origin = match origins {
+        Some(origins) => {
+            let origins: Vec<HeaderValue> = parse_list(origins, "origin", |o| o.parse().ok());
+            info!("CORS restricted to origins: {:?}", origins);
+            AllowOrigin::list(origins)
+        }
+        None => {
+            warn!("CORS_ALLOWED_ORIGINS is not set, allowing all origins");
+            AllowOrigin::from(Any)
+        }
+    };
+
+    let allow_methods = match methods {
+        Some(methods) => AllowMethods::list(parse_list(methods, "method", |m| {
+            Method::from_bytes(m.to_uppercase().as_bytes()).ok()
+        })),
+        None => AllowMethods::from(Any),
+    };
+
+    let allow_headers = match headers {
+        Some(headers) => AllowHeaders::list(parse_list(headers, "header", |h| h.parse().ok())),
+        None => AllowHeaders::from(Any),
+    };
+
+    // Browsers hide ETag from scripts unless exposed, which would defeat
+    // If-Match; the request id is exposed so clients can quote it in reports,
+    // and the next-page cursor so bare-array listings can be paged
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(allow_methods)
+        .allow_headers(allow_headers)
+        .expose_headers([
+            header::ETAG,
+            HeaderName::from_static(crate::correlation::REQUEST_ID_HEADER),
+            HeaderName::from_static(crate::extract::NEXT_CURSOR_HEADER),
+        ])
+}
+
+fn parse_list<T>(list: &str, kind: &str, parse: impl Fn(&str) -> Option<T>) -> Vec<T> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = parse(entry);
+            if parsed.is_none() {
+                warn!("Ignoring invalid CORS {}: {}", kind, entry);
+            }
+            parsed
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use tower::util::ServiceExt;
+
+    fn app(origins: Option<&str>) -> Router {
+        Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(cors_layer(
+                origins,
+                Some("GET, post"),
+                Some("authorization"),
+            ))
+    }
+
+    async fn allowed_origin(app: Router, origin: &str) -> Option<String> {
+        let request = Request::builder()
+            .uri("/")
+            .header("origin", origin)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .map(|value| value.to_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_cors_reflects_only_allowed_origins() {
+        let origins = Some("https://app.example.com, https://admin.example.com");
+
+        assert_eq!(
+            allowed_origin(app(origins), "https://admin.example.com").await,
+            Some("https://admin.example.com".to_string())
+        );
+        assert_eq!(
+            allowed_origin(app(origins), "https://evil.example.com").await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cors_exposes_etag_request_id_and_next_cursor() {
+        let request = Request::builder()
+            .uri("/")
+            .header("origin", "https://app.example.com")
+            .body(Body::empty())
+            .unwrap();
+        let response = app(None).oneshot(request).await.unwrap();
+
+        assert_eq!(
+            response.headers()["access-control-expose-headers"],
+            "etag,x-request-id,x-next-cursor"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cors_allows_any_origin_when_unset() {
+        assert_eq!(
+            allowed_origin(app(None), "https://anywhere.example.com").await,
+            Some("*".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight_lists_configured_methods() {
+        let request = Request::builder()
+            .method("OPTIONS")
+            .uri("/")
+            .header("origin", "https://app.example.com")
+            .header("access-control-request-method", "POST")
+            .body(Body::empty())
+            .unwrap();
+        let response = app(Some("https://app.example.com"))
+            .oneshot(request)
+            .await
+            .unwrap();
+
+        let methods = response.headers()["access-control-allow-methods"]
+            .to_str()
+            .unwrap();
+        assert_eq!(methods, "GET,POST");
+        assert_eq!(
+            response.headers()["access-control-allow-headers"],
+            "authorization"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use log::error;
+use serde_json::{json, Value};
+
+use crate::correlation::current_correlation_id;
+use crate::error::internal_error_body;
+
+/// Media type that opts a request into the `{"data": ..., "meta": ...}` envelope
+pub const ENVELOPE_MEDIA_TYPE: &str = "application/vnd.lockbox.v2+json";
+
+/// Wraps JSON responses in `{"data": ..., "meta": {...}}` when the request's
+/// Accept header asks for ENVELOPE_MEDIA_TYPE. `data` is the body the endpoint
+/// would otherwise return. For error responses `data` is null and that body
+/// goes under `error` instead. Without the header, and for non-JSON responses,
+/// the response is passed through untouched.
+pub async fn response_envelope_middleware(req: Request, next: Next) -> Response {
+    let wants_envelope = accepts_envelope(req.headers().get(header::ACCEPT));
+    let mut response = next.run(req).await;
+
+    if !is_json(response.headers().get(header::CONTENT_TYPE)) {
+        return response;
+    }
+    // The shape of a JSON body now depends on Accept
+    response
+        .headers_mut()
+        .append(header::VARY, HeaderValue::from_static("accept"));
+    if !wants_envelope {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            error!("Failed to read response body for envelope: {:?}", err);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(internal_error_body()),
+            )
+                .into_response();
+        }
+    };
+
+    let Ok(body) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    let enveloped = envelope(parts.status, body, current_correlation_id());
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(ENVELOPE_MEDIA_TYPE),
+    );
+    Response::from_parts(parts, Body::from(enveloped.to_string()))
+}
+
+fn envelope(status: StatusCode, body: Value, request_id: Option<String>) -> Value {
+    let meta = json!({ "status": status.as_u16(), "requestId": request_id });
+    if status.is_success() {
+        json!({ "data": body, "meta": meta })
+    } else {
+        json!({ "data": null, "error": body, "meta": meta })
+    }
+}
+
+// Whether any media range in the Accept header is the envelope type
+fn accepts_envelope(accept: Option<&HeaderValue>) -> bool {
+    let Some(accept) = accept.and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    accept
+        .split(',')
+        .map(media_type)
+        .any(|mime| mime.eq_ignore_ascii_case(ENVELOPE_MEDIA_TYPE))
+}
+
+// application/json or any +json type
+fn is_json(content_type: Option<&HeaderValue>) -> bool {
+    let Some(content_type) = content_type.and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    let mime = media_type(content_type).to_ascii_lowercase();
+    mime == "application/json" || mime.ends_with("+json")
+}
+
+// The media type with any parameters (`; charset=...`, `; q=...`) stripped
+fn media_type(value: &str) -> &str {
+    value.split(';').next().unwrap_or_default().trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        middleware,
+        routing::{get, post},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/items", get(|| async { Json(json!([{ "id": "a" }])) }))
+            .route(
+                "/fail",
+                post(|| async { (StatusCode::CONFLICT, Json(json!({ "error": "taken" }))) }),
+            )
+            .route("/text", get(|| async { "plain" }))
+            .layer(middleware::from_fn(response_envelope_middleware))
+    }
+
+    async fn call(method: &str, uri: &str, accept: Option<&str>) -> (Response, Vec<u8>) {
+        let mut request = axum::http::Request::builder().method(method).uri(uri);
+        if let Some(accept) = accept {
+            request = request.header(header::ACCEPT, accept);
+        }
+        let response = app()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let (parts, body) = response.into_parts();
+        let bytes = to_bytes(body, usize::MAX).await.unwrap();
+        (Response::from_parts(parts, Body::empty()), bytes.to_vec())
+    }
+
+    #[test]
+    fn test_accepts_envelope() {
+        let accept = |value: &str| accepts_envelope(Some(&HeaderValue::from_str(value).unwrap()));
+        assert!(accept(ENVELOPE_MEDIA_TYPE));
+        assert!(accept(
+            "application/json, application/vnd.lockbox.v2+json; q=0.9"
+        ));
+        assert!(accept("Application/Vnd.Lockbox.V2+JSON"));
+        assert!(!accept("application/json"));
+        assert!(!accept("*/*"));
+        assert!(!accepts_envelope(None));
+    }
+
+    #[tokio::test]
+    async fn test_envelope_wraps_success_and_error_bodies() {
+        let (response, body) = call("GET", "/items", Some(ENVELOPE_MEDIA_TYPE)).await;
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            ENVELOPE_MEDIA_TYPE
+        );
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["data"], json!([{ "id": "a" }]));
+        assert_eq!(body["meta"]["status"], 200);
+
+        let (response, body) = call("POST", "/fail", Some(ENVELOPE_MEDIA_TYPE)).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["data"], Value::Null);
+        assert_eq!(body["error"], json!({ "error": "taken" }));
+        assert_eq!(body["meta"]["status"], 409);
+    }
+
+    #[tokio::test]
+    async fn test_envelope_leaves_other_responses_alone() {
+        let (response, body) = call("GET", "/items", None).await;
+        assert_eq!(response.headers()[header::VARY], "accept");
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body, json!([{ "id": "a" }]));
+
+        let (response, body) = call("GET", "/text", Some(ENVELOPE_MEDIA_TYPE)).await;
+        assert!(response.headers().get(header::VARY).is_none());
+        assert_eq!(body, b"plain");
+    }
+}
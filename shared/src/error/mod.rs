@@ -10,6 +10,11 @@ pub enum StoreError {
     InvitationExpired,
     AuthError(String),
     VersionConflict(String),
+    // DynamoDB kept throttling (or failing server-side) through every retry
+    Throttled(String),
+    // An invite code that matches more than one invitation; services map it
+    // to a conflict rather than a generic server error
+    AmbiguousInviteCode,
 }
 
 impl std::fmt::Display for StoreError {
@@ -21,6 +26,8 @@ impl std::fmt::Display for StoreError {
             StoreError::InvitationExpired => write!(f, "Invitation expired"),
             StoreError::AuthError(msg) => write!(f, "Authentication error: {}", msg),
             StoreError::VersionConflict(msg) => write!(f, "Version conflict: {}", msg),
+            StoreError::Throttled(msg) => write!(f, "Throttled: {}", msg),
+            StoreError::AmbiguousInviteCode => write!(f, "Ambiguous invite code"),
         }
     }
 }
@@ -47,3 +54,18 @@ impl From<serde_json::Error> for StoreError {
 pub fn map_dynamo_error(operation: &str, err: impl std::fmt::Display) -> StoreError {
     StoreError::InternalError(format!("DynamoDB {} error: {}", operation, err))
 }
+
+// 503 message for a store that stayed throttled through every retry. Like a 500,
+// the SDK detail behind it is only logged.
+pub const SERVICE_UNAVAILABLE_MESSAGE: &str = "service unavailable";
+// Retry-After sent with that 503
+pub const THROTTLED_RETRY_AFTER_SECS: u64 = 1;
+
+/// Body for every 500 response. The detail behind an internal error (SDK messages,
+/// table names) is only logged; clients get the request id to quote instead.
+pub fn internal_error_body() -> serde_json::Value {
+    serde_json::json!({
+        "error": "internal_error",
+        "requestId": crate::correlation::current_correlation_id(),
+    })
+}
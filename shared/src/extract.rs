@@ -0,0 +1,385 @@
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, FromRequestParts, Query, Request},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use log::warn;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use std::cmp::Ordering;
+use std::error::Error;
+
+/// Drop-in replacement for `axum::Json` as a request extractor. Bodies that
+/// parse but don't fit the target type get a 422 with
+/// `{"error": "validation", "detail": "<serde message>"}` instead of axum's
+/// plain-text rejection. When the target type denies unknown fields, the
+/// offending key is also reported as `"field"`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonBody<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = JsonBodyRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        Json::<T>::from_request(req, state)
+            .await
+            .map(|Json(value)| JsonBody(value))
+            .map_err(JsonBodyRejection)
+    }
+}
+
+/// Rejection for `JsonBody`, rendered as a JSON error body
+#[derive(Debug)]
+pub struct JsonBodyRejection(pub JsonRejection);
+
+impl IntoResponse for JsonBodyRejection {
+    fn into_response(self) -> Response {
+        let rejection = self.0;
+        let body = match &rejection {
+            JsonRejection::JsonDataError(err) => {
+                let detail = serde_detail(err);
+                warn!("Rejecting request body: {}", detail);
+                let mut body = json!({ "error": "validation", "detail": detail });
+                if let Some(field) = unknown_field(&detail) {
+                    body["field"] = json!(field);
+                }
+                return (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response();
+            }
+            other => json!({ "error": other.body_text() }),
+        };
+
+        warn!("Rejecting request body: {}", rejection.body_text());
+        (rejection.status(), Json(body)).into_response()
+    }
+}
+
+// The innermost error is serde's own message, e.g. "missing field `guardian`";
+// the line/column suffix means nothing to API clients so it's dropped
+fn serde_detail(err: &dyn Error) -> String {
+    let mut innermost = err;
+    while let Some(source) = innermost.source() {
+        innermost = source;
+    }
+
+    let message = innermost.to_string();
+    match message.rfind(" at line ") {
+        Some(position) => message[..position].to_string(),
+        None => message,
+    }
+}
+
+// serde quotes the key it didn't expect, e.g. "unknown field `descripton`, expected ...",
+// and axum puts the key's path in front of that ("guardian.nmae: unknown field ...")
+fn unknown_field(detail: &str) -> Option<&str> {
+    match detail.split_once(": unknown field `") {
+        Some((path, _)) => Some(path),
+        None => detail
+            .strip_prefix("unknown field `")
+            .and_then(|rest| rest.split('`').next()),
+    }
+}
+
+/// Largest page size a list endpoint will return
+pub const MAX_LIST_LIMIT: u32 = 100;
+
+/// Response header carrying the cursor for the next page, on list endpoints
+/// whose body is a bare array
+pub const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+/// Sort direction for list endpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl Order {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "asc" => Some(Self::Asc),
+            "desc" => Some(Self::Desc),
+            _ => None,
+        }
+    }
+
+    /// Applies the direction to an ascending comparison
+    pub fn apply(self, ordering: Ordering) -> Ordering {
+        match self {
+            Self::Asc => ordering,
+            Self::Desc => ordering.reverse(),
+        }
+    }
+}
+
+/// Query parameters shared by the list endpoints:
+/// `?limit=&cursor=&sort=&order=`. `limit` must be 1-100 and `order` asc or
+/// desc, otherwise the request gets a 422 with
+/// `{"error": "validation", "field": "<param>", "detail": "..."}`. `sort` is
+/// only passed through, since each endpoint knows which fields it can sort by.
+/// Other query parameters are ignored, so endpoint-specific filters can be
+/// read with a separate `Query`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListParams {
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+    pub sort: Option<String>,
+    pub order: Order,
+}
+
+impl ListParams {
+    /// Whether the caller asked for a page rather than the whole list
+    pub fn is_paginated(&self) -> bool {
+        self.limit.is_some() || self.cursor.is_some()
+    }
+}
+
+// Everything arrives as a string so bad values get our messages, not serde's
+#[derive(Deserialize, Default)]
+struct RawListParams {
+    limit: Option<String>,
+    cursor: Option<String>,
+    sort: Option<String>,
+    order: Option<String>,
+}
+
+impl TryFrom<RawListParams> for ListParams {
+    type Error = ListParamsRejection;
+
+    fn try_from(raw: RawListParams) -> Result<Self, Self::Error> {
+        let limit = raw
+            .limit
+            .map(|limit| {
+                limit
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|limit| (1..=MAX_LIST_LIMIT).contains(limit))
+                    .ok_or_else(|| {
+                        ListParamsRejection::new(
+                            Some("limit"),
+                            format!(
+                                "limit must be a whole number between 1 and {}, got {}",
+                                MAX_LIST_LIMIT, limit
+                            ),
+                        )
+                    })
+            })
+            .transpose()?;
+
+        let order = match raw.order {
+            Some(order) => Order::parse(&order).ok_or_else(|| {
+                ListParamsRejection::new(
+                    Some("order"),
+                    format!("order must be asc or desc, got {}", order),
+                )
+            })?,
+            None => Order::default(),
+        };
+
+        Ok(Self {
+            limit,
+            cursor: raw.cursor,
+            sort: raw.sort,
+            order,
+        })
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ListParams
+where
+    S: Send + Sync,
+{
+    type Rejection = ListParamsRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // Only fails on a query string that isn't valid urlencoding, or a repeated key
+        let Query(raw) = Query::<RawListParams>::try_from_uri(&parts.uri)
+            .map_err(|rejection| ListParamsRejection::new(None, rejection.body_text()))?;
+        raw.try_into()
+    }
+}
+
+/// Rejection for `ListParams`, rendered as a 422 JSON error body
+#[derive(Debug)]
+pub struct ListParamsRejection {
+    pub field: Option<&'static str>,
+    pub detail: String,
+}
+
+impl ListParamsRejection {
+    fn new(field: Option<&'static str>, detail: String) -> Self {
+        Self { field, detail }
+    }
+}
+
+impl IntoResponse for ListParamsRejection {
+    fn into_response(self) -> Response {
+        warn!("Rejecting list parameters: {}", self.detail);
+        let mut body = json!({ "error": "validation", "detail": self.detail });
+        if let Some(field) = self.field {
+            body["field"] = json!(field);
+        }
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::post, Router};
+    use serde::Deserialize;
+    use tower::util::ServiceExt;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Payload {
+        guardian: String,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct StrictPayload {
+        name: String,
+    }
+
+    async fn handler(JsonBody(_payload): JsonBody<Payload>) -> StatusCode {
+        StatusCode::OK
+    }
+
+    async fn strict_handler(JsonBody(_payload): JsonBody<StrictPayload>) -> StatusCode {
+        StatusCode::OK
+    }
+
+    async fn post_body(body: &str) -> (StatusCode, serde_json::Value) {
+        let app = Router::new().route("/", post(handler));
+        post_to(app, "/", body).await
+    }
+
+    async fn post_to(app: Router, uri: &str, body: &str) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_missing_field_is_a_validation_error() {
+        let (status, body) = post_body("{}").await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"], "validation");
+        assert_eq!(body["detail"], "missing field `guardian`");
+    }
+
+    #[tokio::test]
+    async fn test_syntax_error_keeps_axum_status() {
+        let (status, body) = post_body("{not json").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_field_is_named() {
+        let app = Router::new().route("/strict", post(strict_handler));
+        let (status, body) = post_to(app, "/strict", r#"{"name": "x", "nmae": "y"}"#).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"], "validation");
+        assert_eq!(body["field"], "nmae");
+        assert!(body["detail"]
+            .as_str()
+            .unwrap()
+            .contains("unknown field `nmae`"));
+    }
+
+    async fn list_params(query: &str) -> Result<ListParams, ListParamsRejection> {
+        let (mut parts, _) = Request::builder()
+            .uri(format!("/items?{}", query))
+            .body(())
+            .unwrap()
+            .into_parts();
+        ListParams::from_request_parts(&mut parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn test_list_params_valid() {
+        let params = list_params("limit=100&cursor=abc&sort=name&order=desc&locked=true")
+            .await
+            .unwrap();
+        assert_eq!(
+            params,
+            ListParams {
+                limit: Some(100),
+                cursor: Some("abc".to_string()),
+                sort: Some("name".to_string()),
+                order: Order::Desc,
+            }
+        );
+        assert!(params.is_paginated());
+
+        // Everything is optional; order defaults to ascending
+        let params = list_params("").await.unwrap();
+        assert_eq!(params, ListParams::default());
+        assert_eq!(params.order, Order::Asc);
+        assert!(!params.is_paginated());
+    }
+
+    #[tokio::test]
+    async fn test_list_params_limit_out_of_range() {
+        for query in ["limit=0", "limit=101", "limit=-1", "limit=ten"] {
+            let rejection = list_params(query).await.unwrap_err();
+            assert_eq!(rejection.field, Some("limit"), "{}", query);
+        }
+
+        let response = list_params("limit=101").await.unwrap_err().into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"], "validation");
+        assert_eq!(body["field"], "limit");
+        assert_eq!(
+            body["detail"],
+            "limit must be a whole number between 1 and 100, got 101"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_params_invalid_order() {
+        for query in ["order=sideways", "order=DESC", "order="] {
+            let rejection = list_params(query).await.unwrap_err();
+            assert_eq!(rejection.field, Some("order"), "{}", query);
+        }
+        assert_eq!(
+            list_params("order=up").await.unwrap_err().detail,
+            "order must be asc or desc, got up"
+        );
+    }
+
+    #[test]
+    fn test_order_apply() {
+        assert_eq!(Order::Asc.apply(Ordering::Less), Ordering::Less);
+        assert_eq!(Order::Desc.apply(Ordering::Less), Ordering::Greater);
+    }
+}
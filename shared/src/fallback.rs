@@ -0,0 +1,37 @@
+use axum::{
+    extract::OriginalUri,
+    http::{Method, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use log::warn;
+use serde_json::json;
+
+/// Router fallback: 404 with `{"error": "not_found", "path": ...}`
+pub async fn not_found_fallback(method: Method, OriginalUri(uri): OriginalUri) -> Response {
+    warn!("No route matched for: {} {}", method, uri);
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "not_found", "path": uri.path() })),
+    )
+        .into_response()
+}
+
+/// Fallback for a known path called with the wrong method: 405 with
+/// `{"error": "method_not_allowed", "method": ..., "path": ...}`. axum still
+/// adds the `Allow` header.
+pub async fn method_not_allowed_fallback(
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+) -> Response {
+    warn!("Method not allowed: {} {}", method, uri);
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        Json(json!({
+            "error": "method_not_allowed",
+            "method": method.as_str(),
+            "path": uri.path(),
+        })),
+    )
+        .into_response()
+}
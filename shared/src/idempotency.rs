@@ -0,0 +1,181 @@
+use axum::http::HeaderMap;
+use log::{debug, info, warn};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::store::dynamo::DynamoIdempotencyStore;
+use crate::store::memory::MemoryIdempotencyStore;
+use crate::store::IdempotencyStore;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+// Longest Idempotency-Key accepted; UUIDs and similar tokens fit comfortably
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+// How long a key keeps returning the original resource, overridable via IDEMPOTENCY_TTL_SECONDS
+pub const DEFAULT_IDEMPOTENCY_TTL_SECONDS: u64 = 24 * 60 * 60;
+
+pub fn idempotency_ttl() -> Duration {
+    let seconds = std::env::var("IDEMPOTENCY_TTL_SECONDS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .filter(|seconds| *seconds > 0)
+        .unwrap_or(DEFAULT_IDEMPOTENCY_TTL_SECONDS);
+    Duration::from_secs(seconds)
+}
+
+/// Dynamo-backed store when IDEMPOTENCY_TABLE is set, otherwise one that only
+/// dedupes retries reaching this process
+pub async fn idempotency_store_from_env() -> Arc<dyn IdempotencyStore> {
+    if std::env::var("IDEMPOTENCY_TABLE").is_ok() {
+        info!("Using DynamoDB idempotency store");
+        Arc::new(DynamoIdempotencyStore::new().await)
+    } else {
+        warn!("IDEMPOTENCY_TABLE is not set, Idempotency-Key only dedupes within this instance");
+        Arc::new(MemoryIdempotencyStore::new())
+    }
+}
+
+/// The request's Idempotency-Key, if any. Errors with a client-facing message
+/// when the header is present but empty, too long or not visible ASCII.
+pub fn idempotency_key(headers: &HeaderMap) -> std::result::Result<Option<String>, String> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+
+    let key = value
+        .to_str()
+        .map_err(|_| "Idempotency-Key must be visible ASCII".to_string())?
+        .trim();
+    if key.is_empty() {
+        return Err("Idempotency-Key must not be empty".to_string());
+    }
+    if key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+        return Err(format!(
+            "Idempotency-Key must be at most {} characters",
+            MAX_IDEMPOTENCY_KEY_LEN
+        ));
+    }
+    Ok(Some(key.to_string()))
+}
+
+/// Outcome of claiming an Idempotency-Key before a create
+pub enum IdempotencyClaim {
+    /// No key was sent or no store is configured; create as usual
+    Untracked,
+    /// The key now points at the new resource; release it if the create fails
+    Claimed(ClaimedKey),
+    /// The key was used before; this is the id of the resource it created.
+    /// The resource can still be missing while the first request is running.
+    Replay(String),
+}
+
+/// Conflict message for a replay whose resource doesn't exist yet
+pub const REQUEST_IN_PROGRESS: &str =
+    "A request with this Idempotency-Key is still in progress; retry shortly";
+
+/// A key claimed for a resource that hasn't been created yet
+pub struct ClaimedKey {
+    store: Arc<dyn IdempotencyStore>,
+    key: String,
+}
+
+impl ClaimedKey {
+    /// Frees the key so the client's retry can create the resource. Failures
+    /// are only logged; the key then expires with its TTL.
+    pub async fn release(self) {
+        if let Err(e) = self.store.release(&self.key).await {
+            warn!("Failed to release idempotency key {}: {}", self.key, e);
+        }
+    }
+}
+
+/// Claims `key` for `resource_id`, scoped to the operation and caller so two
+/// users (or two kinds of create) never share a key
+pub async fn claim_idempotency_key(
+    store: Option<&Arc<dyn IdempotencyStore>>,
+    key: Option<&str>,
+    operation: &str,
+    user_id: &str,
+    resource_id: &str,
+) -> Result<IdempotencyClaim> {
+    let (Some(store), Some(key)) = (store, key) else {
+        return Ok(IdempotencyClaim::Untracked);
+    };
+
+    let scoped = format!("{}#{}#{}", operation, user_id, key);
+    match store.claim(&scoped, resource_id, idempotency_ttl()).await? {
+        Some(existing) => {
+            debug!(
+                "Idempotency key {} already created {}, replaying",
+                scoped, existing
+            );
+            Ok(IdempotencyClaim::Replay(existing))
+        }
+        None => Ok(IdempotencyClaim::Claimed(ClaimedKey {
+            store: store.clone(),
+            key: scoped,
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers_with_key(key: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(IDEMPOTENCY_KEY_HEADER, HeaderValue::from_str(key).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_idempotency_key_header() {
+        assert_eq!(idempotency_key(&HeaderMap::new()), Ok(None));
+        assert_eq!(
+            idempotency_key(&headers_with_key(" retry-1 ")),
+            Ok(Some("retry-1".to_string()))
+        );
+        assert!(idempotency_key(&headers_with_key("  ")).is_err());
+        assert!(idempotency_key(&headers_with_key(&"k".repeat(256))).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_claim_is_scoped_to_operation_and_user() {
+        let store: Arc<dyn IdempotencyStore> = Arc::new(MemoryIdempotencyStore::new());
+        let claim = |operation, user, resource| {
+            let store = store.clone();
+            async move {
+                claim_idempotency_key(Some(&store), Some("key-1"), operation, user, resource)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        assert!(matches!(
+            claim("create_box", "user-1", "box-1").await,
+            IdempotencyClaim::Claimed(_)
+        ));
+        assert!(matches!(
+            claim("create_box", "user-1", "box-2").await,
+            IdempotencyClaim::Replay(id) if id == "box-1"
+        ));
+        assert!(matches!(
+            claim("create_box", "user-2", "box-3").await,
+            IdempotencyClaim::Claimed(_)
+        ));
+        assert!(matches!(
+            claim("create_invitation", "user-1", "inv-1").await,
+            IdempotencyClaim::Claimed(_)
+        ));
+
+        // A released key can be claimed again
+        if let IdempotencyClaim::Claimed(claimed) = claim("release_me", "user-1", "box-4").await {
+            claimed.release().await;
+        }
+        assert!(matches!(
+            claim("release_me", "user-1", "box-5").await,
+            IdempotencyClaim::Claimed(_)
+        ));
+    }
+}
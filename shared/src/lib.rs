@@ -1,6 +1,16 @@
 pub mod auth;
+pub mod base_path;
+pub mod body_limit;
+pub mod bootstrap;
+pub mod correlation;
+pub mod cors;
+pub mod envelope;
 pub mod error;
+pub mod extract;
+pub mod fallback;
+pub mod idempotency;
 pub mod models;
+pub mod shutdown;
 pub mod store;
 
 #[cfg(test)]
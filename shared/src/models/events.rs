@@ -1,12 +1,191 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::correlation::current_correlation_id;
+use crate::models::{now_str, Invitation};
+
+// Bump when a change to the payload would break consumers still on the old shape
+pub const INVITATION_EVENT_VERSION: u32 = 1;
+
+// Event types the invitation event service knows how to handle
+pub const INVITATION_EVENT_TYPES: &[&str] = &[
+    "invitation_created",
+    "invitation_viewed",
+    "invitation_accepted",
+    "invitation_rejected",
+    "invitation_expired",
+    "invitation_revoked",
+];
 
 /// Event for box invitations
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct InvitationEvent {
     pub event_type: String,
+    // Messages published before versioning are treated as version 1
+    #[serde(default = "default_event_version")]
+    pub event_version: u32,
     pub invitation_id: String,
     pub box_id: String,
     pub user_id: Option<String>,
     pub invite_code: String,
     pub timestamp: String,
+    // Id of the request that caused the event; absent on older messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+}
+
+fn default_event_version() -> u32 {
+    1
+}
+
+/// Why an SNS message couldn't be turned into an InvitationEvent
+#[derive(Debug, Error, PartialEq)]
+pub enum EventError {
+    // Well formed, but nothing here handles it; safe to ignore
+    #[error("Unknown event type: {0}")]
+    UnknownEventType(String),
+
+    // Published by a newer producer; keep it for a consumer that understands it
+    #[error("Unsupported event version: {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("Malformed event: {0}")]
+    Malformed(String),
+}
+
+impl InvitationEvent {
+    /// Event for `invitation`, tagged with the current request's correlation id
+    pub fn for_invitation(invitation: &Invitation, event_type: &str) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            event_version: INVITATION_EVENT_VERSION,
+            invitation_id: invitation.id.clone(),
+            box_id: invitation.box_id.clone(),
+            user_id: invitation.linked_user_id.clone(),
+            invite_code: invitation.invite_code.clone(),
+            timestamp: now_str(),
+            correlation_id: current_correlation_id(),
+        }
+    }
+
+    /// Parses an SNS message body. The version is checked before the rest of
+    /// the payload, since a newer version may have changed its shape. Fields
+    /// this version doesn't know about are ignored.
+    pub fn from_json(message: &str) -> Result<Self, EventError> {
+        let value: Value =
+            serde_json::from_str(message).map_err(|e| EventError::Malformed(e.to_string()))?;
+
+        let version = match value.get("event_version") {
+            None => default_event_version(),
+            Some(v) => v
+                .as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| EventError::Malformed(format!("invalid event_version: {}", v)))?,
+        };
+        if version == 0 || version > INVITATION_EVENT_VERSION {
+            return Err(EventError::UnsupportedVersion(version));
+        }
+
+        let event: InvitationEvent =
+            serde_json::from_value(value).map_err(|e| EventError::Malformed(e.to_string()))?;
+
+        if !INVITATION_EVENT_TYPES.contains(&event.event_type.as_str()) {
+            return Err(EventError::UnknownEventType(event.event_type));
+        }
+
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event_json() -> Value {
+        json!({
+            "event_type": "invitation_viewed",
+            "event_version": 1,
+            "invitation_id": "inv-1",
+            "box_id": "box-1",
+            "user_id": "user-1",
+            "invite_code": "CODE1234",
+            "timestamp": "2024-01-01T00:00:00Z"
+        })
+    }
+
+    #[test]
+    fn test_from_json_parses_current_version() {
+        let event = InvitationEvent::from_json(&event_json().to_string()).unwrap();
+        assert_eq!(event.event_type, "invitation_viewed");
+        assert_eq!(event.event_version, INVITATION_EVENT_VERSION);
+        assert_eq!(event.user_id.as_deref(), Some("user-1"));
+    }
+
+    #[test]
+    fn test_from_json_defaults_missing_version() {
+        let mut payload = event_json();
+        payload.as_object_mut().unwrap().remove("event_version");
+
+        let event = InvitationEvent::from_json(&payload.to_string()).unwrap();
+        assert_eq!(event.event_version, 1);
+    }
+
+    #[test]
+    fn test_from_json_ignores_unknown_fields() {
+        let mut payload = event_json();
+        payload["added_in_a_later_release"] = json!({ "nested": true });
+
+        let event = InvitationEvent::from_json(&payload.to_string()).unwrap();
+        assert_eq!(event.invitation_id, "inv-1");
+    }
+
+    #[test]
+    fn test_from_json_unknown_event_type() {
+        let mut payload = event_json();
+        payload["event_type"] = json!("invitation_exploded");
+
+        assert_eq!(
+            InvitationEvent::from_json(&payload.to_string()).unwrap_err(),
+            EventError::UnknownEventType("invitation_exploded".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_json_unsupported_version() {
+        let mut payload = event_json();
+        payload["event_version"] = json!(INVITATION_EVENT_VERSION + 1);
+        // A newer shape may not parse as this one; the version is reported first
+        payload.as_object_mut().unwrap().remove("invite_code");
+
+        assert_eq!(
+            InvitationEvent::from_json(&payload.to_string()).unwrap_err(),
+            EventError::UnsupportedVersion(INVITATION_EVENT_VERSION + 1)
+        );
+    }
+
+    #[test]
+    fn test_from_json_malformed() {
+        let mut missing_field = event_json();
+        missing_field.as_object_mut().unwrap().remove("box_id");
+        let mut bad_version = event_json();
+        bad_version["event_version"] = json!("one");
+
+        for message in [
+            "{not json".to_string(),
+            "[]".to_string(),
+            missing_field.to_string(),
+            bad_version.to_string(),
+        ] {
+            assert!(
+                matches!(
+                    InvitationEvent::from_json(&message),
+                    Err(EventError::Malformed(_))
+                ),
+                "{}",
+                message
+            );
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use chrono::Utc;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
@@ -85,6 +85,7 @@ pub enum UnlockRequestStatus {
     Approved,  // When enough guardians have approved
     Rejected,  // When request has been rejected
     Completed, // When box has been unlocked
+    Cancelled, // When the initiating lead guardian withdrew the request
 }
 
 impl FromStr for UnlockRequestStatus {
@@ -97,6 +98,7 @@ impl FromStr for UnlockRequestStatus {
             "approved" => Ok(UnlockRequestStatus::Approved),
             "rejected" => Ok(UnlockRequestStatus::Rejected),
             "completed" => Ok(UnlockRequestStatus::Completed),
+            "cancelled" => Ok(UnlockRequestStatus::Cancelled),
             _ => Err(format!("Unknown unlock request status: {}", s)),
         }
     }
@@ -109,6 +111,7 @@ impl fmt::Display for UnlockRequestStatus {
             UnlockRequestStatus::Approved => "approved",
             UnlockRequestStatus::Rejected => "rejected",
             UnlockRequestStatus::Completed => "completed",
+            UnlockRequestStatus::Cancelled => "cancelled",
         };
         write!(f, "{}", status_str)
     }
@@ -116,6 +119,7 @@ impl fmt::Display for UnlockRequestStatus {
 
 // Invitation-related models
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(from = "StoredInvitation")]
 pub struct Invitation {
     pub id: String,
     #[serde(rename = "inviteCode")]
@@ -129,48 +133,265 @@ pub struct Invitation {
     #[serde(rename = "expiresAt")]
     pub expires_at: String, // 48-hour expiry time
     pub opened: bool,
+    pub status: InvitationStatus,
     #[serde(rename = "linkedUserId")]
     pub linked_user_id: Option<String>, // To be filled upon open
     #[serde(rename = "creatorId")]
     pub creator_id: String, // ID of the user who created the invitation
 }
 
+// Wire shape of an Invitation. Items written before `status` existed only carry
+// `opened`, so the status is derived from it when missing.
+#[derive(Deserialize)]
+struct StoredInvitation {
+    id: String,
+    #[serde(rename = "inviteCode")]
+    invite_code: String,
+    #[serde(rename = "invitedName")]
+    invited_name: String,
+    #[serde(rename = "boxId")]
+    box_id: String,
+    #[serde(rename = "createdAt")]
+    created_at: String,
+    #[serde(rename = "expiresAt")]
+    expires_at: String,
+    opened: bool,
+    #[serde(default)]
+    status: Option<InvitationStatus>,
+    #[serde(rename = "linkedUserId")]
+    linked_user_id: Option<String>,
+    #[serde(rename = "creatorId")]
+    creator_id: String,
+}
+
+impl From<StoredInvitation> for Invitation {
+    fn from(stored: StoredInvitation) -> Self {
+        let status = stored.status.unwrap_or(if stored.opened {
+            InvitationStatus::Opened
+        } else {
+            InvitationStatus::Invited
+        });
+
+        Self {
+            id: stored.id,
+            invite_code: stored.invite_code,
+            invited_name: stored.invited_name,
+            box_id: stored.box_id,
+            created_at: stored.created_at,
+            expires_at: stored.expires_at,
+            opened: stored.opened,
+            status,
+            linked_user_id: stored.linked_user_id,
+            creator_id: stored.creator_id,
+        }
+    }
+}
+
+impl Invitation {
+    /// Whether the invitation is past its expiry. An unparseable timestamp counts
+    /// as expired, matching how the stores treat it.
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(Utc::now())
+    }
+
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        DateTime::parse_from_rfc3339(&self.expires_at)
+            .map(|expires_at| now > expires_at)
+            .unwrap_or(true)
+    }
+}
+
 // Box-related models
+
+/// Where a document's `content` lives: inline in the box record, or in S3 with
+/// `content` holding the object key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentStorage {
+    #[default]
+    Inline,
+    S3,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Document {
     pub id: String,
     pub title: String,
     pub content: String,
-    #[serde(rename = "createdAt")]
+    #[serde(rename = "createdAt", alias = "created_at")]
     pub created_at: String,
+    // Documents written before S3 storage existed are inline
+    #[serde(default)]
+    pub storage: DocumentStorage,
+    // Documents written before content types were recorded are plain text
+    #[serde(
+        rename = "contentType",
+        alias = "content_type",
+        default = "default_content_type"
+    )]
+    pub content_type: String,
+}
+
+pub const DEFAULT_DOCUMENT_CONTENT_TYPE: &str = "text/plain";
+
+fn default_content_type() -> String {
+    DEFAULT_DOCUMENT_CONTENT_TYPE.to_string()
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Guardian {
     pub id: String, // user_id
     pub name: String,
-    #[serde(rename = "leadGuardian")]
+    #[serde(rename = "leadGuardian", alias = "lead_guardian")]
     pub lead_guardian: bool,
     pub status: GuardianStatus,
-    #[serde(rename = "addedAt")]
+    #[serde(rename = "addedAt", alias = "added_at")]
     pub added_at: String,
-    #[serde(rename = "invitationId")]
+    #[serde(rename = "invitationId", alias = "invitation_id")]
     pub invitation_id: String,
+    // Carried over from the legacy model; stored trimmed and lowercased
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    // Owner-only annotations (e.g. "spouse", "attorney"); never shown to other guardians
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relationship: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    // Set by the service when the guardian opens the box; clients can't change it
+    #[serde(
+        rename = "lastViewedAt",
+        alias = "last_viewed_at",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_viewed_at: Option<String>,
+}
+
+/// Canonical form of an email address for storage and comparison
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+impl Guardian {
+    /// Normalizes the email and moves `added_at` to UTC, for guardians
+    /// supplied by a client. Fails if `added_at` isn't an RFC 3339 timestamp.
+    pub fn normalize(&mut self) -> crate::error::Result<()> {
+        self.normalize_email();
+        self.normalize_annotations();
+        self.added_at = normalize_rfc3339(&self.added_at)?;
+        Ok(())
+    }
+
+    /// Normalizes the email in place, dropping it if it's blank
+    pub fn normalize_email(&mut self) {
+        self.email = self
+            .email
+            .as_deref()
+            .map(normalize_email)
+            .filter(|email| !email.is_empty());
+    }
+
+    /// Trims the owner's relationship and note, dropping them if they're blank
+    pub fn normalize_annotations(&mut self) {
+        for field in [&mut self.relationship, &mut self.note] {
+            *field = field
+                .take()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty());
+        }
+    }
+
+    /// This guardian as other guardians see it, without their contact details,
+    /// the owner's annotations or view tracking
+    pub fn guardian_view(&self) -> Guardian {
+        Guardian {
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
+            ..self.clone()
+        }
+    }
+
+    /// Whether both entries carry an email and they match once normalized
+    pub fn has_same_email(&self, other: &Guardian) -> bool {
+        match (&self.email, &other.email) {
+            (Some(a), Some(b)) => normalize_email(a) == normalize_email(b),
+            _ => false,
+        }
+    }
+
+    /// Whether both entries are the same guardian: the same user or the same
+    /// invitation. A shared email alone doesn't make them the same guardian.
+    pub fn is_same_guardian(&self, other: &Guardian) -> bool {
+        self.id == other.id || self.invitation_id == other.invitation_id
+    }
+}
+
+/// How guardian approvals are turned into a granted unlock request. Stored as
+/// `{"type": "threshold", "value": 2}`; variants without a value omit it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum UnlockPolicy {
+    // A majority of accepted guardians, or `requiredApprovals` when the box sets it
+    #[default]
+    Majority,
+    // A fixed number of approvals from any of the box's guardians
+    Threshold(u32),
+    // Every lead guardian has to approve
+    AllLeads,
+    // A single lead guardian's approval is enough
+    AnyLead,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct UnlockRequest {
     pub id: String,
-    #[serde(rename = "requestedAt")]
+    #[serde(rename = "requestedAt", alias = "requested_at")]
     pub requested_at: String,
     pub status: UnlockRequestStatus,
     pub message: Option<String>,
-    #[serde(rename = "initiatedBy")]
+    #[serde(rename = "initiatedBy", alias = "initiated_by")]
     pub initiated_by: Option<String>,
-    #[serde(rename = "approvedBy")]
+    #[serde(rename = "approvedBy", alias = "approved_by")]
     pub approved_by: Vec<String>,
-    #[serde(rename = "rejectedBy")]
+    #[serde(rename = "rejectedBy", alias = "rejected_by")]
     pub rejected_by: Vec<String>,
+    // Requests created before expiry was introduced carry no timestamp and never expire
+    #[serde(
+        rename = "expiresAt",
+        alias = "expires_at",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expires_at: Option<String>,
+}
+
+impl UnlockRequest {
+    /// Whether the request has passed its expiry and can no longer be acted on.
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(Utc::now())
+    }
+
+    /// Expiry check against an explicit clock. An unparseable timestamp counts as
+    /// expired so a corrupt record can't keep an unlock request open forever.
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        match &self.expires_at {
+            Some(expires_at) => match DateTime::parse_from_rfc3339(expires_at) {
+                Ok(expires_at) => now > expires_at,
+                Err(e) => {
+                    log::warn!(
+                        "Unlock request {} has invalid expiresAt {:?}: {}",
+                        self.id,
+                        expires_at,
+                        e
+                    );
+                    true
+                }
+            },
+            None => false,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -178,26 +399,175 @@ pub struct BoxRecord {
     pub id: String,
     pub name: String,
     pub description: String,
-    #[serde(rename = "isLocked")]
+    #[serde(rename = "isLocked", alias = "is_locked")]
     pub is_locked: bool,
-    #[serde(rename = "createdAt")]
+    #[serde(rename = "createdAt", alias = "created_at")]
     pub created_at: String,
-    #[serde(rename = "updatedAt")]
+    #[serde(rename = "updatedAt", alias = "updated_at")]
     pub updated_at: String,
-    #[serde(rename = "ownerId")]
+    #[serde(rename = "ownerId", alias = "owner_id")]
     pub owner_id: String,
-    #[serde(rename = "ownerName")]
+    #[serde(rename = "ownerName", alias = "owner_name")]
     pub owner_name: Option<String>,
     pub documents: Vec<Document>,
     pub guardians: Vec<Guardian>,
-    #[serde(rename = "unlockInstructions")]
+    #[serde(rename = "unlockInstructions", alias = "unlock_instructions")]
     pub unlock_instructions: Option<String>,
-    #[serde(rename = "unlockRequest")]
-    pub unlock_request: Option<UnlockRequest>,
+    // Oldest first. Records written before multiple requests were supported store a
+    // single `unlockRequest`, which is read into this list and written back as
+    // `unlockRequests` on the next update.
+    #[serde(
+        rename = "unlockRequests",
+        alias = "unlockRequest",
+        alias = "unlock_requests",
+        alias = "unlock_request",
+        default,
+        deserialize_with = "deserialize_unlock_requests"
+    )]
+    pub unlock_requests: Vec<UnlockRequest>,
+    // Approvals needed before an unlock request is granted; None means a majority of accepted guardians
+    #[serde(
+        rename = "requiredApprovals",
+        alias = "required_approvals",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub required_approvals: Option<usize>,
+    #[serde(rename = "unlockPolicy", alias = "unlock_policy", default)]
+    pub unlock_policy: UnlockPolicy,
+    // Set when the box is soft-deleted; such boxes are hidden from normal reads
+    #[serde(
+        rename = "deletedAt",
+        alias = "deleted_at",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub deleted_at: Option<String>,
     #[serde(default)]
     pub version: u64, // Version for optimistic concurrency control
 }
 
+// Accepts the current list form as well as the legacy single request (or null)
+// stored under `unlockRequest`
+fn deserialize_unlock_requests<'de, D>(deserializer: D) -> Result<Vec<UnlockRequest>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StoredUnlockRequests {
+        Many(Vec<UnlockRequest>),
+        Legacy(Option<UnlockRequest>),
+    }
+
+    Ok(match StoredUnlockRequests::deserialize(deserializer)? {
+        StoredUnlockRequests::Many(requests) => requests,
+        StoredUnlockRequests::Legacy(request) => request.into_iter().collect(),
+    })
+}
+
+impl BoxRecord {
+    /// Whether the box has been soft-deleted and should be hidden from reads.
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// Whether guardians may see document contents: the box is unlocked, or an
+    /// unlock request has completed.
+    pub fn documents_visible_to_guardians(&self) -> bool {
+        !self.is_locked
+            || self
+                .unlock_requests
+                .iter()
+                .any(|request| request.status == UnlockRequestStatus::Completed)
+    }
+
+    /// The most recently created unlock request, if any.
+    pub fn latest_unlock_request(&self) -> Option<&UnlockRequest> {
+        self.unlock_requests.last()
+    }
+
+    /// Looks up an unlock request by id for updating.
+    pub fn unlock_request_mut(&mut self, request_id: &str) -> Option<&mut UnlockRequest> {
+        self.unlock_requests
+            .iter_mut()
+            .find(|request| request.id == request_id)
+    }
+
+    /// Ids of the box's lead guardians, leaving out any that have been rejected.
+    /// Only these guardians may request an unlock.
+    pub fn lead_guardian_ids(&self) -> Vec<&str> {
+        self.guardians
+            .iter()
+            .filter(|g| g.lead_guardian && g.status != GuardianStatus::Rejected)
+            .map(|g| g.id.as_str())
+            .collect()
+    }
+
+    /// Number of guardians that have accepted their invitation to this box.
+    pub fn accepted_guardian_count(&self) -> usize {
+        self.guardians
+            .iter()
+            .filter(|g| g.status == GuardianStatus::Accepted)
+            .count()
+    }
+
+    /// Ids of the guardians whose unlock votes count under the box's unlock
+    /// policy: accepted guardians, or only the accepted leads under the lead
+    /// policies. Guardians still invited or viewing can't vote.
+    pub fn unlock_voter_ids(&self) -> Vec<&str> {
+        let lead_policy = matches!(
+            self.unlock_policy,
+            UnlockPolicy::AllLeads | UnlockPolicy::AnyLead
+        );
+        self.guardians
+            .iter()
+            .filter(|g| g.status == GuardianStatus::Accepted)
+            .filter(|g| g.lead_guardian || !lead_policy)
+            .map(|g| g.id.as_str())
+            .collect()
+    }
+
+    /// Approvals an unlock request needs before it is granted under the box's
+    /// unlock policy. For the lead policies only lead guardians' approvals count.
+    pub fn approval_threshold(&self) -> usize {
+        match self.unlock_policy {
+            UnlockPolicy::Majority => match self.required_approvals {
+                Some(required) => required.max(1),
+                None => self.accepted_guardian_count() / 2 + 1,
+            },
+            UnlockPolicy::Threshold(required) => (required as usize).max(1),
+            UnlockPolicy::AllLeads => self.unlock_voter_ids().len().max(1),
+            UnlockPolicy::AnyLead => 1,
+        }
+    }
+
+    /// Where the votes on `unlock` leave it under the box's unlock policy:
+    /// `Approved` once enough guardians approve, `Rejected` once too few are left
+    /// who could still approve, and None while it is still undecided.
+    pub fn unlock_outcome(&self, unlock: &UnlockRequest) -> Option<UnlockRequestStatus> {
+        // Votes from anyone who can't vote (any more) are ignored
+        let voters = self.unlock_voter_ids();
+        let counted = |votes: &[String]| {
+            votes
+                .iter()
+                .filter(|id| voters.contains(&id.as_str()))
+                .count()
+        };
+        let approvals = counted(&unlock.approved_by);
+        let rejections = counted(&unlock.rejected_by);
+
+        let threshold = self.approval_threshold();
+        if approvals >= threshold {
+            Some(UnlockRequestStatus::Approved)
+        } else if rejections > voters.len().saturating_sub(threshold) {
+            Some(UnlockRequestStatus::Rejected)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GuardianBox {
     pub id: String,
@@ -215,19 +585,48 @@ pub struct GuardianBox {
     pub owner_name: Option<String>,
     #[serde(rename = "unlockInstructions")]
     pub unlock_instructions: Option<String>,
-    #[serde(rename = "unlockRequest")]
-    pub unlock_request: Option<UnlockRequest>,
+    #[serde(rename = "unlockRequests")]
+    pub unlock_requests: Vec<UnlockRequest>,
     #[serde(rename = "pendingGuardianApproval")]
     pub pending_guardian_approval: Option<bool>,
     #[serde(rename = "guardiansCount")]
     pub guardians_count: usize,
     #[serde(rename = "isLeadGuardian")]
     pub is_lead_guardian: bool,
-    // TODO we probably shouldn't be just returning them all for privacy reasons
-    pub documents: Vec<Document>,
+    // Content is withheld until the box is unlocked (see GuardianDocument)
+    pub documents: Vec<GuardianDocument>,
     pub guardians: Vec<Guardian>,
 }
 
+/// A document as a guardian sees it. While the box is locked and no unlock
+/// request has completed, only its metadata is shared and `content` is omitted.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GuardianDocument {
+    pub id: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(default)]
+    pub storage: DocumentStorage,
+    #[serde(rename = "contentType", default = "default_content_type")]
+    pub content_type: String,
+}
+
+impl GuardianDocument {
+    pub fn from_document(document: &Document, reveal_content: bool) -> Self {
+        Self {
+            id: document.id.clone(),
+            title: document.title.clone(),
+            content: reveal_content.then(|| document.content.clone()),
+            created_at: document.created_at.clone(),
+            storage: document.storage,
+            content_type: document.content_type.clone(),
+        }
+    }
+}
+
 // Response DTOs for general use across services
 #[derive(Serialize, Debug)]
 pub struct ErrorResponse {
@@ -241,9 +640,153 @@ pub struct MessageResponse {
     pub box_id: Option<String>,
 }
 
-// Helper function to get current timestamp as string
+// Helper function to get current timestamp as string, always in UTC with a `Z` suffix
 pub fn now_str() -> String {
-    Utc::now().to_rfc3339()
+    utc_rfc3339(Utc::now())
+}
+
+/// `timestamp` in the same UTC, `Z`-suffixed format as `now_str`
+pub fn utc_rfc3339(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Parses an RFC 3339 timestamp with any offset and re-serializes it in UTC,
+/// e.g. `2024-01-01T17:30:00+05:30` becomes `2024-01-01T12:00:00Z`
+pub fn normalize_rfc3339(value: &str) -> crate::error::Result<String> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .map(|timestamp| utc_rfc3339(timestamp.with_timezone(&Utc)))
+        .map_err(|_| {
+            crate::error::StoreError::ValidationError(format!(
+                "Invalid RFC 3339 timestamp: {:?}",
+                value
+            ))
+        })
+}
+
+// Invite code length used when INVITE_CODE_LEN is unset or out of bounds
+pub const DEFAULT_INVITE_CODE_LENGTH: usize = 8;
+pub const MIN_INVITE_CODE_LENGTH: usize = 6;
+pub const MAX_INVITE_CODE_LENGTH: usize = 32;
+
+// Uppercase letters and digits minus the look-alikes in EXCLUDED_INVITE_CODE_CHARS
+pub const DEFAULT_INVITE_CODE_ALPHABET: &str = "ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+// Characters that are easy to misread when a code is typed in by hand
+pub const EXCLUDED_INVITE_CODE_CHARS: [char; 4] = ['0', 'O', '1', 'I'];
+
+// A configured alphabet smaller than this is ignored
+const MIN_INVITE_CODE_ALPHABET_SIZE: usize = 8;
+
+/// Generates an invite code using INVITE_CODE_LEN and INVITE_CODE_ALPHABET.
+/// Every new or refreshed code goes through here
+pub fn generate_invite_code() -> String {
+    let length = parse_invite_code_length(std::env::var("INVITE_CODE_LEN").ok().as_deref());
+    let alphabet =
+        parse_invite_code_alphabet(std::env::var("INVITE_CODE_ALPHABET").ok().as_deref());
+    nanoid::nanoid!(length, &alphabet)
+}
+
+// Accepts lengths from MIN_INVITE_CODE_LENGTH to MAX_INVITE_CODE_LENGTH, falling back to the default otherwise
+pub fn parse_invite_code_length(value: Option<&str>) -> usize {
+    match value.map(|v| v.trim().parse::<usize>()) {
+        Some(Ok(length)) if (MIN_INVITE_CODE_LENGTH..=MAX_INVITE_CODE_LENGTH).contains(&length) => {
+            length
+        }
+        Some(_) => {
+            log::warn!(
+                "Invalid INVITE_CODE_LEN value {:?}, expected {} to {}; using default of {}",
+                value,
+                MIN_INVITE_CODE_LENGTH,
+                MAX_INVITE_CODE_LENGTH,
+                DEFAULT_INVITE_CODE_LENGTH
+            );
+            DEFAULT_INVITE_CODE_LENGTH
+        }
+        None => DEFAULT_INVITE_CODE_LENGTH,
+    }
+}
+
+// Uppercases the configured alphabet and drops duplicates, non-alphanumerics and
+// excluded characters. Falls back to the default if too few characters are left
+pub fn parse_invite_code_alphabet(value: Option<&str>) -> Vec<char> {
+    let Some(value) = value else {
+        return DEFAULT_INVITE_CODE_ALPHABET.chars().collect();
+    };
+
+    let mut alphabet: Vec<char> = Vec::new();
+    for c in value.chars().map(|c| c.to_ascii_uppercase()) {
+        if c.is_ascii_alphanumeric()
+            && !EXCLUDED_INVITE_CODE_CHARS.contains(&c)
+            && !alphabet.contains(&c)
+        {
+            alphabet.push(c);
+        }
+    }
+
+    if alphabet.len() < MIN_INVITE_CODE_ALPHABET_SIZE {
+        log::warn!(
+            "INVITE_CODE_ALPHABET {:?} has fewer than {} usable characters, using default",
+            value,
+            MIN_INVITE_CODE_ALPHABET_SIZE
+        );
+        return DEFAULT_INVITE_CODE_ALPHABET.chars().collect();
+    }
+    alphabet
+}
+
+// Invitation lifetime used when INVITATION_TTL_HOURS is unset or invalid
+pub const DEFAULT_INVITATION_TTL_HOURS: i64 = 48;
+
+// Effective invitation lifetime in hours, configured via env INVITATION_TTL_HOURS
+pub fn invitation_ttl_hours() -> i64 {
+    parse_invitation_ttl_hours(std::env::var("INVITATION_TTL_HOURS").ok().as_deref())
+}
+
+// Accepts only positive integers, falling back to the default otherwise
+pub fn parse_invitation_ttl_hours(value: Option<&str>) -> i64 {
+    parse_ttl_hours("INVITATION_TTL_HOURS", value, DEFAULT_INVITATION_TTL_HOURS)
+}
+
+// Expiry timestamp for an invitation issued at `from` with the given TTL
+pub fn invitation_expires_at(from: DateTime<Utc>, ttl_hours: i64) -> String {
+    utc_rfc3339(from + Duration::hours(ttl_hours))
+}
+
+// Unlock request lifetime used when UNLOCK_REQUEST_TTL_HOURS is unset or invalid
+pub const DEFAULT_UNLOCK_REQUEST_TTL_HOURS: i64 = 72;
+
+// Effective unlock request lifetime in hours, configured via env UNLOCK_REQUEST_TTL_HOURS
+pub fn unlock_request_ttl_hours() -> i64 {
+    parse_unlock_request_ttl_hours(std::env::var("UNLOCK_REQUEST_TTL_HOURS").ok().as_deref())
+}
+
+pub fn parse_unlock_request_ttl_hours(value: Option<&str>) -> i64 {
+    parse_ttl_hours(
+        "UNLOCK_REQUEST_TTL_HOURS",
+        value,
+        DEFAULT_UNLOCK_REQUEST_TTL_HOURS,
+    )
+}
+
+// Expiry timestamp for an unlock request raised at `from` with the given TTL
+pub fn unlock_request_expires_at(from: DateTime<Utc>, ttl_hours: i64) -> String {
+    utc_rfc3339(from + Duration::hours(ttl_hours))
+}
+
+fn parse_ttl_hours(var_name: &str, value: Option<&str>, default: i64) -> i64 {
+    match value.map(|v| v.trim().parse::<i64>()) {
+        Some(Ok(hours)) if hours > 0 => hours,
+        Some(_) => {
+            log::warn!(
+                "Invalid {} value {:?}, using default of {} hours",
+                var_name,
+                value,
+                default
+            );
+            default
+        }
+        None => default,
+    }
 }
 
 #[cfg(test)]
@@ -261,6 +804,7 @@ mod tests {
             created_at: "2024-01-01T00:00:00Z".to_string(),
             expires_at: "2024-01-02T00:00:00Z".to_string(),
             opened: false,
+            status: InvitationStatus::Invited,
             linked_user_id: None,
             creator_id: "creator-123".to_string(),
         };
@@ -280,4 +824,358 @@ mod tests {
         assert!(json.get("linkedUserId").is_some());
         assert!(json.get("creatorId").is_some());
     }
+
+    #[test]
+    fn test_invitation_status_migration() {
+        let legacy = |opened: bool| {
+            serde_json::json!({
+                "id": "legacy-id",
+                "inviteCode": "LEGACY01",
+                "invitedName": "Legacy User",
+                "boxId": "box-123",
+                "createdAt": "2024-01-01T00:00:00Z",
+                "expiresAt": "2024-01-03T00:00:00Z",
+                "opened": opened,
+                "linkedUserId": null,
+                "creatorId": "creator-123"
+            })
+        };
+
+        // Items written before `status` existed take it from `opened`
+        let invitation: Invitation = serde_json::from_value(legacy(false)).unwrap();
+        assert_eq!(invitation.status, InvitationStatus::Invited);
+        let invitation: Invitation = serde_json::from_value(legacy(true)).unwrap();
+        assert_eq!(invitation.status, InvitationStatus::Opened);
+
+        // A stored status wins and survives a round trip
+        let mut stored = legacy(false);
+        stored["status"] = serde_json::json!("accepted");
+        let invitation: Invitation = serde_json::from_value(stored).unwrap();
+        assert_eq!(invitation.status, InvitationStatus::Accepted);
+        let json = serde_json::to_value(&invitation).unwrap();
+        assert_eq!(json["status"], "accepted");
+    }
+
+    #[test]
+    fn test_parse_invitation_ttl_hours() {
+        assert_eq!(parse_invitation_ttl_hours(None), 48);
+        assert_eq!(parse_invitation_ttl_hours(Some("12")), 12);
+        assert_eq!(parse_invitation_ttl_hours(Some("168")), 168);
+
+        // Non-positive or unparseable values fall back to the default
+        assert_eq!(parse_invitation_ttl_hours(Some("0")), 48);
+        assert_eq!(parse_invitation_ttl_hours(Some("-6")), 48);
+        assert_eq!(parse_invitation_ttl_hours(Some("two days")), 48);
+    }
+
+    #[test]
+    fn test_parse_invite_code_length() {
+        assert_eq!(parse_invite_code_length(None), 8);
+        assert_eq!(parse_invite_code_length(Some("6")), 6);
+        assert_eq!(parse_invite_code_length(Some(" 32 ")), 32);
+
+        // Out of bounds or unparseable values fall back to the default
+        assert_eq!(parse_invite_code_length(Some("5")), 8);
+        assert_eq!(parse_invite_code_length(Some("33")), 8);
+        assert_eq!(parse_invite_code_length(Some("-1")), 8);
+        assert_eq!(parse_invite_code_length(Some("long")), 8);
+    }
+
+    #[test]
+    fn test_parse_invite_code_alphabet() {
+        let default: Vec<char> = DEFAULT_INVITE_CODE_ALPHABET.chars().collect();
+        assert_eq!(parse_invite_code_alphabet(None), default);
+        assert!(default
+            .iter()
+            .all(|c| !EXCLUDED_INVITE_CODE_CHARS.contains(c)));
+
+        // Excluded, duplicate and non-alphanumeric characters are dropped
+        assert_eq!(
+            parse_invite_code_alphabet(Some("abcdefghio01-AB")),
+            "ABCDEFGH".chars().collect::<Vec<_>>()
+        );
+
+        // Too few usable characters left
+        assert_eq!(parse_invite_code_alphabet(Some("OI01ABC")), default);
+    }
+
+    #[test]
+    fn test_generated_invite_codes_avoid_excluded_chars() {
+        for _ in 0..200 {
+            let code = generate_invite_code();
+            assert_eq!(code.len(), DEFAULT_INVITE_CODE_LENGTH);
+            assert!(
+                code.chars()
+                    .all(|c| DEFAULT_INVITE_CODE_ALPHABET.contains(c)),
+                "unexpected character in {}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn test_invitation_expires_at_custom_ttl() {
+        let issued_at = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let expires_at = invitation_expires_at(issued_at, 12);
+        assert_eq!(expires_at, "2024-01-01T12:00:00Z");
+        let expires_at = DateTime::parse_from_rfc3339(&expires_at)
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!((expires_at - issued_at).num_hours(), 12);
+    }
+
+    #[test]
+    fn test_unlock_request_expiry() {
+        let requested_at = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut unlock = UnlockRequest {
+            id: "unlock-1".to_string(),
+            requested_at: requested_at.to_rfc3339(),
+            status: UnlockRequestStatus::Requested,
+            message: None,
+            initiated_by: None,
+            approved_by: vec![],
+            rejected_by: vec![],
+            expires_at: None,
+        };
+
+        // Legacy requests without a timestamp never expire
+        assert!(!unlock.is_expired_at(requested_at + Duration::days(365)));
+
+        unlock.expires_at = Some(unlock_request_expires_at(requested_at, 72));
+        assert_eq!(unlock.expires_at.as_deref(), Some("2024-01-04T00:00:00Z"));
+        assert!(!unlock.is_expired_at(requested_at + Duration::hours(71)));
+        assert!(unlock.is_expired_at(requested_at + Duration::hours(73)));
+
+        unlock.expires_at = Some("not a timestamp".to_string());
+        assert!(unlock.is_expired_at(requested_at));
+
+        assert_eq!(parse_unlock_request_ttl_hours(None), 72);
+        assert_eq!(parse_unlock_request_ttl_hours(Some("0")), 72);
+    }
+
+    #[test]
+    fn test_legacy_unlock_request_is_migrated() {
+        let legacy_box = |unlock_request: serde_json::Value| {
+            serde_json::json!({
+                "id": "box-1",
+                "name": "Legacy box",
+                "description": "",
+                "isLocked": true,
+                "createdAt": "2024-01-01T00:00:00Z",
+                "updatedAt": "2024-01-01T00:00:00Z",
+                "ownerId": "owner-1",
+                "ownerName": null,
+                "documents": [],
+                "guardians": [],
+                "unlockInstructions": null,
+                "unlockRequest": unlock_request,
+                "version": 3
+            })
+        };
+
+        let box_record: BoxRecord = serde_json::from_value(legacy_box(serde_json::json!({
+            "id": "unlock-1",
+            "requestedAt": "2024-01-01T00:00:00Z",
+            "status": "requested",
+            "message": null,
+            "initiatedBy": "lead-1",
+            "approvedBy": ["guardian-1"],
+            "rejectedBy": []
+        })))
+        .unwrap();
+        assert_eq!(box_record.unlock_requests.len(), 1);
+        assert_eq!(box_record.latest_unlock_request().unwrap().id, "unlock-1");
+        assert_eq!(
+            box_record.unlock_requests[0].approved_by,
+            vec!["guardian-1"]
+        );
+
+        // Written back in the new shape only
+        let stored = serde_json::to_value(&box_record).unwrap();
+        assert!(stored.get("unlockRequest").is_none());
+        assert_eq!(stored["unlockRequests"][0]["id"], "unlock-1");
+        let reread: BoxRecord = serde_json::from_value(stored).unwrap();
+        assert_eq!(reread.unlock_requests.len(), 1);
+
+        let box_record: BoxRecord =
+            serde_json::from_value(legacy_box(serde_json::Value::Null)).unwrap();
+        assert!(box_record.unlock_requests.is_empty());
+
+        // Items read from DynamoDB go through the same path
+        let item: std::collections::HashMap<String, aws_sdk_dynamodb::types::AttributeValue> =
+            serde_dynamo::to_item(legacy_box(serde_json::Value::Null)).unwrap();
+        let box_record: BoxRecord = serde_dynamo::from_item(item).unwrap();
+        assert!(box_record.unlock_requests.is_empty());
+    }
+
+    #[test]
+    fn test_snake_case_box_record_deserializes() {
+        let legacy = serde_json::json!({
+            "id": "box-1",
+            "name": "Legacy box",
+            "description": "Written by the old models",
+            "is_locked": true,
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-02T00:00:00Z",
+            "owner_id": "owner-1",
+            "owner_name": "Owner One",
+            "documents": [{
+                "id": "doc-1",
+                "title": "Will",
+                "content": "Contents",
+                "created_at": "2024-01-01T00:00:00Z"
+            }],
+            "guardians": [{
+                "id": "guardian-1",
+                "name": "Guardian One",
+                "lead_guardian": true,
+                "status": "accepted",
+                "added_at": "2024-01-01T00:00:00Z",
+                "invitation_id": "inv-1"
+            }],
+            "unlock_instructions": "Call first",
+            "unlock_request": {
+                "id": "unlock-1",
+                "requested_at": "2024-01-03T00:00:00Z",
+                "status": "requested",
+                "message": null,
+                "initiated_by": "guardian-1",
+                "approved_by": [],
+                "rejected_by": []
+            },
+            "required_approvals": 1,
+            "version": 2
+        });
+
+        let box_record: BoxRecord = serde_json::from_value(legacy).unwrap();
+        assert!(box_record.is_locked);
+        assert_eq!(box_record.owner_id, "owner-1");
+        assert_eq!(box_record.owner_name.as_deref(), Some("Owner One"));
+        assert_eq!(box_record.documents[0].created_at, "2024-01-01T00:00:00Z");
+        assert!(box_record.guardians[0].lead_guardian);
+        assert_eq!(box_record.guardians[0].invitation_id, "inv-1");
+        assert_eq!(
+            box_record.unlock_instructions.as_deref(),
+            Some("Call first")
+        );
+        assert_eq!(
+            box_record.unlock_requests[0].initiated_by.as_deref(),
+            Some("guardian-1")
+        );
+        assert_eq!(box_record.required_approvals, Some(1));
+        assert_eq!(box_record.unlock_policy, UnlockPolicy::Majority);
+
+        // Serialized back out in camelCase
+        let stored = serde_json::to_value(&box_record).unwrap();
+        assert_eq!(stored["isLocked"], true);
+        assert_eq!(stored["ownerId"], "owner-1");
+        assert_eq!(stored["guardians"][0]["leadGuardian"], true);
+        assert!(stored.get("is_locked").is_none());
+        assert!(stored.get("owner_id").is_none());
+    }
+
+    #[test]
+    fn test_unlock_policy_serde() {
+        use serde_json::json;
+
+        let cases = [
+            (UnlockPolicy::Majority, json!({ "type": "majority" })),
+            (
+                UnlockPolicy::Threshold(2),
+                json!({ "type": "threshold", "value": 2 }),
+            ),
+            (UnlockPolicy::AllLeads, json!({ "type": "allLeads" })),
+            (UnlockPolicy::AnyLead, json!({ "type": "anyLead" })),
+        ];
+        for (policy, value) in cases {
+            assert_eq!(serde_json::to_value(&policy).unwrap(), value);
+            assert_eq!(
+                serde_json::from_value::<UnlockPolicy>(value).unwrap(),
+                policy
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_rfc3339_converts_offsets_to_utc() {
+        assert_eq!(
+            normalize_rfc3339("2024-01-01T17:30:00+05:30").unwrap(),
+            "2024-01-01T12:00:00Z"
+        );
+        assert_eq!(
+            normalize_rfc3339("2024-01-01T12:00:00.250+00:00").unwrap(),
+            "2024-01-01T12:00:00.250Z"
+        );
+        assert_eq!(
+            normalize_rfc3339("2023-12-31T22:00:00-05:00").unwrap(),
+            "2024-01-01T03:00:00Z"
+        );
+        assert!(normalize_rfc3339("yesterday").is_err());
+        assert!(normalize_rfc3339("2024-01-01 12:00:00").is_err());
+        assert!(now_str().ends_with('Z'));
+    }
+
+    #[test]
+    fn test_legacy_document_defaults_to_text_plain() {
+        let document = Document {
+            id: "doc-1".to_string(),
+            title: "Will".to_string(),
+            content: "Contents".to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            storage: DocumentStorage::Inline,
+            content_type: "application/pdf".to_string(),
+        };
+
+        // A document stored before contentType existed
+        let mut item: std::collections::HashMap<String, aws_sdk_dynamodb::types::AttributeValue> =
+            serde_dynamo::to_item(&document).unwrap();
+        assert!(item.remove("contentType").is_some());
+
+        let legacy: Document = serde_dynamo::from_item(item).unwrap();
+        assert_eq!(legacy.content_type, DEFAULT_DOCUMENT_CONTENT_TYPE);
+
+        let guardian_view = GuardianDocument::from_document(&legacy, false);
+        assert_eq!(guardian_view.content_type, "text/plain");
+    }
+
+    #[test]
+    fn test_guardian_annotations() {
+        // Guardians stored before the annotations existed still load
+        let legacy: Guardian = serde_json::from_value(serde_json::json!({
+            "id": "guardian-1",
+            "name": "Guardian",
+            "leadGuardian": false,
+            "status": "accepted",
+            "addedAt": "2024-01-01T00:00:00Z",
+            "invitationId": "inv-1"
+        }))
+        .unwrap();
+        assert!(legacy.relationship.is_none());
+        assert!(legacy.note.is_none());
+
+        let mut guardian = Guardian {
+            email: Some("guardian@example.com".to_string()),
+            relationship: Some(" spouse ".to_string()),
+            note: Some("   ".to_string()),
+            ..legacy
+        };
+        guardian.normalize_annotations();
+        assert_eq!(guardian.relationship.as_deref(), Some("spouse"));
+        assert!(guardian.note.is_none());
+
+        let json = serde_json::to_value(&guardian).unwrap();
+        assert_eq!(json["relationship"], "spouse");
+        assert!(json.get("note").is_none());
+
+        let view = serde_json::to_value(guardian.guardian_view()).unwrap();
+        assert!(view.get("relationship").is_none());
+        assert!(view.get("email").is_none());
+        assert_eq!(view["name"], "Guardian");
+    }
 }
@@ -1,56 +1,203 @@
 use async_trait::async_trait;
-use aws_config::BehaviorVersion;
+use aws_config::{BehaviorVersion, ConfigLoader};
+use aws_sdk_dynamodb::config::retry::RetryConfig;
 use aws_sdk_dynamodb::error::SdkError;
 use aws_sdk_dynamodb::operation::delete_item::DeleteItemError;
 use aws_sdk_dynamodb::operation::get_item::GetItemError;
 use aws_sdk_dynamodb::operation::query::QueryError;
 use aws_sdk_dynamodb::operation::scan::ScanError;
-use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::operation::transact_write_items::TransactWriteItemsError;
+use aws_sdk_dynamodb::types::{
+    AttributeValue, DeleteRequest, KeysAndAttributes, Put, ReturnValue, Select, TransactWriteItem,
+    WriteRequest,
+};
 use aws_sdk_dynamodb::Client;
-use chrono::{Duration, Utc};
-use serde_dynamo::{from_item, to_item};
-use std::collections::HashMap;
+use chrono::Utc;
+use serde_dynamo::{from_item, to_attribute_value, to_item};
+use std::collections::{HashMap, HashSet};
 use std::env;
 
 use crate::error::{map_dynamo_error, Result, StoreError};
-use crate::models::{now_str, BoxRecord, Invitation};
+use crate::models::{
+    generate_invite_code, invitation_expires_at, invitation_ttl_hours, now_str, BoxRecord,
+    Guardian, GuardianStatus, Invitation,
+};
+use crate::store::retry::{max_attempts_from_env, retry_throttled, retry_transient, throttled_or};
+use crate::store::{decode_cursor, encode_cursor, soft_delete_enabled, MAX_INVITE_CODE_ATTEMPTS};
 
 // Invitation Store Constants
 const TABLE_NAME: &str = "invitation-table";
 const GSI_BOX_ID: &str = "box_id-index";
 const GSI_INVITE_CODE: &str = "invite_code-index";
 const GSI_CREATOR_ID: &str = "creatorId-index";
+const GSI_LINKED_USER_ID: &str = "linkedUserId-index";
+// Numeric unix-seconds copy of expires_at, configured as the table's TTL attribute
+pub const INVITATION_TTL_ATTRIBUTE: &str = "expiresAtEpoch";
+// BatchWriteItem accepts at most 25 requests per call
+const BATCH_WRITE_MAX_ITEMS: usize = 25;
+// Key prefix of the items that reserve invite codes in the invitation table
+const INVITE_CODE_ITEM_PREFIX: &str = "code#";
 
 // Box Store Constants
 const BOX_TABLE_NAME: &str = "box-table";
 const GSI_OWNER_ID: &str = "owner_id-index";
+// BatchGetItem accepts at most 100 keys per request
+const BATCH_GET_MAX_KEYS: usize = 100;
+const BATCH_GET_MAX_RETRIES: u64 = 5;
+
+// Guardian Index Constants
+const GSI_GUARDIAN_ID: &str = "guardian_id-index";
+const GSI_INDEX_BOX_ID: &str = "box_id-index";
+
+// Idempotency Store Constants
+const IDEMPOTENCY_TABLE_NAME: &str = "idempotency-table";
+// Numeric unix-seconds expiry of a key, configured as the table's TTL attribute
+pub const IDEMPOTENCY_TTL_ATTRIBUTE: &str = "expiresAtEpoch";
+
+// Overrides the DynamoDB endpoint, e.g. http://localhost:8000 for DynamoDB Local
+pub const DYNAMODB_ENDPOINT_URL_VAR: &str = "DYNAMODB_ENDPOINT_URL";
+
+/// Applies the `DYNAMODB_ENDPOINT_URL` override to an AWS config loader, if set
+pub fn with_endpoint_from_env(loader: ConfigLoader) -> ConfigLoader {
+    match env::var(DYNAMODB_ENDPOINT_URL_VAR) {
+        Ok(endpoint_url) if !endpoint_url.trim().is_empty() => {
+            log::info!("Using DynamoDB endpoint {}", endpoint_url);
+            loader.endpoint_url(endpoint_url.trim())
+        }
+        _ => loader,
+    }
+}
+
+// Client from the default AWS config, honouring DYNAMODB_ENDPOINT_URL
+async fn client_from_env() -> Client {
+    let config = with_endpoint_from_env(aws_config::defaults(BehaviorVersion::latest()))
+        .load()
+        .await;
+    Client::new(&config)
+}
 
 // DynamoInvitationStore
 
 pub struct DynamoInvitationStore {
     client: Client,
     table_name: String,
+    use_creator_scan: bool,
+    use_linked_user_scan: bool,
 }
 
 impl DynamoInvitationStore {
     pub async fn new() -> Self {
-        // Use the recommended defaults() function with latest behavior version
-        let config = aws_config::defaults(BehaviorVersion::latest()).load().await;
-
-        let client = Client::new(&config);
+        let client = client_from_env().await;
 
         // Use environment variable for table name if available
         let table_name =
             env::var("DYNAMODB_INVITATION_TABLE").unwrap_or_else(|_| TABLE_NAME.to_string());
 
-        Self { client, table_name }
+        Self {
+            client,
+            table_name,
+            use_creator_scan: creator_scan_enabled(),
+            use_linked_user_scan: linked_user_scan_enabled(),
+        }
     }
 
     /// Creates a new DynamoDB store with the specified client and table name.
     /// This is mainly useful for testing with a local DynamoDB instance.
     #[allow(dead_code)]
     pub fn with_client_and_table(client: Client, table_name: String) -> Self {
-        Self { client, table_name }
+        Self {
+            client,
+            table_name,
+            use_creator_scan: creator_scan_enabled(),
+            use_linked_user_scan: linked_user_scan_enabled(),
+        }
+    }
+
+    /// Forces creator lookups to use a full-table scan (or the index when false)
+    pub fn with_creator_scan(mut self, use_creator_scan: bool) -> Self {
+        self.use_creator_scan = use_creator_scan;
+        self
+    }
+
+    /// Forces linked user lookups to use a full-table scan (or the index when false)
+    pub fn with_linked_user_scan(mut self, use_linked_user_scan: bool) -> Self {
+        self.use_linked_user_scan = use_linked_user_scan;
+        self
+    }
+
+    /// Fills in created_at and expires_at when they are missing
+    fn prepare_new_invitation(&self, mut invitation: Invitation) -> Invitation {
+        if invitation.created_at.is_empty() {
+            invitation.created_at = now_str();
+        }
+
+        if invitation.expires_at.is_empty() {
+            // Set expiration using the configured invitation TTL
+            invitation.expires_at = invitation_expires_at(Utc::now(), invitation_ttl_hours());
+        }
+
+        invitation
+    }
+
+    /// Writes `invitation` in one transaction with the item reserving its invite
+    /// code, followed by `extra_items`. The reservation is conditional, so a code
+    /// another invitation holds (or an empty one) is swapped for a fresh code,
+    /// giving up after `MAX_INVITE_CODE_ATTEMPTS` codes. Other failures go
+    /// through `map_err`.
+    async fn write_reserving_code(
+        &self,
+        mut invitation: Invitation,
+        is_new: bool,
+        extra_items: &[TransactWriteItem],
+        map_err: impl Fn(SdkError<TransactWriteItemsError>) -> StoreError,
+    ) -> Result<Invitation> {
+        if invitation.invite_code.is_empty() {
+            invitation.invite_code = generate_invite_code();
+        }
+
+        for attempt in 1..=MAX_INVITE_CODE_ATTEMPTS {
+            let mut put_invitation = Put::builder()
+                .table_name(&self.table_name)
+                .set_item(Some(invitation_to_item(&invitation)?));
+            if is_new {
+                put_invitation = put_invitation.condition_expression("attribute_not_exists(id)");
+            }
+            let put_invitation = put_invitation
+                .build()
+                .map_err(|e| map_dynamo_error("transact_write_items", e))?;
+
+            let mut request = self
+                .client
+                .transact_write_items()
+                .transact_items(
+                    TransactWriteItem::builder()
+                        .put(invite_code_reservation(&self.table_name, &invitation)?)
+                        .build(),
+                )
+                .transact_items(TransactWriteItem::builder().put(put_invitation).build());
+            for item in extra_items {
+                request = request.transact_items(item.clone());
+            }
+
+            match request.send().await {
+                Ok(_) => return Ok(invitation),
+                // Reasons come back in item order; the reservation is the first item
+                Err(err) if cancellation_reason(&err, 0) == Some("ConditionalCheckFailed") => {
+                    log::warn!(
+                        "Invite code {:?} is taken (attempt {}/{}), generating another",
+                        invitation.invite_code,
+                        attempt,
+                        MAX_INVITE_CODE_ATTEMPTS
+                    );
+                    invitation.invite_code = generate_invite_code();
+                }
+                Err(err) => return Err(map_err(err)),
+            }
+        }
+
+        Err(StoreError::InternalError(
+            "Could not generate an unused invite code".to_string(),
+        ))
     }
 
     /// Helper method to check if an invitation has expired
@@ -61,67 +208,419 @@ impl DynamoInvitationStore {
 
         Ok(Utc::now() > expires_at)
     }
+
+    /// Gets a creator's invitations by querying the creator index
+    async fn query_invitations_by_creator_id(&self, creator_id: &str) -> Result<Vec<Invitation>> {
+        let mut invitations = Vec::new();
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name(GSI_CREATOR_ID)
+                .key_condition_expression("creatorId = :creatorId")
+                .expression_attribute_values(
+                    ":creatorId",
+                    AttributeValue::S(creator_id.to_string()),
+                )
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(map_query_dynamo_error)?;
+
+            for item in response.items() {
+                invitations.push(from_item(item.clone())?);
+            }
+
+            start_key = response.last_evaluated_key().cloned();
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(invitations)
+    }
+
+    /// Gets a creator's invitations with a filtered full-table scan (pre-index behaviour)
+    async fn scan_invitations_by_creator_id(&self, creator_id: &str) -> Result<Vec<Invitation>> {
+        log::warn!(
+            "Scanning {} for creator {}; the creator index is not in use",
+            self.table_name,
+            creator_id
+        );
+
+        let mut invitations = Vec::new();
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .filter_expression("creatorId = :creatorId")
+                .expression_attribute_values(
+                    ":creatorId",
+                    AttributeValue::S(creator_id.to_string()),
+                )
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(map_scan_dynamo_error)?;
+
+            for item in response.items() {
+                invitations.push(from_item(item.clone())?);
+            }
+
+            start_key = response.last_evaluated_key().cloned();
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(invitations)
+    }
+
+    /// Gets every invitation whose `attribute` equals `value` by querying `index`
+    async fn query_invitations_where(
+        &self,
+        index: &str,
+        attribute: &str,
+        value: &str,
+    ) -> Result<Vec<Invitation>> {
+        let mut invitations = Vec::new();
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name(index)
+                .key_condition_expression("#attr = :value")
+                .expression_attribute_names("#attr", attribute)
+                .expression_attribute_values(":value", AttributeValue::S(value.to_string()))
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(map_query_dynamo_error)?;
+
+            for item in response.items() {
+                invitations.push(from_item(item.clone())?);
+            }
+
+            start_key = response.last_evaluated_key().cloned();
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(invitations)
+    }
+
+    /// Gets every invitation whose `attribute` equals `value` with a filtered full-table scan
+    async fn scan_invitations_where(
+        &self,
+        attribute: &str,
+        value: &str,
+    ) -> Result<Vec<Invitation>> {
+        log::warn!(
+            "Scanning {} for {} = {}; no index is in use",
+            self.table_name,
+            attribute,
+            value
+        );
+
+        let mut invitations = Vec::new();
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .filter_expression("#attr = :value")
+                .expression_attribute_names("#attr", attribute)
+                .expression_attribute_values(":value", AttributeValue::S(value.to_string()))
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(map_scan_dynamo_error)?;
+
+            for item in response.items() {
+                invitations.push(from_item(item.clone())?);
+            }
+
+            start_key = response.last_evaluated_key().cloned();
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(invitations)
+    }
+
+    /// Counts a creator's invitations with a `Select::Count` query on the creator index
+    async fn query_invitation_count_by_creator_id(&self, creator_id: &str) -> Result<u64> {
+        let mut count = 0u64;
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name(GSI_CREATOR_ID)
+                .select(Select::Count)
+                .key_condition_expression("creatorId = :creatorId")
+                .expression_attribute_values(
+                    ":creatorId",
+                    AttributeValue::S(creator_id.to_string()),
+                )
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(map_query_dynamo_error)?;
+
+            count += response.count().max(0) as u64;
+
+            start_key = response.last_evaluated_key().cloned();
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Counts a creator's invitations with a filtered `Select::Count` scan (pre-index behaviour)
+    async fn scan_invitation_count_by_creator_id(&self, creator_id: &str) -> Result<u64> {
+        log::warn!(
+            "Scanning {} to count creator {}; the creator index is not in use",
+            self.table_name,
+            creator_id
+        );
+
+        let mut count = 0u64;
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .select(Select::Count)
+                .filter_expression("creatorId = :creatorId")
+                .expression_attribute_values(
+                    ":creatorId",
+                    AttributeValue::S(creator_id.to_string()),
+                )
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(map_scan_dynamo_error)?;
+
+            count += response.count().max(0) as u64;
+
+            start_key = response.last_evaluated_key().cloned();
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Invitations whose TTL attribute is already in the past. DynamoDB's
+    /// own TTL sweep can lag by days, so these may still be in the table.
+    async fn scan_expired_invitations(&self) -> Result<Vec<Invitation>> {
+        let mut invitations = Vec::new();
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .filter_expression("#ttl < :now AND NOT begins_with(id, :code_prefix)")
+                .expression_attribute_names("#ttl", INVITATION_TTL_ATTRIBUTE)
+                .expression_attribute_values(
+                    ":now",
+                    AttributeValue::N(Utc::now().timestamp().to_string()),
+                )
+                .expression_attribute_values(
+                    ":code_prefix",
+                    AttributeValue::S(INVITE_CODE_ITEM_PREFIX.to_string()),
+                )
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(map_scan_dynamo_error)?;
+
+            for item in response.items() {
+                invitations.push(from_item(item.clone())?);
+            }
+
+            start_key = response.last_evaluated_key().cloned();
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(invitations)
+    }
+
+    /// Deletes the given invitations with BatchWriteItem, resending anything
+    /// DynamoDB hands back as unprocessed
+    async fn batch_delete_invitations(&self, ids: &[String]) -> Result<()> {
+        for chunk in ids.chunks(BATCH_WRITE_MAX_ITEMS) {
+            let mut requests = chunk
+                .iter()
+                .map(|id| {
+                    let delete = DeleteRequest::builder()
+                        .key("id", AttributeValue::S(id.clone()))
+                        .build()
+                        .map_err(|e| map_dynamo_error("batch_write_item", e))?;
+                    Ok(WriteRequest::builder().delete_request(delete).build())
+                })
+                .collect::<Result<Vec<WriteRequest>>>()?;
+
+            let mut attempts = 0;
+            loop {
+                let response = self
+                    .client
+                    .batch_write_item()
+                    .request_items(&self.table_name, requests)
+                    .send()
+                    .await
+                    .map_err(|e| map_dynamo_error("batch_write_item", e))?;
+
+                match response
+                    .unprocessed_items()
+                    .and_then(|unprocessed| unprocessed.get(&self.table_name))
+                {
+                    Some(unprocessed) if !unprocessed.is_empty() => {
+                        attempts += 1;
+                        if attempts > BATCH_GET_MAX_RETRIES {
+                            return Err(StoreError::InternalError(
+                                "DynamoDB batch_write_item left items unprocessed".into(),
+                            ));
+                        }
+                        tokio::time::sleep(std::time::Duration::from_millis(50 * attempts)).await;
+                        requests = unprocessed.clone();
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Creator lookups fall back to a full scan where the creator index isn't provisioned
+fn creator_scan_enabled() -> bool {
+    env::var("INVITATION_CREATOR_SCAN").unwrap_or_default() == "true"
+}
+
+// Linked user lookups fall back to a full scan where the linked user index isn't provisioned
+fn linked_user_scan_enabled() -> bool {
+    env::var("INVITATION_LINKED_USER_SCAN").unwrap_or_default() == "true"
 }
 
 // DynamoBoxStore
 
+// The box store retries throttled calls itself (see store::retry). Leaving the
+// SDK's own retries on would multiply the attempts DYNAMODB_MAX_ATTEMPTS allows.
+fn without_sdk_retries(client: Client) -> Client {
+    let config = client
+        .config()
+        .to_builder()
+        .retry_config(RetryConfig::disabled())
+        .build();
+    Client::from_conf(config)
+}
+
 /// DynamoDB store for boxes
+///
+/// Guardian relationships are denormalized into a separate guardian index table
+/// (one item per guardian/box pair, keyed by `guardianId` through a GSI) so that
+/// `get_boxes_by_guardian_id` can query rather than scan the box table.
 pub struct DynamoBoxStore {
     client: Client,
     table_name: String,
+    guardian_index_table: String,
+    use_guardian_scan: bool,
+    soft_delete: bool,
+    max_attempts: u32,
 }
 
 impl DynamoBoxStore {
     /// Creates a new DynamoDB store
     pub async fn new() -> Self {
-        // Use the recommended defaults() function with latest behavior version
-        let config = aws_config::defaults(BehaviorVersion::latest()).load().await;
-
-        let client = Client::new(&config);
-
-        // Use environment variable for table name if available
-        let table_name = env::var("DYNAMODB_TABLE").unwrap_or_else(|_| BOX_TABLE_NAME.to_string());
-
-        Self { client, table_name }
+        Self::builder().build().await
     }
 
     /// Creates a new DynamoDB store with the specified client and table name.
     /// This is mainly useful for testing with a local DynamoDB instance.
+    /// The guardian index table defaults to `<table_name>-guardian-index`.
     #[allow(dead_code)]
     pub fn with_client_and_table(client: Client, table_name: String) -> Self {
-        Self { client, table_name }
+        let guardian_index_table = guardian_index_table_for(&table_name);
+        Self {
+            client: without_sdk_retries(client),
+            table_name,
+            guardian_index_table,
+            use_guardian_scan: guardian_scan_enabled(),
+            soft_delete: soft_delete_enabled(),
+            max_attempts: max_attempts_from_env(),
+        }
     }
-}
 
-#[async_trait]
-impl super::BoxStore for DynamoBoxStore {
-    /// Creates a new box record in DynamoDB
-    async fn create_box(&self, box_record: BoxRecord) -> Result<BoxRecord> {
-        let item = to_item(&box_record)?;
+    /// Overrides the guardian index table name
+    #[allow(dead_code)]
+    pub fn with_guardian_index_table(mut self, guardian_index_table: String) -> Self {
+        self.guardian_index_table = guardian_index_table;
+        self
+    }
 
-        self.client
-            .put_item()
-            .table_name(&self.table_name)
-            .set_item(Some(item))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("put_item", e))?;
+    /// Forces guardian lookups to use the legacy full-table scan (or the index when false)
+    #[allow(dead_code)]
+    pub fn with_guardian_scan(mut self, use_guardian_scan: bool) -> Self {
+        self.use_guardian_scan = use_guardian_scan;
+        self
+    }
 
-        Ok(box_record)
+    /// Keeps deleted boxes with `deletedAt` set instead of removing the item
+    pub fn with_soft_delete(mut self, soft_delete: bool) -> Self {
+        self.soft_delete = soft_delete;
+        self
     }
 
-    /// Gets a box by ID
-    async fn get_box(&self, id: &str) -> Result<BoxRecord> {
+    /// Name of the box table this store reads and writes
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Gets a box by ID whether or not it has been soft-deleted
+    async fn fetch_box(&self, id: &str, consistent_read: bool) -> Result<BoxRecord> {
         let key = HashMap::from([("id".to_string(), AttributeValue::S(id.to_string()))]);
 
-        let response = self
+        let request = self
             .client
             .get_item()
             .table_name(&self.table_name)
             .set_key(Some(key))
-            .send()
+            .consistent_read(consistent_read);
+
+        let response = retry_transient("get_item", self.max_attempts, || request.clone().send())
             .await
-            .map_err(|e| map_get_dynamo_error(e, id))?;
+            .map_err(|e| {
+                throttled_or("get_item", self.max_attempts, e, |e| {
+                    map_get_dynamo_error(e, id)
+                })
+            })?;
 
         let item = response
             .item()
@@ -131,6 +630,398 @@ impl super::BoxStore for DynamoBoxStore {
         Ok(box_record)
     }
 
+    /// Brings the guardian index in line with the guardians currently on the box.
+    /// Rejected guardians are not indexed.
+    /// Syncs the guardian index after a box write that has already committed.
+    /// Failing the request at that point would make the client retry a write that
+    /// landed, so errors are only logged. The sync diffs against what is indexed,
+    /// so the box's next write repairs any entries missed here.
+    async fn sync_guardian_index_after_write(&self, box_id: &str, guardians: &[Guardian]) {
+        if let Err(err) = self.sync_guardian_index(box_id, guardians).await {
+            log::error!(
+                "Guardian index out of sync for box {} until its next write: {}",
+                box_id,
+                err
+            );
+        }
+    }
+
+    async fn sync_guardian_index(&self, box_id: &str, guardians: &[Guardian]) -> Result<()> {
+        let wanted: HashSet<String> = guardians
+            .iter()
+            .filter(|guardian| guardian.status != GuardianStatus::Rejected)
+            .map(|guardian| guardian.id.clone())
+            .collect();
+
+        let existing = self.get_indexed_guardian_ids(box_id).await?;
+
+        for guardian_id in existing.difference(&wanted) {
+            self.client
+                .delete_item()
+                .table_name(&self.guardian_index_table)
+                .key(
+                    "id",
+                    AttributeValue::S(guardian_index_key(box_id, guardian_id)),
+                )
+                .send()
+                .await
+                .map_err(map_delete_dynamo_error)?;
+        }
+
+        for guardian_id in wanted.difference(&existing) {
+            let item = HashMap::from([
+                (
+                    "id".to_string(),
+                    AttributeValue::S(guardian_index_key(box_id, guardian_id)),
+                ),
+                (
+                    "guardianId".to_string(),
+                    AttributeValue::S(guardian_id.clone()),
+                ),
+                ("boxId".to_string(), AttributeValue::S(box_id.to_string())),
+            ]);
+
+            self.client
+                .put_item()
+                .table_name(&self.guardian_index_table)
+                .set_item(Some(item))
+                .send()
+                .await
+                .map_err(|e| map_dynamo_error("put_item", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the guardian ids currently indexed for a box
+    async fn get_indexed_guardian_ids(&self, box_id: &str) -> Result<HashSet<String>> {
+        let mut guardian_ids = HashSet::new();
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .query()
+                .table_name(&self.guardian_index_table)
+                .index_name(GSI_INDEX_BOX_ID)
+                .key_condition_expression("boxId = :box_id")
+                .expression_attribute_values(":box_id", AttributeValue::S(box_id.to_string()))
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(map_query_dynamo_error)?;
+
+            guardian_ids.extend(
+                response
+                    .items()
+                    .iter()
+                    .filter_map(|item| item.get("guardianId"))
+                    .filter_map(|value| value.as_s().ok().cloned()),
+            );
+
+            start_key = response.last_evaluated_key().cloned();
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(guardian_ids)
+    }
+
+    /// Gets boxes for a guardian by querying the guardian index
+    async fn query_boxes_by_guardian_id(&self, guardian_id: &str) -> Result<Vec<BoxRecord>> {
+        let mut items = Vec::new();
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .query()
+                .table_name(&self.guardian_index_table)
+                .index_name(GSI_GUARDIAN_ID)
+                .key_condition_expression("guardianId = :guardian_id")
+                .expression_attribute_values(
+                    ":guardian_id",
+                    AttributeValue::S(guardian_id.to_string()),
+                )
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(map_query_dynamo_error)?;
+
+            items.extend_from_slice(response.items());
+
+            start_key = response.last_evaluated_key().cloned();
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        self.load_indexed_boxes(guardian_id, &items).await
+    }
+
+    /// Gets one page of guardian index entries and the boxes they point at
+    async fn query_boxes_by_guardian_id_paged(
+        &self,
+        guardian_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<BoxRecord>, Option<String>)> {
+        let response = self
+            .client
+            .query()
+            .table_name(&self.guardian_index_table)
+            .index_name(GSI_GUARDIAN_ID)
+            .key_condition_expression("guardianId = :guardian_id")
+            .expression_attribute_values(":guardian_id", AttributeValue::S(guardian_id.to_string()))
+            .limit(page_limit(limit))
+            .set_exclusive_start_key(cursor_to_start_key(start_key)?)
+            .send()
+            .await
+            .map_err(map_query_dynamo_error)?;
+
+        let boxes = self
+            .load_indexed_boxes(guardian_id, response.items())
+            .await?;
+        Ok((boxes, start_key_to_cursor(response.last_evaluated_key())))
+    }
+
+    /// Loads the boxes referenced by guardian index items
+    async fn load_indexed_boxes(
+        &self,
+        guardian_id: &str,
+        items: &[HashMap<String, AttributeValue>],
+    ) -> Result<Vec<BoxRecord>> {
+        let box_ids: Vec<String> = items
+            .iter()
+            .filter_map(|item| item.get("boxId").and_then(|value| value.as_s().ok()))
+            .cloned()
+            .collect();
+
+        let boxes = super::BoxStore::get_boxes_by_ids(self, &box_ids).await?;
+
+        // The box record stays the source of truth; skip stale index entries
+        if boxes.len() < box_ids.len() {
+            log::warn!(
+                "Guardian index for {} references {} missing boxes",
+                guardian_id,
+                box_ids.len() - boxes.len()
+            );
+        }
+
+        Ok(boxes
+            .into_iter()
+            .filter(|box_record| is_active_guardian(box_record, guardian_id))
+            .collect())
+    }
+
+    /// Gets boxes for a guardian with a full table scan (pre-index behaviour)
+    async fn scan_boxes_by_guardian_id(&self, guardian_id: &str) -> Result<Vec<BoxRecord>> {
+        let response = self
+            .client
+            .scan()
+            .table_name(&self.table_name)
+            .send()
+            .await
+            .map_err(map_scan_dynamo_error)?;
+
+        let items = response.items();
+
+        let mut boxes = Vec::new();
+        for item in items {
+            let box_record: BoxRecord = from_item(item.clone())?;
+
+            if !box_record.is_deleted() && is_active_guardian(&box_record, guardian_id) {
+                boxes.push(box_record);
+            }
+        }
+
+        Ok(boxes)
+    }
+
+    /// Scans one page of the box table for a guardian's boxes
+    async fn scan_boxes_by_guardian_id_paged(
+        &self,
+        guardian_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<BoxRecord>, Option<String>)> {
+        let response = self
+            .client
+            .scan()
+            .table_name(&self.table_name)
+            .limit(page_limit(limit))
+            .set_exclusive_start_key(cursor_to_start_key(start_key)?)
+            .send()
+            .await
+            .map_err(map_scan_dynamo_error)?;
+
+        let mut boxes = Vec::new();
+        for item in response.items() {
+            let box_record: BoxRecord = from_item(item.clone())?;
+
+            if !box_record.is_deleted() && is_active_guardian(&box_record, guardian_id) {
+                boxes.push(box_record);
+            }
+        }
+
+        Ok((boxes, start_key_to_cursor(response.last_evaluated_key())))
+    }
+}
+
+/// Default guardian index table name for a given box table
+pub fn guardian_index_table_for(table_name: &str) -> String {
+    format!("{}-guardian-index", table_name)
+}
+
+// Guardian lookups fall back to a full scan while the index is being backfilled
+fn guardian_scan_enabled() -> bool {
+    env::var("GUARDIAN_LOOKUP_SCAN").unwrap_or_default() == "true"
+}
+
+fn guardian_index_key(box_id: &str, guardian_id: &str) -> String {
+    format!("{}#{}", box_id, guardian_id)
+}
+
+// Condition (with its attribute names and values) that makes a box write fail
+// when the stored item has moved on from `current_version`
+fn box_version_condition(
+    current_version: u64,
+) -> (
+    &'static str,
+    HashMap<String, String>,
+    HashMap<String, AttributeValue>,
+) {
+    let condition_expression = if current_version > 0 {
+        "#v = :current_version"
+    } else {
+        "attribute_not_exists(#v) OR #v = :current_version"
+    };
+
+    // "version" is a reserved word, hence the placeholder name
+    let expr_attr_names = HashMap::from([("#v".to_string(), "version".to_string())]);
+    let expr_attr_values = HashMap::from([(
+        ":current_version".to_string(),
+        AttributeValue::N(current_version.to_string()),
+    )]);
+
+    (condition_expression, expr_attr_names, expr_attr_values)
+}
+
+fn is_active_guardian(box_record: &BoxRecord, guardian_id: &str) -> bool {
+    box_record
+        .guardians
+        .iter()
+        .any(|guardian| guardian.id == guardian_id && guardian.status != GuardianStatus::Rejected)
+}
+
+#[async_trait]
+impl super::BoxStore for DynamoBoxStore {
+    /// Creates a new box record in DynamoDB
+    async fn create_box(&self, box_record: BoxRecord) -> Result<BoxRecord> {
+        let item = to_item(&box_record)?;
+
+        let request = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item));
+
+        retry_transient("put_item", self.max_attempts, || request.clone().send())
+            .await
+            .map_err(|e| {
+                throttled_or("put_item", self.max_attempts, e, |e| {
+                    map_dynamo_error("put_item", e)
+                })
+            })?;
+
+        self.sync_guardian_index_after_write(&box_record.id, &box_record.guardians)
+            .await;
+
+        Ok(box_record)
+    }
+
+    /// Gets a box by ID, treating soft-deleted boxes as not found
+    async fn get_box(&self, id: &str) -> Result<BoxRecord> {
+        let box_record = self.fetch_box(id, false).await?;
+        if box_record.is_deleted() {
+            return Err(StoreError::NotFound(format!("Box not found: {}", id)));
+        }
+        Ok(box_record)
+    }
+
+    async fn get_box_consistent(&self, id: &str) -> Result<BoxRecord> {
+        let box_record = self.fetch_box(id, true).await?;
+        if box_record.is_deleted() {
+            return Err(StoreError::NotFound(format!("Box not found: {}", id)));
+        }
+        Ok(box_record)
+    }
+
+    /// Gets several boxes by ID with BatchGetItem, skipping missing and deleted boxes
+    async fn get_boxes_by_ids(&self, ids: &[String]) -> Result<Vec<BoxRecord>> {
+        // BatchGetItem rejects duplicate keys, so dedupe while keeping the caller's order
+        let mut seen = HashSet::new();
+        let unique_ids: Vec<&String> = ids.iter().filter(|id| seen.insert(*id)).collect();
+
+        let mut found: HashMap<String, BoxRecord> = HashMap::new();
+        for chunk in unique_ids.chunks(BATCH_GET_MAX_KEYS) {
+            let keys = chunk
+                .iter()
+                .map(|id| HashMap::from([("id".to_string(), AttributeValue::S(id.to_string()))]))
+                .collect();
+            let mut request = KeysAndAttributes::builder()
+                .set_keys(Some(keys))
+                .build()
+                .map_err(|e| map_dynamo_error("batch_get_item", e))?;
+
+            // DynamoDB may hand back part of the batch as unprocessed under load
+            let mut attempts = 0;
+            loop {
+                let response = self
+                    .client
+                    .batch_get_item()
+                    .request_items(&self.table_name, request)
+                    .send()
+                    .await
+                    .map_err(|e| map_dynamo_error("batch_get_item", e))?;
+
+                if let Some(items) = response
+                    .responses()
+                    .and_then(|responses| responses.get(&self.table_name))
+                {
+                    for item in items {
+                        let box_record: BoxRecord = from_item(item.clone())?;
+                        found.insert(box_record.id.clone(), box_record);
+                    }
+                }
+
+                match response
+                    .unprocessed_keys()
+                    .and_then(|unprocessed| unprocessed.get(&self.table_name))
+                {
+                    Some(unprocessed) if !unprocessed.keys().is_empty() => {
+                        attempts += 1;
+                        if attempts > BATCH_GET_MAX_RETRIES {
+                            return Err(StoreError::InternalError(
+                                "DynamoDB batch_get_item left keys unprocessed".into(),
+                            ));
+                        }
+                        tokio::time::sleep(std::time::Duration::from_millis(50 * attempts)).await;
+                        request = unprocessed.clone();
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        Ok(unique_ids
+            .into_iter()
+            .filter_map(|id| found.remove(id))
+            .filter(|b| !b.is_deleted())
+            .collect())
+    }
+
     /// Gets all boxes owned by a user
     async fn get_boxes_by_owner(&self, owner_id: &str) -> Result<Vec<BoxRecord>> {
         let expr_attr_names = HashMap::from([("#owner_id".to_string(), "ownerId".to_string())]);
@@ -146,6 +1037,7 @@ impl super::BoxStore for DynamoBoxStore {
             .table_name(&self.table_name)
             .index_name(GSI_OWNER_ID) // Use the GSI
             .key_condition_expression("#owner_id = :owner_id")
+            .filter_expression("attribute_not_exists(deletedAt)")
             .set_expression_attribute_names(Some(expr_attr_names))
             .set_expression_attribute_values(Some(expr_attr_values))
             .send()
@@ -164,6 +1056,69 @@ impl super::BoxStore for DynamoBoxStore {
         Ok(boxes)
     }
 
+    /// Gets one page of boxes owned by a user
+    async fn get_boxes_by_owner_paged(
+        &self,
+        owner_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<BoxRecord>, Option<String>)> {
+        let response = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .index_name(GSI_OWNER_ID)
+            .key_condition_expression("ownerId = :owner_id")
+            .filter_expression("attribute_not_exists(deletedAt)")
+            .expression_attribute_values(":owner_id", AttributeValue::S(owner_id.to_string()))
+            .limit(page_limit(limit))
+            .set_exclusive_start_key(cursor_to_start_key(start_key)?)
+            .send()
+            .await
+            .map_err(map_query_dynamo_error)?;
+
+        let mut boxes = Vec::new();
+        for item in response.items() {
+            let box_record = from_item(item.clone())?;
+            boxes.push(box_record);
+        }
+
+        Ok((boxes, start_key_to_cursor(response.last_evaluated_key())))
+    }
+
+    /// Counts a user's boxes with a `Select::Count` query on the owner index, so
+    /// no items are read back. Follows the key across pages since a count stops
+    /// at the same 1 MB limit as a normal query.
+    async fn count_boxes_by_owner(&self, owner_id: &str) -> Result<u64> {
+        let mut count = 0u64;
+        let mut start_key = None;
+
+        loop {
+            let response = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name(GSI_OWNER_ID)
+                .select(Select::Count)
+                .key_condition_expression("ownerId = :owner_id")
+                .filter_expression("attribute_not_exists(deletedAt)")
+                .expression_attribute_values(":owner_id", AttributeValue::S(owner_id.to_string()))
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(map_query_dynamo_error)?;
+
+            count += response.count().max(0) as u64;
+
+            start_key = response.last_evaluated_key().cloned();
+            if start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(count)
+    }
+
     /// Updates a box
     async fn update_box(&self, box_record: BoxRecord) -> Result<BoxRecord> {
         // Clone the box record for modification
@@ -177,24 +1132,11 @@ impl super::BoxStore for DynamoBoxStore {
         updated_box.version = current_version + 1;
 
         // Convert to DynamoDB item
-        let item = to_item(&updated_box)?;
-
-        // Create a conditional expression to check the version
-        let condition_expression = if current_version > 0 {
-            "#v = :current_version"
-        } else {
-            "attribute_not_exists(#v) OR #v = :current_version"
-        };
-
-        // Create expression attribute values for version check
-        let mut expr_attr_values = HashMap::new();
-        expr_attr_values.insert(
-            ":current_version".to_string(),
-            AttributeValue::N(current_version.to_string()),
-        );
+        let item = to_item(&updated_box)?;
 
-        // Create expression attribute names to handle reserved keywords
-        let expr_attr_names = HashMap::from([("#v".to_string(), "version".to_string())]);
+        // Only write over the version this update was based on
+        let (condition_expression, expr_attr_names, expr_attr_values) =
+            box_version_condition(current_version);
 
         // Build the update request with conditional expression
         let request = self
@@ -206,9 +1148,13 @@ impl super::BoxStore for DynamoBoxStore {
             .set_expression_attribute_values(Some(expr_attr_values))
             .set_expression_attribute_names(Some(expr_attr_names));
 
-        // Execute the update
-        match request.send().await {
-            Ok(_) => Ok(updated_box),
+        // Execute the update, backing off while DynamoDB is throttling
+        match retry_throttled("put_item", self.max_attempts, || request.clone().send()).await {
+            Ok(_) => {
+                self.sync_guardian_index_after_write(&updated_box.id, &updated_box.guardians)
+                    .await;
+                Ok(updated_box)
+            }
             Err(err) => {
                 // Check if it's a conditional check failure (version mismatch)
                 if let SdkError::ServiceError(service_err) = &err {
@@ -222,13 +1168,107 @@ impl super::BoxStore for DynamoBoxStore {
                 }
 
                 // Other error
-                Err(map_dynamo_error("put_item", err))
+                Err(throttled_or("put_item", self.max_attempts, err, |e| {
+                    map_dynamo_error("put_item", e)
+                }))
+            }
+        }
+    }
+
+    /// Updates only the guardian's list entry with UpdateItem. The condition pins
+    /// the entry to the guardian, so a concurrent edit that reorders the list
+    /// fails the write instead of stamping someone else.
+    async fn record_guardian_view(
+        &self,
+        box_id: &str,
+        guardian_id: &str,
+        viewed_at: &str,
+    ) -> Result<BoxRecord> {
+        let box_record = super::BoxStore::get_box_consistent(self, box_id).await?;
+        let Some(index) = box_record
+            .guardians
+            .iter()
+            .position(|g| g.id == guardian_id && g.status != GuardianStatus::Rejected)
+        else {
+            return Ok(box_record);
+        };
+
+        let entry = format!("guardians[{}]", index);
+        // The version bump makes an owner write based on an earlier read fail its
+        // condition and retry, rather than put the old guardian entry back
+        let mut update_expression = format!(
+            "SET {}.lastViewedAt = :viewed_at, #v = if_not_exists(#v, :zero) + :one",
+            entry
+        );
+        let mut expr_attr_names = HashMap::from([
+            ("#guardian_id".to_string(), "id".to_string()),
+            ("#v".to_string(), "version".to_string()),
+        ]);
+        let mut expr_attr_values = HashMap::from([
+            (
+                ":viewed_at".to_string(),
+                AttributeValue::S(viewed_at.to_string()),
+            ),
+            (
+                ":guardian_id".to_string(),
+                AttributeValue::S(guardian_id.to_string()),
+            ),
+            (":zero".to_string(), AttributeValue::N("0".to_string())),
+            (":one".to_string(), AttributeValue::N("1".to_string())),
+        ]);
+        if box_record.guardians[index].status == GuardianStatus::Invited {
+            update_expression.push_str(&format!(", {}.#status = :viewed", entry));
+            expr_attr_names.insert("#status".to_string(), "status".to_string());
+            expr_attr_values.insert(
+                ":viewed".to_string(),
+                to_attribute_value(GuardianStatus::Viewed)?,
+            );
+        }
+
+        let request = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("id", AttributeValue::S(box_id.to_string()))
+            .update_expression(update_expression)
+            .condition_expression(format!("{}.#guardian_id = :guardian_id", entry))
+            .set_expression_attribute_names(Some(expr_attr_names))
+            .set_expression_attribute_values(Some(expr_attr_values))
+            .return_values(ReturnValue::AllNew);
+
+        match retry_throttled("update_item", self.max_attempts, || request.clone().send()).await {
+            Ok(response) => {
+                let item = response.attributes().cloned().unwrap_or_default();
+                Ok(from_item(item)?)
+            }
+            Err(err) => {
+                if let SdkError::ServiceError(service_err) = &err {
+                    if service_err.err().is_conditional_check_failed_exception() {
+                        return Err(StoreError::VersionConflict(format!(
+                            "Guardian {} moved on box {} while recording a view",
+                            guardian_id, box_id
+                        )));
+                    }
+                }
+
+                Err(throttled_or("update_item", self.max_attempts, err, |e| {
+                    map_dynamo_error("update_item", e)
+                }))
             }
         }
     }
 
-    /// Deletes a box
+    /// Deletes a box, or marks it deleted when soft delete is enabled
     async fn delete_box(&self, id: &str) -> Result<()> {
+        if self.soft_delete {
+            // Guardian index entries are kept so a restore doesn't need a resync;
+            // lookups go through get_box, which hides the deleted box
+            let mut box_record = super::BoxStore::get_box(self, id).await?;
+            box_record.deleted_at = Some(now_str());
+            super::BoxStore::update_box(self, box_record).await?;
+            return Ok(());
+        }
+
         let key = HashMap::from([("id".to_string(), AttributeValue::S(id.to_string()))]);
 
         self.client
@@ -239,83 +1279,89 @@ impl super::BoxStore for DynamoBoxStore {
             .await
             .map_err(|e| map_delete_dynamo_error(e))?;
 
+        // Drop the box's guardian index entries along with it
+        self.sync_guardian_index_after_write(id, &[]).await;
+
         Ok(())
     }
 
     /// Gets all boxes where the given user is a guardian (with status not rejected)
     ///
-    /// Implementation notes:
-    /// - Currently uses a full table scan since guardians are stored in nested arrays within the BoxRecord
-    /// - For production systems with many boxes, this could be improved by:
-    ///   1. Creating a new GSI with a composite key or
-    ///   2. Creating a separate guardian-to-box mapping table with a GSI
-    ///   3. Using DynamoDB's new document path capabilities for filtering
+    /// Queries the guardian index by default. Set `GUARDIAN_LOOKUP_SCAN=true` to fall
+    /// back to a full table scan while migrating existing boxes into the index.
     async fn get_boxes_by_guardian_id(&self, guardian_id: &str) -> Result<Vec<BoxRecord>> {
-        // Currently we perform a full table scan as guardian information is stored in an array within
-        // the box document, not as a separate attribute that can be indexed. In the future, we could
-        // create a separate table or GSI for guardian relationships.
-
-        let response = self
-            .client
-            .scan()
-            .table_name(&self.table_name)
-            .send()
-            .await
-            .map_err(|e| map_scan_dynamo_error(e))?;
-
-        let items = response.items();
-
-        let mut boxes = Vec::new();
-        for item in items {
-            let box_record: BoxRecord = from_item(item.clone())?;
+        if self.use_guardian_scan {
+            self.scan_boxes_by_guardian_id(guardian_id).await
+        } else {
+            self.query_boxes_by_guardian_id(guardian_id).await
+        }
+    }
 
-            // Check if the user is a guardian for this box
-            let is_guardian = box_record.guardians.iter().any(|guardian| {
-                guardian.id == guardian_id
-                    && guardian.status != crate::models::GuardianStatus::Rejected
-            });
+    /// Gets one page of a guardian's boxes, from the index or a scan like the unpaged lookup
+    async fn get_boxes_by_guardian_id_paged(
+        &self,
+        guardian_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<BoxRecord>, Option<String>)> {
+        if self.use_guardian_scan {
+            self.scan_boxes_by_guardian_id_paged(guardian_id, limit, start_key)
+                .await
+        } else {
+            self.query_boxes_by_guardian_id_paged(guardian_id, limit, start_key)
+                .await
+        }
+    }
 
-            if is_guardian {
-                boxes.push(box_record);
-            }
+    /// Gets a soft-deleted box by ID
+    async fn get_deleted_box(&self, id: &str) -> Result<BoxRecord> {
+        let box_record = self.fetch_box(id, true).await?;
+        if !box_record.is_deleted() {
+            return Err(StoreError::NotFound(format!(
+                "Deleted box not found: {}",
+                id
+            )));
         }
+        Ok(box_record)
+    }
 
-        Ok(boxes)
+    /// Restores a soft-deleted box
+    async fn restore_box(&self, id: &str) -> Result<BoxRecord> {
+        let mut box_record = self.get_deleted_box(id).await?;
+        box_record.deleted_at = None;
+        super::BoxStore::update_box(self, box_record).await
+    }
+
+    /// DescribeTable on the box table; no retries, so an outage shows up at once
+    async fn ping(&self) -> Result<()> {
+        self.client
+            .describe_table()
+            .table_name(&self.table_name)
+            .send()
+            .await
+            .map_err(|e| map_dynamo_error("describe_table", e))?;
+        Ok(())
     }
 }
 
 // INVITATION STORE IMPLEMENTATION
 #[async_trait]
 impl super::InvitationStore for DynamoInvitationStore {
-    async fn create_invitation(&self, mut invitation: Invitation) -> Result<Invitation> {
-        // Set created_at and expires_at if not already set
-        if invitation.created_at.is_empty() {
-            invitation.created_at = Utc::now().to_rfc3339();
-        }
-
-        if invitation.expires_at.is_empty() {
-            // Set expiration to 48 hours from now
-            invitation.expires_at = (Utc::now() + Duration::hours(48)).to_rfc3339();
-        }
+    async fn create_invitation(&self, invitation: Invitation) -> Result<Invitation> {
+        let invitation = self.prepare_new_invitation(invitation);
 
         log::debug!(
-            "Storing invitation id={}, creator_id={}, invite_code={}, to table={}",
+            "Storing invitation id={}, creator_id={}, to table={}",
             invitation.id,
             invitation.creator_id,
-            invitation.invite_code,
             self.table_name
         );
 
-        // Convert to DynamoDB item
-        let item = to_item(invitation.clone())?;
-
-        self.client
-            .put_item()
-            .table_name(&self.table_name)
-            .set_item(Some(item))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("put_item", e))?;
+        let invitation = self
+            .write_reserving_code(invitation, true, &[], |e| {
+                map_dynamo_error("transact_write_items", e)
+            })
+            .await?;
 
         log::debug!("Successfully stored invitation {}", invitation.id);
         Ok(invitation)
@@ -374,6 +1420,22 @@ impl super::InvitationStore for DynamoInvitationStore {
             )));
         }
 
+        // Codes should be unique; refuse to guess between duplicates
+        if items.len() > 1 {
+            let ids: Vec<&str> = items
+                .iter()
+                .filter_map(|item| item.get("id").and_then(|id| id.as_s().ok()))
+                .map(String::as_str)
+                .collect();
+            log::error!(
+                "Invite code {} matches {} invitations: {:?}",
+                invite_code,
+                items.len(),
+                ids
+            );
+            return Err(StoreError::AmbiguousInviteCode);
+        }
+
         let invitation: Invitation = from_item(items[0].clone())?;
 
         // Check if the invitation has expired
@@ -395,23 +1457,18 @@ impl super::InvitationStore for DynamoInvitationStore {
         // 3. Create a conditional expression to check current version
         // 4. Handle ConditionalCheckFailedException as StoreError::VersionConflict
 
-        // Convert to DynamoDB item
-        let item = to_item(invitation.clone())?;
-
-        self.client
-            .put_item()
-            .table_name(&self.table_name)
-            .set_item(Some(item))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("put_item", e))?;
-
-        Ok(invitation)
+        // The reservation is rewritten too, so a refreshed code is claimed before it is
+        // stored and the reservation's TTL keeps up with a refreshed expiry. The old
+        // code's reservation is left to lapse with its own TTL.
+        self.write_reserving_code(invitation, false, &[], |e| {
+            map_dynamo_error("transact_write_items", e)
+        })
+        .await
     }
 
     async fn delete_invitation(&self, id: &str) -> Result<()> {
         // Verify invitation exists first
-        self.get_invitation(id).await?;
+        let invitation = self.get_invitation(id).await?;
 
         let key = HashMap::from([("id".to_string(), AttributeValue::S(id.to_string()))]);
 
@@ -423,6 +1480,30 @@ impl super::InvitationStore for DynamoInvitationStore {
             .await
             .map_err(|e| map_dynamo_error("delete_item", e))?;
 
+        // Release the code, unless its reservation already lapsed and another invitation took it
+        let released = self
+            .client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key(
+                "id",
+                AttributeValue::S(invite_code_item_id(&invitation.invite_code)),
+            )
+            .condition_expression("invitationId = :invitation_id")
+            .expression_attribute_values(":invitation_id", AttributeValue::S(id.to_string()))
+            .send()
+            .await;
+        match released {
+            Err(SdkError::ServiceError(err))
+                if matches!(
+                    err.err(),
+                    DeleteItemError::ConditionalCheckFailedException(_)
+                ) => {}
+            result => {
+                result.map_err(|e| map_dynamo_error("delete_item", e))?;
+            }
+        }
+
         Ok(())
     }
 
@@ -444,81 +1525,366 @@ impl super::InvitationStore for DynamoInvitationStore {
 
         let items = result.items();
 
+        // Expired invitations are returned too; callers decide whether to show them
         let mut invitations = Vec::new();
         for item in items {
             let invitation: Invitation = from_item(item.clone())?;
-            // Filter out expired invitations
-            if !self.is_expired(&invitation.expires_at)? {
-                invitations.push(invitation);
-            }
+            invitations.push(invitation);
         }
 
         Ok(invitations)
     }
 
+    /// Gets a creator's invitations from the creator index, or with a full table
+    /// scan when INVITATION_CREATOR_SCAN is set and the index isn't provisioned yet
     async fn get_invitations_by_creator_id(&self, creator_id: &str) -> Result<Vec<Invitation>> {
-        log::info!(
-            "Querying table {} for invitations with creator_id={}, using GSI: {}",
-            self.table_name,
-            creator_id,
-            GSI_CREATOR_ID
-        );
-
-        // Create expression attribute values
-        let expr_attr_values = HashMap::from([(
-            ":creatorId".to_string(),
-            AttributeValue::S(creator_id.to_string()),
-        )]);
+        if self.use_creator_scan {
+            self.scan_invitations_by_creator_id(creator_id).await
+        } else {
+            self.query_invitations_by_creator_id(creator_id).await
+        }
+    }
 
-        log::info!("Query parameters: table={}, index={}, expression=creatorId = :creatorId, values={:?}", 
-                  self.table_name, GSI_CREATOR_ID, expr_attr_values);
+    /// Gets a user's linked invitations from the linked user index, or with a full
+    /// table scan when INVITATION_LINKED_USER_SCAN is set and the index isn't provisioned yet
+    async fn get_invitations_by_linked_user(&self, user_id: &str) -> Result<Vec<Invitation>> {
+        if self.use_linked_user_scan {
+            self.scan_invitations_where("linkedUserId", user_id).await
+        } else {
+            self.query_invitations_where(GSI_LINKED_USER_ID, "linkedUserId", user_id)
+                .await
+        }
+    }
 
-        let result = self
+    async fn get_invitations_by_creator_id_paged(
+        &self,
+        creator_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<Invitation>, Option<String>)> {
+        let response = self
             .client
             .query()
             .table_name(&self.table_name)
             .index_name(GSI_CREATOR_ID)
             .key_condition_expression("creatorId = :creatorId")
-            .set_expression_attribute_values(Some(expr_attr_values))
+            .expression_attribute_values(":creatorId", AttributeValue::S(creator_id.to_string()))
+            .limit(page_limit(limit))
+            .set_exclusive_start_key(cursor_to_start_key(start_key)?)
             .send()
             .await
-            .map_err(|e| map_dynamo_error("query", e))?;
-
-        let items = result.items();
-        log::info!("Found {} items for creator_id={}", items.len(), creator_id);
-
-        if items.is_empty() {
-            log::info!("No items found. Checking if this is a case sensitivity issue or wrong attribute name...");
-            // Let's try to scan a few items to see what creator_id values actually exist
-            let scan_result = self
-                .client
-                .scan()
-                .table_name(&self.table_name)
-                .limit(5)
-                .send()
-                .await
-                .map_err(|e| map_dynamo_error("scan", e))?;
-            
-            let scan_items = scan_result.items();
-            log::info!("Sample scan found {} items in table", scan_items.len());
-            for (i, item) in scan_items.iter().enumerate() {
-                if let Some(creator_attr) = item.get("creatorId") {
-                    log::info!("Sample item {}: creatorId = {:?}", i, creator_attr);
-                } else {
-                    log::info!("Sample item {}: no creatorId attribute found, available attributes: {:?}", i, item.keys().collect::<Vec<_>>());
-                }
-            }
-        }
+            .map_err(map_query_dynamo_error)?;
 
         let mut invitations = Vec::new();
-        for item in items {
+        for item in response.items() {
             let invitation: Invitation = from_item(item.clone())?;
             invitations.push(invitation);
         }
+
+        Ok((
+            invitations,
+            start_key_to_cursor(response.last_evaluated_key()),
+        ))
+    }
+
+    /// Counts a creator's invitations, falling back to a scan like
+    /// `get_invitations_by_creator_id` when INVITATION_CREATOR_SCAN is set
+    async fn count_invitations_by_creator(&self, creator_id: &str) -> Result<u64> {
+        if self.use_creator_scan {
+            self.scan_invitation_count_by_creator_id(creator_id).await
+        } else {
+            self.query_invitation_count_by_creator_id(creator_id).await
+        }
+    }
+
+    /// Scans for invitations past their TTL and batch-deletes them
+    async fn purge_expired_invitations(&self) -> Result<Vec<Invitation>> {
+        let invitations = self.scan_expired_invitations().await?;
+        // Code reservations carry the same TTL and are left to DynamoDB's sweep
+        let ids: Vec<String> = invitations.iter().map(|inv| inv.id.clone()).collect();
+        self.batch_delete_invitations(&ids).await?;
+
+        log::info!(
+            "Purged {} expired invitations from {}",
+            ids.len(),
+            self.table_name
+        );
         Ok(invitations)
     }
 }
 
+/// Converts an invitation to a DynamoDB item, adding the `expiresAtEpoch` TTL attribute
+/// so DynamoDB reaps the item once it has expired
+pub(crate) fn invitation_to_item(
+    invitation: &Invitation,
+) -> Result<HashMap<String, AttributeValue>> {
+    let mut item: HashMap<String, AttributeValue> = to_item(invitation)?;
+
+    // The linked user index is sparse: an unlinked invitation must leave the key
+    // attribute out entirely, as DynamoDB rejects a NULL index key
+    if invitation.linked_user_id.is_none() {
+        item.remove("linkedUserId");
+    }
+
+    let expires_at = chrono::DateTime::parse_from_rfc3339(&invitation.expires_at)
+        .map_err(|_| StoreError::InternalError("Invalid expiration date format".to_string()))?;
+    item.insert(
+        INVITATION_TTL_ATTRIBUTE.to_string(),
+        AttributeValue::N(expires_at.timestamp().to_string()),
+    );
+
+    Ok(item)
+}
+
+/// Key of the item reserving `invite_code` in the invitation table
+pub(crate) fn invite_code_item_id(invite_code: &str) -> String {
+    format!("{}{}", INVITE_CODE_ITEM_PREFIX, invite_code)
+}
+
+/// The item reserving an invitation's code. It carries the invitation's TTL so it
+/// expires with it, and its condition only lets the same invitation rewrite it.
+pub(crate) fn invite_code_item(invitation: &Invitation) -> Result<HashMap<String, AttributeValue>> {
+    let expires_at = chrono::DateTime::parse_from_rfc3339(&invitation.expires_at)
+        .map_err(|_| StoreError::InternalError("Invalid expiration date format".to_string()))?;
+
+    Ok(HashMap::from([
+        (
+            "id".to_string(),
+            AttributeValue::S(invite_code_item_id(&invitation.invite_code)),
+        ),
+        (
+            "invitationId".to_string(),
+            AttributeValue::S(invitation.id.clone()),
+        ),
+        (
+            INVITATION_TTL_ATTRIBUTE.to_string(),
+            AttributeValue::N(expires_at.timestamp().to_string()),
+        ),
+    ]))
+}
+
+fn invite_code_reservation(table_name: &str, invitation: &Invitation) -> Result<Put> {
+    Put::builder()
+        .table_name(table_name)
+        .set_item(Some(invite_code_item(invitation)?))
+        .condition_expression("attribute_not_exists(id) OR invitationId = :invitation_id")
+        .expression_attribute_values(":invitation_id", AttributeValue::S(invitation.id.clone()))
+        .build()
+        .map_err(|e| map_dynamo_error("transact_write_items", e))
+}
+
+/// Cancellation reason code of the `index`th item of a cancelled transaction
+fn cancellation_reason(err: &SdkError<TransactWriteItemsError>, index: usize) -> Option<&str> {
+    match err {
+        SdkError::ServiceError(service_err) => match service_err.err() {
+            TransactWriteItemsError::TransactionCanceledException(cancelled) => cancelled
+                .cancellation_reasons()
+                .get(index)
+                .and_then(|reason| reason.code()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Helper functions for cursor-based pagination
+fn page_limit(limit: usize) -> i32 {
+    i32::try_from(limit.max(1)).unwrap_or(i32::MAX)
+}
+
+fn cursor_to_start_key(cursor: Option<String>) -> Result<Option<HashMap<String, AttributeValue>>> {
+    match cursor {
+        Some(cursor) => {
+            let key = decode_cursor(&cursor)?
+                .into_iter()
+                .map(|(name, value)| (name, AttributeValue::S(value)))
+                .collect();
+            Ok(Some(key))
+        }
+        None => Ok(None),
+    }
+}
+
+fn start_key_to_cursor(key: Option<&HashMap<String, AttributeValue>>) -> Option<String> {
+    let key: HashMap<String, String> = key?
+        .iter()
+        .filter_map(|(name, value)| value.as_s().ok().map(|v| (name.clone(), v.clone())))
+        .collect();
+
+    Some(encode_cursor(&key))
+}
+
+// DynamoInvitationBoxTransaction
+
+/// Writes an invitation and its box's guardian placeholder with a single
+/// TransactWriteItems call, so either both land or neither does. Both tables
+/// have to be in the same account and region.
+pub struct DynamoInvitationBoxTransaction {
+    invitations: DynamoInvitationStore,
+    boxes: DynamoBoxStore,
+}
+
+impl DynamoInvitationBoxTransaction {
+    pub fn new(invitations: DynamoInvitationStore, boxes: DynamoBoxStore) -> Self {
+        Self { invitations, boxes }
+    }
+}
+
+#[async_trait]
+impl super::InvitationBoxTransaction for DynamoInvitationBoxTransaction {
+    async fn create_invitation_with_box(
+        &self,
+        invitation: Invitation,
+        box_record: BoxRecord,
+    ) -> Result<(Invitation, BoxRecord)> {
+        let invitation = self.invitations.prepare_new_invitation(invitation);
+
+        let mut updated_box = box_record;
+        updated_box.updated_at = now_str();
+        let current_version = updated_box.version;
+        updated_box.version = current_version + 1;
+
+        let (condition_expression, expr_attr_names, expr_attr_values) =
+            box_version_condition(current_version);
+        let put_box = Put::builder()
+            .table_name(&self.boxes.table_name)
+            .set_item(Some(to_item(&updated_box)?))
+            .condition_expression(condition_expression)
+            .set_expression_attribute_names(Some(expr_attr_names))
+            .set_expression_attribute_values(Some(expr_attr_values))
+            .build()
+            .map_err(|e| map_dynamo_error("transact_write_items", e))?;
+
+        // The box follows the code reservation and the invitation, so it is the third item
+        let invitation = self
+            .invitations
+            .write_reserving_code(
+                invitation,
+                true,
+                &[TransactWriteItem::builder().put(put_box).build()],
+                |err| {
+                    if cancellation_reason(&err, 2) == Some("ConditionalCheckFailed") {
+                        return StoreError::VersionConflict(format!(
+                            "Box update conflict: id={}, version={}",
+                            updated_box.id, current_version
+                        ));
+                    }
+                    map_dynamo_error("transact_write_items", err)
+                },
+            )
+            .await?;
+
+        // The guardian index is derived data and is kept in sync outside the transaction.
+        // The invitation is committed by now, so an index failure mustn't fail the request.
+        self.boxes
+            .sync_guardian_index_after_write(&updated_box.id, &updated_box.guardians)
+            .await;
+
+        Ok((invitation, updated_box))
+    }
+}
+
+// DynamoIdempotencyStore
+
+/// IdempotencyStore backed by the table in IDEMPOTENCY_TABLE, keyed by `key`.
+/// DynamoDB's TTL removes expired keys eventually; until then the claim's
+/// condition treats them as free.
+pub struct DynamoIdempotencyStore {
+    client: Client,
+    table_name: String,
+}
+
+impl DynamoIdempotencyStore {
+    pub async fn new() -> Self {
+        let table_name =
+            env::var("IDEMPOTENCY_TABLE").unwrap_or_else(|_| IDEMPOTENCY_TABLE_NAME.to_string());
+        Self::with_client_and_table(client_from_env().await, table_name)
+    }
+
+    /// Creates a store with the specified client and table name, e.g. for DynamoDB Local
+    pub fn with_client_and_table(client: Client, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+}
+
+#[async_trait]
+impl super::IdempotencyStore for DynamoIdempotencyStore {
+    async fn claim(
+        &self,
+        key: &str,
+        resource_id: &str,
+        ttl: std::time::Duration,
+    ) -> Result<Option<String>> {
+        let now = Utc::now().timestamp();
+        let item = HashMap::from([
+            ("key".to_string(), AttributeValue::S(key.to_string())),
+            (
+                "resourceId".to_string(),
+                AttributeValue::S(resource_id.to_string()),
+            ),
+            (
+                IDEMPOTENCY_TTL_ATTRIBUTE.to_string(),
+                AttributeValue::N((now + ttl.as_secs() as i64).to_string()),
+            ),
+        ]);
+
+        let result = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .condition_expression("attribute_not_exists(#k) OR #exp < :now")
+            .expression_attribute_names("#k", "key")
+            .expression_attribute_names("#exp", IDEMPOTENCY_TTL_ATTRIBUTE)
+            .expression_attribute_values(":now", AttributeValue::N(now.to_string()))
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(None),
+            Err(SdkError::ServiceError(service_err))
+                if service_err.err().is_conditional_check_failed_exception() =>
+            {
+                // Someone holds the key; report the resource their request created
+                let existing = self
+                    .client
+                    .get_item()
+                    .table_name(&self.table_name)
+                    .key("key", AttributeValue::S(key.to_string()))
+                    .consistent_read(true)
+                    .send()
+                    .await
+                    .map_err(|e| map_dynamo_error("get_item", e))?;
+
+                match existing
+                    .item()
+                    .and_then(|item| item.get("resourceId"))
+                    .and_then(|value| value.as_s().ok())
+                {
+                    Some(resource_id) => Ok(Some(resource_id.clone())),
+                    // Released between the put and the read
+                    None => Err(StoreError::VersionConflict(format!(
+                        "Idempotency key {} changed while being claimed",
+                        key
+                    ))),
+                }
+            }
+            Err(err) => Err(map_dynamo_error("put_item", err)),
+        }
+    }
+
+    async fn release(&self, key: &str) -> Result<()> {
+        self.client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key("key", AttributeValue::S(key.to_string()))
+            .send()
+            .await
+            .map_err(map_delete_dynamo_error)?;
+        Ok(())
+    }
+}
+
 // Helper functions for DynamoDB error mapping
 fn map_get_dynamo_error(err: SdkError<GetItemError>, id: &str) -> StoreError {
     match err {
@@ -564,6 +1930,68 @@ impl DynamoInvitationStore {
     }
 }
 
+impl DynamoBoxStore {
+    /// Creates a new builder to configure a DynamoBoxStore
+    pub fn builder() -> DynamoBoxStoreBuilder {
+        DynamoBoxStoreBuilder::default()
+    }
+
+    /// Synchronous constructor that creates a store with default AWS config
+    /// Note: This should only be used in non-async contexts
+    pub fn new_blocking() -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create Tokio runtime");
+
+        runtime.block_on(Self::new())
+    }
+}
+
+#[derive(Default)]
+pub struct DynamoBoxStoreBuilder {
+    table_name: Option<String>,
+    endpoint_url: Option<String>,
+}
+
+impl DynamoBoxStoreBuilder {
+    pub fn table_name(mut self, table_name: String) -> Self {
+        self.table_name = Some(table_name);
+        self
+    }
+
+    /// Endpoint for the DynamoDB client; takes precedence over DYNAMODB_ENDPOINT_URL
+    pub fn endpoint_url(mut self, endpoint_url: String) -> Self {
+        self.endpoint_url = Some(endpoint_url);
+        self
+    }
+
+    pub async fn build(self) -> DynamoBoxStore {
+        let loader = aws_config::defaults(BehaviorVersion::latest());
+        let loader = match self.endpoint_url {
+            Some(endpoint_url) => loader.endpoint_url(endpoint_url),
+            None => with_endpoint_from_env(loader),
+        };
+        let client = without_sdk_retries(Client::new(&loader.load().await));
+
+        let table_name = self.table_name.unwrap_or_else(|| {
+            env::var("DYNAMODB_TABLE").unwrap_or_else(|_| BOX_TABLE_NAME.to_string())
+        });
+        // Same default as with_client_and_table, so every constructor finds the same index
+        let guardian_index_table = env::var("DYNAMODB_GUARDIAN_INDEX_TABLE")
+            .unwrap_or_else(|_| guardian_index_table_for(&table_name));
+
+        DynamoBoxStore {
+            client,
+            table_name,
+            guardian_index_table,
+            use_guardian_scan: guardian_scan_enabled(),
+            soft_delete: soft_delete_enabled(),
+            max_attempts: max_attempts_from_env(),
+        }
+    }
+}
+
 pub struct DynamoInvitationStoreBuilder {
     table_name: Option<String>,
 }
@@ -581,13 +2009,17 @@ impl DynamoInvitationStoreBuilder {
     }
 
     pub async fn build(self) -> DynamoInvitationStore {
-        let config = aws_config::defaults(BehaviorVersion::latest()).load().await;
-        let client = Client::new(&config);
+        let client = client_from_env().await;
 
         let table_name = self.table_name.unwrap_or_else(|| {
             env::var("DYNAMODB_INVITATION_TABLE").unwrap_or_else(|_| TABLE_NAME.to_string())
         });
 
-        DynamoInvitationStore { client, table_name }
+        DynamoInvitationStore {
+            client,
+            table_name,
+            use_creator_scan: creator_scan_enabled(),
+            use_linked_user_scan: linked_user_scan_enabled(),
+        }
     }
 }
@@ -0,0 +1,290 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::{Result, StoreError};
+use crate::models::{
+    generate_invite_code, invitation_expires_at, invitation_ttl_hours, now_str, Invitation,
+};
+use crate::store::{
+    decode_cursor, encode_cursor, IdempotencyStore, InvitationStore, MAX_INVITE_CODE_ATTEMPTS,
+};
+
+/// In-memory InvitationStore for local development without DynamoDB.
+///
+/// Mirrors the Dynamo store's semantics, including expiry: single lookups
+/// return `InvitationExpired` for expired invitations while the list queries
+/// return every match. State is lost when the process exits.
+pub struct MemoryInvitationStore {
+    invitations: Mutex<HashMap<String, Invitation>>,
+}
+
+impl MemoryInvitationStore {
+    pub fn new() -> Self {
+        Self {
+            invitations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Same rules as the Dynamo store: an unparseable expiry is an error
+    fn check_expiry(invitation: &Invitation) -> Result<()> {
+        let expires_at = DateTime::parse_from_rfc3339(&invitation.expires_at)
+            .map_err(|_| StoreError::InternalError("Invalid expiration date format".to_string()))?
+            .with_timezone(&Utc);
+
+        if Utc::now() > expires_at {
+            return Err(StoreError::InvitationExpired);
+        }
+        Ok(())
+    }
+}
+
+impl Default for MemoryInvitationStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl InvitationStore for MemoryInvitationStore {
+    async fn create_invitation(&self, mut invitation: Invitation) -> Result<Invitation> {
+        if invitation.created_at.is_empty() {
+            invitation.created_at = now_str();
+        }
+
+        if invitation.expires_at.is_empty() {
+            invitation.expires_at = invitation_expires_at(Utc::now(), invitation_ttl_hours());
+        }
+
+        let mut invitations = self.invitations.lock().unwrap();
+
+        // Swap out an empty or already-used code, like the Dynamo store
+        let mut attempts = 1;
+        while invitation.invite_code.is_empty()
+            || invitations
+                .values()
+                .any(|inv| inv.invite_code == invitation.invite_code)
+        {
+            if attempts == MAX_INVITE_CODE_ATTEMPTS {
+                return Err(StoreError::InternalError(
+                    "Could not generate an unused invite code".to_string(),
+                ));
+            }
+            attempts += 1;
+            invitation.invite_code = generate_invite_code();
+        }
+
+        invitations.insert(invitation.id.clone(), invitation.clone());
+        Ok(invitation)
+    }
+
+    async fn get_invitation(&self, id: &str) -> Result<Invitation> {
+        let invitation = self
+            .invitations
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| StoreError::NotFound(format!("Invitation with id {} not found", id)))?;
+
+        Self::check_expiry(&invitation)?;
+        Ok(invitation)
+    }
+
+    async fn get_invitation_by_code(&self, invite_code: &str) -> Result<Invitation> {
+        let mut matches: Vec<Invitation> = self
+            .invitations
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|inv| inv.invite_code == invite_code)
+            .cloned()
+            .collect();
+
+        if matches.len() > 1 {
+            let ids: Vec<&str> = matches.iter().map(|inv| inv.id.as_str()).collect();
+            log::error!("Invite code {} matches invitations {:?}", invite_code, ids);
+            return Err(StoreError::AmbiguousInviteCode);
+        }
+
+        let invitation = matches.pop().ok_or_else(|| {
+            StoreError::NotFound(format!("Invitation with code {} not found", invite_code))
+        })?;
+
+        Self::check_expiry(&invitation)?;
+        Ok(invitation)
+    }
+
+    async fn update_invitation(&self, mut invitation: Invitation) -> Result<Invitation> {
+        let mut invitations = self.invitations.lock().unwrap();
+
+        // Swap out a code another invitation holds, like the Dynamo store
+        let mut attempts = 1;
+        while invitation.invite_code.is_empty()
+            || invitations
+                .values()
+                .any(|inv| inv.id != invitation.id && inv.invite_code == invitation.invite_code)
+        {
+            if attempts == MAX_INVITE_CODE_ATTEMPTS {
+                return Err(StoreError::InternalError(
+                    "Could not generate an unused invite code".to_string(),
+                ));
+            }
+            attempts += 1;
+            invitation.invite_code = generate_invite_code();
+        }
+
+        invitations.insert(invitation.id.clone(), invitation.clone());
+        Ok(invitation)
+    }
+
+    async fn delete_invitation(&self, id: &str) -> Result<()> {
+        // Verify invitation exists (and hasn't expired) first
+        self.get_invitation(id).await?;
+
+        self.invitations.lock().unwrap().remove(id);
+        Ok(())
+    }
+
+    async fn get_invitations_by_box_id(&self, box_id: &str) -> Result<Vec<Invitation>> {
+        let invitations = self
+            .invitations
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|inv| inv.box_id == box_id)
+            .cloned()
+            .collect();
+        Ok(invitations)
+    }
+
+    async fn get_invitations_by_creator_id(&self, creator_id: &str) -> Result<Vec<Invitation>> {
+        let invitations = self
+            .invitations
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|inv| inv.creator_id == creator_id)
+            .cloned()
+            .collect();
+        Ok(invitations)
+    }
+
+    async fn get_invitations_by_linked_user(&self, user_id: &str) -> Result<Vec<Invitation>> {
+        let invitations = self
+            .invitations
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|inv| inv.linked_user_id.as_deref() == Some(user_id))
+            .cloned()
+            .collect();
+        Ok(invitations)
+    }
+
+    async fn get_invitations_by_creator_id_paged(
+        &self,
+        creator_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<Invitation>, Option<String>)> {
+        // Sort by id so pages are stable across calls
+        let mut invitations = self.get_invitations_by_creator_id(creator_id).await?;
+        invitations.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let last_id = match start_key {
+            Some(cursor) => decode_cursor(&cursor)?.get("id").cloned(),
+            None => None,
+        };
+
+        let remaining: Vec<Invitation> = invitations
+            .into_iter()
+            .filter(|inv| match &last_id {
+                Some(last) => &inv.id > last,
+                None => true,
+            })
+            .collect();
+        let has_more = remaining.len() > limit;
+        let page: Vec<Invitation> = remaining.into_iter().take(limit).collect();
+
+        let next_cursor = if has_more {
+            page.last().map(|last| {
+                encode_cursor(&HashMap::from([
+                    ("id".to_string(), last.id.clone()),
+                    ("creatorId".to_string(), creator_id.to_string()),
+                ]))
+            })
+        } else {
+            None
+        };
+
+        Ok((page, next_cursor))
+    }
+
+    async fn count_invitations_by_creator(&self, creator_id: &str) -> Result<u64> {
+        let count = self
+            .invitations
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|inv| inv.creator_id == creator_id)
+            .count();
+        Ok(count as u64)
+    }
+
+    async fn purge_expired_invitations(&self) -> Result<Vec<Invitation>> {
+        let mut invitations = self.invitations.lock().unwrap();
+        let expired: Vec<Invitation> = invitations
+            .values()
+            .filter(|inv| inv.is_expired())
+            .cloned()
+            .collect();
+        for invitation in &expired {
+            invitations.remove(&invitation.id);
+        }
+        Ok(expired)
+    }
+}
+
+/// In-memory IdempotencyStore. Keys only dedupe requests that reach the same
+/// process, so deployments with several instances should use the Dynamo store.
+pub struct MemoryIdempotencyStore {
+    // key -> (resource id, expiry)
+    keys: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl MemoryIdempotencyStore {
+    pub fn new() -> Self {
+        Self {
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for MemoryIdempotencyStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for MemoryIdempotencyStore {
+    async fn claim(&self, key: &str, resource_id: &str, ttl: Duration) -> Result<Option<String>> {
+        let mut keys = self.keys.lock().unwrap();
+        let now = Instant::now();
+        keys.retain(|_, (_, expires_at)| *expires_at > now);
+
+        if let Some((existing, _)) = keys.get(key) {
+            return Ok(Some(existing.clone()));
+        }
+        keys.insert(key.to_string(), (resource_id.to_string(), now + ttl));
+        Ok(None)
+    }
+
+    async fn release(&self, key: &str) -> Result<()> {
+        self.keys.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
@@ -0,0 +1,331 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::error::Result;
+use crate::models::{BoxRecord, Invitation};
+use crate::store::{BoxStore, InvitationStore};
+
+// CloudWatch namespace the store metrics are published under
+pub const EMF_NAMESPACE: &str = "Lockbox";
+
+// Store operation metrics are off unless EMF_METRICS=true
+pub fn emf_metrics_enabled() -> bool {
+    std::env::var("EMF_METRICS").unwrap_or_default() == "true"
+}
+
+/// Builds a CloudWatch Embedded Metric Format record for one store call: a
+/// `Count` of 1 and its `Duration` in milliseconds, dimensioned by operation
+/// and outcome (success or failure)
+pub fn emf_record(operation: &str, success: bool, duration_ms: f64) -> Value {
+    json!({
+        "_aws": {
+            "Timestamp": Utc::now().timestamp_millis(),
+            "CloudWatchMetrics": [{
+                "Namespace": EMF_NAMESPACE,
+                "Dimensions": [["Operation", "Outcome"]],
+                "Metrics": [
+                    { "Name": "Count", "Unit": "Count" },
+                    { "Name": "Duration", "Unit": "Milliseconds" }
+                ]
+            }]
+        },
+        "Operation": operation,
+        "Outcome": if success { "success" } else { "failure" },
+        "Count": 1,
+        "Duration": duration_ms,
+    })
+}
+
+/// Times store calls and hands each EMF record to a sink, stdout by default,
+/// where the Lambda runtime picks it up
+#[derive(Clone)]
+pub struct EmfRecorder {
+    sink: Arc<dyn Fn(String) + Send + Sync>,
+}
+
+impl EmfRecorder {
+    pub fn new() -> Self {
+        Self::with_sink(Arc::new(|line| println!("{}", line)))
+    }
+
+    pub fn with_sink(sink: Arc<dyn Fn(String) + Send + Sync>) -> Self {
+        Self { sink }
+    }
+
+    async fn time<T>(&self, operation: &str, call: impl Future<Output = Result<T>>) -> Result<T> {
+        let started = Instant::now();
+        let result = call.await;
+        let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+        (self.sink)(emf_record(operation, result.is_ok(), duration_ms).to_string());
+        result
+    }
+}
+
+impl Default for EmfRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// BoxStore decorator emitting EMF metrics for every call to the inner store
+pub struct MetricsBoxStore<S: BoxStore + ?Sized> {
+    inner: Arc<S>,
+    recorder: EmfRecorder,
+}
+
+impl<S: BoxStore + ?Sized> MetricsBoxStore<S> {
+    pub fn new(inner: Arc<S>) -> Self {
+        Self::with_recorder(inner, EmfRecorder::new())
+    }
+
+    pub fn with_recorder(inner: Arc<S>, recorder: EmfRecorder) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+#[async_trait]
+impl<S: BoxStore + ?Sized> BoxStore for MetricsBoxStore<S> {
+    async fn create_box(&self, box_record: BoxRecord) -> Result<BoxRecord> {
+        self.recorder
+            .time("create_box", self.inner.create_box(box_record))
+            .await
+    }
+
+    async fn get_box(&self, id: &str) -> Result<BoxRecord> {
+        self.recorder.time("get_box", self.inner.get_box(id)).await
+    }
+
+    async fn get_box_consistent(&self, id: &str) -> Result<BoxRecord> {
+        self.recorder
+            .time("get_box_consistent", self.inner.get_box_consistent(id))
+            .await
+    }
+
+    async fn get_boxes_by_ids(&self, ids: &[String]) -> Result<Vec<BoxRecord>> {
+        self.recorder
+            .time("get_boxes_by_ids", self.inner.get_boxes_by_ids(ids))
+            .await
+    }
+
+    async fn get_boxes_by_owner(&self, owner_id: &str) -> Result<Vec<BoxRecord>> {
+        self.recorder
+            .time(
+                "get_boxes_by_owner",
+                self.inner.get_boxes_by_owner(owner_id),
+            )
+            .await
+    }
+
+    async fn get_boxes_by_owner_paged(
+        &self,
+        owner_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<BoxRecord>, Option<String>)> {
+        self.recorder
+            .time(
+                "get_boxes_by_owner_paged",
+                self.inner
+                    .get_boxes_by_owner_paged(owner_id, limit, start_key),
+            )
+            .await
+    }
+
+    async fn count_boxes_by_owner(&self, owner_id: &str) -> Result<u64> {
+        self.recorder
+            .time(
+                "count_boxes_by_owner",
+                self.inner.count_boxes_by_owner(owner_id),
+            )
+            .await
+    }
+
+    async fn get_boxes_by_guardian_id(&self, guardian_id: &str) -> Result<Vec<BoxRecord>> {
+        self.recorder
+            .time(
+                "get_boxes_by_guardian_id",
+                self.inner.get_boxes_by_guardian_id(guardian_id),
+            )
+            .await
+    }
+
+    async fn get_boxes_by_guardian_id_paged(
+        &self,
+        guardian_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<BoxRecord>, Option<String>)> {
+        self.recorder
+            .time(
+                "get_boxes_by_guardian_id_paged",
+                self.inner
+                    .get_boxes_by_guardian_id_paged(guardian_id, limit, start_key),
+            )
+            .await
+    }
+
+    async fn update_box(&self, box_record: BoxRecord) -> Result<BoxRecord> {
+        self.recorder
+            .time("update_box", self.inner.update_box(box_record))
+            .await
+    }
+
+    async fn record_guardian_view(
+        &self,
+        box_id: &str,
+        guardian_id: &str,
+        viewed_at: &str,
+    ) -> Result<BoxRecord> {
+        self.recorder
+            .time(
+                "record_guardian_view",
+                self.inner
+                    .record_guardian_view(box_id, guardian_id, viewed_at),
+            )
+            .await
+    }
+
+    async fn delete_box(&self, id: &str) -> Result<()> {
+        self.recorder
+            .time("delete_box", self.inner.delete_box(id))
+            .await
+    }
+
+    async fn get_deleted_box(&self, id: &str) -> Result<BoxRecord> {
+        self.recorder
+            .time("get_deleted_box", self.inner.get_deleted_box(id))
+            .await
+    }
+
+    async fn restore_box(&self, id: &str) -> Result<BoxRecord> {
+        self.recorder
+            .time("restore_box", self.inner.restore_box(id))
+            .await
+    }
+
+    async fn ping(&self) -> Result<()> {
+        self.recorder.time("ping", self.inner.ping()).await
+    }
+}
+
+/// InvitationStore decorator emitting EMF metrics for every call to the inner store
+pub struct MetricsInvitationStore<S: InvitationStore + ?Sized> {
+    inner: Arc<S>,
+    recorder: EmfRecorder,
+}
+
+impl<S: InvitationStore + ?Sized> MetricsInvitationStore<S> {
+    pub fn new(inner: Arc<S>) -> Self {
+        Self::with_recorder(inner, EmfRecorder::new())
+    }
+
+    pub fn with_recorder(inner: Arc<S>, recorder: EmfRecorder) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+#[async_trait]
+impl<S: InvitationStore + ?Sized> InvitationStore for MetricsInvitationStore<S> {
+    async fn create_invitation(&self, invitation: Invitation) -> Result<Invitation> {
+        self.recorder
+            .time(
+                "create_invitation",
+                self.inner.create_invitation(invitation),
+            )
+            .await
+    }
+
+    async fn get_invitation(&self, id: &str) -> Result<Invitation> {
+        self.recorder
+            .time("get_invitation", self.inner.get_invitation(id))
+            .await
+    }
+
+    async fn get_invitation_by_code(&self, invite_code: &str) -> Result<Invitation> {
+        self.recorder
+            .time(
+                "get_invitation_by_code",
+                self.inner.get_invitation_by_code(invite_code),
+            )
+            .await
+    }
+
+    async fn update_invitation(&self, invitation: Invitation) -> Result<Invitation> {
+        self.recorder
+            .time(
+                "update_invitation",
+                self.inner.update_invitation(invitation),
+            )
+            .await
+    }
+
+    async fn delete_invitation(&self, id: &str) -> Result<()> {
+        self.recorder
+            .time("delete_invitation", self.inner.delete_invitation(id))
+            .await
+    }
+
+    async fn get_invitations_by_box_id(&self, box_id: &str) -> Result<Vec<Invitation>> {
+        self.recorder
+            .time(
+                "get_invitations_by_box_id",
+                self.inner.get_invitations_by_box_id(box_id),
+            )
+            .await
+    }
+
+    async fn get_invitations_by_creator_id(&self, creator_id: &str) -> Result<Vec<Invitation>> {
+        self.recorder
+            .time(
+                "get_invitations_by_creator_id",
+                self.inner.get_invitations_by_creator_id(creator_id),
+            )
+            .await
+    }
+
+    async fn get_invitations_by_linked_user(&self, user_id: &str) -> Result<Vec<Invitation>> {
+        self.recorder
+            .time(
+                "get_invitations_by_linked_user",
+                self.inner.get_invitations_by_linked_user(user_id),
+            )
+            .await
+    }
+
+    async fn get_invitations_by_creator_id_paged(
+        &self,
+        creator_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<Invitation>, Option<String>)> {
+        self.recorder
+            .time(
+                "get_invitations_by_creator_id_paged",
+                self.inner
+                    .get_invitations_by_creator_id_paged(creator_id, limit, start_key),
+            )
+            .await
+    }
+
+    async fn count_invitations_by_creator(&self, creator_id: &str) -> Result<u64> {
+        self.recorder
+            .time(
+                "count_invitations_by_creator",
+                self.inner.count_invitations_by_creator(creator_id),
+            )
+            .await
+    }
+
+    async fn purge_expired_invitations(&self) -> Result<Vec<Invitation>> {
+        self.recorder
+            .time(
+                "purge_expired_invitations",
+                self.inner.purge_expired_invitations(),
+            )
+            .await
+    }
+}
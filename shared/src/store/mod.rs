@@ -1,15 +1,29 @@
 use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use log::{debug, error};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 
-use crate::error::Result;
-use crate::models::{BoxRecord, GuardianStatus, Invitation};
+use crate::error::{Result, StoreError};
+use crate::models::{BoxRecord, Guardian, GuardianDocument, GuardianStatus, Invitation};
 
 // Expose the DynamoDB store module
 pub mod dynamo;
+// In-memory invitation store for running without DynamoDB
+pub mod memory;
+// Backoff retries for throttled DynamoDB calls
+pub mod retry;
+// EMF metrics decorators for any store (EMF_METRICS=true)
+pub mod metrics;
+// Debug logging decorator for any BoxStore
+pub mod tracing;
 
 /// InvitationStore trait defining the interface for invitation storage implementations
 #[async_trait]
 pub trait InvitationStore: Send + Sync + 'static {
-    /// Creates a new invitation
+    /// Creates a new invitation. If its invite code is empty or already taken a
+    /// fresh one is generated, giving up after `MAX_INVITE_CODE_ATTEMPTS` codes.
     async fn create_invitation(&self, invitation: Invitation) -> Result<Invitation>;
 
     /// Gets an invitation by ID
@@ -18,17 +32,37 @@ pub trait InvitationStore: Send + Sync + 'static {
     /// Gets an invitation by invite code
     async fn get_invitation_by_code(&self, invite_code: &str) -> Result<Invitation>;
 
-    /// Updates an invitation
+    /// Updates an invitation. A changed invite code that another invitation
+    /// already holds is swapped for a fresh one, as in `create_invitation`.
     async fn update_invitation(&self, invitation: Invitation) -> Result<Invitation>;
 
     /// Deletes an invitation
     async fn delete_invitation(&self, id: &str) -> Result<()>;
 
-    /// Gets all invitations for a box
+    /// Gets all invitations for a box, including expired ones
     async fn get_invitations_by_box_id(&self, box_id: &str) -> Result<Vec<Invitation>>;
 
     /// Gets all invitations created by a specific user
     async fn get_invitations_by_creator_id(&self, creator_id: &str) -> Result<Vec<Invitation>>;
+
+    /// Gets all invitations linked to a user, i.e. the ones they have opened,
+    /// accepted or rejected
+    async fn get_invitations_by_linked_user(&self, user_id: &str) -> Result<Vec<Invitation>>;
+
+    /// Gets one page of invitations created by a user, returning an opaque cursor
+    /// for the next page (None when there are no more results)
+    async fn get_invitations_by_creator_id_paged(
+        &self,
+        creator_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<Invitation>, Option<String>)>;
+
+    /// Counts the invitations created by a user without loading them
+    async fn count_invitations_by_creator(&self, creator_id: &str) -> Result<u64>;
+
+    /// Deletes every invitation whose expiry has passed, returning the removed invitations
+    async fn purge_expired_invitations(&self) -> Result<Vec<Invitation>>;
 }
 
 /// BoxStore trait defining the interface for box storage implementations
@@ -40,17 +74,203 @@ pub trait BoxStore: Send + Sync + 'static {
     /// Gets a box by ID
     async fn get_box(&self, id: &str) -> Result<BoxRecord>;
 
+    /// Gets a box by ID with a strongly consistent read, so a write made just
+    /// before is always visible. Costs more than `get_box`; use it for
+    /// read-modify-write and read-after-write paths only.
+    async fn get_box_consistent(&self, id: &str) -> Result<BoxRecord>;
+
+    /// Gets several boxes by ID, skipping any that don't exist or are deleted.
+    /// Results follow the order of `ids` and duplicates are returned once.
+    async fn get_boxes_by_ids(&self, ids: &[String]) -> Result<Vec<BoxRecord>>;
+
     /// Gets all boxes owned by a user
     async fn get_boxes_by_owner(&self, owner_id: &str) -> Result<Vec<BoxRecord>>;
 
+    /// Gets one page of boxes owned by a user, returning an opaque cursor for the
+    /// next page (None when there are no more results)
+    async fn get_boxes_by_owner_paged(
+        &self,
+        owner_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<BoxRecord>, Option<String>)>;
+
+    /// Counts the boxes owned by a user without loading them (deleted boxes excluded)
+    async fn count_boxes_by_owner(&self, owner_id: &str) -> Result<u64>;
+
     /// Gets all boxes where the given user is a guardian (with status not rejected)
     async fn get_boxes_by_guardian_id(&self, guardian_id: &str) -> Result<Vec<BoxRecord>>;
 
+    /// Gets one page of the boxes where the given user is a guardian, returning an
+    /// opaque cursor for the next page (None when there are no more results).
+    /// Pages can come back short when stale or rejected entries are skipped.
+    async fn get_boxes_by_guardian_id_paged(
+        &self,
+        guardian_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<BoxRecord>, Option<String>)>;
+
     /// Updates a box
     async fn update_box(&self, box_record: BoxRecord) -> Result<BoxRecord>;
 
-    /// Deletes a box
+    /// Stamps a guardian's `lastViewedAt` and moves them from invited to viewed.
+    /// Unlike `update_box` this leaves `updatedAt` alone, so the owner's updatedAt
+    /// ordering doesn't change every time a guardian opens the box. `version` is
+    /// still bumped, so an owner write based on an earlier read fails its version
+    /// check instead of erasing the view. Returns the box as stored afterwards; a
+    /// box without a matching, non-rejected guardian comes back unchanged.
+    async fn record_guardian_view(
+        &self,
+        box_id: &str,
+        guardian_id: &str,
+        viewed_at: &str,
+    ) -> Result<BoxRecord>;
+
+    /// Deletes a box. When soft delete is enabled the record is kept with
+    /// `deleted_at` set and hidden from the other read methods.
     async fn delete_box(&self, id: &str) -> Result<()>;
+
+    /// Gets a soft-deleted box by ID (NotFound if it doesn't exist or isn't deleted)
+    async fn get_deleted_box(&self, id: &str) -> Result<BoxRecord>;
+
+    /// Clears `deleted_at` on a soft-deleted box, making it visible again
+    async fn restore_box(&self, id: &str) -> Result<BoxRecord>;
+
+    /// Cheap round trip to the backing store, for readiness probes. Errors when
+    /// the store can't be reached.
+    async fn ping(&self) -> Result<()>;
+}
+
+/// Remembers which resource a create request's Idempotency-Key produced, so a
+/// retried request gets the original back instead of a duplicate
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync + 'static {
+    /// Claims `key` for `resource_id` for `ttl`. Returns None when the claim
+    /// succeeded, or the resource id recorded by the earlier, still live claim.
+    async fn claim(&self, key: &str, resource_id: &str, ttl: Duration) -> Result<Option<String>>;
+
+    /// Frees `key` again, e.g. when the create it guarded failed
+    async fn release(&self, key: &str) -> Result<()>;
+}
+
+/// Creates an invitation together with the box update that records it (the
+/// invited guardian's placeholder), so neither write lands without the other
+#[async_trait]
+pub trait InvitationBoxTransaction: Send + Sync + 'static {
+    /// Stores `invitation` and writes `box_record`, which must already hold the
+    /// invitation's guardian. If the box write fails (e.g. a version conflict)
+    /// the invitation is not kept.
+    async fn create_invitation_with_box(
+        &self,
+        invitation: Invitation,
+        box_record: BoxRecord,
+    ) -> Result<(Invitation, BoxRecord)>;
+}
+
+/// `InvitationBoxTransaction` for stores that can't share a transaction, e.g.
+/// the in-memory invitation store: the invitation is written first and deleted
+/// again when the box update fails
+pub struct SequentialInvitationBoxTransaction<I: ?Sized, B: ?Sized> {
+    invitations: Arc<I>,
+    boxes: Arc<B>,
+}
+
+impl<I: ?Sized, B: ?Sized> SequentialInvitationBoxTransaction<I, B> {
+    pub fn new(invitations: Arc<I>, boxes: Arc<B>) -> Self {
+        Self { invitations, boxes }
+    }
+}
+
+#[async_trait]
+impl<I, B> InvitationBoxTransaction for SequentialInvitationBoxTransaction<I, B>
+where
+    I: InvitationStore + ?Sized,
+    B: BoxStore + ?Sized,
+{
+    async fn create_invitation_with_box(
+        &self,
+        invitation: Invitation,
+        box_record: BoxRecord,
+    ) -> Result<(Invitation, BoxRecord)> {
+        let invitation = self.invitations.create_invitation(invitation).await?;
+
+        match self.boxes.update_box(box_record).await {
+            Ok(box_record) => Ok((invitation, box_record)),
+            Err(err) => {
+                // Roll the invitation back; if that fails too it is left to expire
+                if let Err(delete_err) = self.invitations.delete_invitation(&invitation.id).await {
+                    error!(
+                        "Failed to roll back invitation {} after box update failed: {}",
+                        invitation.id, delete_err
+                    );
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+// Attempts update_box_with_retry makes by default before surfacing a version conflict
+pub const DEFAULT_UPDATE_ATTEMPTS: usize = 3;
+
+/// Reads the latest copy of a box, applies `mutate` and writes it back, starting
+/// over from a fresh read when another writer got in first. Errors returned by
+/// `mutate` abort immediately; a conflict on the last attempt is returned as is.
+pub async fn update_box_with_retry<S, F, E>(
+    store: &S,
+    box_id: &str,
+    max_attempts: usize,
+    mut mutate: F,
+) -> std::result::Result<BoxRecord, E>
+where
+    S: BoxStore + ?Sized,
+    F: FnMut(&mut BoxRecord) -> std::result::Result<(), E>,
+    E: From<StoreError>,
+{
+    let mut attempt = 1;
+    loop {
+        let mut box_record = store.get_box_consistent(box_id).await?;
+        mutate(&mut box_record)?;
+        box_record.updated_at = crate::models::now_str();
+
+        match store.update_box(box_record).await {
+            Err(StoreError::VersionConflict(msg)) if attempt < max_attempts => {
+                let delay_ms = 10 * attempt as u64 + fastrand::u64(0..=20);
+                debug!(
+                    "Version conflict updating box {} (attempt {}/{}), retrying in {}ms: {}",
+                    box_id, attempt, max_attempts, delay_ms, msg
+                );
+                tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+                attempt += 1;
+            }
+            result => return result.map_err(E::from),
+        }
+    }
+}
+
+// Codes tried before create_invitation gives up on finding an unused invite code
+pub const MAX_INVITE_CODE_ATTEMPTS: usize = 5;
+
+// Soft delete keeps deleted boxes recoverable; enabled with SOFT_DELETE=true
+pub fn soft_delete_enabled() -> bool {
+    std::env::var("SOFT_DELETE").unwrap_or_default() == "true"
+}
+
+/// Encodes a page's last evaluated key as an opaque base64 cursor
+pub fn encode_cursor(key: &HashMap<String, String>) -> String {
+    let json = serde_json::to_vec(key).unwrap_or_default();
+    URL_SAFE_NO_PAD.encode(json)
+}
+
+/// Decodes a cursor produced by `encode_cursor` back into its key attributes
+pub fn decode_cursor(cursor: &str) -> Result<HashMap<String, String>> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(cursor)
+        .map_err(|_| StoreError::ValidationError("Invalid pagination cursor".into()))?;
+
+    serde_json::from_slice(&bytes)
+        .map_err(|_| StoreError::ValidationError("Invalid pagination cursor".into()))
 }
 
 // Box store utility functions
@@ -68,6 +288,7 @@ pub fn convert_to_guardian_box(
             GuardianStatus::Invited | GuardianStatus::Viewed
         );
         let is_lead = guardian.lead_guardian;
+        let reveal_content = box_rec.documents_visible_to_guardians();
         Some(crate::models::GuardianBox {
             id: box_rec.id.clone(),
             name: box_rec.name.clone(),
@@ -78,12 +299,20 @@ pub fn convert_to_guardian_box(
             owner_id: box_rec.owner_id.clone(),
             owner_name: box_rec.owner_name.clone(),
             unlock_instructions: box_rec.unlock_instructions.clone(),
-            unlock_request: box_rec.unlock_request.clone(),
+            unlock_requests: box_rec.unlock_requests.clone(),
             pending_guardian_approval: Some(pending),
             guardians_count: box_rec.guardians.len(),
             is_lead_guardian: is_lead,
-            documents: box_rec.documents.clone(),
-            guardians: box_rec.guardians.clone(),
+            documents: box_rec
+                .documents
+                .iter()
+                .map(|document| GuardianDocument::from_document(document, reveal_content))
+                .collect(),
+            guardians: box_rec
+                .guardians
+                .iter()
+                .map(Guardian::guardian_view)
+                .collect(),
         })
     } else {
         None
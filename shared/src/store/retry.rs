@@ -0,0 +1,144 @@
+use aws_sdk_dynamodb::config::http::HttpResponse;
+use aws_sdk_dynamodb::error::{ProvideErrorMetadata, SdkError};
+use log::warn;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::error::StoreError;
+
+// Attempts per DynamoDB call unless DYNAMODB_MAX_ATTEMPTS says otherwise
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+// First backoff ceiling; doubles on every retry up to MAX_BACKOFF
+const BASE_BACKOFF: Duration = Duration::from_millis(50);
+const MAX_BACKOFF: Duration = Duration::from_secs(2);
+
+// DynamoDB error codes that mean "slow down" rather than "this request is wrong"
+const THROTTLING_CODES: &[&str] = &[
+    "ProvisionedThroughputExceededException",
+    "RequestLimitExceeded",
+    "ThrottlingException",
+];
+
+// Max attempts per DynamoDB call from DYNAMODB_MAX_ATTEMPTS (at least 1)
+pub fn max_attempts_from_env() -> u32 {
+    std::env::var("DYNAMODB_MAX_ATTEMPTS")
+        .ok()
+        .and_then(|value| value.parse::<u32>().ok())
+        .map(|attempts| attempts.max(1))
+        .unwrap_or(DEFAULT_MAX_ATTEMPTS)
+}
+
+/// Errors that are worth retrying: throttling and server-side failures
+pub trait TransientError {
+    /// DynamoDB turned the request away without acting on it
+    fn is_throttled(&self) -> bool;
+
+    /// Throttled, or failed server-side. A 5xx may still have applied a write.
+    fn is_transient(&self) -> bool;
+}
+
+impl<E: ProvideErrorMetadata> TransientError for SdkError<E, HttpResponse> {
+    fn is_throttled(&self) -> bool {
+        self.code()
+            .is_some_and(|code| THROTTLING_CODES.contains(&code))
+    }
+
+    fn is_transient(&self) -> bool {
+        self.is_throttled()
+            || self
+                .raw_response()
+                .is_some_and(|response| response.status().is_server_error())
+    }
+}
+
+// Full jitter: a random delay up to the exponential ceiling for this retry
+fn backoff(retry: u32) -> Duration {
+    let ceiling = BASE_BACKOFF
+        .saturating_mul(2u32.saturating_pow(retry))
+        .min(MAX_BACKOFF);
+    Duration::from_millis(fastrand::u64(..=ceiling.as_millis() as u64))
+}
+
+/// Runs `call` up to `max_attempts` times, sleeping with exponential backoff
+/// and jitter between attempts. Only transient errors are retried; anything
+/// else, or the last transient error, is returned to the caller.
+pub async fn retry_transient<T, E, F, Fut>(
+    operation: &str,
+    max_attempts: u32,
+    call: F,
+) -> std::result::Result<T, E>
+where
+    E: TransientError + Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, E>>,
+{
+    retry_while(operation, max_attempts, call, E::is_transient).await
+}
+
+/// Like `retry_transient`, but only retries throttling. For conditional writes:
+/// after a 5xx the write may have gone through, and a retry would then fail its
+/// own condition and report a version conflict that never happened.
+pub async fn retry_throttled<T, E, F, Fut>(
+    operation: &str,
+    max_attempts: u32,
+    call: F,
+) -> std::result::Result<T, E>
+where
+    E: TransientError + Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, E>>,
+{
+    retry_while(operation, max_attempts, call, E::is_throttled).await
+}
+
+async fn retry_while<T, E, F, Fut>(
+    operation: &str,
+    max_attempts: u32,
+    mut call: F,
+    retryable: fn(&E) -> bool,
+) -> std::result::Result<T, E>
+where
+    E: TransientError + Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, E>>,
+{
+    let mut attempt = 1;
+    loop {
+        match call().await {
+            Err(err) if retryable(&err) && attempt < max_attempts => {
+                let delay = backoff(attempt - 1);
+                warn!(
+                    "DynamoDB {} attempt {}/{} failed, retrying in {}ms: {}",
+                    operation,
+                    attempt,
+                    max_attempts,
+                    delay.as_millis(),
+                    err
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Maps the error from `retry_transient`: a transient error means every
+/// attempt was used up and becomes `StoreError::Throttled`, anything else
+/// goes through `otherwise`
+pub fn throttled_or<E: TransientError + Display>(
+    operation: &str,
+    max_attempts: u32,
+    err: E,
+    otherwise: impl FnOnce(E) -> StoreError,
+) -> StoreError {
+    if err.is_transient() {
+        StoreError::Throttled(format!(
+            "DynamoDB {} still failing after {} attempts: {}",
+            operation, max_attempts, err
+        ))
+    } else {
+        otherwise(err)
+    }
+}
@@ -0,0 +1,182 @@
+use async_trait::async_trait;
+use log::debug;
+use std::future::Future;
+use std::time::Instant;
+
+use crate::error::Result;
+use crate::models::BoxRecord;
+use crate::store::BoxStore;
+
+/// BoxStore decorator that logs each call to the inner store with the box
+/// (or owner/guardian) it concerns, its outcome and how long it took. Lines
+/// are written at debug level through `log`, so they carry the request id.
+pub struct TracingBoxStore<S: BoxStore> {
+    inner: S,
+}
+
+impl<S: BoxStore> TracingBoxStore<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+async fn traced<T>(
+    method: &str,
+    subject: String,
+    call: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    debug!("BoxStore::{} started ({})", method, subject);
+    let started = Instant::now();
+    let result = call.await;
+    let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    match &result {
+        Ok(_) => debug!(
+            "BoxStore::{} succeeded ({}) in {:.1}ms",
+            method, subject, elapsed_ms
+        ),
+        Err(e) => debug!(
+            "BoxStore::{} failed ({}) in {:.1}ms: {}",
+            method, subject, elapsed_ms, e
+        ),
+    }
+    result
+}
+
+#[async_trait]
+impl<S: BoxStore> BoxStore for TracingBoxStore<S> {
+    async fn create_box(&self, box_record: BoxRecord) -> Result<BoxRecord> {
+        let subject = format!("box_id={}", box_record.id);
+        traced("create_box", subject, self.inner.create_box(box_record)).await
+    }
+
+    async fn get_box(&self, id: &str) -> Result<BoxRecord> {
+        traced("get_box", format!("box_id={}", id), self.inner.get_box(id)).await
+    }
+
+    async fn get_box_consistent(&self, id: &str) -> Result<BoxRecord> {
+        traced(
+            "get_box_consistent",
+            format!("box_id={}", id),
+            self.inner.get_box_consistent(id),
+        )
+        .await
+    }
+
+    async fn get_boxes_by_ids(&self, ids: &[String]) -> Result<Vec<BoxRecord>> {
+        traced(
+            "get_boxes_by_ids",
+            format!("box_ids={:?}", ids),
+            self.inner.get_boxes_by_ids(ids),
+        )
+        .await
+    }
+
+    async fn get_boxes_by_owner(&self, owner_id: &str) -> Result<Vec<BoxRecord>> {
+        traced(
+            "get_boxes_by_owner",
+            format!("owner_id={}", owner_id),
+            self.inner.get_boxes_by_owner(owner_id),
+        )
+        .await
+    }
+
+    async fn get_boxes_by_owner_paged(
+        &self,
+        owner_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<BoxRecord>, Option<String>)> {
+        traced(
+            "get_boxes_by_owner_paged",
+            format!("owner_id={}, limit={}", owner_id, limit),
+            self.inner
+                .get_boxes_by_owner_paged(owner_id, limit, start_key),
+        )
+        .await
+    }
+
+    async fn count_boxes_by_owner(&self, owner_id: &str) -> Result<u64> {
+        traced(
+            "count_boxes_by_owner",
+            format!("owner_id={}", owner_id),
+            self.inner.count_boxes_by_owner(owner_id),
+        )
+        .await
+    }
+
+    async fn get_boxes_by_guardian_id(&self, guardian_id: &str) -> Result<Vec<BoxRecord>> {
+        traced(
+            "get_boxes_by_guardian_id",
+            format!("guardian_id={}", guardian_id),
+            self.inner.get_boxes_by_guardian_id(guardian_id),
+        )
+        .await
+    }
+
+    async fn get_boxes_by_guardian_id_paged(
+        &self,
+        guardian_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<BoxRecord>, Option<String>)> {
+        traced(
+            "get_boxes_by_guardian_id_paged",
+            format!("guardian_id={}, limit={}", guardian_id, limit),
+            self.inner
+                .get_boxes_by_guardian_id_paged(guardian_id, limit, start_key),
+        )
+        .await
+    }
+
+    async fn update_box(&self, box_record: BoxRecord) -> Result<BoxRecord> {
+        let subject = format!("box_id={}, version={}", box_record.id, box_record.version);
+        traced("update_box", subject, self.inner.update_box(box_record)).await
+    }
+
+    async fn record_guardian_view(
+        &self,
+        box_id: &str,
+        guardian_id: &str,
+        viewed_at: &str,
+    ) -> Result<BoxRecord> {
+        traced(
+            "record_guardian_view",
+            format!("box_id={}, guardian_id={}", box_id, guardian_id),
+            self.inner
+                .record_guardian_view(box_id, guardian_id, viewed_at),
+        )
+        .await
+    }
+
+    async fn delete_box(&self, id: &str) -> Result<()> {
+        traced(
+            "delete_box",
+            format!("box_id={}", id),
+            self.inner.delete_box(id),
+        )
+        .await
+    }
+
+    async fn get_deleted_box(&self, id: &str) -> Result<BoxRecord> {
+        traced(
+            "get_deleted_box",
+            format!("box_id={}", id),
+            self.inner.get_deleted_box(id),
+        )
+        .await
+    }
+
+    async fn restore_box(&self, id: &str) -> Result<BoxRecord> {
+        traced(
+            "restore_box",
+            format!("box_id={}", id),
+            self.inner.restore_box(id),
+        )
+        .await
+    }
+
+    async fn ping(&self) -> Result<()> {
+        traced("ping", "readiness".to_string(), self.inner.ping()).await
+    }
+}
@@ -1,6 +1,7 @@
 use aws_sdk_dynamodb::types::{
     AttributeDefinition, AttributeValue, GlobalSecondaryIndex, IndexStatus, KeySchemaElement,
     KeyType, Projection, ProjectionType, ProvisionedThroughput, ScalarAttributeType, TableStatus,
+    TimeToLiveSpecification,
 };
 use aws_sdk_dynamodb::Client;
 use std::error::Error;
@@ -260,14 +261,51 @@ pub async fn create_invitation_table(
     let gsi_configs = vec![
         ("box_id-index", "box_id", KeyType::Hash),
         ("invite_code-index", "invite_code", KeyType::Hash),
-        ("creator_id-index", "creator_id", KeyType::Hash),
+        ("creatorId-index", "creatorId", KeyType::Hash),
+        ("linkedUserId-index", "linkedUserId", KeyType::Hash),
+    ];
+
+    create_dynamo_table(client, table_name, gsi_configs).await?;
+
+    // Match production: expired invitations are reaped via the expiresAtEpoch TTL attribute
+    client
+        .update_time_to_live()
+        .table_name(table_name)
+        .time_to_live_specification(
+            TimeToLiveSpecification::builder()
+                .attribute_name(crate::store::dynamo::INVITATION_TTL_ATTRIBUTE)
+                .enabled(true)
+                .build()?,
+        )
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+// Helper to create the guardian index table that backs guardian box lookups
+pub async fn create_guardian_index_table(
+    client: &Client,
+    table_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    info!("Creating guardian index table '{}'...", table_name);
+
+    let gsi_configs = vec![
+        ("guardian_id-index", "guardianId", KeyType::Hash),
+        ("box_id-index", "boxId", KeyType::Hash),
     ];
 
     create_dynamo_table(client, table_name, gsi_configs).await
 }
 
-// Helper to create the box table for testing
+// Helper to create the box table for testing, along with its guardian index table
 pub async fn create_box_table(client: &Client, table_name: &str) -> Result<(), Box<dyn Error>> {
+    create_guardian_index_table(
+        client,
+        &crate::store::dynamo::guardian_index_table_for(table_name),
+    )
+    .await?;
+
     info!("Creating box table '{}' for testing...", table_name);
 
     // Check if table already exists
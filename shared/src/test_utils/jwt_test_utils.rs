@@ -0,0 +1,46 @@
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::auth::test_claims;
+
+/// Key id of the test RSA key pair in `test_jwks()`
+pub const TEST_JWK_KID: &str = "lockbox-test-key";
+
+// Throwaway 2048-bit key pair used only by tests; the public half is TEST_JWK_MODULUS
+const TEST_RS256_PRIVATE_KEY: &[u8] = include_bytes!("test_rs256_key.pem");
+const TEST_JWK_MODULUS: &str = "nNRHFOr__OZzPjMEEMXThKOhaR4G76p_8MMTAA3yCHBF51lzxiYnkZolgSJWX1DRK46oyv1KvWY9yJiIf3fFzkN9nNcPopromn3if9Q1RY6VIu5T6GjjlAx6D3SKfIxR3YZ82SI5ci4B23svN0s2vajZXT6tMgYk9L3qmbrwnitA09e5So8vRwYu7yrz80HrRd-j4JFcRbkizrjv6dUlFxUsevMTyFdLEY48rUDSIIZ4qXzu03UN82wiJsFseVBqyBcaqxc51VOUlGGdZkP1Rzkdccbf2xJ0nVXPCC5WaMuTMgJhxvIH3XUM9iSkv-kXpxJBU2o72-VuF0OfvMaISw";
+
+/// Private key matching the public JWK in `test_jwks()`
+pub fn test_rs256_encoding_key() -> EncodingKey {
+    EncodingKey::from_rsa_pem(TEST_RS256_PRIVATE_KEY).expect("Invalid test RSA key")
+}
+
+/// JWK set holding the public half of the test key pair under `TEST_JWK_KID`,
+/// shaped like the document Cognito serves at /.well-known/jwks.json
+pub fn test_jwks() -> JwkSet {
+    serde_json::from_value(serde_json::json!({
+        "keys": [{
+            "kty": "RSA",
+            "alg": "RS256",
+            "use": "sig",
+            "kid": TEST_JWK_KID,
+            "n": TEST_JWK_MODULUS,
+            "e": "AQAB"
+        }]
+    }))
+    .expect("Invalid test JWK set")
+}
+
+/// Creates an RS256-signed id token like Cognito issues, valid for one hour
+pub fn create_rs256_jwt_token(user_id: &str, key: &EncodingKey, kid: &str) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before Unix epoch")
+        .as_secs() as usize;
+
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = Some(kid.to_string());
+
+    encode(&header, &test_claims(user_id, now + 3600), key).expect("Failed to create JWT")
+}
@@ -1,15 +1,20 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Mutex;
 
 use crate::error::{Result, StoreError};
-use crate::models::{BoxRecord, GuardianStatus};
-use crate::store::BoxStore;
+use crate::models::{now_str, BoxRecord, GuardianStatus};
+use crate::store::{decode_cursor, encode_cursor, BoxStore};
 use async_trait::async_trait;
 
 /// MockBoxStore is a simple in-memory implementation of BoxStore for testing
 pub struct MockBoxStore {
     boxes: Mutex<HashMap<String, BoxRecord>>,
     owner_indexes: Mutex<HashMap<String, Vec<String>>>, // owner_id -> [box_id]
+    soft_delete: bool,
+    concurrent_writes: AtomicUsize,
+    throttled_reads: AtomicUsize,
+    unavailable: AtomicBool,
 }
 
 impl MockBoxStore {
@@ -18,9 +23,38 @@ impl MockBoxStore {
         Self {
             boxes: Mutex::new(HashMap::new()),
             owner_indexes: Mutex::new(HashMap::new()),
+            soft_delete: false,
+            concurrent_writes: AtomicUsize::new(0),
+            throttled_reads: AtomicUsize::new(0),
+            unavailable: AtomicBool::new(false),
         }
     }
 
+    /// Keep deleted boxes with `deleted_at` set instead of removing them
+    pub fn with_soft_delete(mut self, soft_delete: bool) -> Self {
+        self.soft_delete = soft_delete;
+        self
+    }
+
+    /// Simulate another writer bumping the stored version just before each of the
+    /// next `count` updates, so they fail with a version conflict
+    pub fn with_concurrent_writes(self, count: usize) -> Self {
+        self.concurrent_writes.store(count, Ordering::SeqCst);
+        self
+    }
+
+    /// Fail the next `count` box reads with `Throttled`, as if DynamoDB kept
+    /// throttling through every retry
+    pub fn with_throttled_reads(self, count: usize) -> Self {
+        self.throttled_reads.store(count, Ordering::SeqCst);
+        self
+    }
+
+    /// Make `ping` fail (or succeed again), as if the backing store went away
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.unavailable.store(unavailable, Ordering::SeqCst);
+    }
+
     /// Create a MockBoxStore with initial test data
     pub fn with_data(box_records: Vec<BoxRecord>) -> Self {
         let store = Self::new();
@@ -75,14 +109,45 @@ impl BoxStore for MockBoxStore {
     }
 
     async fn get_box(&self, id: &str) -> Result<BoxRecord> {
+        let pending_throttles = self.throttled_reads.load(Ordering::SeqCst);
+        if pending_throttles > 0 {
+            self.throttled_reads
+                .store(pending_throttles - 1, Ordering::SeqCst);
+            return Err(StoreError::Throttled(format!(
+                "Read of box {} throttled",
+                id
+            )));
+        }
+
         self.boxes
             .lock()
             .unwrap()
             .get(id)
+            .filter(|b| !b.is_deleted())
             .cloned()
             .ok_or_else(|| StoreError::NotFound(format!("Box with id {} not found", id)))
     }
 
+    async fn get_box_consistent(&self, id: &str) -> Result<BoxRecord> {
+        // Every read of the in-memory store sees the latest write
+        self.get_box(id).await
+    }
+
+    async fn get_boxes_by_ids(&self, ids: &[String]) -> Result<Vec<BoxRecord>> {
+        let boxes = self.boxes.lock().unwrap();
+        let mut seen = HashSet::new();
+
+        let result: Vec<BoxRecord> = ids
+            .iter()
+            .filter(|id| seen.insert(id.as_str()))
+            .filter_map(|id| boxes.get(id))
+            .filter(|b| !b.is_deleted())
+            .cloned()
+            .collect();
+
+        Ok(result)
+    }
+
     async fn get_boxes_by_owner(&self, owner_id: &str) -> Result<Vec<BoxRecord>> {
         // Lock boxes first to maintain consistent lock ordering with other methods
         let boxes = self.boxes.lock().unwrap();
@@ -97,17 +162,83 @@ impl BoxStore for MockBoxStore {
 
         let result: Vec<BoxRecord> = owner_boxes
             .iter()
-            .filter_map(|id| boxes.get(id).cloned())
+            .filter_map(|id| boxes.get(id))
+            .filter(|b| !b.is_deleted())
+            .cloned()
             .collect();
 
         Ok(result)
     }
 
+    async fn get_boxes_by_owner_paged(
+        &self,
+        owner_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<BoxRecord>, Option<String>)> {
+        let owner_boxes = self.get_boxes_by_owner(owner_id).await?;
+
+        // Resume after the box id recorded in the cursor
+        let start = match start_key {
+            Some(cursor) => {
+                let key = decode_cursor(&cursor)?;
+                let last_id = key.get("id").cloned().unwrap_or_default();
+                owner_boxes
+                    .iter()
+                    .position(|b| b.id == last_id)
+                    .map(|pos| pos + 1)
+                    .unwrap_or(owner_boxes.len())
+            }
+            None => 0,
+        };
+
+        let page: Vec<BoxRecord> = owner_boxes
+            .iter()
+            .skip(start)
+            .take(limit)
+            .cloned()
+            .collect();
+
+        let next_cursor = if start + page.len() < owner_boxes.len() {
+            page.last().map(|last| {
+                encode_cursor(&HashMap::from([
+                    ("id".to_string(), last.id.clone()),
+                    ("ownerId".to_string(), owner_id.to_string()),
+                ]))
+            })
+        } else {
+            None
+        };
+
+        Ok((page, next_cursor))
+    }
+
+    async fn count_boxes_by_owner(&self, owner_id: &str) -> Result<u64> {
+        // Lock boxes first to maintain consistent lock ordering with other methods
+        let boxes = self.boxes.lock().unwrap();
+
+        let count = self
+            .owner_indexes
+            .lock()
+            .unwrap()
+            .get(owner_id)
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| boxes.get(id))
+                    .filter(|b| !b.is_deleted())
+                    .count()
+            })
+            .unwrap_or(0);
+
+        Ok(count as u64)
+    }
+
     async fn get_boxes_by_guardian_id(&self, guardian_id: &str) -> Result<Vec<BoxRecord>> {
         let boxes = self.boxes.lock().unwrap();
 
         let guardian_boxes: Vec<BoxRecord> = boxes
             .values()
+            .filter(|b| !b.is_deleted())
             .filter(|b| {
                 b.guardians.iter().any(|guardian| {
                     guardian.id == guardian_id && guardian.status != GuardianStatus::Rejected
@@ -119,17 +250,68 @@ impl BoxStore for MockBoxStore {
         Ok(guardian_boxes)
     }
 
+    async fn get_boxes_by_guardian_id_paged(
+        &self,
+        guardian_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<BoxRecord>, Option<String>)> {
+        // Pages walk the boxes in id order so the cursor has a stable position
+        let mut guardian_boxes = self.get_boxes_by_guardian_id(guardian_id).await?;
+        guardian_boxes.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let start = match start_key {
+            Some(cursor) => {
+                let key = decode_cursor(&cursor)?;
+                let last_id = key.get("boxId").cloned().unwrap_or_default();
+                guardian_boxes
+                    .iter()
+                    .position(|b| b.id > last_id)
+                    .unwrap_or(guardian_boxes.len())
+            }
+            None => 0,
+        };
+
+        let page: Vec<BoxRecord> = guardian_boxes
+            .iter()
+            .skip(start)
+            .take(limit)
+            .cloned()
+            .collect();
+
+        let next_cursor = if start + page.len() < guardian_boxes.len() {
+            page.last().map(|last| {
+                encode_cursor(&HashMap::from([
+                    ("boxId".to_string(), last.id.clone()),
+                    ("guardianId".to_string(), guardian_id.to_string()),
+                ]))
+            })
+        } else {
+            None
+        };
+
+        Ok((page, next_cursor))
+    }
+
     async fn update_box(&self, box_record: BoxRecord) -> Result<BoxRecord> {
         let box_id = box_record.id.clone();
         let new_owner_id = box_record.owner_id.clone();
 
         // Get the existing box to check the current owner and version
         let current_box = {
-            let boxes = self.boxes.lock().unwrap();
-            boxes
-                .get(&box_id)
-                .ok_or_else(|| StoreError::NotFound(format!("Box with id {} not found", box_id)))?
-                .clone()
+            let mut boxes = self.boxes.lock().unwrap();
+            let current = boxes
+                .get_mut(&box_id)
+                .ok_or_else(|| StoreError::NotFound(format!("Box with id {} not found", box_id)))?;
+
+            let pending_writes = self.concurrent_writes.load(Ordering::SeqCst);
+            if pending_writes > 0 {
+                self.concurrent_writes
+                    .store(pending_writes - 1, Ordering::SeqCst);
+                current.version += 1;
+            }
+
+            current.clone()
         };
 
         // Check version for optimistic concurrency control
@@ -169,7 +351,41 @@ impl BoxStore for MockBoxStore {
         Ok(updated_box)
     }
 
+    async fn record_guardian_view(
+        &self,
+        box_id: &str,
+        guardian_id: &str,
+        viewed_at: &str,
+    ) -> Result<BoxRecord> {
+        let mut boxes = self.boxes.lock().unwrap();
+        let box_record = boxes
+            .get_mut(box_id)
+            .filter(|b| !b.is_deleted())
+            .ok_or_else(|| StoreError::NotFound(format!("Box with id {} not found", box_id)))?;
+
+        if let Some(guardian) = box_record
+            .guardians
+            .iter_mut()
+            .find(|g| g.id == guardian_id && g.status != GuardianStatus::Rejected)
+        {
+            if guardian.status == GuardianStatus::Invited {
+                guardian.status = GuardianStatus::Viewed;
+            }
+            guardian.last_viewed_at = Some(viewed_at.to_string());
+            box_record.version += 1;
+        }
+
+        Ok(box_record.clone())
+    }
+
     async fn delete_box(&self, id: &str) -> Result<()> {
+        if self.soft_delete {
+            let mut box_record = self.get_box(id).await?;
+            box_record.deleted_at = Some(now_str());
+            self.update_box(box_record).await?;
+            return Ok(());
+        }
+
         // Check if box exists and get owner_id
         let owner_id = {
             let boxes = self.boxes.lock().unwrap();
@@ -189,4 +405,29 @@ impl BoxStore for MockBoxStore {
 
         Ok(())
     }
+
+    async fn get_deleted_box(&self, id: &str) -> Result<BoxRecord> {
+        self.boxes
+            .lock()
+            .unwrap()
+            .get(id)
+            .filter(|b| b.is_deleted())
+            .cloned()
+            .ok_or_else(|| StoreError::NotFound(format!("Deleted box with id {} not found", id)))
+    }
+
+    async fn restore_box(&self, id: &str) -> Result<BoxRecord> {
+        let mut box_record = self.get_deleted_box(id).await?;
+        box_record.deleted_at = None;
+        self.update_box(box_record).await
+    }
+
+    async fn ping(&self) -> Result<()> {
+        if self.unavailable.load(Ordering::SeqCst) {
+            return Err(StoreError::InternalError(
+                "Mock store is unavailable".to_string(),
+            ));
+        }
+        Ok(())
+    }
 }
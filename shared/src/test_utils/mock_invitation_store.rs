@@ -2,8 +2,8 @@ use std::collections::HashMap;
 use std::sync::Mutex;
 
 use crate::error::{Result, StoreError};
-use crate::models::Invitation;
-use crate::store::InvitationStore;
+use crate::models::{generate_invite_code, Invitation};
+use crate::store::{decode_cursor, encode_cursor, InvitationStore, MAX_INVITE_CODE_ATTEMPTS};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 
@@ -75,20 +75,33 @@ impl MockInvitationStore {
 
 #[async_trait]
 impl InvitationStore for MockInvitationStore {
-    async fn create_invitation(&self, invitation: Invitation) -> Result<Invitation> {
+    async fn create_invitation(&self, mut invitation: Invitation) -> Result<Invitation> {
         if self.error_mode {
             return Err(StoreError::InternalError("Mock".into()));
         }
 
-        let id = invitation.id.clone();
-        let invite_code = invitation.invite_code.clone();
-
         // Take both locks in a fixed order (same as get_invitation_by_code)
         let (mut invitations_lock, mut codes_lock) = (
             self.invitations.lock().unwrap(),
             self.invitation_codes.lock().unwrap(),
         );
 
+        // Swap out an empty or already-used code, like the Dynamo store
+        let mut attempts = 1;
+        while invitation.invite_code.is_empty() || codes_lock.contains_key(&invitation.invite_code)
+        {
+            if attempts == MAX_INVITE_CODE_ATTEMPTS {
+                return Err(StoreError::InternalError(
+                    "Could not generate an unused invite code".to_string(),
+                ));
+            }
+            attempts += 1;
+            invitation.invite_code = generate_invite_code();
+        }
+
+        let id = invitation.id.clone();
+        let invite_code = invitation.invite_code.clone();
+
         // Store by ID
         invitations_lock.insert(id.clone(), invitation.clone());
 
@@ -131,26 +144,27 @@ impl InvitationStore for MockInvitationStore {
             return Err(StoreError::InternalError("Mock".into()));
         }
 
-        // Take both locks in a fixed order (same as create_invitation and update_invitation)
-        let (invitations_lock, codes_lock) = (
-            self.invitations.lock().unwrap(),
-            self.invitation_codes.lock().unwrap(),
-        );
+        // Scan rather than use the code index so duplicate codes (e.g. from with_data)
+        // are detected the same way the Dynamo GSI query would see them
+        let mut matches: Vec<Invitation> = self
+            .invitations
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|inv| inv.invite_code == invite_code)
+            .cloned()
+            .collect();
+
+        if matches.len() > 1 {
+            let ids: Vec<&str> = matches.iter().map(|inv| inv.id.as_str()).collect();
+            log::error!("Invite code {} matches invitations {:?}", invite_code, ids);
+            return Err(StoreError::AmbiguousInviteCode);
+        }
 
-        let id = codes_lock.get(invite_code).cloned().ok_or_else(|| {
+        let invitation = matches.pop().ok_or_else(|| {
             StoreError::NotFound(format!("Invitation not found with code: {}", invite_code))
         })?;
 
-        // Look up directly in invitations while holding both locks
-        let invitation = invitations_lock
-            .get(&id)
-            .cloned()
-            .ok_or_else(|| StoreError::NotFound(format!("Invitation not found: {}", id)))?;
-
-        // Drop the locks before checking expiry
-        drop(codes_lock);
-        drop(invitations_lock);
-
         // Enforce expiry only if enabled
         if self.enforce_expiry {
             let expires_at = DateTime::parse_from_rfc3339(&invitation.expires_at)
@@ -164,7 +178,7 @@ impl InvitationStore for MockInvitationStore {
         Ok(invitation)
     }
 
-    async fn update_invitation(&self, invitation: Invitation) -> Result<Invitation> {
+    async fn update_invitation(&self, mut invitation: Invitation) -> Result<Invitation> {
         if self.error_mode {
             return Err(StoreError::InternalError("Mock".into()));
         }
@@ -177,6 +191,22 @@ impl InvitationStore for MockInvitationStore {
             self.invitation_codes.lock().unwrap(),
         );
 
+        // Swap out a code another invitation holds, like the Dynamo store
+        let mut attempts = 1;
+        while invitation.invite_code.is_empty()
+            || codes_lock
+                .get(&invitation.invite_code)
+                .is_some_and(|holder| *holder != id)
+        {
+            if attempts == MAX_INVITE_CODE_ATTEMPTS {
+                return Err(StoreError::InternalError(
+                    "Could not generate an unused invite code".to_string(),
+                ));
+            }
+            attempts += 1;
+            invitation.invite_code = generate_invite_code();
+        }
+
         // Check if invite code changed
         let old_invite_code = invitations_lock.get(&id).map(|inv| inv.invite_code.clone());
 
@@ -216,26 +246,14 @@ impl InvitationStore for MockInvitationStore {
         if self.error_mode {
             return Err(StoreError::InternalError("Mock".into()));
         }
-        // Return invitations for the box, optionally filtering out expired
-        let now = Utc::now();
+        // Return every invitation for the box, expired or not
         let invitations = self
             .invitations
             .lock()
             .unwrap()
             .values()
             .filter(|inv| inv.box_id == box_id)
-            .filter_map(|inv| {
-                if self.enforce_expiry {
-                    // parse expiration and include if not expired
-                    DateTime::parse_from_rfc3339(&inv.expires_at)
-                        .ok()
-                        .map(|dt| dt.with_timezone(&Utc))
-                        .filter(|dt| &now <= dt)
-                        .map(|_| inv.clone())
-                } else {
-                    Some(inv.clone())
-                }
-            })
+            .cloned()
             .collect();
         Ok(invitations)
     }
@@ -255,4 +273,98 @@ impl InvitationStore for MockInvitationStore {
 
         Ok(invitations)
     }
+
+    async fn get_invitations_by_linked_user(&self, user_id: &str) -> Result<Vec<Invitation>> {
+        if self.error_mode {
+            return Err(StoreError::InternalError("Mock".into()));
+        }
+        let invitations = self
+            .invitations
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|inv| inv.linked_user_id.as_deref() == Some(user_id))
+            .cloned()
+            .collect();
+
+        Ok(invitations)
+    }
+
+    async fn get_invitations_by_creator_id_paged(
+        &self,
+        creator_id: &str,
+        limit: usize,
+        start_key: Option<String>,
+    ) -> Result<(Vec<Invitation>, Option<String>)> {
+        // Sort by id so pages are stable across calls
+        let mut invitations = self.get_invitations_by_creator_id(creator_id).await?;
+        invitations.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let last_id = match start_key {
+            Some(cursor) => decode_cursor(&cursor)?.get("id").cloned(),
+            None => None,
+        };
+
+        let remaining: Vec<Invitation> = invitations
+            .into_iter()
+            .filter(|inv| match &last_id {
+                Some(last) => &inv.id > last,
+                None => true,
+            })
+            .collect();
+        let has_more = remaining.len() > limit;
+        let page: Vec<Invitation> = remaining.into_iter().take(limit).collect();
+
+        let next_cursor = if has_more {
+            page.last().map(|last| {
+                encode_cursor(&HashMap::from([
+                    ("id".to_string(), last.id.clone()),
+                    ("creatorId".to_string(), creator_id.to_string()),
+                ]))
+            })
+        } else {
+            None
+        };
+
+        Ok((page, next_cursor))
+    }
+
+    async fn count_invitations_by_creator(&self, creator_id: &str) -> Result<u64> {
+        if self.error_mode {
+            return Err(StoreError::InternalError("Mock".into()));
+        }
+        let count = self
+            .invitations
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|inv| inv.creator_id == creator_id)
+            .count();
+
+        Ok(count as u64)
+    }
+
+    async fn purge_expired_invitations(&self) -> Result<Vec<Invitation>> {
+        if self.error_mode {
+            return Err(StoreError::InternalError("Mock".into()));
+        }
+
+        // Take both locks in a fixed order (same as get_invitation_by_code)
+        let (mut invitations_lock, mut codes_lock) = (
+            self.invitations.lock().unwrap(),
+            self.invitation_codes.lock().unwrap(),
+        );
+
+        let expired: Vec<Invitation> = invitations_lock
+            .values()
+            .filter(|inv| inv.is_expired())
+            .cloned()
+            .collect();
+        for invitation in &expired {
+            invitations_lock.remove(&invitation.id);
+            codes_lock.remove(&invitation.invite_code);
+        }
+
+        Ok(expired)
+    }
 }
@@ -1,6 +1,7 @@
 // Test utilities for shared components
 pub mod dynamo_test_utils;
 pub mod http_test_utils;
+pub mod jwt_test_utils;
 pub mod mock_box_store;
 pub mod mock_invitation_store;
 pub mod test_logging;
@@ -0,0 +1,167 @@
+use chrono::{Duration, Utc};
+
+use crate::error::StoreError;
+use crate::models::{Invitation, InvitationStatus};
+use crate::store::memory::MemoryInvitationStore;
+use crate::store::InvitationStore;
+
+fn invitation(id: &str, code: &str, box_id: &str, creator_id: &str) -> Invitation {
+    Invitation {
+        id: id.to_string(),
+        invite_code: code.to_string(),
+        invited_name: "Test Invitee".to_string(),
+        box_id: box_id.to_string(),
+        created_at: String::new(),
+        expires_at: String::new(),
+        opened: false,
+        status: InvitationStatus::Invited,
+        linked_user_id: None,
+        creator_id: creator_id.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_memory_invitation_store_lookups() {
+    let store = MemoryInvitationStore::new();
+
+    let created = store
+        .create_invitation(invitation("inv_1", "CODEAAAA", "box_1", "creator_1"))
+        .await
+        .unwrap();
+    // Timestamps are filled in the same way the Dynamo store does
+    assert!(!created.created_at.is_empty());
+    assert!(!created.is_expired());
+
+    store
+        .create_invitation(invitation("inv_2", "CODEBBBB", "box_1", "creator_2"))
+        .await
+        .unwrap();
+    store
+        .create_invitation(invitation("inv_3", "CODECCCC", "box_2", "creator_1"))
+        .await
+        .unwrap();
+
+    assert_eq!(store.get_invitation("inv_1").await.unwrap().id, "inv_1");
+    assert_eq!(
+        store.get_invitation_by_code("CODEBBBB").await.unwrap().id,
+        "inv_2"
+    );
+    assert!(matches!(
+        store.get_invitation("missing").await,
+        Err(StoreError::NotFound(_))
+    ));
+    assert!(matches!(
+        store.get_invitation_by_code("NOSUCHCD").await,
+        Err(StoreError::NotFound(_))
+    ));
+
+    let mut by_box = store.get_invitations_by_box_id("box_1").await.unwrap();
+    by_box.sort_by(|a, b| a.id.cmp(&b.id));
+    let ids: Vec<&str> = by_box.iter().map(|inv| inv.id.as_str()).collect();
+    assert_eq!(ids, vec!["inv_1", "inv_2"]);
+
+    let (page, cursor) = store
+        .get_invitations_by_creator_id_paged("creator_1", 1, None)
+        .await
+        .unwrap();
+    assert_eq!(page[0].id, "inv_1");
+    let (page, cursor) = store
+        .get_invitations_by_creator_id_paged("creator_1", 1, cursor)
+        .await
+        .unwrap();
+    assert_eq!(page[0].id, "inv_3");
+    assert!(cursor.is_none());
+
+    // A taken code is swapped for a fresh one
+    let duplicate = store
+        .create_invitation(invitation("inv_4", "CODEAAAA", "box_3", "creator_3"))
+        .await
+        .unwrap();
+    assert_ne!(duplicate.invite_code, "CODEAAAA");
+
+    // So is one an update tries to take from another invitation
+    let mut refreshed = duplicate.clone();
+    refreshed.invite_code = "CODEBBBB".to_string();
+    let refreshed = store.update_invitation(refreshed).await.unwrap();
+    assert_ne!(refreshed.invite_code, "CODEBBBB");
+    assert_eq!(
+        store.get_invitation_by_code("CODEBBBB").await.unwrap().id,
+        "inv_2"
+    );
+
+    store.delete_invitation("inv_1").await.unwrap();
+    assert!(matches!(
+        store.get_invitation("inv_1").await,
+        Err(StoreError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_memory_invitation_store_linked_user_lookup() {
+    let store = MemoryInvitationStore::new();
+    for (id, code, linked_user_id) in [
+        ("inv_1", "CODEAAAA", Some("guardian_1")),
+        ("inv_2", "CODEBBBB", Some("guardian_2")),
+        ("inv_3", "CODECCCC", None),
+        ("inv_4", "CODEDDDD", Some("guardian_1")),
+    ] {
+        let mut inv = invitation(id, code, "box_1", "creator_1");
+        inv.linked_user_id = linked_user_id.map(str::to_string);
+        store.create_invitation(inv).await.unwrap();
+    }
+
+    let mut linked = store
+        .get_invitations_by_linked_user("guardian_1")
+        .await
+        .unwrap();
+    linked.sort_by(|a, b| a.id.cmp(&b.id));
+    let ids: Vec<&str> = linked.iter().map(|inv| inv.id.as_str()).collect();
+    assert_eq!(ids, vec!["inv_1", "inv_4"]);
+
+    assert!(store
+        .get_invitations_by_linked_user("nobody")
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_memory_invitation_store_expiry() {
+    let store = MemoryInvitationStore::new();
+
+    let mut expired = invitation("old", "OLDCODEE", "box_1", "creator_1");
+    expired.expires_at = (Utc::now() - Duration::hours(1)).to_rfc3339();
+    store.create_invitation(expired).await.unwrap();
+
+    // Single lookups reject the expired invitation
+    assert!(matches!(
+        store.get_invitation("old").await,
+        Err(StoreError::InvitationExpired)
+    ));
+    assert!(matches!(
+        store.get_invitation_by_code("OLDCODEE").await,
+        Err(StoreError::InvitationExpired)
+    ));
+    assert!(matches!(
+        store.delete_invitation("old").await,
+        Err(StoreError::InvitationExpired)
+    ));
+
+    // List queries still return it, as the Dynamo indexes do
+    assert_eq!(
+        store
+            .get_invitations_by_box_id("box_1")
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(
+        store
+            .get_invitations_by_creator_id("creator_1")
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
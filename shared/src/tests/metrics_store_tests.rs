@@ -0,0 +1,103 @@
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+use crate::models::{Invitation, InvitationStatus};
+use crate::store::memory::MemoryInvitationStore;
+use crate::store::metrics::{EmfRecorder, MetricsBoxStore, MetricsInvitationStore, EMF_NAMESPACE};
+use crate::store::{BoxStore, InvitationStore};
+use crate::test_utils::mock_box_store::MockBoxStore;
+
+// Recorder that keeps the emitted EMF lines instead of printing them
+fn capturing_recorder() -> (EmfRecorder, Arc<Mutex<Vec<Value>>>) {
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let captured = lines.clone();
+    let recorder = EmfRecorder::with_sink(Arc::new(move |line: String| {
+        captured
+            .lock()
+            .unwrap()
+            .push(serde_json::from_str(&line).unwrap());
+    }));
+    (recorder, lines)
+}
+
+#[tokio::test]
+async fn test_metrics_box_store_emits_emf() {
+    let (recorder, lines) = capturing_recorder();
+    let store = MetricsBoxStore::with_recorder(Arc::new(MockBoxStore::new()), recorder);
+
+    assert!(store.get_box("missing").await.is_err());
+    assert!(store.get_boxes_by_owner("owner").await.is_ok());
+
+    let lines = lines.lock().unwrap();
+    assert_eq!(lines.len(), 2);
+
+    let record = &lines[0];
+    let metadata = &record["_aws"]["CloudWatchMetrics"][0];
+    assert!(record["_aws"]["Timestamp"].is_i64());
+    assert_eq!(metadata["Namespace"], EMF_NAMESPACE);
+    assert_eq!(
+        metadata["Dimensions"],
+        serde_json::json!([["Operation", "Outcome"]])
+    );
+    let metric_names: Vec<&str> = metadata["Metrics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|metric| metric["Name"].as_str().unwrap())
+        .collect();
+    assert_eq!(metric_names, vec!["Count", "Duration"]);
+
+    // Every metric and dimension named in the metadata is present at the top level
+    assert_eq!(record["Operation"], "get_box");
+    assert_eq!(record["Outcome"], "failure");
+    assert_eq!(record["Count"], 1);
+    assert!(record["Duration"].as_f64().unwrap() >= 0.0);
+
+    assert_eq!(lines[1]["Operation"], "get_boxes_by_owner");
+    assert_eq!(lines[1]["Outcome"], "success");
+}
+
+#[tokio::test]
+async fn test_metrics_invitation_store_delegates() {
+    let (recorder, lines) = capturing_recorder();
+    let inner: Arc<dyn InvitationStore> = Arc::new(MemoryInvitationStore::new());
+    let store = MetricsInvitationStore::with_recorder(inner, recorder);
+
+    let created = store
+        .create_invitation(Invitation {
+            id: "inv_1".to_string(),
+            invite_code: "CODEAAAA".to_string(),
+            invited_name: "Test Invitee".to_string(),
+            box_id: "box_1".to_string(),
+            created_at: String::new(),
+            expires_at: String::new(),
+            opened: false,
+            status: InvitationStatus::Invited,
+            linked_user_id: None,
+            creator_id: "creator_1".to_string(),
+        })
+        .await
+        .unwrap();
+    let fetched = store.get_invitation_by_code("CODEAAAA").await.unwrap();
+    assert_eq!(fetched.id, created.id);
+
+    let operations: Vec<String> = lines
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|record| {
+            format!(
+                "{}:{}",
+                record["Operation"].as_str().unwrap(),
+                record["Outcome"].as_str().unwrap()
+            )
+        })
+        .collect();
+    assert_eq!(
+        operations,
+        vec![
+            "create_invitation:success",
+            "get_invitation_by_code:success"
+        ]
+    );
+}
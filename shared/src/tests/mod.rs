@@ -1,3 +1,7 @@
 // Tests for shared crate functionality
+pub mod memory_store_tests;
+pub mod metrics_store_tests;
 pub mod mock_store_tests;
+pub mod retry_tests;
 pub mod store_tests;
+pub mod tracing_store_tests;
@@ -0,0 +1,118 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::error::StoreError;
+use crate::store::retry::{retry_throttled, retry_transient, throttled_or, TransientError};
+
+// Stand-in for an SDK error
+#[derive(Debug, Clone, Copy)]
+enum FakeError {
+    Throttled,
+    ServerError,
+    Validation,
+}
+
+impl fmt::Display for FakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FakeError::Throttled => write!(f, "ProvisionedThroughputExceededException"),
+            FakeError::ServerError => write!(f, "InternalServerError"),
+            FakeError::Validation => write!(f, "ValidationException"),
+        }
+    }
+}
+
+impl TransientError for FakeError {
+    fn is_throttled(&self) -> bool {
+        matches!(self, FakeError::Throttled)
+    }
+
+    fn is_transient(&self) -> bool {
+        matches!(self, FakeError::Throttled | FakeError::ServerError)
+    }
+}
+
+// Fake client call that fails with `error` for the first `failures` calls, then succeeds
+async fn fake_send(
+    calls: &AtomicU32,
+    failures: u32,
+    error: FakeError,
+) -> std::result::Result<&'static str, FakeError> {
+    if calls.fetch_add(1, Ordering::SeqCst) < failures {
+        Err(error)
+    } else {
+        Ok("item")
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_retry_succeeds_after_throttling_twice() {
+    let calls = AtomicU32::new(0);
+
+    let result =
+        retry_transient("put_item", 3, || fake_send(&calls, 2, FakeError::Throttled)).await;
+
+    assert_eq!(result.unwrap(), "item");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_retry_exhausted_surfaces_throttled() {
+    let calls = AtomicU32::new(0);
+
+    let err = retry_transient("put_item", 3, || fake_send(&calls, 5, FakeError::Throttled))
+        .await
+        .unwrap_err();
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    let store_err = throttled_or("put_item", 3, err, |e| {
+        StoreError::InternalError(e.to_string())
+    });
+    assert!(matches!(store_err, StoreError::Throttled(_)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_retry_skips_non_transient_errors() {
+    let calls = AtomicU32::new(0);
+
+    let err = retry_transient("put_item", 3, || {
+        fake_send(&calls, 5, FakeError::Validation)
+    })
+    .await
+    .unwrap_err();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let store_err = throttled_or("put_item", 3, err, |e| {
+        StoreError::InternalError(e.to_string())
+    });
+    assert!(matches!(store_err, StoreError::InternalError(_)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_retry_throttled_leaves_server_errors_alone() {
+    // A conditional write that hit a 5xx may have been applied, so it isn't repeated
+    let calls = AtomicU32::new(0);
+    let err = retry_throttled("put_item", 3, || {
+        fake_send(&calls, 5, FakeError::ServerError)
+    })
+    .await
+    .unwrap_err();
+    assert!(matches!(err, FakeError::ServerError));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    // Throttling is still retried
+    let calls = AtomicU32::new(0);
+    let result =
+        retry_throttled("put_item", 3, || fake_send(&calls, 2, FakeError::Throttled)).await;
+    assert_eq!(result.unwrap(), "item");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    // while retry_transient repeats the 5xx
+    let calls = AtomicU32::new(0);
+    let result = retry_transient("get_item", 3, || {
+        fake_send(&calls, 2, FakeError::ServerError)
+    })
+    .await;
+    assert_eq!(result.unwrap(), "item");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
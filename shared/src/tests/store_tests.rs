@@ -2,12 +2,20 @@
 mod dynamo_tests {
     use crate::models::BoxRecord;
     use crate::models::GuardianStatus;
-    use crate::store::{dynamo::DynamoBoxStore, BoxStore};
+    use crate::models::UnlockPolicy;
+    use crate::store::{
+        dynamo::{guardian_index_table_for, DynamoBoxStore},
+        BoxStore,
+    };
+    use crate::test_utils::dynamo_test_utils::create_guardian_index_table;
     use crate::test_utils::test_logging::init_test_logging;
     use aws_sdk_dynamodb::Client;
     use log::info;
     use uuid::Uuid;
 
+    // Serialises the tests that set store environment variables
+    static ENV_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
     fn create_test_box(name: &str, owner_id: &str) -> BoxRecord {
         let now = crate::models::now_str();
         BoxRecord {
@@ -22,7 +30,10 @@ mod dynamo_tests {
             documents: vec![],
             guardians: vec![],
             unlock_instructions: None,
-            unlock_request: None,
+            unlock_requests: vec![],
+            required_approvals: None,
+            unlock_policy: UnlockPolicy::Majority,
+            deleted_at: None,
             version: 0,
         }
     }
@@ -152,6 +163,11 @@ mod dynamo_tests {
         client: &Client,
         table_name: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let _ = client
+            .delete_table()
+            .table_name(guardian_index_table_for(table_name))
+            .send()
+            .await;
         client.delete_table().table_name(table_name).send().await?;
 
         // Wait for the table to be deleted
@@ -176,6 +192,9 @@ mod dynamo_tests {
         create_test_table(&client, &table_name)
             .await
             .expect("Failed to create test table");
+        create_guardian_index_table(&client, &guardian_index_table_for(&table_name))
+            .await
+            .expect("Failed to create guardian index table");
 
         // Create the store with our client and table
         let store = DynamoBoxStore::with_client_and_table(client.clone(), table_name.clone());
@@ -250,6 +269,35 @@ mod dynamo_tests {
             .expect("Failed to delete test table");
     }
 
+    // Test that a consistent read sees an update made immediately before it
+    #[tokio::test]
+    async fn dynamo_store_get_box_consistent_after_update() {
+        init_test_logging();
+        // Check if DynamoDB local is running
+        if !is_dynamodb_local_running() {
+            info!("Skipping test dynamo_store_get_box_consistent_after_update: DynamoDB Local is not running");
+            return;
+        }
+
+        // Create the test store
+        let (store, client, table_name) = create_test_store().await;
+
+        let test_box = create_test_box("Before Update", "test_owner");
+        let mut created_box = store.create_box(test_box.clone()).await.unwrap();
+        created_box.name = "After Update".to_string();
+        store.update_box(created_box).await.unwrap();
+
+        // No sleep: the consistent read must already see the new name and version
+        let fetched_box = store.get_box_consistent(&test_box.id).await.unwrap();
+        assert_eq!(fetched_box.name, "After Update");
+        assert_eq!(fetched_box.version, test_box.version + 1);
+
+        // Clean up
+        delete_test_table(&client, &table_name)
+            .await
+            .expect("Failed to delete test table");
+    }
+
     // Test for getting boxes by owner
     #[tokio::test]
     async fn dynamo_store_get_boxes_by_owner() {
@@ -381,6 +429,10 @@ mod dynamo_tests {
             lead_guardian: false,
             added_at: crate::models::now_str(),
             invitation_id: Uuid::new_v4().to_string(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
         });
 
         // Box 2 - has test_guardian as a rejected guardian (shouldn't show up)
@@ -392,6 +444,10 @@ mod dynamo_tests {
             lead_guardian: false,
             added_at: crate::models::now_str(),
             invitation_id: Uuid::new_v4().to_string(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
         });
 
         // Box 3 - different guardian
@@ -403,6 +459,10 @@ mod dynamo_tests {
             lead_guardian: false,
             added_at: crate::models::now_str(),
             invitation_id: Uuid::new_v4().to_string(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
         });
 
         store.create_box(test_box1.clone()).await.unwrap();
@@ -423,4 +483,625 @@ mod dynamo_tests {
             .await
             .expect("Failed to delete test table");
     }
+
+    // Test that the guardian index and the legacy scan return the same boxes
+    #[tokio::test]
+    async fn dynamo_store_guardian_index_matches_scan() {
+        init_test_logging();
+        // Check if DynamoDB local is running
+        if !is_dynamodb_local_running() {
+            info!("Skipping test dynamo_store_guardian_index_matches_scan: DynamoDB Local is not running");
+            return;
+        }
+
+        // Create the test store
+        let (store, client, table_name) = create_test_store().await;
+
+        let guardian_id = "guardian_id";
+        let new_guardian = |status: GuardianStatus| crate::models::Guardian {
+            id: guardian_id.to_string(),
+            name: "Test Guardian".to_string(),
+            status,
+            lead_guardian: false,
+            added_at: crate::models::now_str(),
+            invitation_id: Uuid::new_v4().to_string(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
+        };
+
+        // Two boxes guarded by the user, one where they rejected, one without them
+        let mut accepted_box = create_test_box("Accepted Box", "test_owner");
+        accepted_box
+            .guardians
+            .push(new_guardian(GuardianStatus::Accepted));
+        let mut invited_box = create_test_box("Invited Box", "test_owner");
+        invited_box
+            .guardians
+            .push(new_guardian(GuardianStatus::Invited));
+        let mut rejected_box = create_test_box("Rejected Box", "test_owner");
+        rejected_box
+            .guardians
+            .push(new_guardian(GuardianStatus::Rejected));
+        let unrelated_box = create_test_box("Unrelated Box", "test_owner");
+
+        store.create_box(accepted_box.clone()).await.unwrap();
+        store.create_box(invited_box.clone()).await.unwrap();
+        store.create_box(rejected_box.clone()).await.unwrap();
+        store.create_box(unrelated_box.clone()).await.unwrap();
+
+        // Guardian changes made through update_box must reach the index too
+        let mut updated_box = store.get_box(&unrelated_box.id).await.unwrap();
+        updated_box
+            .guardians
+            .push(new_guardian(GuardianStatus::Accepted));
+        store.update_box(updated_box).await.unwrap();
+
+        let mut updated_box = store.get_box(&invited_box.id).await.unwrap();
+        updated_box.guardians[0].status = GuardianStatus::Rejected;
+        store.update_box(updated_box).await.unwrap();
+
+        let scan_store = DynamoBoxStore::with_client_and_table(client.clone(), table_name.clone())
+            .with_guardian_scan(true);
+        let index_store = store.with_guardian_scan(false);
+
+        let mut scanned: Vec<String> = scan_store
+            .get_boxes_by_guardian_id(guardian_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|b| b.id)
+            .collect();
+        let mut indexed: Vec<String> = index_store
+            .get_boxes_by_guardian_id(guardian_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|b| b.id)
+            .collect();
+        scanned.sort();
+        indexed.sort();
+
+        let mut expected = vec![accepted_box.id.clone(), unrelated_box.id.clone()];
+        expected.sort();
+        assert_eq!(scanned, expected);
+        assert_eq!(indexed, scanned);
+
+        // Deleting a box removes it from the index as well
+        index_store.delete_box(&accepted_box.id).await.unwrap();
+        let remaining = index_store
+            .get_boxes_by_guardian_id(guardian_id)
+            .await
+            .unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, unrelated_box.id);
+
+        // Clean up
+        delete_test_table(&client, &table_name)
+            .await
+            .expect("Failed to delete test table");
+    }
+
+    // Guardians of more boxes than one BatchGetItem call takes still get every box back
+    #[tokio::test]
+    async fn dynamo_store_guardian_index_loads_many_boxes() {
+        init_test_logging();
+        // Check if DynamoDB local is running
+        if !is_dynamodb_local_running() {
+            info!("Skipping test dynamo_store_guardian_index_loads_many_boxes: DynamoDB Local is not running");
+            return;
+        }
+
+        // Create the test store
+        let (store, client, table_name) = create_test_store().await;
+        let store = store.with_guardian_scan(false);
+
+        let guardian_id = "busy_guardian";
+        let mut expected = Vec::new();
+        for i in 0..105 {
+            let mut test_box = create_test_box(&format!("Guarded Box {}", i), "test_owner");
+            test_box.guardians.push(crate::models::Guardian {
+                id: guardian_id.to_string(),
+                name: "Busy Guardian".to_string(),
+                status: GuardianStatus::Accepted,
+                lead_guardian: false,
+                added_at: crate::models::now_str(),
+                invitation_id: Uuid::new_v4().to_string(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
+            });
+            expected.push(test_box.id.clone());
+            store.create_box(test_box).await.unwrap();
+        }
+
+        let mut indexed: Vec<String> = store
+            .get_boxes_by_guardian_id(guardian_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|b| b.id)
+            .collect();
+        indexed.sort();
+        expected.sort();
+        assert_eq!(indexed, expected);
+
+        // Clean up
+        delete_test_table(&client, &table_name)
+            .await
+            .expect("Failed to delete test table");
+    }
+
+    fn create_test_invitation(creator_id: &str) -> crate::models::Invitation {
+        use crate::models::{invitation_expires_at, Invitation, InvitationStatus};
+
+        Invitation {
+            id: Uuid::new_v4().to_string(),
+            invite_code: String::new(),
+            invited_name: "Creator Test Invitee".to_string(),
+            box_id: Uuid::new_v4().to_string(),
+            created_at: crate::models::now_str(),
+            expires_at: invitation_expires_at(chrono::Utc::now(), 48),
+            opened: false,
+            status: InvitationStatus::Invited,
+            linked_user_id: None,
+            creator_id: creator_id.to_string(),
+        }
+    }
+
+    // Creator lookups query the creatorId index and only return that creator's invitations
+    #[tokio::test]
+    async fn dynamo_store_get_invitations_by_creator_id() {
+        use crate::store::{dynamo::DynamoInvitationStore, InvitationStore};
+        use crate::test_utils::dynamo_test_utils::create_invitation_table;
+
+        init_test_logging();
+        if !is_dynamodb_local_running() {
+            info!(
+                "Skipping test dynamo_store_get_invitations_by_creator_id: DynamoDB Local is not running"
+            );
+            return;
+        }
+
+        let client = create_local_dynamo_client().await;
+        let table_name = format!("test-invitations-{}", Uuid::new_v4());
+        create_invitation_table(&client, &table_name)
+            .await
+            .expect("Failed to create invitation table");
+
+        let store =
+            DynamoInvitationStore::with_client_and_table(client.clone(), table_name.clone())
+                .with_creator_scan(false);
+
+        let mine = store
+            .create_invitation(create_test_invitation("creator_a"))
+            .await
+            .unwrap();
+        store
+            .create_invitation(create_test_invitation("creator_b"))
+            .await
+            .unwrap();
+
+        let invitations = store
+            .get_invitations_by_creator_id("creator_a")
+            .await
+            .unwrap();
+        assert_eq!(invitations.len(), 1);
+        assert_eq!(invitations[0].id, mine.id);
+
+        // The scan fallback must agree with the index
+        let scanned = store
+            .with_creator_scan(true)
+            .get_invitations_by_creator_id("creator_a")
+            .await
+            .unwrap();
+        assert_eq!(scanned.len(), 1);
+        assert_eq!(scanned[0].id, mine.id);
+
+        client
+            .delete_table()
+            .table_name(&table_name)
+            .send()
+            .await
+            .expect("Failed to delete invitation table");
+    }
+
+    // Linked user lookups query the sparse linkedUserId index; unlinked invitations stay out of it
+    #[tokio::test]
+    async fn dynamo_store_get_invitations_by_linked_user() {
+        use crate::store::{dynamo::DynamoInvitationStore, InvitationStore};
+        use crate::test_utils::dynamo_test_utils::create_invitation_table;
+
+        init_test_logging();
+        if !is_dynamodb_local_running() {
+            info!(
+                "Skipping test dynamo_store_get_invitations_by_linked_user: DynamoDB Local is not running"
+            );
+            return;
+        }
+
+        let client = create_local_dynamo_client().await;
+        let table_name = format!("test-invitations-{}", Uuid::new_v4());
+        create_invitation_table(&client, &table_name)
+            .await
+            .expect("Failed to create invitation table");
+
+        let store =
+            DynamoInvitationStore::with_client_and_table(client.clone(), table_name.clone())
+                .with_linked_user_scan(false);
+
+        let mut linked = create_test_invitation("creator_a");
+        linked.linked_user_id = Some("guardian_a".to_string());
+        let linked = store.create_invitation(linked).await.unwrap();
+        store
+            .create_invitation(create_test_invitation("creator_a"))
+            .await
+            .unwrap();
+
+        let invitations = store
+            .get_invitations_by_linked_user("guardian_a")
+            .await
+            .unwrap();
+        assert_eq!(invitations.len(), 1);
+        assert_eq!(invitations[0].id, linked.id);
+
+        // The scan fallback must agree with the index
+        let scanned = store
+            .with_linked_user_scan(true)
+            .get_invitations_by_linked_user("guardian_a")
+            .await
+            .unwrap();
+        assert_eq!(scanned.len(), 1);
+        assert_eq!(scanned[0].id, linked.id);
+
+        client
+            .delete_table()
+            .table_name(&table_name)
+            .send()
+            .await
+            .expect("Failed to delete invitation table");
+    }
+
+    // The TTL attribute is written alongside the invitation and mirrors expires_at
+    #[test]
+    fn invitation_item_includes_ttl_epoch() {
+        use crate::models::{Invitation, InvitationStatus};
+        use crate::store::dynamo::{invitation_to_item, INVITATION_TTL_ATTRIBUTE};
+
+        let invitation = Invitation {
+            id: Uuid::new_v4().to_string(),
+            invite_code: "TTLCODE1".to_string(),
+            invited_name: "TTL Invitee".to_string(),
+            box_id: Uuid::new_v4().to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            expires_at: "2024-01-03T00:00:00+00:00".to_string(),
+            opened: false,
+            status: InvitationStatus::Invited,
+            linked_user_id: None,
+            creator_id: "creator".to_string(),
+        };
+
+        let item = invitation_to_item(&invitation).unwrap();
+        let epoch = item
+            .get(INVITATION_TTL_ATTRIBUTE)
+            .expect("expiresAtEpoch attribute should be present")
+            .as_n()
+            .unwrap()
+            .parse::<i64>()
+            .unwrap();
+
+        let expected = chrono::DateTime::parse_from_rfc3339(&invitation.expires_at)
+            .unwrap()
+            .timestamp();
+        assert_eq!(epoch, expected);
+
+        // Unlinked invitations leave the sparse linked user index key out
+        assert!(!item.contains_key("linkedUserId"));
+
+        // The extra attribute must not break reading the invitation back
+        let round_trip: Invitation = serde_dynamo::from_item(item).unwrap();
+        assert_eq!(round_trip.expires_at, invitation.expires_at);
+        assert_eq!(round_trip.linked_user_id, None);
+
+        let linked = Invitation {
+            linked_user_id: Some("guardian".to_string()),
+            ..invitation
+        };
+        let item = invitation_to_item(&linked).unwrap();
+        assert_eq!(item["linkedUserId"].as_s().unwrap(), "guardian");
+    }
+
+    // The item reserving a code points back at its invitation and expires with it
+    #[test]
+    fn invite_code_item_reserves_code() {
+        use crate::store::dynamo::{
+            invitation_to_item, invite_code_item, invite_code_item_id, INVITATION_TTL_ATTRIBUTE,
+        };
+
+        let mut invitation = create_test_invitation("creator");
+        invitation.invite_code = "RESERVE1".to_string();
+
+        let item = invite_code_item(&invitation).unwrap();
+        assert_eq!(item["id"].as_s().unwrap(), "code#RESERVE1");
+        assert_eq!(item["id"].as_s().unwrap(), &invite_code_item_id("RESERVE1"));
+        assert_eq!(item["invitationId"].as_s().unwrap(), &invitation.id);
+        assert_eq!(
+            item[INVITATION_TTL_ATTRIBUTE],
+            invitation_to_item(&invitation).unwrap()[INVITATION_TTL_ATTRIBUTE]
+        );
+
+        // No index key attributes, so the reservation stays out of every GSI
+        for attribute in ["invite_code", "box_id", "creatorId", "linkedUserId"] {
+            assert!(!item.contains_key(attribute));
+        }
+    }
+
+    // Codes are reserved with a conditional write, so a taken code is never stored twice
+    #[tokio::test]
+    async fn dynamo_store_reserves_invite_codes() {
+        use crate::store::{dynamo::DynamoInvitationStore, InvitationStore};
+        use crate::test_utils::dynamo_test_utils::create_invitation_table;
+
+        init_test_logging();
+        if !is_dynamodb_local_running() {
+            info!(
+                "Skipping test dynamo_store_reserves_invite_codes: DynamoDB Local is not running"
+            );
+            return;
+        }
+
+        let client = create_local_dynamo_client().await;
+        let table_name = format!("test-invitations-{}", Uuid::new_v4());
+        create_invitation_table(&client, &table_name)
+            .await
+            .expect("Failed to create invitation table");
+        let store =
+            DynamoInvitationStore::with_client_and_table(client.clone(), table_name.clone())
+                .with_creator_scan(true);
+
+        let mut first = create_test_invitation("creator_a");
+        first.invite_code = "TAKENCDE".to_string();
+        let first = store.create_invitation(first).await.unwrap();
+        assert_eq!(first.invite_code, "TAKENCDE");
+
+        let mut second = create_test_invitation("creator_a");
+        second.invite_code = "TAKENCDE".to_string();
+        let second = store.create_invitation(second).await.unwrap();
+        assert_ne!(second.invite_code, "TAKENCDE");
+
+        // A refresh onto the taken code is swapped as well
+        let mut refreshed = second.clone();
+        refreshed.invite_code = "TAKENCDE".to_string();
+        let refreshed = store.update_invitation(refreshed).await.unwrap();
+        assert_ne!(refreshed.invite_code, "TAKENCDE");
+        assert_eq!(
+            store.get_invitation_by_code("TAKENCDE").await.unwrap().id,
+            first.id
+        );
+
+        // Reservations don't show up as invitations in a scan
+        assert_eq!(
+            store
+                .get_invitations_by_creator_id("creator_a")
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // Deleting the invitation releases its code
+        store.delete_invitation(&first.id).await.unwrap();
+        let mut third = create_test_invitation("creator_b");
+        third.invite_code = "TAKENCDE".to_string();
+        let third = store.create_invitation(third).await.unwrap();
+        assert_eq!(third.invite_code, "TAKENCDE");
+
+        client
+            .delete_table()
+            .table_name(&table_name)
+            .send()
+            .await
+            .expect("Failed to delete invitation table");
+    }
+
+    // DYNAMODB_ENDPOINT_URL points the store's client at another endpoint
+    #[tokio::test]
+    async fn endpoint_override_from_env() {
+        use crate::store::dynamo::{with_endpoint_from_env, DYNAMODB_ENDPOINT_URL_VAR};
+        let _env = ENV_LOCK.lock().await;
+
+        let loader =
+            || aws_config::defaults(aws_config::BehaviorVersion::latest()).region("us-east-1");
+
+        std::env::set_var(DYNAMODB_ENDPOINT_URL_VAR, "http://localhost:8000");
+        let config = with_endpoint_from_env(loader()).load().await;
+        std::env::remove_var(DYNAMODB_ENDPOINT_URL_VAR);
+        assert_eq!(config.endpoint_url(), Some("http://localhost:8000"));
+
+        let config = with_endpoint_from_env(loader()).load().await;
+        assert_eq!(config.endpoint_url(), None);
+    }
+
+    // The builder's table name wins over DYNAMODB_TABLE, which wins over the default
+    #[tokio::test]
+    async fn box_store_builder_table_name() {
+        let builder =
+            || DynamoBoxStore::builder().endpoint_url("http://localhost:8000".to_string());
+        let _env = ENV_LOCK.lock().await;
+
+        std::env::set_var("DYNAMODB_TABLE", "env-box-table");
+        let custom = builder()
+            .table_name("custom-box-table".to_string())
+            .build()
+            .await;
+        let from_env = builder().build().await;
+        std::env::remove_var("DYNAMODB_TABLE");
+        let default = builder().build().await;
+
+        assert_eq!(custom.table_name(), "custom-box-table");
+        assert_eq!(from_env.table_name(), "env-box-table");
+        assert_eq!(default.table_name(), "box-table");
+    }
+
+    // new_blocking builds the store outside any runtime; DynamoDB Local is reached
+    // through the same environment variables the binaries use
+    #[test]
+    fn dynamo_store_new_blocking_round_trip() {
+        use crate::store::dynamo::DYNAMODB_ENDPOINT_URL_VAR;
+
+        init_test_logging();
+        if !is_dynamodb_local_running() {
+            info!(
+                "Skipping test dynamo_store_new_blocking_round_trip: DynamoDB Local is not running"
+            );
+            return;
+        }
+        let _env = ENV_LOCK.blocking_lock();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let (_, client, table_name) = runtime.block_on(create_test_store());
+
+        let set_region = std::env::var("AWS_REGION").is_err();
+        if set_region {
+            std::env::set_var("AWS_REGION", "us-east-1");
+        }
+        std::env::set_var(DYNAMODB_ENDPOINT_URL_VAR, "http://localhost:8000");
+        std::env::set_var("DYNAMODB_TABLE", &table_name);
+
+        // Without DYNAMODB_GUARDIAN_INDEX_TABLE the index table name is derived the
+        // same way with_client_and_table does it
+        let store = DynamoBoxStore::new_blocking();
+
+        std::env::remove_var(DYNAMODB_ENDPOINT_URL_VAR);
+        std::env::remove_var("DYNAMODB_TABLE");
+        if set_region {
+            std::env::remove_var("AWS_REGION");
+        }
+        assert_eq!(store.table_name(), table_name);
+
+        let mut test_box = create_test_box("Blocking Test Box", "test_owner");
+        test_box.guardians.push(crate::models::Guardian {
+            id: "blocking_guardian".to_string(),
+            name: "Blocking Guardian".to_string(),
+            status: GuardianStatus::Accepted,
+            lead_guardian: false,
+            added_at: crate::models::now_str(),
+            invitation_id: Uuid::new_v4().to_string(),
+            email: None,
+            relationship: None,
+            note: None,
+            last_viewed_at: None,
+        });
+        runtime.block_on(async {
+            store.create_box(test_box.clone()).await.unwrap();
+            let fetched = store.get_box(&test_box.id).await.unwrap();
+            assert_eq!(fetched.name, "Blocking Test Box");
+
+            let guarded = store
+                .with_guardian_scan(false)
+                .get_boxes_by_guardian_id("blocking_guardian")
+                .await
+                .unwrap();
+            assert_eq!(guarded.len(), 1);
+
+            delete_test_table(&client, &table_name)
+                .await
+                .expect("Failed to delete test table");
+        });
+    }
+}
+
+#[cfg(test)]
+mod guardian_box_tests {
+    use crate::models::{
+        BoxRecord, Document, DocumentStorage, Guardian, GuardianStatus, UnlockPolicy,
+    };
+    use crate::store::convert_to_guardian_box;
+
+    fn box_with_guardian(status: GuardianStatus) -> BoxRecord {
+        let now = crate::models::now_str();
+        BoxRecord {
+            id: "box_1".to_string(),
+            name: "Guardian Box".to_string(),
+            description: "Box for conversion tests".to_string(),
+            is_locked: false,
+            created_at: now.clone(),
+            updated_at: now.clone(),
+            owner_id: "owner_1".to_string(),
+            owner_name: None,
+            documents: vec![],
+            guardians: vec![Guardian {
+                id: "guardian_1".to_string(),
+                name: "Guardian One".to_string(),
+                lead_guardian: false,
+                status,
+                added_at: now,
+                invitation_id: "invitation_1".to_string(),
+                email: None,
+                relationship: None,
+                note: None,
+                last_viewed_at: None,
+            }],
+            unlock_instructions: None,
+            unlock_requests: vec![],
+            required_approvals: None,
+            unlock_policy: UnlockPolicy::Majority,
+            deleted_at: None,
+            version: 0,
+        }
+    }
+
+    #[test]
+    fn invited_and_viewed_guardians_are_pending_approval() {
+        for status in [GuardianStatus::Invited, GuardianStatus::Viewed] {
+            let guardian_box = convert_to_guardian_box(&box_with_guardian(status), "guardian_1")
+                .expect("guardian should see the box");
+            assert_eq!(guardian_box.pending_guardian_approval, Some(true));
+        }
+    }
+
+    #[test]
+    fn accepted_guardian_is_not_pending_approval() {
+        let box_record = box_with_guardian(GuardianStatus::Accepted);
+        let guardian_box = convert_to_guardian_box(&box_record, "guardian_1").unwrap();
+        assert_eq!(guardian_box.pending_guardian_approval, Some(false));
+    }
+
+    #[test]
+    fn locked_box_hides_document_content() {
+        let mut box_record = box_with_guardian(GuardianStatus::Accepted);
+        box_record.documents.push(Document {
+            id: "doc_1".to_string(),
+            title: "Will".to_string(),
+            content: "Secret contents".to_string(),
+            created_at: crate::models::now_str(),
+            storage: DocumentStorage::Inline,
+            content_type: "text/plain".to_string(),
+        });
+
+        let guardian_box = convert_to_guardian_box(&box_record, "guardian_1").unwrap();
+        assert_eq!(
+            guardian_box.documents[0].content.as_deref(),
+            Some("Secret contents")
+        );
+
+        box_record.is_locked = true;
+        let guardian_box = convert_to_guardian_box(&box_record, "guardian_1").unwrap();
+        assert_eq!(guardian_box.documents[0].title, "Will");
+        assert!(guardian_box.documents[0].content.is_none());
+    }
+
+    #[test]
+    fn rejected_or_unknown_guardian_gets_no_box() {
+        let box_record = box_with_guardian(GuardianStatus::Rejected);
+        assert!(convert_to_guardian_box(&box_record, "guardian_1").is_none());
+
+        let box_record = box_with_guardian(GuardianStatus::Accepted);
+        assert!(convert_to_guardian_box(&box_record, "someone_else").is_none());
+    }
 }
@@ -0,0 +1,97 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::Result;
+use crate::models::{now_str, BoxRecord, UnlockPolicy};
+use crate::store::tracing::TracingBoxStore;
+use crate::store::BoxStore;
+use crate::test_utils::mock_box_store::MockBoxStore;
+
+fn test_box(id: &str, owner_id: &str) -> BoxRecord {
+    let now = now_str();
+    BoxRecord {
+        id: id.to_string(),
+        name: format!("Box {}", id),
+        description: "Tracing test box".to_string(),
+        is_locked: false,
+        created_at: now.clone(),
+        updated_at: now,
+        owner_id: owner_id.to_string(),
+        owner_name: None,
+        documents: vec![],
+        guardians: vec![],
+        unlock_instructions: None,
+        unlock_requests: vec![],
+        required_approvals: None,
+        unlock_policy: UnlockPolicy::Majority,
+        deleted_at: None,
+        version: 0,
+    }
+}
+
+// Results compared by value, errors by message, since StoreError has no PartialEq
+fn outcome<T: Serialize>(result: Result<T>) -> std::result::Result<Value, String> {
+    result
+        .map(|value| serde_json::to_value(value).unwrap())
+        .map_err(|e| e.to_string())
+}
+
+#[tokio::test]
+async fn test_tracing_box_store_delegates() {
+    let boxes = vec![test_box("box-1", "owner-1"), test_box("box-2", "owner-1")];
+    let plain = MockBoxStore::with_data(boxes.clone());
+    let traced = TracingBoxStore::new(MockBoxStore::with_data(boxes));
+
+    let new_box = test_box("box-3", "owner-2");
+    assert_eq!(
+        outcome(traced.create_box(new_box.clone()).await),
+        outcome(plain.create_box(new_box).await)
+    );
+    assert_eq!(
+        outcome(traced.get_box("box-1").await),
+        outcome(plain.get_box("box-1").await)
+    );
+    assert_eq!(
+        outcome(traced.get_box("missing").await),
+        outcome(plain.get_box("missing").await)
+    );
+
+    let mut by_owner = outcome(traced.get_boxes_by_owner("owner-1").await).unwrap();
+    let mut expected = outcome(plain.get_boxes_by_owner("owner-1").await).unwrap();
+    by_owner
+        .as_array_mut()
+        .unwrap()
+        .sort_by_key(|b| b["id"].to_string());
+    expected
+        .as_array_mut()
+        .unwrap()
+        .sort_by_key(|b| b["id"].to_string());
+    assert_eq!(by_owner, expected);
+
+    let ids = vec!["box-2".to_string(), "box-1".to_string()];
+    assert_eq!(
+        outcome(traced.get_boxes_by_ids(&ids).await),
+        outcome(plain.get_boxes_by_ids(&ids).await)
+    );
+
+    // Writes, including a stale version, come back exactly as the inner store returns them
+    let mut renamed = plain.get_box("box-1").await.unwrap();
+    renamed.name = "Renamed".to_string();
+    assert_eq!(
+        outcome(traced.update_box(renamed.clone()).await),
+        outcome(plain.update_box(renamed.clone()).await)
+    );
+    assert_eq!(
+        outcome(traced.update_box(renamed.clone()).await),
+        outcome(plain.update_box(renamed).await)
+    );
+
+    assert_eq!(
+        outcome(traced.delete_box("box-2").await),
+        outcome(plain.delete_box("box-2").await)
+    );
+    assert_eq!(
+        outcome(traced.get_box("box-2").await),
+        outcome(plain.get_box("box-2").await)
+    );
+}